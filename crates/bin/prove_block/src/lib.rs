@@ -18,12 +18,12 @@ use rpc_utils::{get_class_proofs, get_storage_proofs};
 use starknet::core::types::{BlockId, MaybePendingBlockWithTxHashes, MaybePendingBlockWithTxs, StarknetError};
 use starknet::providers::{Provider, ProviderError};
 use starknet_api::StarknetApiError;
-use starknet_os::config::{StarknetGeneralConfig, StarknetOsConfig, STORED_BLOCK_HASH_BUFFER};
+use starknet_os::config::{eth_price_in_fri, StarknetGeneralConfig, StarknetOsConfig, STORED_BLOCK_HASH_BUFFER};
 use starknet_os::crypto::pedersen::PedersenHash;
 use starknet_os::crypto::poseidon::PoseidonHash;
 use starknet_os::error::SnOsError::{self};
 use starknet_os::execution::helper::{ContractStorageMap, ExecutionHelperWrapper};
-use starknet_os::io::input::StarknetOsInput;
+use starknet_os::io::input::{StarknetOsInput, OS_INPUT_SCHEMA_VERSION};
 use starknet_os::io::output::StarknetOsOutput;
 use starknet_os::run_os;
 use starknet_os::starknet::business_logic::fact_state::contract_state_objects::ContractState;
@@ -48,7 +48,6 @@ mod state_utils;
 mod types;
 mod utils;
 
-
 #[derive(Debug, Error)]
 pub enum ProveBlockError {
     #[error("RPC Error: {0}")]
@@ -203,6 +202,8 @@ pub async fn prove_block(
             fee_token_address: block_context.chain_info().fee_token_addresses.strk_fee_token_address,
             deprecated_fee_token_address: block_context.chain_info().fee_token_addresses.eth_fee_token_address,
         },
+        starknet_version: block_with_txs.starknet_version.clone(),
+        eth_price_in_fri: eth_price_in_fri(&block_context.block_info().gas_prices),
         ..default_general_config
     };
 
@@ -265,6 +266,7 @@ pub async fn prove_block(
             contract_hash: previous_class_hash.to_bytes_be().to_vec(),
             storage_commitment_tree: previous_tree,
             nonce: previous_nonce,
+            storage: Default::default(),
         };
 
         contract_states.insert(contract_address, contract_state);
@@ -338,7 +340,9 @@ pub async fn prove_block(
         declared_class_hash_to_component_hashes: declared_class_hash_component_hashes,
         new_block_hash: block_with_txs.block_hash,
         prev_block_hash: previous_block.block_hash,
+        block_hashes: Default::default(),
         full_output,
+        schema_version: OS_INPUT_SCHEMA_VERSION,
     });
     let execution_helper = ExecutionHelperWrapper::<ProverPerContractStorage>::new(
         contract_storages,
@@ -5,7 +5,7 @@ use cairo_vm::Felt252;
 use serde::ser::Error;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_with::serde_as;
-use starknet_core::types::{EntryPointsByType, FlattenedSierraClass};
+use starknet_core::types::{EntryPointsByType, FlattenedSierraClass, SierraEntryPoint};
 use starknet_types_core::felt::Felt;
 
 use crate::casm_contract_class::{CairoLangCasmClass, GenericCasmContractClass};
@@ -51,6 +51,14 @@ impl GenericSierraContractClass {
     }
 
     fn build_starknet_core_class(&self) -> Result<StarknetCoreSierraContractClass, ContractClassError> {
+        // A class without ABI must keep an empty ABI string, which is what its class hash is
+        // computed on. Going through `SierraClass` would turn it into an empty list ("[]").
+        if let Some(cairo_lang_class) = self.cairo_lang_contract_class.get() {
+            if cairo_lang_class.abi.is_none() {
+                return Ok(abi_less_starknet_core_class(cairo_lang_class));
+            }
+        }
+
         let serialized_class = self.get_serialized_contract_class()?;
         let sierra_class: starknet_core::types::contract::SierraClass =
             serde_json::from_slice(serialized_class).map_err(ContractClassError::SerdeError)?;
@@ -102,6 +110,31 @@ impl GenericSierraContractClass {
     }
 }
 
+/// Converts a cairo-lang class that has no ABI, leaving the ABI of the result empty.
+fn abi_less_starknet_core_class(cairo_lang_class: &CairoLangSierraContractClass) -> StarknetCoreSierraContractClass {
+    let convert_entry_points = |entry_points: &[cairo_lang_starknet_classes::contract_class::ContractEntryPoint]| {
+        entry_points
+            .iter()
+            .map(|entry_point| SierraEntryPoint {
+                selector: Felt::from(entry_point.selector.clone()),
+                function_idx: entry_point.function_idx as u64,
+            })
+            .collect()
+    };
+    let entry_points = &cairo_lang_class.entry_points_by_type;
+
+    FlattenedSierraClass {
+        sierra_program: cairo_lang_class.sierra_program.iter().map(|felt| Felt::from(felt.value.clone())).collect(),
+        contract_class_version: cairo_lang_class.contract_class_version.clone(),
+        entry_points_by_type: EntryPointsByType {
+            constructor: convert_entry_points(&entry_points.constructor),
+            external: convert_entry_points(&entry_points.external),
+            l1_handler: convert_entry_points(&entry_points.l1_handler),
+        },
+        abi: String::new(),
+    }
+}
+
 #[serde_as]
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct FlattenedSierraClassWithAbi {
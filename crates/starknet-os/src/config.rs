@@ -1,5 +1,7 @@
 use std::fs::File;
 use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
 
 use blockifier::blockifier::block::{BlockInfo, GasPrices};
 use blockifier::bouncer::BouncerConfig;
@@ -7,6 +9,9 @@ use blockifier::context::{BlockContext, ChainInfo, FeeTokenAddresses};
 use blockifier::transaction::objects::FeeType;
 use blockifier::versioned_constants::VersionedConstants;
 use cairo_vm::types::layout_name::LayoutName;
+use cairo_vm::Felt252;
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 use starknet_api::block::{BlockNumber, BlockTimestamp};
@@ -14,6 +19,7 @@ use starknet_api::core::{ChainId, ContractAddress, PatriciaKey};
 use starknet_api::{contract_address, felt, patricia_key};
 
 use crate::error::SnOsError;
+use crate::io::prover_trace::ProverTracePaths;
 use crate::utils::ChainIdNum;
 
 pub const fn default_layout() -> LayoutName {
@@ -24,12 +30,18 @@ pub const fn default_layout() -> LayoutName {
 const MAX_STEPS_PER_TX: u32 = 4_000_000;
 
 const DEFAULT_CONFIG_PATH: &str = "../../cairo-lang/src/starkware/starknet/definitions/general_config.yml";
+/// Environment variable pointing to the OS input file, read when the input is not provided by the
+/// caller of the OS.
+pub const INPUT_PATH_ENV_VAR: &str = "SNOS_INPUT_PATH";
 pub const STORED_BLOCK_HASH_BUFFER: u64 = 10;
 pub const BLOCK_HASH_CONTRACT_ADDRESS: u64 = 1;
 pub const STARKNET_OS_CONFIG_HASH_VERSION: &str = "StarknetOsConfig1";
 pub const DEFAULT_COMPILER_VERSION: &str = "0.12.2";
+pub const DEFAULT_STARKNET_VERSION: &str = "0.13.2";
 pub const DEFAULT_STORAGE_TREE_HEIGHT: u64 = 251;
 pub const COMPILED_CLASS_HASH_COMMITMENT_TREE_HEIGHT: usize = 251;
+/// Number of wei in one ETH, and of fri in one STRK.
+const WEI_PER_ETH: u128 = 1_000_000_000_000_000_000;
 pub const CONTRACT_STATES_COMMITMENT_TREE_HEIGHT: usize = 251;
 pub const DEFAULT_INNER_TREE_HEIGHT: u64 = 64;
 // TODO: update with relevant address
@@ -51,6 +63,69 @@ pub struct StarknetOsConfig {
     pub deprecated_fee_token_address: ContractAddress,
 }
 
+impl StarknetOsConfig {
+    /// Returns the address of the token used to pay fees for a transaction of the given version.
+    /// V3 transactions pay in STRK (`fee_token_address`), older ones pay in ETH
+    /// (`deprecated_fee_token_address`).
+    pub fn fee_token_address_for_version(&self, tx_version: Felt252) -> ContractAddress {
+        if tx_version == Felt252::THREE { self.fee_token_address } else { self.deprecated_fee_token_address }
+    }
+}
+
+/// How hints react to a violated invariant, as reported by `hint_assert!`.
+///
+/// Hints that cannot produce their result when a check fails, e.g. because a value is missing
+/// from the input or from the VM memory, fail in every mode: going on would leave the OS reading
+/// values that were never written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AssertionMode {
+    /// The hint fails, which aborts the run.
+    #[default]
+    Strict,
+    /// The violation is logged as a warning and the hint goes on.
+    Lenient,
+    /// The hint panics with the violation and the context of the hint, e.g. to get a backtrace.
+    Debug,
+}
+
+/// Options of a single OS run.
+#[derive(Debug, Clone)]
+pub struct OsRunConfig {
+    /// Layout used to run the OS program.
+    pub layout: LayoutName,
+    /// If set, the memory segments and builtin usage of the run are dumped as JSON to this file
+    /// once the run completes.
+    pub segment_dump_path: Option<PathBuf>,
+    /// If set, the relocated execution trace and memory of the run are written to these files,
+    /// in the binary format expected by the prover.
+    pub prover_trace_paths: Option<ProverTracePaths>,
+    /// If set, the run is aborted with `SnOsError::Cancelled` once the flag is raised. The flag
+    /// is checked before executing each transaction.
+    pub cancellation_flag: Option<Arc<AtomicBool>>,
+    /// If set, inputs with more transactions are rejected with
+    /// `SnOsError::TooManyTransactions` before the run starts.
+    pub max_transactions: Option<usize>,
+    /// If set, panics in hints are caught and turned into hint errors naming the hint, so that
+    /// a bug in a hint fails the run instead of the whole process.
+    pub catch_hint_panics: bool,
+    /// How hints react to a violated invariant.
+    pub assertion_mode: AssertionMode,
+}
+
+impl Default for OsRunConfig {
+    fn default() -> Self {
+        Self {
+            layout: default_layout(),
+            segment_dump_path: None,
+            prover_trace_paths: None,
+            cancellation_flag: None,
+            max_transactions: None,
+            catch_hint_panics: false,
+            assertion_mode: AssertionMode::default(),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Clone, Deserialize, PartialEq)]
 pub struct GasPriceBounds {
     pub min_wei_l1_gas_price: u128,
@@ -65,6 +140,18 @@ const fn default_use_kzg_da() -> bool {
     true
 }
 
+const fn default_block_max_gas() -> u64 {
+    u64::MAX
+}
+
+const fn default_allow_zero_sequencer_address() -> bool {
+    true
+}
+
+fn default_starknet_version() -> String {
+    DEFAULT_STARKNET_VERSION.to_string()
+}
+
 #[derive(Debug, Serialize, Clone, Deserialize, PartialEq)]
 pub struct StarknetGeneralConfig {
     pub starknet_os_config: StarknetOsConfig,
@@ -76,6 +163,40 @@ pub struct StarknetGeneralConfig {
     pub enforce_l1_handler_fee: bool,
     #[serde(default = "default_use_kzg_da")]
     pub use_kzg_da: bool,
+    /// Maximum gas that can be consumed by all the transactions of a block.
+    #[serde(default = "default_block_max_gas")]
+    pub block_max_gas: u64,
+    /// Fail when a transaction accesses a contract that is not part of the OS input, instead of
+    /// treating it as never deployed.
+    #[serde(default)]
+    pub require_known_contracts: bool,
+    /// Version of the Starknet protocol the block was produced with, e.g. "0.13.2".
+    #[serde(default = "default_starknet_version")]
+    pub starknet_version: String,
+    /// Only replay the `__validate__` phase of the transactions and skip the `__execute__` call of
+    /// INVOKE txs, to check that the transactions of a block are valid without executing them.
+    /// DEPLOY_ACCOUNT constructors and L1 handlers are still executed, as the OS cannot skip them.
+    #[serde(default)]
+    pub validate_only: bool,
+    /// Accept blocks whose sequencer is the zero address, as found on some test and local
+    /// networks. When unset, reading a zero sequencer address fails the run.
+    #[serde(default = "default_allow_zero_sequencer_address")]
+    pub allow_zero_sequencer_address: bool,
+    /// Fail the run when a contract is called while one of its calls is still being executed.
+    /// Library calls run in the context of their caller and are not considered re-entries.
+    #[serde(default)]
+    pub forbid_reentrancy: bool,
+    /// Price of one ETH in fri for the block, used to convert fees between ETH and STRK. When
+    /// unset, `default_eth_price_in_fri` is used.
+    #[serde(default)]
+    pub eth_price_in_fri: Option<u128>,
+    /// Skip the rest of a transaction when one of its hints fails an assertion (see
+    /// `hint_assert!`), recording its hash, instead of failing the run. The OS program still goes
+    /// through the code of the failed transaction, so only assertion failures, raised by hints that
+    /// check the replayed calls without writing anything, are skipped. Any other hint error still
+    /// fails the run.
+    #[serde(default)]
+    pub continue_on_tx_failure: bool,
 }
 
 impl Default for StarknetGeneralConfig {
@@ -100,6 +221,14 @@ impl Default for StarknetGeneralConfig {
             sequencer_address: contract_address!(SEQUENCER_ADDR_0_13_2),
             enforce_l1_handler_fee: true,
             use_kzg_da: false,
+            block_max_gas: default_block_max_gas(),
+            require_known_contracts: false,
+            starknet_version: default_starknet_version(),
+            validate_only: false,
+            allow_zero_sequencer_address: default_allow_zero_sequencer_address(),
+            forbid_reentrancy: false,
+            eth_price_in_fri: None,
+            continue_on_tx_failure: false,
         }
     }
 }
@@ -114,6 +243,16 @@ impl StarknetGeneralConfig {
         StarknetGeneralConfig::from_file(PathBuf::from(DEFAULT_CONFIG_PATH))
     }
 
+    /// Returns `fee`, paid in the currency of `fee_type`, in wei and in fri. The conversion uses
+    /// the ETH price of the block. Returns `None` if the converted fee overflows.
+    pub fn fee_in_wei_and_fri(&self, fee: u128, fee_type: FeeType) -> Option<(u128, u128)> {
+        let eth_price_in_fri = self.eth_price_in_fri.unwrap_or(self.default_eth_price_in_fri);
+        match fee_type {
+            FeeType::Eth => Some((fee, mul_div(fee, eth_price_in_fri, WEI_PER_ETH)?)),
+            FeeType::Strk => Some((mul_div(fee, WEI_PER_ETH, eth_price_in_fri)?, fee)),
+        }
+    }
+
     pub fn empty_block_context(&self) -> BlockContext {
         let mut versioned_constants = VersionedConstants::default();
         versioned_constants.invoke_tx_max_n_steps = self.invoke_tx_max_n_steps;
@@ -137,8 +276,8 @@ impl StarknetGeneralConfig {
         let chain_info = ChainInfo {
             chain_id: self.starknet_os_config.chain_id.clone(),
             fee_token_addresses: FeeTokenAddresses {
-                eth_fee_token_address: self.starknet_os_config.fee_token_address,
-                strk_fee_token_address: contract_address!("0x0"),
+                eth_fee_token_address: self.starknet_os_config.deprecated_fee_token_address,
+                strk_fee_token_address: self.starknet_os_config.fee_token_address,
             },
         };
 
@@ -155,18 +294,33 @@ impl TryFrom<BlockContext> for StarknetGeneralConfig {
         Ok(Self {
             starknet_os_config: StarknetOsConfig {
                 chain_id: block_context.chain_info().chain_id.clone(),
-                fee_token_address: block_context.chain_info().fee_token_addresses.get_by_fee_type(&FeeType::Eth),
+                fee_token_address: block_context.chain_info().fee_token_addresses.get_by_fee_type(&FeeType::Strk),
                 deprecated_fee_token_address: block_context
                     .chain_info()
                     .fee_token_addresses
-                    .get_by_fee_type(&FeeType::Strk),
+                    .get_by_fee_type(&FeeType::Eth),
             },
             sequencer_address: block_context.block_info().sequencer_address,
+            eth_price_in_fri: eth_price_in_fri(&block_context.block_info().gas_prices),
             ..Default::default()
         })
     }
 }
 
+/// Derives the price of one ETH in fri from the L1 gas prices of a block, which are given in
+/// both currencies.
+pub fn eth_price_in_fri(gas_prices: &GasPrices) -> Option<u128> {
+    mul_div(gas_prices.strk_l1_gas_price.get(), WEI_PER_ETH, gas_prices.eth_l1_gas_price.get())
+}
+
+/// Computes `a * b / c`, rounded down, without overflowing on the intermediate product.
+fn mul_div(a: u128, b: u128, c: u128) -> Option<u128> {
+    if c == 0 {
+        return None;
+    }
+    (BigUint::from(a) * b / c).to_u128()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -194,8 +348,55 @@ mod tests {
         assert_eq!(conf.starknet_os_config.chain_id, ctx.chain_info().chain_id);
         assert_eq!(
             conf.starknet_os_config.fee_token_address,
+            ctx.chain_info().fee_token_addresses.get_by_fee_type(&FeeType::Strk)
+        );
+        assert_eq!(
+            conf.starknet_os_config.deprecated_fee_token_address,
             ctx.chain_info().fee_token_addresses.get_by_fee_type(&FeeType::Eth)
         );
         assert_eq!(conf.sequencer_address, ctx.block_info().sequencer_address);
     }
+
+    #[test]
+    fn fee_in_wei_and_fri() {
+        // 1 ETH = 2500 STRK
+        let conf = StarknetGeneralConfig { eth_price_in_fri: Some(2500 * WEI_PER_ETH), ..Default::default() };
+
+        assert_eq!(conf.fee_in_wei_and_fri(1_000, FeeType::Eth), Some((1_000, 2_500_000)));
+        assert_eq!(conf.fee_in_wei_and_fri(2_500_000, FeeType::Strk), Some((1_000, 2_500_000)));
+        // Rounded down
+        assert_eq!(conf.fee_in_wei_and_fri(2_499, FeeType::Strk), Some((0, 2_499)));
+        assert_eq!(conf.fee_in_wei_and_fri(u128::MAX, FeeType::Eth), None);
+    }
+
+    #[test]
+    fn eth_price_in_fri_from_block_context() {
+        let conf = StarknetGeneralConfig::default();
+        let block_context = conf.empty_block_context();
+        let mut block_info = block_context.block_info().clone();
+        block_info.gas_prices.eth_l1_gas_price = 10u128.try_into().unwrap();
+        block_info.gas_prices.strk_l1_gas_price = 25_000u128.try_into().unwrap();
+        let block_context = BlockContext::new(
+            block_info,
+            block_context.chain_info().clone(),
+            block_context.versioned_constants().clone(),
+            BouncerConfig::max(),
+        );
+
+        let conf = StarknetGeneralConfig::try_from(block_context).unwrap();
+        assert_eq!(conf.eth_price_in_fri, Some(2500 * WEI_PER_ETH));
+    }
+
+    #[test]
+    fn fee_token_address_by_tx_version() {
+        let os_config = StarknetOsConfig {
+            chain_id: ChainId::Sepolia,
+            fee_token_address: contract_address!("0x1234"),
+            deprecated_fee_token_address: contract_address!("0x5678"),
+        };
+
+        assert_eq!(os_config.fee_token_address_for_version(Felt252::ONE), contract_address!("0x5678"));
+        assert_eq!(os_config.fee_token_address_for_version(Felt252::TWO), contract_address!("0x5678"));
+        assert_eq!(os_config.fee_token_address_for_version(Felt252::THREE), contract_address!("0x1234"));
+    }
 }
@@ -1,3 +1,6 @@
+use std::path::PathBuf;
+
+use cairo_vm::types::errors::program_errors::ProgramError;
 use cairo_vm::vm::errors::cairo_run_errors::CairoRunError;
 use cairo_vm::Felt252;
 
@@ -21,6 +24,45 @@ pub enum SnOsError {
     IO(#[from] std::io::Error),
     #[error(transparent)]
     SerdeJson(#[from] serde_json::Error),
+    #[error(transparent)]
+    MessagePackEncode(#[from] rmp_serde::encode::Error),
+    #[error(transparent)]
+    MessagePackDecode(#[from] rmp_serde::decode::Error),
     #[error("SnOs Deprecated Syscall Error: {0}")]
     InvalidDeprecatedSyscallSelector(Felt252),
+    #[error("The OS run was cancelled")]
+    Cancelled,
+    #[error("The input has {n_transactions} transactions, more than the maximum of {max_transactions}")]
+    TooManyTransactions { n_transactions: usize, max_transactions: usize },
+}
+
+/// Errors returned when running the OS from files on disk.
+#[derive(thiserror::Error, Debug)]
+pub enum OsRunError {
+    #[error("Failed to load the compiled OS program from {path}: {error}")]
+    CompiledProgram { path: PathBuf, error: CompiledProgramError },
+    #[error("Failed to load the OS input from {path}: {error}")]
+    Input { path: PathBuf, error: SnOsError },
+    #[error("The OS run was cancelled")]
+    Cancelled,
+    #[error(transparent)]
+    Run(SnOsError),
+}
+
+impl From<SnOsError> for OsRunError {
+    fn from(error: SnOsError) -> Self {
+        match error {
+            SnOsError::Cancelled => Self::Cancelled,
+            error => Self::Run(error),
+        }
+    }
+}
+
+/// Reasons for which the compiled OS program could not be loaded.
+#[derive(thiserror::Error, Debug)]
+pub enum CompiledProgramError {
+    #[error("the file could not be read: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("the file is not a valid compiled Cairo program: {0}")]
+    Parse(#[from] ProgramError),
 }
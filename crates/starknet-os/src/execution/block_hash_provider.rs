@@ -0,0 +1,28 @@
+use std::collections::HashMap;
+
+use cairo_vm::Felt252;
+
+/// Source of the hashes of past blocks, used to serve `get_block_hash` requests without storing
+/// the whole history of the chain.
+pub trait BlockHashProvider {
+    /// Returns the hash of the given block, or None if the provider does not know it.
+    fn get_block_hash(&self, block_number: u64) -> Option<Felt252>;
+}
+
+/// Block hashes known in advance, typically loaded from the OS input.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryBlockHashProvider {
+    block_hashes: HashMap<u64, Felt252>,
+}
+
+impl InMemoryBlockHashProvider {
+    pub fn new(block_hashes: HashMap<u64, Felt252>) -> Self {
+        Self { block_hashes }
+    }
+}
+
+impl BlockHashProvider for InMemoryBlockHashProvider {
+    fn get_block_hash(&self, block_number: u64) -> Option<Felt252> {
+        self.block_hashes.get(&block_number).copied()
+    }
+}
@@ -5,16 +5,18 @@ use cairo_vm::types::relocatable::{MaybeRelocatable, Relocatable};
 use cairo_vm::vm::errors::hint_errors::HintError;
 use cairo_vm::vm::vm_core::VirtualMachine;
 use cairo_vm::Felt252;
+use starknet_api::deprecated_contract_class::EntryPointType;
 use tokio::sync::RwLock;
 
 use super::helper::ExecutionHelperWrapper;
 use crate::cairo_types::new_syscalls::{BlockInfo as BlockInfoStruct, ExecutionInfo};
 use crate::cairo_types::syscalls::{
-    CallContract, CallContractResponse, Deploy, DeployResponse, GetBlockNumber, GetBlockNumberResponse,
+    CallContract, CallContractResponse, Deploy, DeployRequest, DeployResponse, GetBlockNumber, GetBlockNumberResponse,
     GetBlockTimestamp, GetBlockTimestampResponse, GetContractAddress, GetContractAddressResponse, GetSequencerAddress,
     GetSequencerAddressResponse, GetTxInfo, GetTxInfoResponse, GetTxSignature, GetTxSignatureResponse, LibraryCall,
-    TxInfo,
 };
+use crate::cairo_types::traits::CairoType;
+use crate::hints::layout::TxInfoLayout;
 use crate::starknet::starknet_storage::PerContractStorage;
 
 /// DeprecatedSyscallHandler implementation for execution of system calls in the StarkNet OS
@@ -117,6 +119,10 @@ where
             .next()
             .ok_or(HintError::SyscallError("Could not find matching deployed contract".to_string().into_boxed_str()))?;
 
+        let class_hash_offset = Deploy::request_offset() + DeployRequest::class_hash_offset();
+        let class_hash = vm.get_integer((syscall_ptr + class_hash_offset)?)?.into_owned();
+        execution_helper.record_deployed_contract(contract_address, class_hash);
+
         let contract_address_offset = Deploy::response_offset() + DeployResponse::contract_address_offset();
         let constructor_retdata_size_offset =
             Deploy::response_offset() + DeployResponse::constructor_retdata_size_offset();
@@ -174,8 +180,12 @@ where
     pub async fn get_caller_address(&self, syscall_ptr: Relocatable, vm: &mut VirtualMachine) {
         let sys_hand = self.deprecated_syscall_handler.read().await;
         let exec_helper = sys_hand.exec_wrapper.execution_helper.read().await;
-        let caller_address =
-            exec_helper.call_info.as_ref().expect("A call should have some call info").call.caller_address.0.key();
+        // Top-level calls are made by the OS, which has no address
+        let caller_address = if exec_helper.is_top_level_call {
+            Felt252::ZERO
+        } else {
+            *exec_helper.call_info.as_ref().expect("A call should have some call info").call.caller_address.0.key()
+        };
 
         // TODO: create proper struct for this (similar to GetCallerAddress and friends)
         // TODO: abstract this similar to pythonic _write_syscall_response()
@@ -192,6 +202,9 @@ where
         let syscall_handler = self.deprecated_syscall_handler.read().await;
         let exec_helper = syscall_handler.exec_wrapper.execution_helper.read().await;
 
+        // The storage address is the address of the contract whose code is running, except for
+        // library calls, where it is the one of the delegating contract: this is the address the
+        // library code must see.
         let contract_address =
             exec_helper.call_info.as_ref().map(|info| info.call.storage_address).ok_or(HintError::SyscallError(
                 "Missing storage address from call info".to_string().into_boxed_str(),
@@ -241,14 +254,13 @@ where
             .tx_info_ptr
             .ok_or(HintError::SyscallError("Tx info pointer not set".to_string().into_boxed_str()))?;
 
-        let signature_len = vm.get_integer((tx_info_ptr + TxInfo::signature_len_offset())?)?.into_owned();
-        let signature = vm.get_relocatable((tx_info_ptr + TxInfo::signature_offset())?)?;
+        let tx_info = TxInfoLayout::from_memory(vm, tx_info_ptr)?;
 
         let signature_len_offset = GetTxSignature::response_offset() + GetTxSignatureResponse::signature_len_offset();
         let signature_offset = GetTxSignature::response_offset() + GetTxSignatureResponse::signature_offset();
 
-        vm.insert_value((syscall_ptr + signature_len_offset)?, signature_len)?;
-        vm.insert_value((syscall_ptr + signature_offset)?, signature)?;
+        vm.insert_value((syscall_ptr + signature_len_offset)?, tx_info.signature_len)?;
+        vm.insert_value((syscall_ptr + signature_offset)?, tx_info.signature)?;
 
         Ok(())
     }
@@ -260,8 +272,33 @@ where
         syscall_ptr: Relocatable,
         vm: &mut VirtualMachine,
     ) -> Result<(), HintError> {
+        self.check_next_inner_call_type(EntryPointType::L1Handler).await?;
         self.call_contract_and_write_response(syscall_ptr, LibraryCall::response_offset(), vm).await
     }
+
+    /// Checks that the inner call whose result is served next targets an entry point of the
+    /// expected type.
+    async fn check_next_inner_call_type(&self, expected_type: EntryPointType) -> Result<(), HintError> {
+        let syscall_handler = self.deprecated_syscall_handler.read().await;
+        let execution_helper = syscall_handler.exec_wrapper.execution_helper.read().await;
+
+        let call_info = execution_helper
+            .call_info
+            .as_ref()
+            .ok_or(HintError::SyscallError("No call is being executed".to_string().into_boxed_str()))?;
+        let next_call_index = call_info.inner_calls.len().saturating_sub(execution_helper.result_iter.len());
+
+        match call_info.inner_calls.get(next_call_index) {
+            Some(inner_call) if inner_call.call.entry_point_type != expected_type => Err(HintError::SyscallError(
+                format!(
+                    "Expected a call to a {:?} entry point, got {:?}",
+                    expected_type, inner_call.call.entry_point_type
+                )
+                .into_boxed_str(),
+            )),
+            _ => Ok(()),
+        }
+    }
     pub fn replace_class(&self) {
         // Nothing to do.
     }
@@ -302,18 +339,25 @@ mod test {
     use blockifier::blockifier::block::{BlockInfo, GasPrices};
     use blockifier::bouncer::BouncerConfig;
     use blockifier::context::{BlockContext, ChainInfo, FeeTokenAddresses};
-    use blockifier::execution::call_info::Retdata;
+    use blockifier::execution::call_info::{CallExecution, CallInfo, Retdata};
+    use blockifier::execution::entry_point::{CallEntryPoint, CallType};
     use blockifier::execution::entry_point_execution::CallResult;
+    use blockifier::transaction::objects::TransactionExecutionInfo;
     use blockifier::versioned_constants::VersionedConstants;
     use cairo_vm::types::exec_scope::ExecutionScopes;
     use cairo_vm::types::relocatable::{MaybeRelocatable, Relocatable};
+    use cairo_vm::vm::errors::hint_errors::HintError;
     use cairo_vm::vm::vm_core::VirtualMachine;
     use cairo_vm::Felt252;
     use rstest::{fixture, rstest};
     use starknet_api::block::{BlockNumber, BlockTimestamp};
-    use starknet_api::core::{ChainId, ContractAddress, PatriciaKey};
+    use starknet_api::core::{ChainId, ClassHash, ContractAddress, PatriciaKey};
+    use starknet_api::deprecated_contract_class::EntryPointType;
     use starknet_api::{contract_address, felt, patricia_key};
 
+    use crate::cairo_types::syscalls::{
+        CallContractResponse, GetContractAddress, GetContractAddressResponse, LibraryCall,
+    };
     use crate::config::STORED_BLOCK_HASH_BUFFER;
     use crate::crypto::pedersen::PedersenHash;
     use crate::execution::deprecated_syscall_handler::DeprecatedOsSyscallHandlerWrapper;
@@ -418,4 +462,168 @@ mod test {
             ]
         );
     }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_get_contract_address_in_library_call(
+        block_context: BlockContext,
+        old_block_number_and_hash: (Felt252, Felt252),
+    ) {
+        let mut vm = VirtualMachine::new(false);
+        let syscall_ptr = vm.add_memory_segment();
+
+        let caller_address = contract_address!("0x1234");
+        let library_class_hash = ClassHash(felt!("0x5678"));
+        let library_call = CallInfo {
+            call: CallEntryPoint {
+                class_hash: Some(library_class_hash),
+                code_address: None,
+                storage_address: caller_address,
+                caller_address,
+                call_type: CallType::Delegate,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let mut exec_helper = ExecutionHelperWrapper::<OsSingleStarknetStorage<DictStorage, PedersenHash>>::new(
+            ContractStorageMap::default(),
+            Default::default(),
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        );
+        exec_helper.execution_helper.write().await.call_iter = vec![library_call].into_iter();
+        exec_helper.enter_call(None).await.unwrap();
+        assert!(exec_helper.is_library_call().await);
+
+        let syscall_handler = DeprecatedOsSyscallHandlerWrapper::new(
+            exec_helper.clone(),
+            syscall_ptr,
+            block_context.block_info().clone(),
+        );
+        syscall_handler.get_contract_address(syscall_ptr, &mut vm).await.unwrap();
+
+        let response_offset =
+            GetContractAddress::response_offset() + GetContractAddressResponse::contract_address_offset();
+        let contract_address = vm.get_integer((syscall_ptr + response_offset).unwrap()).unwrap().into_owned();
+        assert_eq!(contract_address, *caller_address.0.key());
+
+        exec_helper.exit_call().await;
+        assert!(!exec_helper.is_library_call().await);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_get_caller_address_of_top_level_call(
+        block_context: BlockContext,
+        old_block_number_and_hash: (Felt252, Felt252),
+    ) {
+        let mut vm = VirtualMachine::new(false);
+        let syscall_ptr = vm.add_memory_segment();
+
+        let account_address = contract_address!("0x100");
+        let contract_address = contract_address!("0x200");
+        let call = |storage_address, caller_address, inner_calls| CallInfo {
+            call: CallEntryPoint { storage_address, caller_address, ..Default::default() },
+            inner_calls,
+            ..Default::default()
+        };
+        // The call info of `__validate__` records a caller, the OS must serve zero anyway
+        let validate_call = call(account_address, account_address, vec![]);
+        let inner_call = call(contract_address, account_address, vec![call(account_address, contract_address, vec![])]);
+        let execute_call = call(account_address, ContractAddress::default(), vec![inner_call]);
+        let tx_execution_info = TransactionExecutionInfo {
+            validate_call_info: Some(validate_call),
+            execute_call_info: Some(execute_call),
+            ..Default::default()
+        };
+
+        let mut exec_helper = ExecutionHelperWrapper::<OsSingleStarknetStorage<DictStorage, PedersenHash>>::new(
+            ContractStorageMap::default(),
+            vec![tx_execution_info],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        );
+        let syscall_handler = DeprecatedOsSyscallHandlerWrapper::new(
+            exec_helper.clone(),
+            syscall_ptr,
+            block_context.block_info().clone(),
+        );
+
+        exec_helper.start_tx(None).await;
+        let mut caller_addresses = vec![];
+        for _ in 0..4 {
+            // Each call gets its own syscall segment, memory cells can only be written once
+            let call_syscall_ptr = vm.add_memory_segment();
+            exec_helper.enter_call(None).await.unwrap();
+            syscall_handler.get_caller_address(call_syscall_ptr, &mut vm).await;
+            caller_addresses.push(vm.get_integer((call_syscall_ptr + 1usize).unwrap()).unwrap().into_owned());
+            // The results of the inner calls would be consumed by the `call_contract` syscalls
+            exec_helper.execution_helper.write().await.result_iter = vec![].into_iter();
+            exec_helper.exit_call().await;
+        }
+        exec_helper.end_tx().await;
+
+        assert_eq!(caller_addresses, vec![Felt252::ZERO, Felt252::ZERO, felt!("0x100"), felt!("0x200")]);
+    }
+
+    #[rstest]
+    #[case::l1_handler(EntryPointType::L1Handler, true)]
+    #[case::external(EntryPointType::External, false)]
+    #[tokio::test]
+    async fn test_library_call_l1_handler(
+        block_context: BlockContext,
+        old_block_number_and_hash: (Felt252, Felt252),
+        #[case] entry_point_type: EntryPointType,
+        #[case] expected_success: bool,
+    ) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        let syscall_ptr = vm.add_memory_segment();
+
+        let exec_helper = ExecutionHelperWrapper::<OsSingleStarknetStorage<DictStorage, PedersenHash>>::new(
+            ContractStorageMap::default(),
+            Default::default(),
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        );
+
+        // the current call performs a single library call
+        let retdata = Retdata(vec![felt!(1234_u16), felt!(42_u16)]);
+        let inner_call = CallInfo {
+            call: CallEntryPoint { entry_point_type, ..Default::default() },
+            execution: CallExecution { retdata: retdata.clone(), ..Default::default() },
+            ..Default::default()
+        };
+        {
+            let mut execution_helper = exec_helper.execution_helper.write().await;
+            execution_helper.result_iter =
+                vec![CallResult { failed: false, retdata: retdata.clone(), gas_consumed: 0 }].into_iter();
+            execution_helper.call_info = Some(CallInfo { inner_calls: vec![inner_call], ..Default::default() });
+        }
+
+        let syscall_handler =
+            DeprecatedOsSyscallHandlerWrapper::new(exec_helper, syscall_ptr, block_context.block_info().clone());
+
+        let result = syscall_handler.library_call_l1_handler(syscall_ptr, &mut vm).await;
+        if !expected_success {
+            assert!(matches!(result, Err(HintError::SyscallError(_))));
+            return;
+        }
+        result.unwrap();
+
+        let response_ptr = (syscall_ptr + LibraryCall::response_offset()).unwrap();
+        let retdata_size =
+            vm.get_integer((response_ptr + CallContractResponse::retdata_size_offset()).unwrap()).unwrap();
+        let retdata_ptr = vm.get_relocatable((response_ptr + CallContractResponse::retdata_offset()).unwrap()).unwrap();
+        assert_eq!(retdata_size.into_owned(), Felt252::TWO);
+        assert_eq!(
+            vm.get_integer_range(retdata_ptr, 2).unwrap().into_iter().map(Cow::into_owned).collect::<Vec<_>>(),
+            retdata.0
+        );
+    }
 }
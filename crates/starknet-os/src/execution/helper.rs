@@ -1,28 +1,93 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::ops::Deref;
 use std::rc::Rc;
 use std::vec::IntoIter;
 
 use blockifier::context::BlockContext;
 use blockifier::execution::call_info::CallInfo;
+use blockifier::execution::entry_point::CallType;
 use blockifier::execution::entry_point_execution::CallResult;
+use blockifier::transaction::constants::{
+    VALIDATE_DECLARE_ENTRY_POINT_NAME, VALIDATE_DEPLOY_ENTRY_POINT_NAME, VALIDATE_ENTRY_POINT_NAME,
+};
 use blockifier::transaction::objects::TransactionExecutionInfo;
 use cairo_vm::types::relocatable::Relocatable;
 use cairo_vm::vm::errors::hint_errors::HintError;
 use cairo_vm::Felt252;
 use starknet_api::deprecated_contract_class::EntryPointType;
+use starknet_os_types::casm_contract_class::{CairoLangCasmClass, GenericCasmContractClass};
 use tokio::sync::RwLock;
 
+use super::block_hash_provider::{BlockHashProvider, InMemoryBlockHashProvider};
 use super::secp_handler::SecpSyscallProcessor;
+use super::syscall_trace::{SyscallTrace, SyscallTraceEntry};
 use crate::config::STORED_BLOCK_HASH_BUFFER;
 use crate::io::input::StarknetOsInput;
+use crate::starknet::core::os::block_hash::Event;
 use crate::starknet::core::os::kzg_manager::KzgManager;
 use crate::starknet::starknet_storage::{CommitmentInfo, CommitmentInfoError, PerContractStorage};
 use crate::storage::storage::StorageError;
+use crate::utils::get_selector_from_name;
 
 // TODO: make the execution helper generic over the storage and hash function types.
 pub type ContractStorageMap<PCS> = HashMap<Felt252, PCS>;
 
+/// Compiled classes parsed during an OS run, keyed by compiled class hash, so that a class
+/// used by several txs is only parsed once.
+#[derive(Debug, Default)]
+pub struct ClassCache {
+    classes: HashMap<Felt252, Rc<CairoLangCasmClass>>,
+    n_parsed: usize,
+}
+
+impl ClassCache {
+    /// Returns the parsed version of `class`, parsing it if it is not cached yet.
+    pub fn get_or_parse(
+        &mut self,
+        compiled_class_hash: Felt252,
+        class: &GenericCasmContractClass,
+    ) -> Result<Rc<CairoLangCasmClass>, HintError> {
+        if let Some(parsed_class) = self.classes.get(&compiled_class_hash) {
+            return Ok(parsed_class.clone());
+        }
+
+        let parsed_class = Rc::new(
+            class
+                .get_cairo_lang_contract_class()
+                .map_err(|e| HintError::CustomHint(e.to_string().into_boxed_str()))?
+                .clone(),
+        );
+        self.n_parsed += 1;
+        self.classes.insert(compiled_class_hash, parsed_class.clone());
+
+        Ok(parsed_class)
+    }
+
+    /// Number of classes parsed so far.
+    pub fn n_parsed(&self) -> usize {
+        self.n_parsed
+    }
+}
+
+/// Values served to the storage reads of each call, supplied separately from the execution infos,
+/// e.g. by a witness generator. A call is identified by the index of its tx in the block and its
+/// index among the calls of the tx, in the order the OS replays them (depth-first).
+#[derive(Debug, Clone, Default)]
+pub struct StorageReadWitness {
+    reads: HashMap<(usize, usize), Vec<Felt252>>,
+}
+
+impl StorageReadWitness {
+    pub fn new(reads: HashMap<(usize, usize), Vec<Felt252>>) -> Self {
+        Self { reads }
+    }
+
+    /// Returns the values read by a call, in order. Calls missing from the witness read nothing.
+    pub fn reads(&self, tx_index: usize, call_index: usize) -> &[Felt252] {
+        self.reads.get(&(tx_index, call_index)).map(Vec::as_slice).unwrap_or_default()
+    }
+}
+
 /// Maintains the info for executing txns in the OS
 pub struct ExecutionHelper<PCS>
 where
@@ -49,12 +114,26 @@ where
     pub call_iter: IntoIter<CallInfo>,
     // CallInfo for the call currently being executed
     pub call_info: Option<CallInfo>,
+    // Whether the call currently being executed is a library (delegate) call
+    pub is_library_call: bool,
+    // Whether the call currently being executed is a top-level call of the tx, i.e. called by the OS
+    pub is_top_level_call: bool,
+    // Gas consumed by the syscalls of the call currently being executed, base cost included
+    pub call_syscall_gas_consumed: u64,
+    // Number of calls of `call_iter` that are still part of the call tree of the last top-level call
+    pub pending_inner_calls: usize,
     // Iter to the results of the current call's internal calls
     pub result_iter: IntoIter<CallResult>,
     // Iter over contract addresses that were deployed during that call
     pub deployed_contracts_iter: IntoIter<Felt252>,
+    // Class hashes of the contracts deployed so far in the block, by contract address
+    pub deployed_class_hashes: HashMap<Felt252, Felt252>,
     // Iter to the read_values array consumed when tx code is executed
     pub execute_code_read_iter: IntoIter<Felt252>,
+    // Source of the read values of each call, instead of the execution infos, if set
+    pub storage_read_witness: Option<StorageReadWitness>,
+    // Index of the next call of the current tx, in replay order
+    pub tx_call_index: usize,
     // Per-contract storage
     pub storage_by_address: ContractStorageMap<PCS>,
 
@@ -64,6 +143,35 @@ where
 
     // Sha256 segments
     pub sha256_segment: Option<Relocatable>,
+
+    // Events emitted by each transaction started so far, in emission order, by index of the tx in
+    // the block
+    pub event_log: BTreeMap<usize, Vec<Event>>,
+    // Gas consumed by the transactions started so far
+    pub block_gas_consumed: u64,
+    // Segments allocated in the segment arenas finalized so far
+    pub segment_arena_n_segments: usize,
+    // Index in the block of the current (or last) tx, None until the first tx is started
+    pub tx_index: Option<usize>,
+
+    // Whether to skip the rest of a tx whose replay failed instead of aborting the run
+    pub continue_on_tx_failure: bool,
+    // Whether the replay of the current tx failed, its remaining calls being skipped
+    pub current_tx_failed: bool,
+    // Hashes of the txs whose replay failed, when `continue_on_tx_failure` is set
+    pub failed_tx_hashes: Vec<Felt252>,
+    // Whether only the `__validate__` calls of the txs are replayed, `__execute__` being skipped
+    pub validate_only: bool,
+    // Whether re-entering a contract fails the run
+    pub forbid_reentrancy: bool,
+    // (contract address, key) pairs served to `storage_read` syscalls
+    pub read_keys: HashSet<(Felt252, Felt252)>,
+    // Compiled classes parsed so far, shared by all the txs of the run
+    pub class_cache: ClassCache,
+    // Hashes of past blocks served to `get_block_hash` requests
+    pub block_hash_provider: Rc<dyn BlockHashProvider>,
+    // Syscalls served so far, None unless recording was requested
+    pub syscall_trace: Option<SyscallTrace>,
 }
 /// ExecutionHelper is wrapped in Rc<RefCell<_>> in order
 /// to clone the refrence when entering and exiting vm scopes
@@ -96,16 +204,127 @@ where
             .field("old_block_number_and_hash", &self.old_block_number_and_hash)
             .field("call_iter", &self.call_iter)
             .field("call_info", &self.call_info)
+            .field("is_library_call", &self.is_library_call)
+            .field("is_top_level_call", &self.is_top_level_call)
+            .field("call_syscall_gas_consumed", &self.call_syscall_gas_consumed)
+            .field("pending_inner_calls", &self.pending_inner_calls)
             .field("result_iter", &self.result_iter)
             .field("deployed_contracts_iter", &self.deployed_contracts_iter)
+            .field("deployed_class_hashes", &self.deployed_class_hashes)
             .field("execute_code_read_iter", &self.execute_code_read_iter)
+            .field("storage_read_witness", &self.storage_read_witness)
+            .field("tx_call_index", &self.tx_call_index)
             .field("storage_by_address", &self.storage_by_address)
             .field("secp256k1_syscall_processor", &"SecpHintProcessor<ark_secp256k1::Config>")
             .field("secp256r1_syscall_processor", &"SecpHintProcessor<ark_secp256r1::Config>")
+            .field("event_log", &self.event_log)
+            .field("block_gas_consumed", &self.block_gas_consumed)
+            .field("segment_arena_n_segments", &self.segment_arena_n_segments)
+            .field("continue_on_tx_failure", &self.continue_on_tx_failure)
+            .field("current_tx_failed", &self.current_tx_failed)
+            .field("failed_tx_hashes", &self.failed_tx_hashes)
+            .field("validate_only", &self.validate_only)
+            .field("forbid_reentrancy", &self.forbid_reentrancy)
+            .field("read_keys", &self.read_keys)
+            .field("class_cache", &self.class_cache)
+            .field("block_hash_provider", &"dyn BlockHashProvider")
+            .field("syscall_trace", &self.syscall_trace)
             .finish()
     }
 }
 
+impl<PCS> ExecutionHelper<PCS>
+where
+    PCS: PerContractStorage,
+{
+    /// Returns the calldata of the call currently being executed, or None outside of a call.
+    pub fn current_calldata(&self) -> Option<Vec<Felt252>> {
+        self.call_info.as_ref().map(|call_info| call_info.call.calldata.0.iter().copied().collect())
+    }
+
+    /// Whether the call currently being executed is the validation of an account, i.e. one of the
+    /// `__validate__` entry points.
+    pub fn is_validate_call(&self) -> bool {
+        self.call_info.as_ref().is_some_and(|call_info| {
+            let selector = call_info.call.entry_point_selector.0;
+            [VALIDATE_ENTRY_POINT_NAME, VALIDATE_DECLARE_ENTRY_POINT_NAME, VALIDATE_DEPLOY_ENTRY_POINT_NAME]
+                .into_iter()
+                .any(|name| get_selector_from_name(name) == selector)
+        })
+    }
+
+    /// Returns the address of the first contract re-entered by the call currently being executed,
+    /// i.e. called again by one of the inner calls it triggers, if any.
+    pub fn find_reentered_contract(&self) -> Option<Felt252> {
+        fn visit(call_info: &CallInfo, call_stack: &mut Vec<Felt252>) -> Option<Felt252> {
+            // Library calls execute in the context of the calling contract
+            let is_contract_call = call_info.call.call_type != CallType::Delegate;
+            if is_contract_call {
+                let contract_address = Felt252::from(call_info.call.storage_address);
+                if call_stack.contains(&contract_address) {
+                    return Some(contract_address);
+                }
+                call_stack.push(contract_address);
+            }
+
+            let reentered_contract = call_info.inner_calls.iter().find_map(|inner_call| visit(inner_call, call_stack));
+
+            if is_contract_call {
+                call_stack.pop();
+            }
+            reentered_contract
+        }
+
+        self.call_info.as_ref().and_then(|call_info| visit(call_info, &mut vec![]))
+    }
+
+    /// Records the class hash of a contract deployed by the block, so that it is served before
+    /// the state of the block is committed.
+    pub fn record_deployed_contract(&mut self, contract_address: Felt252, class_hash: Felt252) {
+        self.deployed_class_hashes.insert(contract_address, class_hash);
+    }
+
+    /// Returns the class hash of the contract at `contract_address`: the one it was deployed with
+    /// if the block deployed it, otherwise the one from the OS input.
+    pub fn get_class_hash_at(&self, contract_address: Felt252) -> Option<Felt252> {
+        self.deployed_class_hashes.get(&contract_address).copied().or_else(|| {
+            self.os_input
+                .as_ref()
+                .and_then(|os_input| os_input.contract_address_to_class_hash.get(&contract_address).copied())
+        })
+    }
+
+    /// Marks the current tx as failed, recording its hash the first time.
+    fn record_tx_failure(&mut self, tx_hash: Felt252, error: &HintError) {
+        if !self.current_tx_failed {
+            log::warn!("Replay of tx {} failed, skipping the rest of it: {error}", tx_hash.to_hex_string());
+            self.failed_tx_hashes.push(tx_hash);
+            self.current_tx_failed = true;
+            // The skipped calls do not emit their events
+            if let Some(tx_index) = self.tx_index {
+                self.event_log.insert(tx_index, vec![]);
+            }
+        }
+    }
+
+    /// Returns the compiled class with the given hash from the OS input, parsed. Each class is
+    /// parsed at most once per run.
+    pub fn get_compiled_class(&mut self, compiled_class_hash: Felt252) -> Result<Rc<CairoLangCasmClass>, HintError> {
+        let class = self
+            .os_input
+            .as_ref()
+            .and_then(|os_input| os_input.compiled_classes.get(&compiled_class_hash))
+            .ok_or_else(|| {
+                HintError::CustomHint(
+                    format!("No compiled class for compiled class hash {}", compiled_class_hash.to_hex_string())
+                        .into_boxed_str(),
+                )
+            })?;
+
+        self.class_cache.get_or_parse(compiled_class_hash, class)
+    }
+}
+
 impl<PCS> ExecutionHelperWrapper<PCS>
 where
     PCS: PerContractStorage + 'static,
@@ -118,13 +337,22 @@ where
         old_block_number_and_hash: (Felt252, Felt252),
     ) -> Self {
         // Block number and block hash (current_block_number - buffer) block buffer=STORED_BLOCK_HASH_BUFFER
-        // Hash that is going to be written by this OS run
+        // Hash that is going to be written by this OS run.
+        // Blocks below the buffer have no such block: there is no previous context and the
+        // `get_block_hash` path serves 0 for them.
         let prev_block_context = block_context
             .block_info()
             .block_number
             .0
             .checked_sub(STORED_BLOCK_HASH_BUFFER)
             .map(|_| block_context.clone());
+        let validate_only = os_input.as_ref().is_some_and(|os_input| os_input.general_config.validate_only);
+        let forbid_reentrancy = os_input.as_ref().is_some_and(|os_input| os_input.general_config.forbid_reentrancy);
+        let continue_on_tx_failure =
+            os_input.as_ref().is_some_and(|os_input| os_input.general_config.continue_on_tx_failure);
+        let block_hash_provider = InMemoryBlockHashProvider::new(
+            os_input.as_ref().map(|os_input| os_input.block_hashes.clone()).unwrap_or_default(),
+        );
 
         Self {
             execution_helper: Rc::new(RwLock::new(ExecutionHelper {
@@ -138,13 +366,33 @@ where
                 call_execution_info_ptr: None,
                 old_block_number_and_hash: Some(old_block_number_and_hash),
                 call_info: None,
+                is_library_call: false,
+                is_top_level_call: false,
+                call_syscall_gas_consumed: 0,
+                pending_inner_calls: 0,
                 result_iter: vec![].into_iter(),
                 deployed_contracts_iter: vec![].into_iter(),
+                deployed_class_hashes: HashMap::new(),
                 execute_code_read_iter: vec![].into_iter(),
+                storage_read_witness: None,
+                tx_call_index: 0,
                 storage_by_address: contract_storage_map,
                 secp256k1_syscall_processor: Default::default(),
                 secp256r1_syscall_processor: Default::default(),
                 sha256_segment: None,
+                event_log: BTreeMap::new(),
+                block_gas_consumed: 0,
+                segment_arena_n_segments: 0,
+                tx_index: None,
+                continue_on_tx_failure,
+                current_tx_failed: false,
+                failed_tx_hashes: vec![],
+                validate_only,
+                forbid_reentrancy,
+                read_keys: HashSet::new(),
+                class_cache: Default::default(),
+                block_hash_provider: Rc::new(block_hash_provider),
+                syscall_trace: None,
             })),
         }
     }
@@ -162,7 +410,22 @@ where
         eh_ref.tx_info_ptr = tx_info_ptr;
         assert!(eh_ref.tx_execution_info.is_none());
         eh_ref.tx_execution_info = eh_ref.tx_execution_info_iter.next();
-        eh_ref.call_iter = eh_ref.tx_execution_info.as_ref().unwrap().gen_call_iterator();
+        let tx_execution_info = eh_ref.tx_execution_info.as_ref().unwrap();
+        let call_iter = if eh_ref.validate_only {
+            tx_execution_info.gen_validate_call_iterator()
+        } else {
+            tx_execution_info.gen_call_iterator()
+        };
+        let tx_events = tx_execution_info.get_events();
+        let tx_gas_consumed = tx_execution_info.gas_consumed();
+        eh_ref.call_iter = call_iter;
+        eh_ref.current_tx_failed = false;
+        eh_ref.tx_call_index = 0;
+        eh_ref.pending_inner_calls = 0;
+        let tx_index = eh_ref.tx_index.map_or(0, |tx_index| tx_index + 1);
+        eh_ref.event_log.insert(tx_index, tx_events);
+        eh_ref.block_gas_consumed += tx_gas_consumed;
+        eh_ref.tx_index = Some(tx_index);
     }
     pub async fn end_tx(&self) {
         let mut eh_ref = self.execution_helper.write().await;
@@ -171,19 +434,92 @@ where
         assert!(eh_ref.tx_execution_info.is_some());
         eh_ref.tx_execution_info = None;
     }
+    /// Ends the current tx, checking that all its calls were replayed.
+    ///
+    /// If some calls were not replayed, the replay of the tx failed. This is an error unless
+    /// `continue_on_tx_failure` is set, in which case the tx is recorded as failed, see `fail_tx`.
+    pub async fn end_tx_or_record_failure(&self, tx_hash: Felt252) -> Result<(), HintError> {
+        {
+            let mut eh_ref = self.execution_helper.write().await;
+            if eh_ref.call_iter.clone().peekable().peek().is_some() && !eh_ref.current_tx_failed {
+                let error = HintError::AssertionFailed(
+                    format!("Not all calls of tx {} were replayed", tx_hash.to_hex_string()).into_boxed_str(),
+                );
+                if !eh_ref.continue_on_tx_failure {
+                    return Err(error);
+                }
+                eh_ref.record_tx_failure(tx_hash, &error);
+            }
+            eh_ref.call_iter = vec![].into_iter();
+        }
+
+        self.end_tx().await;
+        Ok(())
+    }
+    /// Records that a hint of the current tx failed, if `continue_on_tx_failure` is set, and skips
+    /// the calls of the tx that were not replayed yet. The tx ends as usual with `end_tx`.
+    ///
+    /// Returns false if the failure cannot be skipped, i.e. the flag is unset or no tx is being
+    /// executed.
+    pub async fn fail_tx(&self, tx_hash: Felt252, error: &HintError) -> bool {
+        let mut eh_ref = self.execution_helper.write().await;
+        if !eh_ref.continue_on_tx_failure || eh_ref.tx_execution_info.is_none() {
+            return false;
+        }
+
+        eh_ref.record_tx_failure(tx_hash, error);
+        eh_ref.call_iter = vec![].into_iter();
+        true
+    }
+    /// Replaces the source of past block hashes, by default the hashes of the OS input.
+    pub async fn set_block_hash_provider(&self, block_hash_provider: Rc<dyn BlockHashProvider>) {
+        let mut eh_ref = self.execution_helper.write().await;
+        eh_ref.block_hash_provider = block_hash_provider;
+    }
+    /// Serves the storage reads of the calls from `storage_read_witness` instead of the values
+    /// recorded in the execution infos.
+    pub async fn set_storage_read_witness(&self, storage_read_witness: StorageReadWitness) {
+        let mut eh_ref = self.execution_helper.write().await;
+        eh_ref.storage_read_witness = Some(storage_read_witness);
+    }
+    pub async fn failed_tx_hashes(&self) -> Vec<Felt252> {
+        let eh_ref = self.execution_helper.read().await;
+        eh_ref.failed_tx_hashes.clone()
+    }
+    /// Starts recording the syscalls served from now on, discarding any previous recording.
+    pub async fn start_syscall_trace(&self) {
+        self.execution_helper.write().await.syscall_trace = Some(SyscallTrace::default());
+    }
+    /// Records a served syscall, if recording was started.
+    pub async fn record_syscall(&self, entry: SyscallTraceEntry) {
+        if let Some(syscall_trace) = self.execution_helper.write().await.syscall_trace.as_mut() {
+            syscall_trace.push(entry);
+        }
+    }
+    pub async fn is_recording_syscalls(&self) -> bool {
+        self.execution_helper.read().await.syscall_trace.is_some()
+    }
+    /// Returns the syscalls recorded so far, or None if recording was not started.
+    pub async fn syscall_trace(&self) -> Option<SyscallTrace> {
+        self.execution_helper.read().await.syscall_trace.clone()
+    }
     pub async fn skip_tx(&self) {
         self.start_tx(None).await;
         self.end_tx().await
     }
-    pub async fn enter_call(&self, execution_info_ptr: Option<Relocatable>) {
+    /// Starts replaying the next recorded call of the current tx. Fails if the tx has no call
+    /// left, i.e. the OS executes more calls than were recorded.
+    pub async fn enter_call(&self, execution_info_ptr: Option<Relocatable>) -> Result<(), HintError> {
         let mut eh_ref = self.execution_helper.write().await;
-        assert!(eh_ref.call_execution_info_ptr.is_none());
-        eh_ref.call_execution_info_ptr = execution_info_ptr;
-
         assert_iterators_exhausted(eh_ref.deref());
-
         assert!(eh_ref.call_info.is_none());
-        let call_info = eh_ref.call_iter.next().unwrap();
+
+        let tx_index = eh_ref.tx_index.unwrap_or_default();
+        let call_info = eh_ref.call_iter.next().ok_or_else(|| {
+            HintError::AssertionFailed(format!("No recorded call left to replay in tx {tx_index}").into_boxed_str())
+        })?;
+        assert!(eh_ref.call_execution_info_ptr.is_none());
+        eh_ref.call_execution_info_ptr = execution_info_ptr;
 
         // unpack deployed calls
         eh_ref.deployed_contracts_iter = call_info
@@ -212,19 +548,82 @@ where
             .into_iter();
 
         // unpack storage reads
-        eh_ref.execute_code_read_iter = call_info.storage_read_values.clone().into_iter();
+        let call_index = eh_ref.tx_call_index;
+        eh_ref.tx_call_index += 1;
+        eh_ref.execute_code_read_iter = match &eh_ref.storage_read_witness {
+            Some(storage_read_witness) => storage_read_witness.reads(tx_index, call_index).to_vec().into_iter(),
+            None => call_info.storage_read_values.clone().into_iter(),
+        };
+        eh_ref.is_library_call = call_info.call.call_type == CallType::Delegate;
+        eh_ref.call_syscall_gas_consumed = 0;
+        // The calls of a tx are replayed depth-first: a call that is not part of the call tree
+        // of the previous top-level call is the next top-level call
+        eh_ref.is_top_level_call = eh_ref.pending_inner_calls == 0;
+        if eh_ref.is_top_level_call {
+            eh_ref.pending_inner_calls = count_inner_calls(&call_info);
+        } else {
+            eh_ref.pending_inner_calls -= 1;
+        }
         eh_ref.call_info = Some(call_info);
+        Ok(())
     }
     pub async fn exit_call(&mut self) {
         let mut eh_ref = self.execution_helper.write().await;
         eh_ref.call_execution_info_ptr = None;
+        if eh_ref.current_tx_failed {
+            // The call may have been interrupted by the failure
+            eh_ref.call_info = None;
+            eh_ref.is_library_call = false;
+            eh_ref.is_top_level_call = false;
+            return;
+        }
         assert_iterators_exhausted(&eh_ref);
         assert!(eh_ref.call_info.is_some());
         eh_ref.call_info = None;
+        eh_ref.is_library_call = false;
+        eh_ref.is_top_level_call = false;
+    }
+    pub async fn is_library_call(&self) -> bool {
+        self.execution_helper.read().await.is_library_call
+    }
+    /// Records the gas consumed by a syscall of the current call.
+    pub async fn add_syscall_gas_consumed(&self, gas_consumed: u64) {
+        let mut eh_ref = self.execution_helper.write().await;
+        eh_ref.call_syscall_gas_consumed += gas_consumed;
+    }
+    /// Records the segments of a finalized segment arena.
+    pub async fn add_segment_arena_segments(&self, n_segments: usize) {
+        let mut eh_ref = self.execution_helper.write().await;
+        eh_ref.segment_arena_n_segments += n_segments;
     }
-    pub async fn skip_call(&mut self) {
-        self.enter_call(None).await;
+    /// Returns the number of segments allocated in the segment arenas finalized so far.
+    pub async fn segment_arena_n_segments(&self) -> usize {
+        self.execution_helper.read().await.segment_arena_n_segments
+    }
+    /// Returns the gas consumed by the syscalls replayed so far in the current call.
+    pub async fn call_syscall_gas_consumed(&self) -> u64 {
+        self.execution_helper.read().await.call_syscall_gas_consumed
+    }
+    /// Whether the current call is a top-level call of the tx (`__validate__`, `__execute__`,
+    /// constructor or L1 handler), whose caller is the OS.
+    pub async fn is_top_level_call(&self) -> bool {
+        self.execution_helper.read().await.is_top_level_call
+    }
+    pub async fn current_calldata(&self) -> Option<Vec<Felt252>> {
+        let eh_ref = self.execution_helper.read().await;
+        eh_ref.current_calldata()
+    }
+    /// Skips the next call of the tx, e.g. the `__execute__` call of a reverted tx.
+    ///
+    /// In validation-only mode, `__execute__` calls are not part of the replayed calls, so there
+    /// is nothing to skip.
+    pub async fn skip_call(&mut self) -> Result<(), HintError> {
+        if self.execution_helper.read().await.validate_only {
+            return Ok(());
+        }
+        self.enter_call(None).await?;
         self.exit_call().await;
+        Ok(())
     }
 
     pub async fn read_storage_for_address(&mut self, address: Felt252, key: Felt252) -> Result<Felt252, StorageError> {
@@ -237,6 +636,21 @@ where
         Err(StorageError::ContentNotFound)
     }
 
+    /// Reads a storage value for a `storage_read` syscall and records the key as read.
+    pub async fn serve_storage_read(&mut self, address: Felt252, key: Felt252) -> Result<Felt252, StorageError> {
+        let value = self.read_storage_for_address(address, key).await?;
+        self.execution_helper.write().await.read_keys.insert((address, key));
+
+        Ok(value)
+    }
+
+    /// Returns the (contract address, key) pairs served to `storage_read` syscalls so far, i.e.
+    /// the storage witness needed by the run.
+    pub async fn read_keys(&self) -> HashSet<(Felt252, Felt252)> {
+        let eh_ref = self.execution_helper.read().await;
+        eh_ref.read_keys.clone()
+    }
+
     pub async fn write_storage_for_address(
         &mut self,
         address: Felt252,
@@ -280,40 +694,143 @@ where
 /// Required for recursive iteration on 'inner_calls'
 pub trait GenCallIter {
     fn gen_call_iterator(&self) -> IntoIter<CallInfo>;
+
+    /// Same as `gen_call_iterator`, without the `__execute__` call of INVOKE txs, which the OS
+    /// skips in validation-only mode. The constructor of a DEPLOY_ACCOUNT tx and the handler of an
+    /// L1_HANDLER tx are still executed by the OS, so they are kept.
+    fn gen_validate_call_iterator(&self) -> IntoIter<CallInfo>;
+
+    /// Returns the events emitted by the transaction, in emission order.
+    fn get_events(&self) -> Vec<Event>;
+
+    /// Returns the gas consumed by the calls of the transaction.
+    fn gas_consumed(&self) -> u64;
+
+    /// Renders the call tree of the transaction, one call per line in the order of
+    /// `gen_call_iterator`, inner calls being indented below their caller. Meant for debugging
+    /// and error messages.
+    fn render_call_topology(&self) -> String;
+}
+
+/// Returns the number of calls made, directly or not, by `call_info`.
+fn count_inner_calls(call_info: &CallInfo) -> usize {
+    call_info.inner_calls.iter().map(|call| 1 + count_inner_calls(call)).sum()
+}
+
+/// Returns the top-level calls of a transaction, in the order in which the OS executes them.
+fn top_level_call_infos(tx_execution_info: &TransactionExecutionInfo) -> Vec<&CallInfo> {
+    // Determine if we are treating a DEPLOY_ACCOUNT tx. For deployments we need
+    // to order call infos differently, __validate_deploy__ is called after the constructor.
+    // See https://docs.starknet.io/architecture-and-concepts/accounts/account-functions/#overview
+    // for more details.
+    let is_deploy = match &tx_execution_info.execute_call_info {
+        Some(call_info) => matches!(call_info.call.entry_point_type, EntryPointType::Constructor),
+        None => false,
+    };
+
+    let call_info_iter = if is_deploy {
+        // For DEPLOY_ACCOUNT, validation is performed after executing the constructor
+        tx_execution_info
+            .execute_call_info
+            .iter()
+            .chain(tx_execution_info.validate_call_info.iter())
+            .chain(tx_execution_info.fee_transfer_call_info.iter())
+    } else {
+        // For other tx types, validation comes before the execution of the call
+        tx_execution_info
+            .validate_call_info
+            .iter()
+            .chain(tx_execution_info.execute_call_info.iter())
+            .chain(tx_execution_info.fee_transfer_call_info.iter())
+    };
+
+    call_info_iter.collect()
+}
+
+/// Renders a call and its inner calls, indented by their depth in the call tree.
+fn render_call(call_info: &CallInfo, depth: usize, rendered: &mut String) {
+    let call = &call_info.call;
+    rendered.push_str(&format!(
+        "{}{:#x} {:#x} {:?} gas={}\n",
+        "  ".repeat(depth),
+        call.storage_address.0.key(),
+        call.entry_point_selector.0,
+        call.entry_point_type,
+        call_info.execution.gas_consumed
+    ));
+
+    for inner_call in &call_info.inner_calls {
+        render_call(inner_call, depth + 1, rendered);
+    }
+}
+
+/// Collects the events emitted by a call and its inner calls, along with their order.
+fn collect_call_events(call_info: &CallInfo, events: &mut Vec<(usize, Event)>) {
+    let from_address = *call_info.call.storage_address.0.key();
+    for ordered_event in &call_info.execution.events {
+        let event = Event {
+            from_address,
+            keys: ordered_event.event.keys.iter().map(|key| key.0).collect(),
+            data: ordered_event.event.data.0.clone(),
+        };
+        events.push((ordered_event.order, event));
+    }
+
+    for inner_call in &call_info.inner_calls {
+        collect_call_events(inner_call, events);
+    }
 }
 
 impl GenCallIter for TransactionExecutionInfo {
     fn gen_call_iterator(&self) -> IntoIter<CallInfo> {
         let mut call_infos = vec![];
+        for call_info in top_level_call_infos(self) {
+            call_infos.extend(call_info.clone().gen_call_topology());
+        }
+        call_infos.into_iter()
+    }
 
-        // Determine if we are treating a DEPLOY_ACCOUNT tx. For deployments we need
-        // to order call infos differently, __validate_deploy__ is called after the constructor.
-        // See https://docs.starknet.io/architecture-and-concepts/accounts/account-functions/#overview
-        // for more details.
-        let is_deploy = match &self.execute_call_info {
-            Some(call_info) => matches!(call_info.call.entry_point_type, EntryPointType::Constructor),
-            None => false,
-        };
-
-        let call_info_iter = if is_deploy {
-            // For DEPLOY_ACCOUNT, validation is performed after executing the constructor
-            self.execute_call_info
-                .iter()
-                .chain(self.validate_call_info.iter())
-                .chain(self.fee_transfer_call_info.iter())
-        } else {
-            // For other tx types, validation comes before the execution of the call
-            self.validate_call_info
-                .iter()
-                .chain(self.execute_call_info.iter())
-                .chain(self.fee_transfer_call_info.iter())
-        };
+    fn gen_validate_call_iterator(&self) -> IntoIter<CallInfo> {
+        // The execute call of a tx is an external entry point only for `__execute__`
+        let skipped_call = self
+            .execute_call_info
+            .as_ref()
+            .filter(|call_info| call_info.call.entry_point_type == EntryPointType::External);
 
-        for call_info in call_info_iter {
+        let mut call_infos = vec![];
+        for call_info in top_level_call_infos(self) {
+            if skipped_call.is_some_and(|skipped_call| std::ptr::eq(call_info, skipped_call)) {
+                continue;
+            }
             call_infos.extend(call_info.clone().gen_call_topology());
         }
         call_infos.into_iter()
     }
+
+    fn get_events(&self) -> Vec<Event> {
+        let mut tx_events = vec![];
+        // Event orders are only meaningful within the call tree of a single top-level call
+        for call_info in top_level_call_infos(self) {
+            let mut call_events = vec![];
+            collect_call_events(call_info, &mut call_events);
+            call_events.sort_by_key(|(order, _)| *order);
+            tx_events.extend(call_events.into_iter().map(|(_, event)| event));
+        }
+        tx_events
+    }
+
+    fn gas_consumed(&self) -> u64 {
+        // The gas consumed by a call includes the gas consumed by its inner calls
+        top_level_call_infos(self).iter().map(|call_info| call_info.execution.gas_consumed).sum()
+    }
+
+    fn render_call_topology(&self) -> String {
+        let mut rendered = String::new();
+        for call_info in top_level_call_infos(self) {
+            render_call(call_info, 0, &mut rendered);
+        }
+        rendered
+    }
 }
 
 trait GenCallTopology {
@@ -334,3 +851,110 @@ impl GenCallTopology for CallInfo {
         results.into_iter()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use blockifier::execution::call_info::CallExecution;
+    use blockifier::execution::entry_point::CallEntryPoint;
+    use starknet_api::core::{ContractAddress, EntryPointSelector, PatriciaKey};
+    use starknet_api::{contract_address, felt, patricia_key};
+
+    use super::*;
+    use crate::crypto::pedersen::PedersenHash;
+    use crate::starknet::starknet_storage::OsSingleStarknetStorage;
+    use crate::storage::dict_storage::DictStorage;
+
+    type PCS = OsSingleStarknetStorage<DictStorage, PedersenHash>;
+
+    fn call_info(
+        address: ContractAddress,
+        selector: u64,
+        entry_point_type: EntryPointType,
+        gas_consumed: u64,
+        inner_calls: Vec<CallInfo>,
+    ) -> CallInfo {
+        CallInfo {
+            call: CallEntryPoint {
+                storage_address: address,
+                entry_point_selector: EntryPointSelector(felt!(selector)),
+                entry_point_type,
+                ..Default::default()
+            },
+            execution: CallExecution { gas_consumed, ..Default::default() },
+            inner_calls,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render_call_topology() {
+        let inner_call = call_info(contract_address!("0x200"), 0x22, EntryPointType::External, 300, vec![]);
+        let tx_execution_info = TransactionExecutionInfo {
+            validate_call_info: Some(call_info(
+                contract_address!("0x100"),
+                0x11,
+                EntryPointType::External,
+                100,
+                vec![],
+            )),
+            execute_call_info: Some(call_info(
+                contract_address!("0x100"),
+                0x15,
+                EntryPointType::External,
+                1000,
+                vec![inner_call.clone(), inner_call],
+            )),
+            fee_transfer_call_info: None,
+            revert_error: None,
+            transaction_receipt: Default::default(),
+        };
+
+        let expected = indoc::indoc! {"
+            0x100 0x11 External gas=100
+            0x100 0x15 External gas=1000
+              0x200 0x22 External gas=300
+              0x200 0x22 External gas=300
+        "};
+        assert_eq!(tx_execution_info.render_call_topology(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_storage_reads_served_from_witness() {
+        let mut inner_call = call_info(contract_address!("0x200"), 0x22, EntryPointType::External, 0, vec![]);
+        inner_call.storage_read_values = vec![Felt252::from(3)];
+        let mut execute_call_info =
+            call_info(contract_address!("0x100"), 0x15, EntryPointType::External, 0, vec![inner_call]);
+        execute_call_info.storage_read_values = vec![Felt252::from(1), Felt252::from(2)];
+        let tx_execution_info = TransactionExecutionInfo {
+            validate_call_info: None,
+            execute_call_info: Some(execute_call_info),
+            fee_transfer_call_info: None,
+            revert_error: None,
+            transaction_receipt: Default::default(),
+        };
+
+        let mut exec_wrapper = ExecutionHelperWrapper::<PCS>::new(
+            ContractStorageMap::default(),
+            vec![tx_execution_info],
+            &BlockContext::create_for_account_testing(),
+            None,
+            (Felt252::ZERO, Felt252::ZERO),
+        );
+        // The inner call is missing from the witness: it reads nothing
+        exec_wrapper
+            .set_storage_read_witness(StorageReadWitness::new(HashMap::from([(
+                (0, 0),
+                vec![Felt252::from(7), Felt252::from(8), Felt252::from(9)],
+            )])))
+            .await;
+        exec_wrapper.start_tx(None).await;
+
+        exec_wrapper.enter_call(None).await.unwrap();
+        let reads: Vec<_> = exec_wrapper.execution_helper.write().await.execute_code_read_iter.by_ref().collect();
+        assert_eq!(reads, vec![Felt252::from(7), Felt252::from(8), Felt252::from(9)]);
+
+        exec_wrapper.exit_call().await;
+        exec_wrapper.enter_call(None).await.unwrap();
+        assert_eq!(exec_wrapper.execution_helper.read().await.execute_code_read_iter.len(), 0);
+    }
+}
@@ -1,3 +1,4 @@
+pub mod block_hash_provider;
 mod constants;
 pub mod deprecated_syscall_handler;
 pub mod execute_syscalls;
@@ -5,3 +6,4 @@ pub mod helper;
 pub mod secp_handler;
 pub mod syscall_handler;
 pub mod syscall_handler_utils;
+pub mod syscall_trace;
@@ -27,6 +27,7 @@ use crate::execution::syscall_handler_utils::{
     felt_from_ptr, run_handler, write_felt, write_maybe_relocatable, write_segment, EmptyRequest, EmptyResponse,
     ReadOnlySegment, SyscallExecutionError, SyscallHandler, SyscallResult, SyscallSelector, WriteResponseResult,
 };
+use crate::execution::syscall_trace::SyscallTraceEntry;
 use crate::starknet::starknet_storage::PerContractStorage;
 
 /// DeprecatedSyscallHandler implementation for execution of system calls in the StarkNet OS
@@ -103,10 +104,11 @@ where
         assert_eq!(*ptr, syscall_ptr);
 
         let selector = SyscallSelector::try_from(felt_from_ptr(vm, ptr)?)?;
+        let request_ptr = *ptr;
 
         let ehw = &mut syscall_handler.exec_wrapper;
 
-        match selector {
+        let response_ptr = match selector {
             SyscallSelector::CallContract => {
                 run_handler::<CallContractHandler, PCS>(ptr, vm, ehw, CALL_CONTRACT_GAS_COST).await
             }
@@ -189,6 +191,11 @@ where
             _ => Err(HintError::CustomHint(format!("Unknown syscall selector: {:?}", selector).into())),
         }?;
 
+        if ehw.is_recording_syscalls().await {
+            let entry = SyscallTraceEntry::from_memory(vm, format!("{:?}", selector), request_ptr, response_ptr, *ptr)?;
+            ehw.record_syscall(entry).await;
+        }
+
         syscall_handler.syscall_ptr = Some(*ptr);
 
         Ok(())
@@ -244,6 +251,10 @@ where
 
 pub struct DeployHandler;
 
+pub struct DeployRequest {
+    pub class_hash: Felt252,
+}
+
 pub struct DeployResponse {
     pub contract_address: Felt252,
     pub constructor_retdata: ReadOnlySegment,
@@ -254,26 +265,32 @@ impl<PCS> SyscallHandler<PCS> for DeployHandler
 where
     PCS: PerContractStorage + 'static,
 {
-    type Request = EmptyRequest;
+    type Request = DeployRequest;
     type Response = DeployResponse;
 
-    fn read_request(_vm: &VirtualMachine, ptr: &mut Relocatable) -> SyscallResult<Self::Request> {
+    fn read_request(vm: &VirtualMachine, ptr: &mut Relocatable) -> SyscallResult<Self::Request> {
+        let class_hash = vm.get_integer((*ptr + new_syscalls::DeployRequest::class_hash_offset())?)?.into_owned();
         *ptr = (*ptr + new_syscalls::DeployRequest::cairo_size())?;
-        Ok(EmptyRequest)
+        Ok(DeployRequest { class_hash })
     }
 
     async fn execute(
-        _request: Self::Request,
+        request: Self::Request,
         vm: &mut VirtualMachine,
         exec_wrapper: &mut ExecutionHelperWrapper<PCS>,
         remaining_gas: &mut u64,
     ) -> SyscallResult<Self::Response> {
         let mut execution_helper = exec_wrapper.execution_helper.write().await;
 
+        // The constructor call is both a call result and a deployed contract: consume both before
+        // checking the result, so that the iterators stay in sync if the constructor failed.
         let result = execution_helper
             .result_iter
             .next()
             .ok_or(SyscallExecutionError::InternalError(Box::from("No result left in the result iterator.")))?;
+        let contract_address = execution_helper.deployed_contracts_iter.next().ok_or(HintError::SyscallError(
+            "No more deployed contracts available to replay".to_string().into_boxed_str(),
+        ))?;
 
         *remaining_gas -= result.gas_consumed;
 
@@ -282,42 +299,32 @@ where
         if result.failed {
             return Err(SyscallExecutionError::SyscallError { error_data: retdata });
         }
+        execution_helper.record_deployed_contract(contract_address, request.class_hash);
 
         let start_ptr = vm.add_temporary_segment();
         vm.load_data(start_ptr, &retdata.iter().map(MaybeRelocatable::from).collect::<Vec<_>>())?;
 
         let constructor_retdata = ReadOnlySegment { start_ptr, length: retdata.len() };
 
-        let contract_address = execution_helper.deployed_contracts_iter.next().ok_or(HintError::SyscallError(
-            "No more deployed contracts available to replay".to_string().into_boxed_str(),
-        ))?;
-
-        let need_retdata_hack = if let Some(os_input) = execution_helper.os_input.as_ref() {
-            let class_hash = os_input
-                .contract_address_to_class_hash
-                .get(&contract_address)
-                .expect("No class_hash for contract_address");
-            let num_constructors =
-                if let Some(compiled_class_hash) = os_input.class_hash_to_compiled_class_hash.get(class_hash) {
-                    let casm = os_input.compiled_classes.get(compiled_class_hash).expect("No CASM");
-                    let num_constructors = casm
-                        .get_cairo_lang_contract_class()
-                        .expect("couldn't get cairo lang class")
-                        .entry_points_by_type
-                        .constructor
-                        .len();
-                    num_constructors
-                } else {
-                    let deprecated_cc = os_input.deprecated_compiled_classes.get(class_hash).expect("no deprecated CC");
-                    let num_constructors = deprecated_cc
-                        .get_starknet_api_contract_class()
-                        .expect("couldn't get starknet api class")
-                        .entry_points_by_type
-                        .get(&starknet_api::deprecated_contract_class::EntryPointType::Constructor)
-                        .expect("should have constructor list")
-                        .len();
-                    num_constructors
-                };
+        let need_retdata_hack = if let Some(os_input) = execution_helper.os_input.clone() {
+            let class_hash =
+                execution_helper.get_class_hash_at(contract_address).expect("No class_hash for contract_address");
+            let num_constructors = if let Some(compiled_class_hash) =
+                os_input.class_hash_to_compiled_class_hash.get(&class_hash)
+            {
+                let casm = execution_helper.get_compiled_class(*compiled_class_hash)?;
+                casm.entry_points_by_type.constructor.len()
+            } else {
+                let deprecated_cc = os_input.deprecated_compiled_classes.get(&class_hash).expect("no deprecated CC");
+                let num_constructors = deprecated_cc
+                    .get_starknet_api_contract_class()
+                    .expect("couldn't get starknet api class")
+                    .entry_points_by_type
+                    .get(&starknet_api::deprecated_contract_class::EntryPointType::Constructor)
+                    .expect("should have constructor list")
+                    .len();
+                num_constructors
+            };
 
             // we need the hack if there are no constructor entry points
             num_constructors == 0
@@ -795,3 +802,202 @@ where
         Ok(Sha256ProcessBlockResponse { state_ptr: response })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use blockifier::context::BlockContext;
+    use blockifier::execution::call_info::Retdata;
+    use blockifier::execution::entry_point_execution::CallResult;
+    use rstest::rstest;
+
+    use super::*;
+    use crate::crypto::pedersen::PedersenHash;
+    use crate::execution::constants::SYSCALL_BASE_GAS_COST;
+    use crate::execution::helper::ContractStorageMap;
+    use crate::execution::syscall_trace::SyscallTrace;
+    use crate::starknet::starknet_storage::OsSingleStarknetStorage;
+    use crate::storage::dict_storage::DictStorage;
+
+    type PCS = OsSingleStarknetStorage<DictStorage, PedersenHash>;
+
+    #[rstest]
+    #[case::constructor_succeeded(false)]
+    #[case::constructor_failed(true)]
+    #[tokio::test]
+    async fn test_deploy_serves_address_and_constructor_retdata(#[case] failed: bool) {
+        let mut vm = VirtualMachine::new(false);
+        let mut exec_wrapper = ExecutionHelperWrapper::<PCS>::new(
+            ContractStorageMap::default(),
+            vec![],
+            &BlockContext::create_for_account_testing(),
+            None,
+            (Felt252::ZERO, Felt252::ZERO),
+        );
+
+        let contract_address = Felt252::from(0x1234);
+        let retdata = vec![Felt252::from(7), Felt252::from(8)];
+        {
+            let mut execution_helper = exec_wrapper.execution_helper.write().await;
+            execution_helper.result_iter =
+                vec![CallResult { failed, retdata: Retdata(retdata.clone()), gas_consumed: 100 }].into_iter();
+            execution_helper.deployed_contracts_iter = vec![contract_address].into_iter();
+        }
+
+        let class_hash = Felt252::from(0x5678);
+        let mut remaining_gas = 1000;
+        let result =
+            DeployHandler::execute(DeployRequest { class_hash }, &mut vm, &mut exec_wrapper, &mut remaining_gas).await;
+
+        // Both the call result and the deployed contract are consumed, whatever the outcome
+        {
+            let execution_helper = exec_wrapper.execution_helper.read().await;
+            assert_eq!(execution_helper.result_iter.len(), 0);
+            assert_eq!(execution_helper.deployed_contracts_iter.len(), 0);
+            // A contract whose constructor failed is not deployed
+            let expected_class_hash = if failed { None } else { Some(class_hash) };
+            assert_eq!(execution_helper.get_class_hash_at(contract_address), expected_class_hash);
+        }
+        assert_eq!(remaining_gas, 900);
+
+        if failed {
+            assert!(matches!(result, Err(SyscallExecutionError::SyscallError { error_data }) if error_data == retdata));
+            return;
+        }
+
+        let response = result.unwrap();
+        assert_eq!(response.contract_address, contract_address);
+        assert!(!response.need_retdata_hack);
+        assert_eq!(response.constructor_retdata.length, retdata.len());
+        let served_retdata: Vec<_> = vm
+            .get_integer_range(response.constructor_retdata.start_ptr, retdata.len())
+            .unwrap()
+            .into_iter()
+            .map(|felt| felt.into_owned())
+            .collect();
+        assert_eq!(served_retdata, retdata);
+    }
+
+    #[tokio::test]
+    async fn test_call_syscall_gas_consumed() {
+        let mut vm = VirtualMachine::new(false);
+        let mut exec_wrapper = ExecutionHelperWrapper::<PCS>::new(
+            ContractStorageMap::default(),
+            vec![],
+            &BlockContext::create_for_account_testing(),
+            None,
+            (Felt252::ZERO, Felt252::ZERO),
+        );
+        // The called contract consumes 100 gas on top of the cost of the syscall
+        exec_wrapper.execution_helper.write().await.result_iter =
+            vec![CallResult { failed: false, retdata: Retdata(vec![]), gas_consumed: 100 }].into_iter();
+
+        let mut syscall_ptr = vm.add_memory_segment();
+        vm.insert_value(syscall_ptr, Felt252::from(1_000_000)).unwrap();
+        run_handler::<EmitEventHandler, PCS>(&mut syscall_ptr, &mut vm, &mut exec_wrapper, EMIT_EVENT_GAS_COST)
+            .await
+            .unwrap();
+        assert_eq!(exec_wrapper.call_syscall_gas_consumed().await, EMIT_EVENT_GAS_COST);
+
+        vm.insert_value(syscall_ptr, Felt252::from(1_000_000)).unwrap();
+        run_handler::<CallContractHandler, PCS>(&mut syscall_ptr, &mut vm, &mut exec_wrapper, CALL_CONTRACT_GAS_COST)
+            .await
+            .unwrap();
+        assert_eq!(exec_wrapper.call_syscall_gas_consumed().await, EMIT_EVENT_GAS_COST + CALL_CONTRACT_GAS_COST + 100);
+    }
+
+    #[tokio::test]
+    async fn test_get_class_hash_at_deployed_contract() {
+        let mut vm = VirtualMachine::new(false);
+        let mut exec_wrapper = ExecutionHelperWrapper::<PCS>::new(
+            ContractStorageMap::default(),
+            vec![],
+            &BlockContext::create_for_account_testing(),
+            None,
+            (Felt252::ZERO, Felt252::ZERO),
+        );
+        let contract_address = Felt252::from(0x1234);
+        let class_hash = Felt252::from(0x5678);
+        {
+            let mut execution_helper = exec_wrapper.execution_helper.write().await;
+            execution_helper.result_iter =
+                vec![CallResult { failed: false, retdata: Retdata(vec![]), gas_consumed: 0 }].into_iter();
+            execution_helper.deployed_contracts_iter = vec![contract_address].into_iter();
+        }
+
+        assert_eq!(exec_wrapper.execution_helper.read().await.get_class_hash_at(contract_address), None);
+
+        // The class hash is read from the request written by the deploying contract, after the gas counter
+        let mut syscall_ptr = vm.add_memory_segment();
+        vm.insert_value(syscall_ptr, Felt252::from(1_000_000)).unwrap();
+        let class_hash_ptr = (syscall_ptr + (1 + new_syscalls::DeployRequest::class_hash_offset())).unwrap();
+        vm.insert_value(class_hash_ptr, class_hash).unwrap();
+
+        run_handler::<DeployHandler, PCS>(&mut syscall_ptr, &mut vm, &mut exec_wrapper, DEPLOY_GAS_COST).await.unwrap();
+
+        let execution_helper = exec_wrapper.execution_helper.read().await;
+        assert_eq!(execution_helper.get_class_hash_at(contract_address), Some(class_hash));
+    }
+
+    #[tokio::test]
+    async fn test_syscall_trace() {
+        let mut vm = VirtualMachine::new(false);
+        let exec_wrapper = ExecutionHelperWrapper::<PCS>::new(
+            ContractStorageMap::default(),
+            vec![],
+            &BlockContext::create_for_account_testing(),
+            None,
+            (Felt252::ZERO, Felt252::ZERO),
+        );
+        let retdata = vec![Felt252::from(7), Felt252::from(8)];
+        exec_wrapper.execution_helper.write().await.result_iter =
+            vec![CallResult { failed: false, retdata: Retdata(retdata), gas_consumed: 100 }].into_iter();
+        exec_wrapper.start_syscall_trace().await;
+
+        let syscall_handler = OsSyscallHandlerWrapper::new(exec_wrapper.clone());
+        let gas = Felt252::from(1_000_000);
+
+        // `emit_event` with keys and data spans, then `call_contract` with an address, a selector
+        // and a calldata span
+        let emit_event_ptr = vm.add_memory_segment();
+        let emit_event_request: Vec<MaybeRelocatable> =
+            [1, 2, 3, 4].into_iter().map(|felt| Felt252::from(felt).into()).collect();
+        vm.load_data(emit_event_ptr, &[Felt252::from_bytes_be_slice(b"EmitEvent").into(), gas.into()]).unwrap();
+        vm.load_data((emit_event_ptr + 2).unwrap(), &emit_event_request).unwrap();
+        syscall_handler.set_syscall_ptr(emit_event_ptr).await;
+        syscall_handler.execute_syscall(&mut vm, emit_event_ptr).await.unwrap();
+
+        let call_contract_ptr = syscall_handler.syscall_ptr().await.unwrap();
+        let call_contract_request: Vec<MaybeRelocatable> =
+            [0x1234, 0x5678, 5, 6].into_iter().map(|felt| Felt252::from(felt).into()).collect();
+        vm.load_data(call_contract_ptr, &[Felt252::from_bytes_be_slice(b"CallContract").into(), gas.into()]).unwrap();
+        vm.load_data((call_contract_ptr + 2).unwrap(), &call_contract_request).unwrap();
+        syscall_handler.execute_syscall(&mut vm, call_contract_ptr).await.unwrap();
+
+        let syscall_trace = exec_wrapper.syscall_trace().await.unwrap();
+        assert_eq!(syscall_trace.names(), vec!["EmitEvent", "CallContract"]);
+
+        let emit_event = &syscall_trace.0[0];
+        assert_eq!(emit_event.inputs, [vec![gas.into()], emit_event_request].concat());
+        let remaining_gas = 1_000_000 - (EMIT_EVENT_GAS_COST - SYSCALL_BASE_GAS_COST);
+        assert_eq!(emit_event.outputs, vec![Felt252::from(remaining_gas).into(), Felt252::ZERO.into()]);
+
+        // The retdata is served in the first temporary segment
+        let call_contract = &syscall_trace.0[1];
+        assert_eq!(call_contract.inputs, [vec![gas.into()], call_contract_request].concat());
+        let remaining_gas = 1_000_000 - (CALL_CONTRACT_GAS_COST - SYSCALL_BASE_GAS_COST) - 100;
+        let retdata_start = Relocatable::from((-1, 0));
+        assert_eq!(
+            call_contract.outputs,
+            vec![
+                Felt252::from(remaining_gas).into(),
+                Felt252::ZERO.into(),
+                retdata_start.into(),
+                (retdata_start + 2).unwrap().into()
+            ]
+        );
+
+        // The trace round-trips through JSON, so that it can be compared against a golden file
+        let serialized = serde_json::to_string(&syscall_trace).unwrap();
+        assert_eq!(serde_json::from_str::<SyscallTrace>(&serialized).unwrap(), syscall_trace);
+    }
+}
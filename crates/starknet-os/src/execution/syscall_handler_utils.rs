@@ -290,12 +290,14 @@ fn write_failure(
 
 pub const OUT_OF_GAS_ERROR: &str = "0x000000000000000000000000000000000000000000004f7574206f6620676173";
 
+/// Serves the syscall whose request starts at `syscall_ptr` and moves the pointer past its
+/// response. Returns the start of the response.
 pub async fn run_handler<SH, PCS>(
     syscall_ptr: &mut Relocatable,
     vm: &mut VirtualMachine,
     exec_wrapper: &mut ExecutionHelperWrapper<PCS>,
     syscall_gas_cost: u64,
-) -> Result<(), HintError>
+) -> Result<Relocatable, HintError>
 where
     SH: SyscallHandler<PCS>,
     PCS: PerContractStorage + 'static,
@@ -312,16 +314,21 @@ where
     if gas_counter < required_gas {
         //  Out of gas failure.
         let out_of_gas_error = Felt252::from_hex(OUT_OF_GAS_ERROR).unwrap();
+        let response_ptr = *syscall_ptr;
         write_failure(gas_counter, vec![out_of_gas_error], vm, syscall_ptr)?;
-        return Ok(());
+        exec_wrapper.add_syscall_gas_consumed(SYSCALL_BASE_GAS_COST).await;
+        return Ok(response_ptr);
     }
 
     let request = SH::read_request(vm, syscall_ptr)?;
+    let response_ptr = *syscall_ptr;
 
     // Execute.
     let mut remaining_gas = gas_counter - required_gas;
 
     let syscall_result = SH::execute(request, vm, exec_wrapper, &mut remaining_gas).await;
+    // Failed syscalls consume gas as well, including the gas of a failed inner call
+    exec_wrapper.add_syscall_gas_consumed(SYSCALL_BASE_GAS_COST + gas_counter - remaining_gas).await;
 
     match syscall_result {
         Ok(response) => {
@@ -336,5 +343,5 @@ where
         Err(error) => return Err(error.into()),
     };
 
-    Ok(())
+    Ok(response_ptr)
 }
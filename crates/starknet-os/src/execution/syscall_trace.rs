@@ -0,0 +1,48 @@
+use cairo_vm::types::relocatable::{MaybeRelocatable, Relocatable};
+use cairo_vm::vm::errors::hint_errors::HintError;
+use cairo_vm::vm::vm_core::VirtualMachine;
+use serde::{Deserialize, Serialize};
+
+/// A syscall served by the OS, as laid out in the syscall segment.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyscallTraceEntry {
+    /// Name of the syscall selector, e.g. `CallContract`.
+    pub name: String,
+    /// Request written by the contract, starting with its gas counter.
+    pub inputs: Vec<MaybeRelocatable>,
+    /// Response written by the OS, starting with the remaining gas and the failure flag.
+    pub outputs: Vec<MaybeRelocatable>,
+}
+
+impl SyscallTraceEntry {
+    /// Reads the request in `[request_start, response_start)` and the response in
+    /// `[response_start, response_end)` from the syscall segment.
+    pub fn from_memory(
+        vm: &VirtualMachine,
+        name: String,
+        request_start: Relocatable,
+        response_start: Relocatable,
+        response_end: Relocatable,
+    ) -> Result<Self, HintError> {
+        let inputs = vm.get_continuous_range(request_start, (response_start - request_start)?)?;
+        let outputs = vm.get_continuous_range(response_start, (response_end - response_start)?)?;
+        Ok(Self { name, inputs, outputs })
+    }
+}
+
+/// Syscalls served during a run, in the order they were served. Meant to be compared against a
+/// trace recorded from a known-good run.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SyscallTrace(pub Vec<SyscallTraceEntry>);
+
+impl SyscallTrace {
+    pub fn push(&mut self, entry: SyscallTraceEntry) {
+        self.0.push(entry);
+    }
+
+    /// Names of the syscalls served, in order.
+    pub fn names(&self) -> Vec<&str> {
+        self.0.iter().map(|entry| entry.name.as_str()).collect()
+    }
+}
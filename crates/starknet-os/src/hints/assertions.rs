@@ -0,0 +1,111 @@
+use cairo_vm::types::exec_scope::ExecutionScopes;
+use cairo_vm::vm::errors::hint_errors::HintError;
+
+use crate::config::AssertionMode;
+use crate::hints::vars;
+use crate::utils::get_variable_from_root_exec_scope;
+
+/// Checks an invariant of a hint. When it does not hold, the hint fails, logs a warning or panics
+/// depending on the `AssertionMode` of the run.
+///
+/// Usage: `hint_assert!(exec_scopes, condition, "format string", args...)`.
+macro_rules! hint_assert {
+    ($exec_scopes:expr, $condition:expr, $($message:tt)+) => {
+        if !$condition {
+            $crate::hints::assertions::assertion_failed($exec_scopes, format!($($message)+))?;
+        }
+    };
+}
+
+pub(crate) use hint_assert;
+
+/// Returns the assertion mode of the run. Runs that did not set one, e.g. hint unit tests, are
+/// strict.
+fn assertion_mode(exec_scopes: &ExecutionScopes) -> AssertionMode {
+    get_variable_from_root_exec_scope::<AssertionMode>(exec_scopes, vars::scopes::ASSERTION_MODE).unwrap_or_default()
+}
+
+/// Reports a violated invariant according to the assertion mode of the run. Only returns an error
+/// in strict mode.
+pub(crate) fn assertion_failed(exec_scopes: &ExecutionScopes, message: String) -> Result<(), HintError> {
+    match assertion_mode(exec_scopes) {
+        AssertionMode::Strict => Err(HintError::AssertionFailed(message.into_boxed_str())),
+        AssertionMode::Lenient => {
+            log::warn!("Hint assertion failed, continuing: {message}");
+            Ok(())
+        }
+        AssertionMode::Debug => {
+            let mut local_variables: Vec<_> =
+                exec_scopes.get_local_variables().map(|variables| variables.keys().collect()).unwrap_or_default();
+            local_variables.sort();
+            panic!(
+                "Hint assertion failed: {message}\nScope depth: {}\nLocal variables: {:?}",
+                exec_scopes.data.len(),
+                local_variables
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn check_positive(exec_scopes: &ExecutionScopes, value: i64) -> Result<(), HintError> {
+        hint_assert!(exec_scopes, value > 0, "Expected a positive value, got {value}");
+        Ok(())
+    }
+
+    fn exec_scopes_with_mode(assertion_mode: AssertionMode) -> ExecutionScopes {
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::scopes::ASSERTION_MODE, assertion_mode);
+        exec_scopes
+    }
+
+    #[rstest]
+    #[case::strict(AssertionMode::Strict)]
+    #[case::lenient(AssertionMode::Lenient)]
+    #[case::debug(AssertionMode::Debug)]
+    fn test_hint_assert_holds(#[case] assertion_mode: AssertionMode) {
+        let exec_scopes = exec_scopes_with_mode(assertion_mode);
+
+        check_positive(&exec_scopes, 1).unwrap();
+    }
+
+    #[test]
+    fn test_hint_assert_strict() {
+        let exec_scopes = exec_scopes_with_mode(AssertionMode::Strict);
+
+        let err = check_positive(&exec_scopes, -1).unwrap_err();
+
+        assert!(
+            matches!(err, HintError::AssertionFailed(message) if message.as_ref() == "Expected a positive value, got -1")
+        );
+    }
+
+    #[test]
+    fn test_hint_assert_defaults_to_strict() {
+        let exec_scopes = ExecutionScopes::new();
+
+        let err = check_positive(&exec_scopes, -1).unwrap_err();
+
+        assert!(matches!(err, HintError::AssertionFailed(_)));
+    }
+
+    #[test]
+    fn test_hint_assert_lenient() {
+        let exec_scopes = exec_scopes_with_mode(AssertionMode::Lenient);
+
+        check_positive(&exec_scopes, -1).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "Hint assertion failed: Expected a positive value, got -1")]
+    fn test_hint_assert_debug() {
+        let exec_scopes = exec_scopes_with_mode(AssertionMode::Debug);
+
+        let _ = check_positive(&exec_scopes, -1);
+    }
+}
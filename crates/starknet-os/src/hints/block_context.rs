@@ -7,7 +7,8 @@ use std::rc::Rc;
 use blockifier::context::BlockContext;
 use cairo_vm::hint_processor::builtin_hint_processor::dict_manager::Dictionary;
 use cairo_vm::hint_processor::builtin_hint_processor::hint_utils::{
-    get_ptr_from_var_name, get_relocatable_from_var_name, insert_value_from_var_name, insert_value_into_ap,
+    get_integer_from_var_name, get_ptr_from_var_name, get_relocatable_from_var_name, insert_value_from_var_name,
+    insert_value_into_ap,
 };
 use cairo_vm::hint_processor::hint_processor_definition::{HintExtension, HintProcessor, HintReference};
 use cairo_vm::serde::deserialize_program::ApTracking;
@@ -17,13 +18,18 @@ use cairo_vm::vm::errors::hint_errors::HintError;
 use cairo_vm::vm::vm_core::VirtualMachine;
 use cairo_vm::{any_box, Felt252};
 use indoc::indoc;
+use starknet_api::deprecated_contract_class::EntryPointType;
 use starknet_os_types::casm_contract_class::GenericCasmContractClass;
 use starknet_os_types::chain_id::chain_id_to_felt;
 
 use crate::cairo_types::structs::{CompiledClass, CompiledClassFact};
-use crate::hints::vars;
+use crate::cairo_types::traits::CairoType;
+use crate::hints::assertions::hint_assert;
+use crate::hints::layout::DeprecatedBlockInfoLayout;
+use crate::hints::{get_typed, vars};
 use crate::io::classes::write_class;
 use crate::io::input::StarknetOsInput;
+use crate::io::InternalTransaction;
 use crate::starknet::core::os::contract_class::compiled_class_hash_objects::BytecodeSegmentStructureImpl;
 use crate::utils::{custom_hint_error, get_constant};
 
@@ -157,17 +163,14 @@ pub fn load_class(
     let compiled_class_fact_addr =
         get_relocatable_from_var_name(vars::ids::COMPILED_CLASS_FACT, vm, ids_data, ap_tracking)?;
     let computed_hash = vm.get_integer((compiled_class_fact_addr + CompiledClassFact::hash_offset())?)?;
-    let expected_hash = exec_scopes.get::<Felt252>(vars::scopes::COMPILED_CLASS_HASH).unwrap();
-
-    if computed_hash.as_ref() != &expected_hash {
-        return Err(HintError::AssertionFailed(
-            format!(
-                "Computed compiled_class_hash is inconsistent with the hash in the os_input. Computed hash \
-                 ={computed_hash}, Expected hash = {expected_hash}"
-            )
-            .into_boxed_str(),
-        ));
-    }
+    let expected_hash = get_typed::<Felt252>(exec_scopes, vars::scopes::COMPILED_CLASS_HASH)?;
+
+    hint_assert!(
+        exec_scopes,
+        computed_hash.as_ref() == &expected_hash,
+        "Computed compiled_class_hash is inconsistent with the hash in the os_input. Computed hash ={computed_hash}, \
+         Expected hash = {expected_hash}"
+    );
 
     let class = exec_scopes.get::<GenericCasmContractClass>(vars::scopes::COMPILED_CLASS)?;
 
@@ -186,6 +189,79 @@ pub fn load_class(
     Ok(hint_extension)
 }
 
+/// Selector of the `__default__` entry point, called when no entry point matches the selector.
+const DEFAULT_ENTRY_POINT_SELECTOR: Felt252 = Felt252::ZERO;
+
+/// Returns the bytecode offset and the builtins of the entry point of a compiled class matching
+/// the given selector and type. Falls back to the default entry point of that type, if the class
+/// has one.
+pub fn find_class_entry_point(
+    compiled_class: &GenericCasmContractClass,
+    selector: Felt252,
+    entry_point_type: EntryPointType,
+) -> Result<(usize, Vec<String>), HintError> {
+    let cairo_lang_class =
+        compiled_class.get_cairo_lang_contract_class().map_err(|e| custom_hint_error(e.to_string()))?;
+    let entry_points = match entry_point_type {
+        EntryPointType::External => &cairo_lang_class.entry_points_by_type.external,
+        EntryPointType::L1Handler => &cairo_lang_class.entry_points_by_type.l1_handler,
+        EntryPointType::Constructor => &cairo_lang_class.entry_points_by_type.constructor,
+    };
+
+    let find_entry_point =
+        |selector: Felt252| entry_points.iter().find(|entry_point| Felt252::from(&entry_point.selector) == selector);
+
+    find_entry_point(selector)
+        .or_else(|| find_entry_point(DEFAULT_ENTRY_POINT_SELECTOR))
+        .map(|entry_point| (entry_point.offset, entry_point.builtins.clone()))
+        .ok_or_else(|| {
+            custom_hint_error(format!(
+                "No {entry_point_type:?} entry point found for selector {}",
+                selector.to_hex_string()
+            ))
+        })
+}
+
+pub const FIND_CLASS_ENTRY_POINT: &str = indoc! {r#"
+    entry_point = find_class_entry_point(
+        compiled_class=compiled_class,
+        selector=ids.selector,
+        entry_point_type=ids.entry_point_type,
+    )
+    ids.entry_point_offset = entry_point.offset
+    ids.n_builtins = len(entry_point.builtins)
+    ids.builtin_list = segments.gen_arg(
+        [int.from_bytes(builtin.encode("ascii"), "big") for builtin in entry_point.builtins]
+    )"#
+};
+pub fn find_class_entry_point_hint(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError> {
+    let selector = get_integer_from_var_name(vars::ids::SELECTOR, vm, ids_data, ap_tracking)?;
+    // Matches the ENTRY_POINT_TYPE_* constants of the OS
+    let entry_point_type = match get_integer_from_var_name(vars::ids::ENTRY_POINT_TYPE, vm, ids_data, ap_tracking)? {
+        t if t == Felt252::ZERO => EntryPointType::External,
+        t if t == Felt252::ONE => EntryPointType::L1Handler,
+        t if t == Felt252::TWO => EntryPointType::Constructor,
+        t => return Err(custom_hint_error(format!("Invalid entry point type: {t}"))),
+    };
+
+    let compiled_class = exec_scopes.get_ref::<GenericCasmContractClass>(vars::scopes::COMPILED_CLASS)?;
+    let (offset, builtins) = find_class_entry_point(compiled_class, selector, entry_point_type)?;
+
+    let builtins: Vec<MaybeRelocatable> =
+        builtins.iter().map(|builtin| Felt252::from_bytes_be_slice(builtin.as_bytes()).into()).collect();
+    let builtin_list = vm.gen_arg(&builtins)?;
+
+    insert_value_from_var_name(vars::ids::ENTRY_POINT_OFFSET, offset, vm, ids_data, ap_tracking)?;
+    insert_value_from_var_name(vars::ids::N_BUILTINS, builtins.len(), vm, ids_data, ap_tracking)?;
+    insert_value_from_var_name(vars::ids::BUILTIN_LIST, builtin_list, vm, ids_data, ap_tracking)
+}
+
 pub const BLOCK_NUMBER: &str = "memory[ap] = to_felt_or_relocatable(syscall_handler.block_info.block_number)";
 pub fn block_number(
     vm: &mut VirtualMachine,
@@ -224,6 +300,27 @@ pub fn chain_id(
     insert_value_into_ap(vm, chain_id)
 }
 
+pub const GET_STARKNET_VERSION: &str = indoc! {r#"
+    memory[ap] = to_felt_or_relocatable(
+        int.from_bytes(os_input.general_config.starknet_version.encode("ascii"), "big")
+    )"#
+};
+/// Serves the Starknet version of the block, encoded as a short string (e.g. "0.13.2").
+pub fn get_starknet_version(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    _ids_data: &HashMap<String, HintReference>,
+    _ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError> {
+    let os_input = exec_scopes.get::<Rc<StarknetOsInput>>(vars::scopes::OS_INPUT)?;
+    let starknet_version = &os_input.general_config.starknet_version;
+    if !starknet_version.is_ascii() || starknet_version.len() > 31 {
+        return Err(custom_hint_error(format!("Invalid Starknet version: {starknet_version}")));
+    }
+    insert_value_into_ap(vm, Felt252::from_bytes_be_slice(starknet_version.as_bytes()))
+}
+
 pub const FEE_TOKEN_ADDRESS: &str = "memory[ap] = to_felt_or_relocatable(os_input.general_config.fee_token_address)";
 pub fn fee_token_address(
     vm: &mut VirtualMachine,
@@ -233,7 +330,19 @@ pub fn fee_token_address(
     _constants: &HashMap<String, Felt252>,
 ) -> Result<(), HintError> {
     let os_input = exec_scopes.get::<Rc<StarknetOsInput>>(vars::scopes::OS_INPUT)?;
-    let fee_token_address = *os_input.general_config.starknet_os_config.fee_token_address.0.key();
+    let os_config = &os_input.general_config.starknet_os_config;
+    // The OS runs this hint when building the block context, before any tx is loaded: the default
+    // fee token is served then. The tx scope variable is only set while a tx is executed (see
+    // `clear_tx_scope`), so if the hint runs inside a tx, the token is the one that tx pays its fee
+    // with: STRK for v3, ETH otherwise, as the fee hints do with `fee_token_address_for_version`.
+    let fee_token_address = if exec_scopes.get_local_variables()?.contains_key(vars::scopes::TX) {
+        let tx = exec_scopes.get_ref::<InternalTransaction>(vars::scopes::TX)?;
+        let tx_version = tx.version.ok_or(custom_hint_error("tx.version is None"))?;
+        os_config.fee_token_address_for_version(tx_version)
+    } else {
+        os_config.fee_token_address
+    };
+    let fee_token_address = *fee_token_address.0.key();
     log::debug!("fee_token_address: {}", fee_token_address);
     insert_value_into_ap(vm, fee_token_address)
 }
@@ -260,9 +369,75 @@ pub fn sequencer_address(
     _ids_data: &HashMap<String, HintReference>,
     _ap_tracking: &ApTracking,
     _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError> {
+    let os_input = exec_scopes.get::<Rc<StarknetOsInput>>(vars::scopes::OS_INPUT)?;
+    let block_context = exec_scopes.get_ref::<BlockContext>(vars::scopes::BLOCK_CONTEXT)?;
+    let sequencer_address = *block_context.block_info().sequencer_address.0.key();
+
+    hint_assert!(
+        exec_scopes,
+        sequencer_address != Felt252::ZERO || os_input.general_config.allow_zero_sequencer_address,
+        "The sequencer address is zero, which is rejected by the configuration (see `allow_zero_sequencer_address`)"
+    );
+
+    insert_value_into_ap(vm, sequencer_address)
+}
+
+pub const GET_GAS_PRICES: &str = indoc! {r#"
+    gas_prices = syscall_handler.block_info.gas_prices
+    ids.l1_gas_price_wei = gas_prices.eth_l1_gas_price
+    ids.l1_gas_price_fri = gas_prices.strk_l1_gas_price
+    ids.l1_data_gas_price_wei = gas_prices.eth_l1_data_gas_price
+    ids.l1_data_gas_price_fri = gas_prices.strk_l1_data_gas_price"#
+};
+pub fn get_gas_prices(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError> {
+    let block_context = exec_scopes.get_ref::<BlockContext>(vars::scopes::BLOCK_CONTEXT)?;
+    let gas_prices = &block_context.block_info().gas_prices;
+
+    let prices = [
+        (vars::ids::L1_GAS_PRICE_WEI, gas_prices.eth_l1_gas_price),
+        (vars::ids::L1_GAS_PRICE_FRI, gas_prices.strk_l1_gas_price),
+        (vars::ids::L1_DATA_GAS_PRICE_WEI, gas_prices.eth_l1_data_gas_price),
+        (vars::ids::L1_DATA_GAS_PRICE_FRI, gas_prices.strk_l1_data_gas_price),
+    ];
+    for (var_name, price) in prices {
+        insert_value_from_var_name(var_name, Felt252::from(price.get()), vm, ids_data, ap_tracking)?;
+    }
+
+    Ok(())
+}
+
+pub const GET_BLOCK_INFO_DEPRECATED: &str = indoc! {r#"
+    block_info = syscall_handler.block_info
+    ids.deprecated_block_info.block_timestamp = block_info.block_timestamp
+    ids.deprecated_block_info.block_number = block_info.block_number
+    ids.deprecated_block_info.sequencer_address = block_info.sequencer_address"#
+};
+pub fn get_block_info_deprecated(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
 ) -> Result<(), HintError> {
     let block_context = exec_scopes.get_ref::<BlockContext>(vars::scopes::BLOCK_CONTEXT)?;
-    insert_value_into_ap(vm, *block_context.block_info().sequencer_address.0.key())
+    let block_info = block_context.block_info();
+
+    let deprecated_block_info = DeprecatedBlockInfoLayout {
+        block_timestamp: Felt252::from(block_info.block_timestamp.0),
+        block_number: Felt252::from(block_info.block_number.0),
+        sequencer_address: *block_info.sequencer_address.0.key(),
+    };
+    let deprecated_block_info_ptr = get_ptr_from_var_name(vars::ids::DEPRECATED_BLOCK_INFO, vm, ids_data, ap_tracking)?;
+    deprecated_block_info.to_memory(vm, deprecated_block_info_ptr)?;
+
+    Ok(())
 }
 
 pub const GET_BLOCK_MAPPING: &str = indoc! {r#"
@@ -365,3 +540,332 @@ pub fn write_use_kzg_da_to_mem(
 
     vm.insert_value((vm.get_fp() + 18)?, use_kzg_da_felt).map_err(HintError::Memory)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU128;
+
+    use blockifier::blockifier::block::GasPrices;
+    use blockifier::bouncer::BouncerConfig;
+    use rstest::{fixture, rstest};
+    use starknet_api::block::{BlockNumber, BlockTimestamp};
+    use starknet_api::core::{ContractAddress, PatriciaKey};
+    use starknet_api::{contract_address, felt, patricia_key};
+
+    use super::*;
+    use crate::cairo_types::new_syscalls::BlockInfo;
+    use crate::config::{AssertionMode, StarknetGeneralConfig};
+    use crate::utils::get_selector_from_name;
+
+    const STRK_FEE_TOKEN_ADDRESS: &str = "0x4718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d";
+    const ETH_FEE_TOKEN_ADDRESS: &str = "0x49d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7";
+
+    #[fixture]
+    fn os_input() -> StarknetOsInput {
+        let mut general_config = StarknetGeneralConfig::default();
+        general_config.starknet_os_config.fee_token_address = contract_address!(STRK_FEE_TOKEN_ADDRESS);
+        general_config.starknet_os_config.deprecated_fee_token_address = contract_address!(ETH_FEE_TOKEN_ADDRESS);
+
+        StarknetOsInput { general_config, ..Default::default() }
+    }
+
+    fn run_fee_token_address_hint(os_input: StarknetOsInput, tx: Option<InternalTransaction>) -> Felt252 {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::scopes::OS_INPUT, Rc::new(os_input));
+        if let Some(tx) = tx {
+            exec_scopes.insert_value(vars::scopes::TX, tx);
+        }
+
+        fee_token_address(&mut vm, &mut exec_scopes, &HashMap::new(), &ApTracking::new(), &HashMap::new())
+            .expect("fee_token_address hint failed");
+
+        vm.get_integer(vm.get_ap()).unwrap().into_owned()
+    }
+
+    #[rstest]
+    #[case::v1(Felt252::ONE, ETH_FEE_TOKEN_ADDRESS)]
+    #[case::v3(Felt252::THREE, STRK_FEE_TOKEN_ADDRESS)]
+    fn test_fee_token_address_depends_on_tx_version(
+        os_input: StarknetOsInput,
+        #[case] tx_version: Felt252,
+        #[case] expected_fee_token_address: &str,
+    ) {
+        let tx = InternalTransaction { version: Some(tx_version), ..Default::default() };
+
+        let fee_token_address = run_fee_token_address_hint(os_input, Some(tx));
+        assert_eq!(fee_token_address, Felt252::from_hex(expected_fee_token_address).unwrap());
+    }
+
+    #[rstest]
+    fn test_fee_token_address_outside_of_tx(os_input: StarknetOsInput) {
+        let fee_token_address = run_fee_token_address_hint(os_input, None);
+        assert_eq!(fee_token_address, Felt252::from_hex(STRK_FEE_TOKEN_ADDRESS).unwrap());
+    }
+
+    #[rstest]
+    fn test_fee_token_address_propagates_scope_errors(os_input: StarknetOsInput) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+
+        // A tx without a version
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::scopes::OS_INPUT, Rc::new(os_input));
+        exec_scopes.insert_value(vars::scopes::TX, InternalTransaction::default());
+        let result = fee_token_address(&mut vm, &mut exec_scopes, &HashMap::new(), &ApTracking::new(), &HashMap::new());
+        assert!(matches!(result, Err(HintError::CustomHint(_))));
+
+        // A tx scope variable of the wrong type
+        exec_scopes.insert_value(vars::scopes::TX, Felt252::ONE);
+        let result = fee_token_address(&mut vm, &mut exec_scopes, &HashMap::new(), &ApTracking::new(), &HashMap::new());
+        assert!(matches!(result, Err(HintError::VariableNotInScopeError(_))));
+    }
+
+    #[rstest]
+    fn test_get_starknet_version(mut os_input: StarknetOsInput) {
+        os_input.general_config.starknet_version = "0.13.2.1".to_string();
+
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::scopes::OS_INPUT, Rc::new(os_input));
+
+        get_starknet_version(&mut vm, &mut exec_scopes, &HashMap::new(), &ApTracking::new(), &HashMap::new())
+            .expect("get_starknet_version hint failed");
+
+        // "0.13.2.1" as a short string
+        let starknet_version = vm.get_integer(vm.get_ap()).unwrap().into_owned();
+        assert_eq!(starknet_version, Felt252::from_hex("0x302e31332e322e31").unwrap());
+    }
+
+    #[rstest]
+    #[case::zero_permitted(Felt252::ZERO, true, AssertionMode::Strict, Some(Felt252::ZERO))]
+    #[case::zero_rejected(Felt252::ZERO, false, AssertionMode::Strict, None)]
+    #[case::zero_rejected_lenient(Felt252::ZERO, false, AssertionMode::Lenient, Some(Felt252::ZERO))]
+    #[case::non_zero(Felt252::from(0x1234), false, AssertionMode::Strict, Some(Felt252::from(0x1234)))]
+    fn test_sequencer_address(
+        mut os_input: StarknetOsInput,
+        #[case] sequencer: Felt252,
+        #[case] allow_zero_sequencer_address: bool,
+        #[case] assertion_mode: AssertionMode,
+        #[case] expected: Option<Felt252>,
+    ) {
+        os_input.general_config.allow_zero_sequencer_address = allow_zero_sequencer_address;
+        let block_context = BlockContext::create_for_account_testing();
+        let mut block_info = block_context.block_info().clone();
+        block_info.sequencer_address = ContractAddress::try_from(sequencer).unwrap();
+        let block_context = BlockContext::new(
+            block_info,
+            block_context.chain_info().clone(),
+            block_context.versioned_constants().clone(),
+            BouncerConfig::max(),
+        );
+
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::scopes::OS_INPUT, Rc::new(os_input));
+        exec_scopes.insert_value(vars::scopes::BLOCK_CONTEXT, block_context);
+        exec_scopes.insert_value(vars::scopes::ASSERTION_MODE, assertion_mode);
+
+        let result = sequencer_address(&mut vm, &mut exec_scopes, &HashMap::new(), &ApTracking::new(), &HashMap::new());
+        match expected {
+            Some(expected) => {
+                result.unwrap();
+                assert_eq!(vm.get_integer(vm.get_ap()).unwrap().into_owned(), expected);
+            }
+            None => assert!(matches!(result, Err(HintError::AssertionFailed(_)))),
+        }
+    }
+
+    #[test]
+    fn test_get_gas_prices() {
+        let gas_price = |price: u128| NonZeroU128::new(price).unwrap();
+        let block_context = BlockContext::create_for_account_testing();
+        let mut block_info = block_context.block_info().clone();
+        block_info.gas_prices = GasPrices {
+            eth_l1_gas_price: gas_price(11),
+            strk_l1_gas_price: gas_price(22),
+            eth_l1_data_gas_price: gas_price(33),
+            strk_l1_data_gas_price: gas_price(44),
+        };
+        let block_context = BlockContext::new(
+            block_info,
+            block_context.chain_info().clone(),
+            block_context.versioned_constants().clone(),
+            BouncerConfig::max(),
+        );
+
+        let var_names = [
+            vars::ids::L1_GAS_PRICE_WEI,
+            vars::ids::L1_GAS_PRICE_FRI,
+            vars::ids::L1_DATA_GAS_PRICE_WEI,
+            vars::ids::L1_DATA_GAS_PRICE_FRI,
+        ];
+        let ids_data: HashMap<_, _> = var_names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.to_string(), HintReference::new_simple(i as i32 - var_names.len() as i32)))
+            .collect();
+
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(var_names.len());
+
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::scopes::BLOCK_CONTEXT, block_context);
+
+        let ap_tracking = ApTracking::new();
+        get_gas_prices(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &HashMap::new())
+            .expect("get_gas_prices hint failed");
+
+        let prices: Vec<_> = var_names
+            .iter()
+            .map(|name| get_integer_from_var_name(name, &vm, &ids_data, &ap_tracking).unwrap())
+            .collect();
+        assert_eq!(prices, vec![Felt252::from(11), Felt252::from(22), Felt252::from(33), Felt252::from(44)]);
+    }
+    #[test]
+    fn test_get_block_info_deprecated() {
+        let block_context = BlockContext::create_for_account_testing();
+        let mut block_info = block_context.block_info().clone();
+        block_info.block_number = BlockNumber(1000);
+        block_info.block_timestamp = BlockTimestamp(2000);
+        block_info.sequencer_address = contract_address!("0x3000");
+        let block_context = BlockContext::new(
+            block_info,
+            block_context.chain_info().clone(),
+            block_context.versioned_constants().clone(),
+            BouncerConfig::max(),
+        );
+
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+        let deprecated_block_info_ptr = vm.add_memory_segment();
+        vm.insert_value(Relocatable::from((1, 0)), deprecated_block_info_ptr).unwrap();
+        let ids_data = HashMap::from([(vars::ids::DEPRECATED_BLOCK_INFO.to_string(), HintReference::new_simple(-1))]);
+
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::scopes::BLOCK_CONTEXT, block_context);
+
+        get_block_info_deprecated(&mut vm, &mut exec_scopes, &ids_data, &ApTracking::new(), &HashMap::new())
+            .expect("get_block_info_deprecated hint failed");
+
+        assert_eq!(
+            DeprecatedBlockInfoLayout::from_memory(&vm, deprecated_block_info_ptr).unwrap(),
+            DeprecatedBlockInfoLayout {
+                block_timestamp: Felt252::from(2000),
+                block_number: Felt252::from(1000),
+                sequencer_address: Felt252::from(0x3000),
+            }
+        );
+
+        // Same size as the modern struct, but the block number and timestamp are swapped
+        assert_eq!(DeprecatedBlockInfoLayout::n_fields(), BlockInfo::cairo_size());
+        let read_at =
+            |offset: usize| vm.get_integer((deprecated_block_info_ptr + offset).unwrap()).unwrap().into_owned();
+        assert_eq!(read_at(BlockInfo::block_number_offset()), Felt252::from(2000));
+        assert_eq!(read_at(BlockInfo::block_timestamp_offset()), Felt252::from(1000));
+        assert_eq!(read_at(BlockInfo::sequencer_address_offset()), Felt252::from(0x3000));
+    }
+
+    #[fixture]
+    fn compiled_class() -> GenericCasmContractClass {
+        let casm_bytes = include_bytes!(
+            "../../../../tests/integration/contracts/blockifier_contracts/feature_contracts/cairo1/compiled/\
+             test_contract.casm.json"
+        );
+        GenericCasmContractClass::from_bytes(casm_bytes.to_vec())
+    }
+
+    #[rstest]
+    fn test_find_class_entry_point(compiled_class: GenericCasmContractClass) {
+        let selector = get_selector_from_name("xor_counters");
+
+        let (offset, builtins) = find_class_entry_point(&compiled_class, selector, EntryPointType::External).unwrap();
+        assert_eq!(offset, 3098);
+        assert_eq!(builtins, vec!["pedersen", "range_check", "bitwise"]);
+
+        // The selector is not a constructor
+        assert!(find_class_entry_point(&compiled_class, selector, EntryPointType::Constructor).is_err());
+    }
+
+    #[rstest]
+    #[case::with_default_entry_point(true)]
+    #[case::without_default_entry_point(false)]
+    fn test_find_class_entry_point_missing_selector(
+        compiled_class: GenericCasmContractClass,
+        #[case] has_default_entry_point: bool,
+    ) {
+        let mut cairo_lang_class = compiled_class.to_cairo_lang_contract_class().unwrap();
+        if has_default_entry_point {
+            let mut default_entry_point = cairo_lang_class.entry_points_by_type.external[0].clone();
+            default_entry_point.selector = get_selector_from_name("__default__").to_biguint();
+            default_entry_point.offset = 1234;
+            cairo_lang_class.entry_points_by_type.external.push(default_entry_point);
+        }
+        let compiled_class = GenericCasmContractClass::from(cairo_lang_class);
+
+        let selector = get_selector_from_name("no_such_entry_point");
+        let result = find_class_entry_point(&compiled_class, selector, EntryPointType::External);
+
+        if has_default_entry_point {
+            let (offset, _) = result.unwrap();
+            assert_eq!(offset, 1234);
+        } else {
+            assert!(result.is_err());
+        }
+    }
+
+    #[rstest]
+    fn test_find_class_entry_point_hint(compiled_class: GenericCasmContractClass) {
+        let var_names = [
+            vars::ids::SELECTOR,
+            vars::ids::ENTRY_POINT_TYPE,
+            vars::ids::ENTRY_POINT_OFFSET,
+            vars::ids::N_BUILTINS,
+            vars::ids::BUILTIN_LIST,
+        ];
+        let ids_data: HashMap<_, _> = var_names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.to_string(), HintReference::new_simple(i as i32 - var_names.len() as i32)))
+            .collect();
+
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(var_names.len());
+        let selector = get_selector_from_name("xor_counters");
+        vm.insert_value(Relocatable::from((1, 0)), selector).unwrap();
+        vm.insert_value(Relocatable::from((1, 1)), Felt252::ZERO).unwrap();
+
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::scopes::COMPILED_CLASS, compiled_class);
+
+        let ap_tracking = ApTracking::new();
+        find_class_entry_point_hint(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &HashMap::new())
+            .expect("find_class_entry_point hint failed");
+
+        let offset = get_integer_from_var_name(vars::ids::ENTRY_POINT_OFFSET, &vm, &ids_data, &ap_tracking).unwrap();
+        assert_eq!(offset, Felt252::from(3098));
+        let n_builtins = get_integer_from_var_name(vars::ids::N_BUILTINS, &vm, &ids_data, &ap_tracking).unwrap();
+        assert_eq!(n_builtins, Felt252::THREE);
+
+        let builtin_list = get_ptr_from_var_name(vars::ids::BUILTIN_LIST, &vm, &ids_data, &ap_tracking).unwrap();
+        let builtins: Vec<_> =
+            vm.get_integer_range(builtin_list, 3).unwrap().into_iter().map(|felt| felt.into_owned()).collect();
+        let expected_builtins: Vec<_> =
+            ["pedersen", "range_check", "bitwise"].iter().map(|b| Felt252::from_bytes_be_slice(b.as_bytes())).collect();
+        assert_eq!(builtins, expected_builtins);
+    }
+}
@@ -9,15 +9,22 @@ use cairo_vm::hint_processor::hint_processor_definition::HintReference;
 use cairo_vm::hint_processor::hint_processor_utils::felt_to_usize;
 use cairo_vm::serde::deserialize_program::ApTracking;
 use cairo_vm::types::exec_scope::ExecutionScopes;
-use cairo_vm::types::relocatable::MaybeRelocatable;
+use cairo_vm::types::relocatable::{MaybeRelocatable, Relocatable};
 use cairo_vm::vm::errors::hint_errors::HintError;
 use cairo_vm::vm::vm_core::VirtualMachine;
 use cairo_vm::Felt252;
 use indoc::indoc;
 use num_traits::Zero;
+use starknet_types_core::curve::ProjectivePoint;
 
 use crate::cairo_types::structs::BuiltinParams;
+use crate::cairo_types::traits::CairoType;
+use crate::execution::helper::ExecutionHelperWrapper;
+use crate::hints::assertions::hint_assert;
+use crate::hints::layout::{SegmentArenaBuiltinLayout, SegmentInfoLayout};
 use crate::hints::vars;
+use crate::starknet::starknet_storage::PerContractStorage;
+use crate::utils::execute_coroutine;
 
 pub const SELECTED_BUILTINS: &str = "vm_enter_scope({'n_selected_builtins': ids.n_selected_builtins})";
 pub fn selected_builtins(
@@ -121,3 +128,521 @@ pub fn update_builtin_ptrs(
     vm.load_data(return_builtin_ptrs_base, &returned_builtins)?;
     insert_value_from_var_name(vars::ids::RETURN_BUILTIN_PTRS, return_builtin_ptrs_base, vm, ids_data, ap_tracking)
 }
+
+/// Number of 200-bit words in the input (and output) of a keccak builtin instance.
+const KECCAK_STATE_N_WORDS: usize = 8;
+/// Size of a keccak builtin instance: the input state followed by the output state.
+const KECCAK_BUILTIN_INSTANCE_SIZE: usize = 2 * KECCAK_STATE_N_WORDS;
+/// Size of a 200-bit word of the keccak builtin, in bytes.
+const KECCAK_WORD_N_BYTES: usize = 25;
+
+pub const FILL_KECCAK_BUILTIN_OUTPUTS: &str = indoc! {r#"
+    from starkware.cairo.common.keccak_utils.keccak_utils import keccak_func
+
+    for i in range(ids.n_blocks):
+        instance = ids.keccak_ptr.address_ + i * ids.KeccakBuiltin.SIZE
+        input_state = b"".join(
+            memory[instance + j].to_bytes(25, "little") for j in range(ids.KeccakBuiltinState.SIZE)
+        )
+        output_state = keccak_func(input_state)
+        for j in range(ids.KeccakBuiltinState.SIZE):
+            memory[instance + ids.KeccakBuiltinState.SIZE + j] = int.from_bytes(
+                output_state[25 * j : 25 * (j + 1)], "little"
+            )"#
+};
+
+/// Applies the keccak-f[1600] permutation to a state given as 8 little-endian 200-bit words,
+/// as laid out in the keccak builtin segment.
+fn keccak_builtin_permutation(input: &[Felt252]) -> Result<Vec<Felt252>, HintError> {
+    let mut state_bytes = Vec::with_capacity(KECCAK_STATE_N_WORDS * KECCAK_WORD_N_BYTES);
+    for word in input {
+        let word_bytes = word.to_bytes_le();
+        if word_bytes[KECCAK_WORD_N_BYTES..].iter().any(|byte| *byte != 0) {
+            return Err(HintError::AssertionFailed(
+                format!("Keccak builtin input {word:#x} does not fit in 200 bits").into_boxed_str(),
+            ));
+        }
+        state_bytes.extend_from_slice(&word_bytes[..KECCAK_WORD_N_BYTES]);
+    }
+
+    let mut state = [0u64; 25];
+    for (lane, bytes) in state.iter_mut().zip(state_bytes.chunks(8)) {
+        *lane = u64::from_le_bytes(bytes.try_into().expect("the state is made of 8-byte lanes"));
+    }
+    keccak::f1600(&mut state);
+
+    let state_bytes: Vec<u8> = state.iter().flat_map(|lane| lane.to_le_bytes()).collect();
+    Ok(state_bytes.chunks(KECCAK_WORD_N_BYTES).map(Felt252::from_bytes_le_slice).collect())
+}
+
+/// Writes the output state of each of the `n_blocks` keccak builtin instances starting at
+/// `keccak_ptr`, computed from their input state.
+pub fn fill_keccak_builtin_outputs(
+    vm: &mut VirtualMachine,
+    _exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError> {
+    let keccak_ptr = get_ptr_from_var_name(vars::ids::KECCAK_PTR, vm, ids_data, ap_tracking)?;
+    let n_blocks = felt_to_usize(&get_integer_from_var_name(vars::ids::N_BLOCKS, vm, ids_data, ap_tracking)?)?;
+
+    for block_index in 0..n_blocks {
+        let input_ptr = (keccak_ptr + block_index * KECCAK_BUILTIN_INSTANCE_SIZE)?;
+        let input: Vec<Felt252> =
+            vm.get_integer_range(input_ptr, KECCAK_STATE_N_WORDS)?.into_iter().map(|word| word.into_owned()).collect();
+        let output = keccak_builtin_permutation(&input)?;
+
+        let output: Vec<MaybeRelocatable> = output.into_iter().map(MaybeRelocatable::from).collect();
+        vm.load_data((input_ptr + KECCAK_STATE_N_WORDS)?, &output)?;
+    }
+
+    Ok(())
+}
+
+/// Size of the input of an ec_op builtin instance: the points p and q, and the scalar m.
+const EC_OP_INPUT_SIZE: usize = 5;
+/// Size of an ec_op builtin instance: the input followed by the resulting point.
+const EC_OP_BUILTIN_INSTANCE_SIZE: usize = EC_OP_INPUT_SIZE + 2;
+
+pub const FILL_EC_OP_BUILTIN_OUTPUTS: &str = indoc! {r#"
+    from starkware.crypto.signature.signature import ALPHA, FIELD_PRIME
+    from starkware.python.math_utils import ec_safe_add, ec_safe_mult
+
+    for i in range(ids.n_instances):
+        instance = ids.ec_op_ptr.address_ + i * ids.EcOpBuiltin.SIZE
+        p = (memory[instance], memory[instance + 1])
+        q = (memory[instance + 2], memory[instance + 3])
+        m = memory[instance + 4]
+        r = ec_safe_add(p, ec_safe_mult(m, q, ALPHA, FIELD_PRIME), ALPHA, FIELD_PRIME)
+        memory[instance + 5], memory[instance + 6] = r"#
+};
+
+/// Computes p + m * q over the STARK curve, as done by the ec_op builtin.
+fn ec_op(p: (Felt252, Felt252), q: (Felt252, Felt252), m: Felt252) -> Result<(Felt252, Felt252), HintError> {
+    let to_point = |(x, y): (Felt252, Felt252)| {
+        ProjectivePoint::from_affine(x, y).map_err(|_| {
+            HintError::AssertionFailed(format!("ec_op input ({x:#x}, {y:#x}) is not on the curve").into_boxed_str())
+        })
+    };
+    let p = to_point(p)?;
+    let q = to_point(q)?;
+
+    let r = (p + &q * m).to_affine().map_err(|_| {
+        HintError::AssertionFailed("ec_op result is the point at infinity".to_string().into_boxed_str())
+    })?;
+
+    Ok((r.x(), r.y()))
+}
+
+/// Writes the resulting point of each of the `n_instances` ec_op builtin instances starting at
+/// `ec_op_ptr`, computed from their input.
+pub fn fill_ec_op_builtin_outputs(
+    vm: &mut VirtualMachine,
+    _exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError> {
+    let ec_op_ptr = get_ptr_from_var_name(vars::ids::EC_OP_PTR, vm, ids_data, ap_tracking)?;
+    let n_instances = felt_to_usize(&get_integer_from_var_name(vars::ids::N_INSTANCES, vm, ids_data, ap_tracking)?)?;
+
+    for instance_index in 0..n_instances {
+        let instance_ptr = (ec_op_ptr + instance_index * EC_OP_BUILTIN_INSTANCE_SIZE)?;
+        let input: Vec<Felt252> =
+            vm.get_integer_range(instance_ptr, EC_OP_INPUT_SIZE)?.into_iter().map(|value| value.into_owned()).collect();
+        let (r_x, r_y) = ec_op((input[0], input[1]), (input[2], input[3]), input[4])?;
+
+        vm.load_data((instance_ptr + EC_OP_INPUT_SIZE)?, &[r_x.into(), r_y.into()])?;
+    }
+
+    Ok(())
+}
+
+/// Size of the input of a bitwise builtin instance: the operands x and y.
+const BITWISE_INPUT_SIZE: usize = 2;
+/// Size of a bitwise builtin instance: the operands followed by x & y, x ^ y and x | y.
+const BITWISE_BUILTIN_INSTANCE_SIZE: usize = BITWISE_INPUT_SIZE + 3;
+/// Number of bits of the operands of the bitwise builtin.
+const BITWISE_N_BITS: usize = 251;
+
+pub const FILL_BITWISE_BUILTIN_OUTPUTS: &str = indoc! {r#"
+    for i in range(ids.n_instances):
+        instance = ids.bitwise_ptr.address_ + i * ids.BitwiseBuiltin.SIZE
+        x = memory[instance]
+        y = memory[instance + 1]
+        assert x < 2**251 and y < 2**251, f"Bitwise builtin inputs {x}, {y} must be less than 2**251."
+        memory[instance + 2] = x & y
+        memory[instance + 3] = x ^ y
+        memory[instance + 4] = x | y"#
+};
+
+/// Computes (x & y, x ^ y, x | y), as done by the bitwise builtin.
+fn bitwise(x: Felt252, y: Felt252) -> Result<(Felt252, Felt252, Felt252), HintError> {
+    for value in [x, y] {
+        if value.bits() > BITWISE_N_BITS {
+            return Err(HintError::AssertionFailed(
+                format!("Bitwise builtin input {value:#x} does not fit in {BITWISE_N_BITS} bits").into_boxed_str(),
+            ));
+        }
+    }
+    let (x, y) = (x.to_biguint(), y.to_biguint());
+
+    Ok((Felt252::from(&x & &y), Felt252::from(&x ^ &y), Felt252::from(&x | &y)))
+}
+
+/// Writes the outputs of each of the `n_instances` bitwise builtin instances starting at
+/// `bitwise_ptr`, computed from their operands.
+pub fn fill_bitwise_builtin_outputs(
+    vm: &mut VirtualMachine,
+    _exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError> {
+    let bitwise_ptr = get_ptr_from_var_name(vars::ids::BITWISE_PTR, vm, ids_data, ap_tracking)?;
+    let n_instances = felt_to_usize(&get_integer_from_var_name(vars::ids::N_INSTANCES, vm, ids_data, ap_tracking)?)?;
+
+    for instance_index in 0..n_instances {
+        let instance_ptr = (bitwise_ptr + instance_index * BITWISE_BUILTIN_INSTANCE_SIZE)?;
+        let input: Vec<Felt252> = vm
+            .get_integer_range(instance_ptr, BITWISE_INPUT_SIZE)?
+            .into_iter()
+            .map(|value| value.into_owned())
+            .collect();
+        let (x_and_y, x_xor_y, x_or_y) = bitwise(input[0], input[1])?;
+
+        vm.load_data((instance_ptr + BITWISE_INPUT_SIZE)?, &[x_and_y.into(), x_xor_y.into(), x_or_y.into()])?;
+    }
+
+    Ok(())
+}
+
+pub const FINALIZE_SEGMENT_ARENA: &str = indoc! {r#"
+    assert ids.segment_arena.n_finalized == ids.segment_arena.n_segments, (
+        f"Only {ids.segment_arena.n_finalized} out of {ids.segment_arena.n_segments} segments of the "
+        "segment arena were squashed."
+    )
+    for i in range(1, ids.segment_arena.n_segments):
+        memory.add_relocation_rule(
+            src_ptr=ids.segment_arena.infos[i].start, dest_ptr=ids.segment_arena.infos[i - 1].end + 1
+        )"#
+};
+
+/// Checks that all the segments allocated in the segment arena were squashed, then relocates
+/// them so that each segment directly follows the previous one. The segments are counted in the
+/// resources used by the run, see `ExecutionHelperWrapper::segment_arena_n_segments`.
+pub fn finalize_segment_arena<PCS>(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let segment_arena_ptr = get_ptr_from_var_name(vars::ids::SEGMENT_ARENA, vm, ids_data, ap_tracking)?;
+    let segment_arena = SegmentArenaBuiltinLayout::from_memory(vm, segment_arena_ptr)?;
+    hint_assert!(
+        exec_scopes,
+        segment_arena.n_finalized == segment_arena.n_segments,
+        "Only {} out of {} segments of the segment arena were squashed",
+        segment_arena.n_finalized,
+        segment_arena.n_segments
+    );
+
+    let n_segments = felt_to_usize(&segment_arena.n_segments)?;
+    let mut previous_end: Option<Relocatable> = None;
+    for segment_index in 0..n_segments {
+        let info_ptr = (segment_arena.infos + segment_index * SegmentInfoLayout::n_fields())?;
+        let info = SegmentInfoLayout::from_memory(vm, info_ptr).map_err(|_| {
+            HintError::AssertionFailed(
+                format!("Segment {segment_index} of the segment arena is not finalized").into_boxed_str(),
+            )
+        })?;
+
+        // Segments allocated as temporary segments are moved right after the previous one
+        if let Some(previous_end) = previous_end {
+            if info.start.segment_index < 0 {
+                vm.add_relocation_rule(info.start, (previous_end + 1usize)?)?;
+            }
+        }
+        previous_end = Some(info.end);
+    }
+
+    // Arenas finalized outside of an OS run, e.g. in hint tests, are not accounted for
+    if let Ok(execution_helper) = exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER) {
+        execute_coroutine(execution_helper.add_segment_arena_segments(n_segments))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use blockifier::context::BlockContext;
+
+    use super::*;
+    use crate::crypto::pedersen::PedersenHash;
+    use crate::execution::helper::ContractStorageMap;
+    use crate::starknet::starknet_storage::OsSingleStarknetStorage;
+    use crate::storage::dict_storage::DictStorage;
+
+    type PCS = OsSingleStarknetStorage<DictStorage, PedersenHash>;
+
+    /// Output of keccak-f[1600] applied to the all-zero state.
+    const ZERO_STATE_PERMUTATION: [&str; KECCAK_STATE_N_WORDS] = [
+        "0x4dd598261ea65aa9ee84d5ccf933c0478af1258f7940e1dde7",
+        "0x47c4ff97a42d7f8e6fd48b284e056253d057bd1547306f8049",
+        "0x8ffc64ad30a6f71b19059c8c5bda0cd6192e7690fee5a0a446",
+        "0xdbcf555fa9a6e6260d712103eb5aa93f2317d63530935ab7d0",
+        "0x5a21d9ae6101f22f1a11a5569f43b831cd0347c82681a57c16",
+        "0x5a554fd00ecb613670957bc4661164befef28cc970f205e563",
+        "0x41f924a2c509e4940c7922ae3a26148c3ee88a1ccf32c8b87c",
+        "0xeaf1ff7b5ceca24975f644e97f30a13b16f53526e70465c218",
+    ];
+
+    fn keccak_ids_data() -> HashMap<String, HintReference> {
+        HashMap::from([
+            (vars::ids::KECCAK_PTR.to_string(), HintReference::new_simple(-2)),
+            (vars::ids::N_BLOCKS.to_string(), HintReference::new_simple(-1)),
+        ])
+    }
+
+    #[test]
+    fn test_fill_keccak_builtin_outputs() {
+        let mut vm = VirtualMachine::new(false);
+        vm.set_fp(2);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        let keccak_ptr = vm.add_memory_segment();
+        vm.load_data((1, 0).into(), &[keccak_ptr.into(), Felt252::ONE.into()]).unwrap();
+        let zero_state = vec![MaybeRelocatable::from(Felt252::ZERO); KECCAK_STATE_N_WORDS];
+        vm.load_data(keccak_ptr, &zero_state).unwrap();
+
+        fill_keccak_builtin_outputs(
+            &mut vm,
+            &mut ExecutionScopes::new(),
+            &keccak_ids_data(),
+            &ApTracking::new(),
+            &HashMap::new(),
+        )
+        .unwrap();
+
+        let output = vm.get_integer_range((keccak_ptr + KECCAK_STATE_N_WORDS).unwrap(), KECCAK_STATE_N_WORDS).unwrap();
+        let expected: Vec<Felt252> =
+            ZERO_STATE_PERMUTATION.iter().map(|word| Felt252::from_hex_unchecked(word)).collect();
+        assert_eq!(output.into_iter().map(|word| word.into_owned()).collect::<Vec<_>>(), expected);
+        // The first lane of the state is the low 64 bits of the first word
+        assert_eq!(expected[0].to_bytes_le()[..8], 0xf1258f7940e1dde7u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_keccak_builtin_permutation_rejects_large_words() {
+        let mut input = vec![Felt252::ZERO; KECCAK_STATE_N_WORDS];
+        input[3] = Felt252::TWO.pow(200u32);
+
+        assert!(matches!(keccak_builtin_permutation(&input), Err(HintError::AssertionFailed(_))));
+    }
+
+    #[test]
+    fn test_fill_ec_op_builtin_outputs() {
+        let mut vm = VirtualMachine::new(false);
+        vm.set_fp(2);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        let ec_op_ptr = vm.add_memory_segment();
+        vm.load_data((1, 0).into(), &[ec_op_ptr.into(), Felt252::ONE.into()]).unwrap();
+        // Vector of the ec_op builtin runner of cairo-vm
+        let input = [
+            Felt252::from_hex_unchecked("0x68caa9509b7c2e90b4d92661cbf7c465471c1e8598c5f989691eef6653e0f38"),
+            Felt252::from_hex_unchecked("0x79a8673f498531002fc549e06ff2010ffc0c191cceb7da5532acb95cdcb591"),
+            Felt252::from_hex_unchecked("0x1ef15c18599971b7beced415a40f0c7deacfd9b0d1819e03d723d8bc943cfca"),
+            Felt252::from_hex_unchecked("0x5668060aa49730b7be4801df46ec62de53ecd11abe43a32873000c36e8dc1f"),
+            Felt252::from(34),
+        ];
+        let input: Vec<MaybeRelocatable> = input.into_iter().map(MaybeRelocatable::from).collect();
+        vm.load_data(ec_op_ptr, &input).unwrap();
+
+        let ids_data = HashMap::from([
+            (vars::ids::EC_OP_PTR.to_string(), HintReference::new_simple(-2)),
+            (vars::ids::N_INSTANCES.to_string(), HintReference::new_simple(-1)),
+        ]);
+        fill_ec_op_builtin_outputs(
+            &mut vm,
+            &mut ExecutionScopes::new(),
+            &ids_data,
+            &ApTracking::new(),
+            &HashMap::new(),
+        )
+        .unwrap();
+
+        let output = vm.get_integer_range((ec_op_ptr + EC_OP_INPUT_SIZE).unwrap(), 2).unwrap();
+        let expected = [
+            Felt252::from_dec_str("2778063437308421278851140253538604815869848682781135193774472480292420096757")
+                .unwrap(),
+            Felt252::from_dec_str("3598390311618116577316045819420613574162151407434885460365915347732568210029")
+                .unwrap(),
+        ];
+        assert_eq!(output.into_iter().map(|value| value.into_owned()).collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_fill_bitwise_builtin_outputs() {
+        let mut vm = VirtualMachine::new(false);
+        vm.set_fp(2);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        let bitwise_ptr = vm.add_memory_segment();
+        vm.load_data((1, 0).into(), &[bitwise_ptr.into(), Felt252::TWO.into()]).unwrap();
+        let operands = [(0b1100, 0b1010), (0xff00, 0x0ff0)];
+        for (i, (x, y)) in operands.into_iter().enumerate() {
+            let instance_ptr = (bitwise_ptr + i * BITWISE_BUILTIN_INSTANCE_SIZE).unwrap();
+            vm.load_data(instance_ptr, &[Felt252::from(x).into(), Felt252::from(y).into()]).unwrap();
+        }
+
+        let ids_data = HashMap::from([
+            (vars::ids::BITWISE_PTR.to_string(), HintReference::new_simple(-2)),
+            (vars::ids::N_INSTANCES.to_string(), HintReference::new_simple(-1)),
+        ]);
+        fill_bitwise_builtin_outputs(
+            &mut vm,
+            &mut ExecutionScopes::new(),
+            &ids_data,
+            &ApTracking::new(),
+            &HashMap::new(),
+        )
+        .unwrap();
+
+        let outputs: Vec<Vec<Felt252>> = (0..operands.len())
+            .map(|i| {
+                let output_ptr = (bitwise_ptr + i * BITWISE_BUILTIN_INSTANCE_SIZE + BITWISE_INPUT_SIZE).unwrap();
+                vm.get_integer_range(output_ptr, 3).unwrap().into_iter().map(|value| value.into_owned()).collect()
+            })
+            .collect();
+        assert_eq!(
+            outputs,
+            vec![
+                vec![Felt252::from(0b1000), Felt252::from(0b0110), Felt252::from(0b1110)],
+                vec![Felt252::from(0x0f00), Felt252::from(0xf0f0), Felt252::from(0xfff0)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bitwise_rejects_large_inputs() {
+        let x = Felt252::TWO.pow(251u32);
+
+        assert!(matches!(bitwise(x, Felt252::ONE), Err(HintError::AssertionFailed(_))));
+        assert!(bitwise(x - Felt252::ONE, Felt252::ONE).is_ok());
+    }
+
+    #[test]
+    fn test_ec_op_rejects_points_off_curve() {
+        let p = (Felt252::ONE, Felt252::TWO);
+        let q = (Felt252::ONE, Felt252::TWO);
+
+        assert!(matches!(ec_op(p, q, Felt252::ONE), Err(HintError::AssertionFailed(_))));
+    }
+
+    fn segment_arena_ids_data() -> HashMap<String, HintReference> {
+        HashMap::from([(vars::ids::SEGMENT_ARENA.to_string(), HintReference::new_simple(-1))])
+    }
+
+    /// Builds a segment arena with two segments, the second one being a temporary segment.
+    /// Only the first `n_finalized` segments are squashed.
+    fn vm_with_segment_arena(n_finalized: usize) -> (VirtualMachine, Relocatable) {
+        let mut vm = VirtualMachine::new(false);
+        vm.set_fp(1);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        let segment_arena_ptr = vm.add_memory_segment();
+        let infos = vm.add_memory_segment();
+        vm.insert_value((1, 0).into(), segment_arena_ptr).unwrap();
+
+        let first_segment = vm.add_memory_segment();
+        let second_segment = vm.add_temporary_segment();
+        vm.insert_value(second_segment, Felt252::from(42)).unwrap();
+
+        let segments = [(first_segment, 3usize), (second_segment, 1usize)];
+        for (index, (start, size)) in segments.into_iter().enumerate() {
+            let info_ptr = (infos + index * SegmentInfoLayout::n_fields()).unwrap();
+            vm.insert_value(info_ptr, start).unwrap();
+            if index < n_finalized {
+                vm.insert_value((info_ptr + 1usize).unwrap(), (start + size).unwrap()).unwrap();
+                vm.insert_value((info_ptr + 2usize).unwrap(), Felt252::from(index)).unwrap();
+            }
+        }
+
+        let segment_arena = SegmentArenaBuiltinLayout {
+            infos,
+            n_segments: Felt252::from(segments.len()),
+            n_finalized: Felt252::from(n_finalized),
+        };
+        segment_arena.to_memory(&mut vm, segment_arena_ptr).unwrap();
+
+        (vm, first_segment)
+    }
+
+    #[test]
+    fn test_finalize_segment_arena() {
+        let (mut vm, first_segment) = vm_with_segment_arena(2);
+
+        finalize_segment_arena::<PCS>(
+            &mut vm,
+            &mut ExecutionScopes::new(),
+            &segment_arena_ids_data(),
+            &ApTracking::new(),
+            &HashMap::new(),
+        )
+        .expect("Finalization should succeed");
+
+        // The second segment is moved right after the end of the first one
+        vm.segments.memory.relocate_memory().unwrap();
+        assert_eq!(vm.get_integer((first_segment + 4usize).unwrap()).unwrap().into_owned(), Felt252::from(42));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_finalize_segment_arena_counts_segments() {
+        let execution_helper = ExecutionHelperWrapper::<PCS>::new(
+            ContractStorageMap::default(),
+            vec![],
+            &BlockContext::create_for_account_testing(),
+            None,
+            (Felt252::ZERO, Felt252::ZERO),
+        );
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, execution_helper.clone());
+
+        // Two arenas of two segments each, e.g. finalized by two calls
+        for _ in 0..2 {
+            let (mut vm, _) = vm_with_segment_arena(2);
+            finalize_segment_arena::<PCS>(
+                &mut vm,
+                &mut exec_scopes,
+                &segment_arena_ids_data(),
+                &ApTracking::new(),
+                &HashMap::new(),
+            )
+            .expect("Finalization should succeed");
+        }
+
+        assert_eq!(execution_helper.segment_arena_n_segments().await, 4);
+    }
+
+    #[test]
+    fn test_finalize_segment_arena_with_unsquashed_segment() {
+        let (mut vm, _) = vm_with_segment_arena(1);
+
+        let result = finalize_segment_arena::<PCS>(
+            &mut vm,
+            &mut ExecutionScopes::new(),
+            &segment_arena_ids_data(),
+            &ApTracking::new(),
+            &HashMap::new(),
+        );
+
+        assert!(matches!(result, Err(HintError::AssertionFailed(_))));
+    }
+}
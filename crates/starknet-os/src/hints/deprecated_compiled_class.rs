@@ -14,7 +14,7 @@ use cairo_vm::Felt252;
 use indoc::indoc;
 use starknet_os_types::deprecated_compiled_class::GenericDeprecatedCompiledClass;
 
-use crate::hints::vars;
+use crate::hints::{get_typed, vars};
 use crate::io::classes::get_deprecated_contract_class_struct;
 use crate::io::input::StarknetOsInput;
 use crate::utils::custom_hint_error;
@@ -111,7 +111,7 @@ pub fn load_deprecated_class(
 ) -> Result<HintExtension, HintError> {
     let computed_hash_addr = get_ptr_from_var_name(vars::ids::COMPILED_CLASS_FACT, vm, ids_data, ap_tracking)?;
     let computed_hash = vm.get_integer(computed_hash_addr)?;
-    let expected_hash = exec_scopes.get::<Felt252>(vars::scopes::COMPILED_CLASS_HASH).unwrap();
+    let expected_hash = get_typed::<Felt252>(exec_scopes, vars::scopes::COMPILED_CLASS_HASH)?;
 
     if computed_hash.as_ref() != &expected_hash {
         return Err(HintError::AssertionFailed(
@@ -17,6 +17,7 @@ use crate::cairo_types::contract_class::ContractClassComponentHashes;
 use crate::cairo_types::structs::ExecutionContext;
 use crate::execution::helper::ExecutionHelperWrapper;
 use crate::execution::syscall_handler::OsSyscallHandlerWrapper;
+use crate::hints::assertions::hint_assert;
 use crate::hints::vars;
 use crate::io::InternalTransaction;
 use crate::starknet::starknet_storage::PerContractStorage;
@@ -163,16 +164,13 @@ pub fn set_component_hashes(
         )
     })?;
 
-    if class_component_hashes.len() != ContractClassComponentHashes::cairo_size() {
-        return Err(HintError::AssertionFailed(
-            format!(
-                "Wrong number of class component hashes: got {}, expected {}",
-                ContractClassComponentHashes::cairo_size(),
-                class_component_hashes.len()
-            )
-            .into_boxed_str(),
-        ));
-    }
+    hint_assert!(
+        exec_scopes,
+        class_component_hashes.len() == ContractClassComponentHashes::cairo_size(),
+        "Wrong number of class component hashes: got {}, expected {}",
+        ContractClassComponentHashes::cairo_size(),
+        class_component_hashes.len()
+    );
 
     let class_component_hashes: Vec<_> = class_component_hashes.iter().map(MaybeRelocatable::from).collect();
 
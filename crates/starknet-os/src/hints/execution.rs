@@ -2,12 +2,16 @@ use std::any::Any;
 use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::vec::IntoIter;
 
+use blockifier::context::BlockContext;
+use blockifier::transaction::constants::QUERY_VERSION_BASE_BIT;
 use cairo_vm::hint_processor::builtin_hint_processor::dict_manager::Dictionary;
 use cairo_vm::hint_processor::builtin_hint_processor::hint_utils::{
-    get_integer_from_var_name, get_maybe_relocatable_from_var_name, get_ptr_from_var_name, insert_value_from_var_name,
-    insert_value_into_ap,
+    get_integer_from_var_name, get_maybe_relocatable_from_var_name, get_ptr_from_var_name,
+    get_relocatable_from_var_name, insert_value_from_var_name, insert_value_into_ap,
 };
 use cairo_vm::hint_processor::hint_processor_definition::HintReference;
 use cairo_vm::hint_processor::hint_processor_utils::felt_to_usize;
@@ -21,23 +25,32 @@ use cairo_vm::{any_box, Felt252};
 use indoc::indoc;
 use num_bigint::BigUint;
 use num_traits::ToPrimitive;
+use starknet_api::deprecated_contract_class::ContractClassAbiEntry;
+use starknet_os_types::chain_id::chain_id_to_felt;
 
 use crate::cairo_types::new_syscalls;
 use crate::cairo_types::structs::{EntryPointReturnValues, ExecutionContext};
 use crate::cairo_types::syscalls::{CallContractResponse, StorageRead, StorageReadRequest, StorageWrite, TxInfo};
+use crate::cairo_types::traits::CairoType;
 use crate::execution::deprecated_syscall_handler::DeprecatedOsSyscallHandlerWrapper;
 use crate::execution::helper::ExecutionHelperWrapper;
 use crate::execution::syscall_handler::OsSyscallHandlerWrapper;
 use crate::execution::syscall_handler_utils::SyscallSelector;
-use crate::hints::types::{PatriciaSkipValidationRunner, Preimage};
-use crate::hints::vars;
+use crate::hints::assertions::hint_assert;
+use crate::hints::layout::{StateEntryLayout, TxInfoLayout, TxInfoV3Layout};
+use crate::hints::types::{L1ToL2Messages, PatriciaSkipValidationRunner, Preimage};
+use crate::hints::{get_typed, vars};
 use crate::io::input::StarknetOsInput;
 use crate::io::InternalTransaction;
+use crate::starknet::core::os::messages::compute_l1_to_l2_message_hash;
 use crate::starknet::core::os::transaction_hash::create_resource_bounds_list;
 use crate::starknet::starknet_storage::{PerContractStorage, StorageLeaf};
 use crate::starkware_utils::commitment_tree::base_types::DescentMap;
 use crate::starkware_utils::commitment_tree::update_tree::{DecodeNodeCase, TreeUpdate, UpdateTree};
-use crate::utils::{custom_hint_error, execute_coroutine, get_constant};
+use crate::utils::{
+    custom_hint_error, execute_coroutine, get_constant, get_variable_from_root_exec_scope,
+    set_variable_in_root_exec_scope,
+};
 
 pub const LOAD_NEXT_TX: &str = indoc! {r#"
         tx = next(transactions)
@@ -68,9 +81,18 @@ pub fn load_next_tx(
     ap_tracking: &ApTracking,
     _constants: &HashMap<String, Felt252>,
 ) -> Result<(), HintError> {
+    let cancellation_flag =
+        get_variable_from_root_exec_scope::<Option<Arc<AtomicBool>>>(exec_scopes, vars::scopes::CANCELLATION_FLAG)
+            .ok()
+            .flatten();
+    if cancellation_flag.is_some_and(|flag| flag.load(Ordering::Relaxed)) {
+        return Err(custom_hint_error("The OS run was cancelled"));
+    }
+
     let mut transactions = exec_scopes.get::<IntoIter<InternalTransaction>>(vars::scopes::TRANSACTIONS)?;
     // Safe to unwrap because the remaining number of txs is checked in the cairo code.
     let tx = transactions.next().unwrap();
+    check_tx_version(exec_scopes, &tx)?;
     if let Some(address) = tx.sender_address {
         log::debug!("executing {} on: {}", tx.r#type, address);
     }
@@ -86,6 +108,40 @@ pub fn load_next_tx(
     // TODO: add logger
 }
 
+/// Versions of each tx type that the OS is able to execute, query bit excluded.
+fn supported_tx_versions(tx_type: &str) -> Option<&'static [u64]> {
+    match tx_type {
+        "DECLARE" => Some(&[0, 1, 2, 3]),
+        "DEPLOY" | "L1_HANDLER" => Some(&[0]),
+        "DEPLOY_ACCOUNT" => Some(&[1, 3]),
+        "INVOKE_FUNCTION" => Some(&[0, 1, 3]),
+        _ => None,
+    }
+}
+
+/// Rejects txs whose version the OS does not support, which would otherwise fail much later
+/// with an unrelated error (or worse, be executed with the wrong semantics). Txs of an unknown type
+/// or without a version are rejected as well, as their version cannot be checked.
+fn check_tx_version(exec_scopes: &ExecutionScopes, tx: &InternalTransaction) -> Result<(), HintError> {
+    let supported_versions = supported_tx_versions(&tx.r#type)
+        .ok_or_else(|| custom_hint_error(format!("Unexpected transaction type: {}.", tx.r#type)))?;
+    let version = tx
+        .version
+        .ok_or_else(|| custom_hint_error(format!("{} transaction {:#x} has no version", tx.r#type, tx.hash_value)))?;
+    let effective_version = strip_query_bit(version);
+    hint_assert!(
+        exec_scopes,
+        supported_versions.iter().any(|supported| effective_version == Felt252::from(*supported)),
+        "Unsupported version {} for {} transaction {:#x} (supported versions: {:?})",
+        effective_version,
+        tx.r#type,
+        tx.hash_value,
+        supported_versions
+    );
+
+    Ok(())
+}
+
 pub const EXIT_TX: &str = "exit_tx()";
 pub fn exit_tx(
     _vm: &mut VirtualMachine,
@@ -98,20 +154,121 @@ pub fn exit_tx(
     Ok(())
 }
 
+/// Computes the calldata length expected by the constructor of a deprecated (Cairo 0) class,
+/// based on its ABI. Returns None if it cannot be determined from the ABI.
+///
+/// Array lengths are read from the calldata, so the expected length can overflow; it is then
+/// reported as `usize::MAX`, which no calldata can match.
+fn expected_deprecated_constructor_calldata_len(abi: &[ContractClassAbiEntry], calldata: &[Felt252]) -> Option<usize> {
+    let constructor = abi.iter().find_map(|entry| match entry {
+        ContractClassAbiEntry::Constructor(constructor) => Some(constructor),
+        _ => None,
+    });
+    // Classes without a constructor can only be deployed with an empty calldata
+    let Some(constructor) = constructor else {
+        return Some(0);
+    };
+
+    let struct_sizes: HashMap<&str, usize> = abi
+        .iter()
+        .filter_map(|entry| match entry {
+            ContractClassAbiEntry::Struct(struct_entry) => Some((struct_entry.name.as_str(), struct_entry.size)),
+            _ => None,
+        })
+        .collect();
+    let type_size = |type_name: &str| if type_name == "felt" { Some(1) } else { struct_sizes.get(type_name).copied() };
+
+    let mut calldata_len: usize = 0;
+    for input in &constructor.inputs {
+        let input_len = if let Some(element_type) = input.r#type.strip_suffix('*') {
+            // Arrays are preceded by their length
+            let n_elements = calldata_len.checked_sub(1).and_then(|index| calldata.get(index))?;
+            let Ok(n_elements) = felt_to_usize(n_elements) else {
+                return Some(usize::MAX);
+            };
+            n_elements.checked_mul(type_size(element_type)?)
+        } else {
+            Some(type_size(&input.r#type)?)
+        };
+        let Some(new_calldata_len) = input_len.and_then(|input_len| calldata_len.checked_add(input_len)) else {
+            return Some(usize::MAX);
+        };
+        calldata_len = new_calldata_len;
+    }
+
+    Some(calldata_len)
+}
+
+/// Checks that the constructor calldata of a deployment matches the constructor of the deployed class.
+/// The check is skipped if the expected calldata length cannot be determined from the class.
+///
+/// Cairo 1 classes are only served to the OS as CASM, which does not carry the ABI (it is part of
+/// the Sierra class), so the arguments of their constructor are unknown. Their calldata is only
+/// checked to be empty when the class has no constructor.
+fn validate_constructor_calldata_len<PCS>(
+    exec_scopes: &ExecutionScopes,
+    class_hash: Felt252,
+    calldata: &[Felt252],
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let os_input = get_variable_from_root_exec_scope::<Rc<StarknetOsInput>>(exec_scopes, vars::scopes::OS_INPUT)?;
+
+    let expected_calldata_len =
+        if let Some(compiled_class_hash) = os_input.class_hash_to_compiled_class_hash.get(&class_hash) {
+            let execution_helper = exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER)?;
+            let compiled_class = execute_coroutine(async {
+                execution_helper.execution_helper.write().await.get_compiled_class(*compiled_class_hash)
+            })??;
+            if compiled_class.entry_points_by_type.constructor.is_empty() { Some(0) } else { None }
+        } else if let Some(deprecated_compiled_class) = os_input.deprecated_compiled_classes.get(&class_hash) {
+            let contract_class = deprecated_compiled_class
+                .get_starknet_api_contract_class()
+                .map_err(|e| custom_hint_error(e.to_string()))?;
+            contract_class.abi.as_ref().and_then(|abi| expected_deprecated_constructor_calldata_len(abi, calldata))
+        } else {
+            None
+        };
+
+    if let Some(expected_calldata_len) = expected_calldata_len {
+        hint_assert!(
+            exec_scopes,
+            expected_calldata_len == calldata.len(),
+            "Constructor calldata length mismatch for class {}: expected {}, got {}",
+            class_hash.to_hex_string(),
+            expected_calldata_len,
+            calldata.len()
+        );
+    }
+
+    Ok(())
+}
+
 pub const PREPARE_CONSTRUCTOR_EXECUTION: &str = indoc! {r#"
     ids.contract_address_salt = tx.contract_address_salt
     ids.class_hash = tx.class_hash
     ids.constructor_calldata_size = len(tx.constructor_calldata)
     ids.constructor_calldata = segments.gen_arg(arg=tx.constructor_calldata)"#
 };
-pub fn prepare_constructor_execution(
+pub fn prepare_constructor_execution<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     _constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
     let tx = exec_scopes.get::<InternalTransaction>(vars::scopes::TX)?;
+    let class_hash = tx.class_hash.ok_or(custom_hint_error("tx.class_hash is not set"))?;
+    validate_constructor_calldata_len::<PCS>(
+        exec_scopes,
+        class_hash,
+        tx.constructor_calldata.as_deref().unwrap_or_default(),
+    )?;
+
     insert_value_from_var_name(
         vars::ids::CONTRACT_ADDRESS_SALT,
         tx.contract_address_salt.expect("`contract_address_salt` must be present"),
@@ -119,13 +276,7 @@ pub fn prepare_constructor_execution(
         ids_data,
         ap_tracking,
     )?;
-    insert_value_from_var_name(
-        vars::ids::CLASS_HASH,
-        tx.class_hash.expect("`class_hash` must be present"),
-        vm,
-        ids_data,
-        ap_tracking,
-    )?;
+    insert_value_from_var_name(vars::ids::CLASS_HASH, class_hash, vm, ids_data, ap_tracking)?;
 
     let constructor_calldata_size = match &tx.constructor_calldata {
         None => 0,
@@ -146,6 +297,59 @@ pub fn prepare_constructor_execution(
     insert_value_from_var_name(vars::ids::CONSTRUCTOR_CALLDATA, constructor_calldata_base, vm, ids_data, ap_tracking)
 }
 
+pub const ASSERT_CONSTRUCTOR_CALLDATA: &str = indoc! {r#"
+	constructor_calldata = memory.get_range(addr=ids.constructor_calldata, size=ids.constructor_calldata_size)
+	executed_calldata = execution_helper.tx_execution_info.execute_call_info.call.calldata
+	assert constructor_calldata == executed_calldata, (
+	    f"Constructor calldata mismatch: the address is computed with {constructor_calldata}, "
+	    f"but the constructor was called with {executed_calldata}."
+	)"#
+};
+/// Checks that the constructor calldata used to compute the address of a DEPLOY_ACCOUNT tx is
+/// the calldata the constructor was executed with.
+pub fn assert_constructor_calldata<PCS>(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let constructor_calldata_ptr = get_ptr_from_var_name(vars::ids::CONSTRUCTOR_CALLDATA, vm, ids_data, ap_tracking)?;
+    let constructor_calldata_size =
+        get_integer_from_var_name(vars::ids::CONSTRUCTOR_CALLDATA_SIZE, vm, ids_data, ap_tracking)?;
+    let constructor_calldata: Vec<Felt252> = vm
+        .get_integer_range(constructor_calldata_ptr, felt_to_usize(&constructor_calldata_size)?)?
+        .into_iter()
+        .map(|felt| felt.into_owned())
+        .collect();
+
+    let execution_helper = exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER)?;
+    let executed_calldata = execute_coroutine(async {
+        let eh_ref = execution_helper.execution_helper.read().await;
+        eh_ref
+            .tx_execution_info
+            .as_ref()
+            .and_then(|tx_execution_info| tx_execution_info.execute_call_info.as_ref())
+            .map(|call_info| call_info.call.calldata.0.to_vec())
+    })?
+    .ok_or(custom_hint_error("The current transaction has no constructor call"))?;
+
+    let format_calldata =
+        |calldata: &[Felt252]| calldata.iter().map(|felt| felt.to_hex_string()).collect::<Vec<_>>().join(", ");
+    hint_assert!(
+        exec_scopes,
+        constructor_calldata == executed_calldata,
+        "Constructor calldata mismatch: the address is computed with [{}], but the constructor was called with [{}].",
+        format_calldata(&constructor_calldata),
+        format_calldata(&executed_calldata)
+    );
+
+    Ok(())
+}
+
 pub const TRANSACTION_VERSION: &str = "memory[ap] = to_felt_or_relocatable(tx.version)";
 pub fn transaction_version(
     vm: &mut VirtualMachine,
@@ -158,6 +362,28 @@ pub fn transaction_version(
     insert_value_into_ap(vm, tx.version.expect("Transaction version should be set"))
 }
 
+/// Removes the query bit (2**128) from a tx version, returning the version the tx would have
+/// outside of a query (e.g. `estimate_fee`).
+fn strip_query_bit(version: Felt252) -> Felt252 {
+    let query_version_base = Felt252::TWO.pow(QUERY_VERSION_BASE_BIT);
+    if version >= query_version_base { version - query_version_base } else { version }
+}
+
+pub const EFFECTIVE_TRANSACTION_VERSION: &str = "memory[ap] = to_felt_or_relocatable(tx.version % 2**128)";
+/// Serves the tx version as seen by contracts, i.e. without the query bit. The raw version,
+/// used internally by the OS, is served by `transaction_version`.
+pub fn effective_transaction_version(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    _ids_data: &HashMap<String, HintReference>,
+    _ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError> {
+    let tx = exec_scopes.get_ref::<InternalTransaction>(vars::scopes::TX)?;
+    let version = tx.version.ok_or(custom_hint_error("tx.version is not set"))?;
+    insert_value_into_ap(vm, strip_query_bit(version))
+}
+
 pub const ASSERT_TRANSACTION_HASH: &str = indoc! {r#"
     assert ids.transaction_hash == tx.hash_value, (
         "Computed transaction_hash is inconsistent with the hash in the transaction. "
@@ -186,6 +412,44 @@ pub fn assert_transaction_hash(
     Ok(())
 }
 
+pub const ASSERT_COMPILED_CLASS_HASH: &str = indoc! {r#"
+    from starkware.starknet.core.os.contract_class.compiled_class_hash import compute_compiled_class_hash
+
+    computed_hash = compute_compiled_class_hash(os_input.compiled_classes[tx.compiled_class_hash])
+    assert computed_hash == tx.compiled_class_hash, (
+        "Computed compiled_class_hash is inconsistent with the hash in the transaction. "
+        f"Computed hash = {computed_hash}, Expected hash = {tx.compiled_class_hash}.")"#
+};
+/// Checks that the CASM class provided for a DECLARE v2/v3 tx hashes to the compiled class hash
+/// declared by the tx.
+pub fn assert_compiled_class_hash(
+    _vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    _ids_data: &HashMap<String, HintReference>,
+    _ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError> {
+    let tx = exec_scopes.get_ref::<InternalTransaction>(vars::scopes::TX)?;
+    let expected_hash = tx.compiled_class_hash.ok_or(custom_hint_error("tx.compiled_class_hash is not set"))?;
+    let os_input = exec_scopes.get_ref::<Rc<StarknetOsInput>>(vars::scopes::OS_INPUT)?;
+
+    let compiled_class = os_input.compiled_classes.get(&expected_hash).ok_or_else(|| {
+        custom_hint_error(format!("No compiled class found for compiled class hash {}", expected_hash.to_hex_string()))
+    })?;
+    let computed_hash: Felt252 = compiled_class.class_hash().map_err(|e| custom_hint_error(e.to_string()))?.into();
+
+    hint_assert!(
+        exec_scopes,
+        computed_hash == expected_hash,
+        "Computed compiled_class_hash is inconsistent with the hash in the transaction. Computed hash = {}, Expected \
+         hash = {}.",
+        computed_hash.to_hex_string(),
+        expected_hash.to_hex_string()
+    );
+
+    Ok(())
+}
+
 pub const ENTER_SCOPE_DEPRECATED_SYSCALL_HANDLER: &str =
     "vm_enter_scope({'syscall_handler': deprecated_syscall_handler})";
 pub fn enter_scope_deprecated_syscall_handler<PCS>(
@@ -201,8 +465,10 @@ where
     let dep_sys =
         exec_scopes.get::<DeprecatedOsSyscallHandlerWrapper<PCS>>(vars::scopes::DEPRECATED_SYSCALL_HANDLER)?;
     let deprecated_syscall_handler: Box<dyn Any> = Box::new(dep_sys);
-    exec_scopes
-        .enter_scope(HashMap::from_iter([(String::from(vars::scopes::SYSCALL_HANDLER), deprecated_syscall_handler)]));
+    enter_call_scope(
+        exec_scopes,
+        HashMap::from_iter([(String::from(vars::scopes::SYSCALL_HANDLER), deprecated_syscall_handler)]),
+    );
     Ok(())
 }
 
@@ -219,7 +485,30 @@ where
 {
     let sys = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER)?;
     let syscall_handler: Box<dyn Any> = Box::new(sys);
-    exec_scopes.enter_scope(HashMap::from_iter([(String::from(vars::scopes::SYSCALL_HANDLER), syscall_handler)]));
+    enter_call_scope(exec_scopes, HashMap::from_iter([(String::from(vars::scopes::SYSCALL_HANDLER), syscall_handler)]));
+    Ok(())
+}
+
+/// Enters the scope of a nested call. The dict manager of the current scope, if any, is shared
+/// with the new scope: otherwise, dicts allocated by the inner call would be tracked by a new
+/// manager, dropped when exiting the scope.
+fn enter_call_scope(exec_scopes: &mut ExecutionScopes, mut new_scope: HashMap<String, Box<dyn Any>>) {
+    if let Ok(dict_manager) = exec_scopes.get_dict_manager() {
+        new_scope.insert(String::from(vars::scopes::DICT_MANAGER), Box::new(dict_manager));
+    }
+    exec_scopes.enter_scope(new_scope);
+}
+
+pub const ENTER_SCOPE_WITH_DICT_MANAGER: &str = "vm_enter_scope({'__dict_manager': __dict_manager})";
+pub fn enter_scope_with_dict_manager(
+    _vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    _ids_data: &HashMap<String, HintReference>,
+    _ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError> {
+    let dict_manager: Box<dyn Any> = Box::new(exec_scopes.get_dict_manager()?);
+    exec_scopes.enter_scope(HashMap::from_iter([(String::from(vars::scopes::DICT_MANAGER), dict_manager)]));
     Ok(())
 }
 
@@ -237,12 +526,47 @@ fn set_state_entry(
             return Err(custom_hint_error("State changes dictionary should not be a default dict"));
         }
     };
-    let val = val.ok_or(custom_hint_error("State changes dictionary should not be None"))?;
+    let val = match val {
+        Some(val) => val,
+        None => insert_uninitialized_state_entry(dict_ptr, key, vm, exec_scopes)?,
+    };
 
     insert_value_from_var_name(vars::ids::STATE_ENTRY, val, vm, ids_data, ap_tracking)?;
     Ok(())
 }
 
+/// Handles a contract that is not part of `os_input.contracts`. Such a contract was never
+/// deployed, so it has the uninitialized state: zero class hash, empty storage and zero nonce.
+/// Runs that require all contracts to be known fail instead.
+fn insert_uninitialized_state_entry(
+    dict_ptr: Relocatable,
+    contract_address: Felt252,
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+) -> Result<MaybeRelocatable, HintError> {
+    let os_input = get_variable_from_root_exec_scope::<Rc<StarknetOsInput>>(exec_scopes, vars::scopes::OS_INPUT)?;
+    if os_input.general_config.require_known_contracts {
+        return Err(custom_hint_error(format!(
+            "Contract {} is not part of the OS input",
+            contract_address.to_hex_string()
+        )));
+    }
+
+    let state_entry_ptr = vm.add_memory_segment();
+    let state_entry =
+        StateEntryLayout { class_hash: Felt252::ZERO, storage_ptr: vm.add_memory_segment(), nonce: Felt252::ZERO };
+    state_entry.to_memory(vm, state_entry_ptr)?;
+
+    let state_entry_ptr = MaybeRelocatable::from(state_entry_ptr);
+    exec_scopes
+        .get_dict_manager()?
+        .borrow_mut()
+        .get_tracker_mut(dict_ptr)?
+        .insert_value(&MaybeRelocatable::from(contract_address), &state_entry_ptr);
+
+    Ok(state_entry_ptr)
+}
+
 pub const GET_CONTRACT_ADDRESS_STATE_ENTRY: &str = indoc! {r#"
     # Fetch a state_entry in this hint and validate it in the update at the end
     # of this function.
@@ -355,6 +679,110 @@ pub fn get_contract_address_state_entry_and_set_new_state_entry(
     Ok(())
 }
 
+/// Reads the state entry of `contract_address` from the contract state changes dict at `dict_ptr`,
+/// if the contract was accessed by the block.
+fn read_state_entry(
+    vm: &VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    dict_ptr: Relocatable,
+    contract_address: Felt252,
+) -> Result<Option<StateEntryLayout>, HintError> {
+    let state_entry = match exec_scopes.get_dict_manager()?.borrow().get_tracker(dict_ptr)?.data.clone() {
+        Dictionary::SimpleDictionary(dict) => dict.get(&MaybeRelocatable::Int(contract_address)).cloned(),
+        Dictionary::DefaultDictionary { dict: _d, default_value: _v } => {
+            return Err(custom_hint_error("State changes dictionary should not be a default dict"));
+        }
+    };
+    let Some(state_entry) = state_entry else {
+        return Ok(None);
+    };
+
+    let state_entry_ptr = state_entry
+        .get_relocatable()
+        .ok_or_else(|| custom_hint_error(format!("Invalid state entry for contract {:#x}", contract_address)))?;
+    StateEntryLayout::from_memory(vm, state_entry_ptr).map(Some)
+}
+
+pub const ASSERT_ADDRESS_UNOCCUPIED: &str = indoc! {r#"
+	state_entry = __dict_manager.get_dict(ids.contract_state_changes).get(ids.contract_address)
+	if state_entry is not None:
+	    class_hash = memory[state_entry + ids.StateEntry.class_hash]
+	    assert class_hash == 0, (
+	        f"Cannot deploy to {hex(ids.contract_address)}: the address is occupied by class {hex(class_hash)}."
+	    )"#
+};
+
+/// Rejects a deployment to an address that already holds a contract, i.e. whose state entry
+/// has a non-zero class hash. Addresses missing from the state changes were never deployed.
+pub fn assert_address_unoccupied(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError> {
+    let dict_ptr = get_ptr_from_var_name(vars::ids::CONTRACT_STATE_CHANGES, vm, ids_data, ap_tracking)?;
+    let contract_address = get_integer_from_var_name(vars::ids::CONTRACT_ADDRESS, vm, ids_data, ap_tracking)?;
+
+    let Some(state_entry) = read_state_entry(vm, exec_scopes, dict_ptr, contract_address)? else {
+        return Ok(());
+    };
+    hint_assert!(
+        exec_scopes,
+        state_entry.class_hash == Felt252::ZERO,
+        "Cannot deploy to {:#x}: the address is occupied by class {:#x}.",
+        contract_address,
+        state_entry.class_hash
+    );
+
+    Ok(())
+}
+
+pub const GET_ACCOUNT_NONCE: &str = indoc! {r#"
+	state_entry = __dict_manager.get_dict(ids.contract_state_changes).get(ids.contract_address)
+	nonce = 0 if state_entry is None else memory[state_entry + ids.StateEntry.nonce]
+	if (
+	    execution_helper.is_validate_call
+	    and ids.contract_address == tx.sender_address
+	    and tx.nonce is not None
+	):
+	    # The nonce of the account is incremented before its validation.
+	    nonce = tx.nonce
+	ids.nonce = nonce"#
+};
+
+/// Serves the nonce of `ids.contract_address`. The OS increments the nonce of the sender of a tx
+/// before running `__validate__`, so a sender validating its own tx gets the nonce of the tx, i.e.
+/// the value before the increment. Other reads get the nonce from the state changes.
+pub fn get_account_nonce<PCS>(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let dict_ptr = get_ptr_from_var_name(vars::ids::CONTRACT_STATE_CHANGES, vm, ids_data, ap_tracking)?;
+    let contract_address = get_integer_from_var_name(vars::ids::CONTRACT_ADDRESS, vm, ids_data, ap_tracking)?;
+
+    let state_nonce =
+        read_state_entry(vm, exec_scopes, dict_ptr, contract_address)?.map_or(Felt252::ZERO, |entry| entry.nonce);
+
+    let execution_helper = exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER)?;
+    let is_validate_call =
+        execute_coroutine(async { execution_helper.execution_helper.read().await.is_validate_call() })?;
+    let tx: &InternalTransaction = exec_scopes.get_ref(vars::scopes::TX)?;
+
+    let nonce = match tx.nonce {
+        Some(tx_nonce) if is_validate_call && tx.sender_address == Some(contract_address) => tx_nonce,
+        _ => state_nonce,
+    };
+
+    insert_value_from_var_name(vars::ids::NONCE, nonce, vm, ids_data, ap_tracking)
+}
+
 pub const CHECK_IS_DEPRECATED: &str =
     "is_deprecated = 1 if ids.execution_context.class_hash in __deprecated_class_hashes else 0";
 pub fn check_is_deprecated(
@@ -436,17 +864,19 @@ pub fn enter_syscall_scopes<PCS>(
 where
     PCS: PerContractStorage + 'static,
 {
-    let os_input = exec_scopes.get::<Rc<StarknetOsInput>>(vars::scopes::OS_INPUT)?;
+    let os_input = get_typed::<Rc<StarknetOsInput>>(exec_scopes, vars::scopes::OS_INPUT)?;
     let deprecated_class_hashes: Box<dyn Any> =
-        Box::new(exec_scopes.get::<HashSet<Felt252>>(vars::scopes::DEPRECATED_CLASS_HASHES)?);
+        Box::new(get_typed::<HashSet<Felt252>>(exec_scopes, vars::scopes::DEPRECATED_CLASS_HASHES)?);
     let transactions: Box<dyn Any> = Box::new(os_input.transactions.clone().into_iter());
     let component_hashes: Box<dyn Any> = Box::new(os_input.declared_class_hash_to_component_hashes.clone());
     let execution_helper: Box<dyn Any> =
-        Box::new(exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER)?);
-    let deprecated_syscall_handler: Box<dyn Any> =
-        Box::new(exec_scopes.get::<DeprecatedOsSyscallHandlerWrapper<PCS>>(vars::scopes::DEPRECATED_SYSCALL_HANDLER)?);
+        Box::new(get_typed::<ExecutionHelperWrapper<PCS>>(exec_scopes, vars::scopes::EXECUTION_HELPER)?);
+    let deprecated_syscall_handler: Box<dyn Any> = Box::new(get_typed::<DeprecatedOsSyscallHandlerWrapper<PCS>>(
+        exec_scopes,
+        vars::scopes::DEPRECATED_SYSCALL_HANDLER,
+    )?);
     let syscall_handler: Box<dyn Any> =
-        Box::new(exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER)?);
+        Box::new(get_typed::<OsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?);
     let dict_manager: Box<dyn Any> = Box::new(exec_scopes.get_dict_manager()?);
     exec_scopes.enter_scope(HashMap::from_iter([
         (String::from(vars::scopes::DEPRECATED_CLASS_HASHES), deprecated_class_hashes),
@@ -465,73 +895,80 @@ pub async fn end_tx_async<PCS>(exec_scopes: &mut ExecutionScopes) -> Result<(),
 where
     PCS: PerContractStorage + 'static,
 {
+    let tx = exec_scopes.get::<InternalTransaction>(vars::scopes::TX)?;
     let execution_helper = exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER)?;
-    execution_helper.end_tx().await;
-    Ok(())
+    execution_helper.end_tx_or_record_failure(tx.hash_value).await
 }
 
 pub fn end_tx<PCS>(
-    _vm: &mut VirtualMachine,
-    exec_scopes: &mut ExecutionScopes,
-    _ids_data: &HashMap<String, HintReference>,
-    _ap_tracking: &ApTracking,
-    _constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError>
-where
-    PCS: PerContractStorage + 'static,
-{
-    execute_coroutine(end_tx_async::<PCS>(exec_scopes))?
-}
-
-#[rustfmt::skip]
-pub const ENTER_CALL: &str = indoc! {r#"
-execution_helper.enter_call(
-    cairo_execution_info=ids.execution_context.execution_info)"#};
-
-pub async fn enter_call_async<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
+    constants: &HashMap<String, Felt252>,
 ) -> Result<(), HintError>
 where
     PCS: PerContractStorage + 'static,
 {
-    let execution_info_ptr = vm.get_relocatable(
-        (get_ptr_from_var_name(vars::ids::EXECUTION_CONTEXT, vm, ids_data, ap_tracking)?
-            + ExecutionContext::execution_info_offset())
-        .unwrap(),
-    )?;
-
-    let execution_helper = exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER)?;
-    execution_helper.enter_call(Some(execution_info_ptr)).await;
-    Ok(())
+    execute_coroutine(end_tx_async::<PCS>(exec_scopes))??;
+    clear_tx_scope(vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 
-pub fn enter_call<PCS>(
-    vm: &mut VirtualMachine,
+/// Scope variables that are only valid while a given tx is executed.
+const TX_SCOPE_VARIABLES: [&str; 1] = [vars::scopes::TX];
+
+pub const CLEAR_TX_SCOPE: &str = "clear_tx_scope()";
+
+/// Removes the variables of the tx that just ended from the scope, so that a hint of the next
+/// tx cannot read them by mistake.
+pub fn clear_tx_scope(
+    _vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
-    ids_data: &HashMap<String, HintReference>,
-    ap_tracking: &ApTracking,
+    _ids_data: &HashMap<String, HintReference>,
+    _ap_tracking: &ApTracking,
     _constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError>
-where
-    PCS: PerContractStorage + 'static,
-{
-    execute_coroutine(enter_call_async::<PCS>(vm, exec_scopes, ids_data, ap_tracking))?
+) -> Result<(), HintError> {
+    for name in TX_SCOPE_VARIABLES {
+        exec_scopes.delete_variable(name);
+    }
+
+    Ok(())
 }
 
-pub const EXIT_CALL: &str = "execution_helper.exit_call()";
-pub async fn exit_call_async<PCS>(exec_scopes: &mut ExecutionScopes) -> Result<(), HintError>
+pub const ASSERT_TRANSACTIONS_CONSUMED: &str = indoc! {r#"
+    assert next(transactions, None) is None, "Not all transactions were executed."
+    assert next(execution_helper.tx_execution_info_iter, None) is None, (
+        "Not all transaction execution infos were consumed."
+    )"#
+};
+
+pub async fn assert_transactions_consumed_async<PCS>(exec_scopes: &mut ExecutionScopes) -> Result<(), HintError>
 where
     PCS: PerContractStorage + 'static,
 {
-    let mut execution_helper = exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER)?;
-    execution_helper.exit_call().await;
+    let transactions = exec_scopes.get_ref::<IntoIter<InternalTransaction>>(vars::scopes::TRANSACTIONS)?;
+    hint_assert!(
+        exec_scopes,
+        transactions.len() == 0,
+        "Not all transactions were executed: {} left",
+        transactions.len()
+    );
+
+    let execution_helper = exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER)?;
+    let eh_ref = execution_helper.execution_helper.read().await;
+    let n_remaining_execution_infos = eh_ref.tx_execution_info_iter.len();
+    hint_assert!(
+        exec_scopes,
+        n_remaining_execution_infos == 0,
+        "Not all transaction execution infos were consumed: {n_remaining_execution_infos} left"
+    );
+
     Ok(())
 }
 
-pub fn exit_call<PCS>(
+/// Checks that the OS went through all the txs of the block. Leftover txs mean that the Cairo
+/// program stopped before the end of the block.
+pub fn assert_transactions_consumed<PCS>(
     _vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     _ids_data: &HashMap<String, HintReference>,
@@ -541,91 +978,304 @@ pub fn exit_call<PCS>(
 where
     PCS: PerContractStorage + 'static,
 {
-    execute_coroutine(exit_call_async::<PCS>(exec_scopes))?
+    execute_coroutine(assert_transactions_consumed_async::<PCS>(exec_scopes))?
 }
 
-pub const CONTRACT_ADDRESS: &str = indoc! {r#"
-    from starkware.starknet.business_logic.transaction.deprecated_objects import (
-        InternalL1Handler,
-    )
-    ids.contract_address = (
-        tx.contract_address if isinstance(tx, InternalL1Handler) else tx.sender_address
+pub const ASSERT_EVENTS_CONSUMED: &str = indoc! {r#"
+    n_recorded_events = sum(len(tx_events) for tx_events in execution_helper.event_log)
+    assert ids.n_events == n_recorded_events, (
+        f"Not all emitted events were output: {ids.n_events} out of {n_recorded_events}."
     )"#
 };
 
-pub fn contract_address(
+/// Checks that the OS output holds as many events as the txs emitted, as recorded by the
+/// execution helper. A mismatch means that events were dropped (or duplicated) on the way.
+pub fn assert_events_consumed<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     _constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    let tx = exec_scopes.get::<InternalTransaction>(vars::scopes::TX)?;
-    let contract_address = if tx.r#type == "L1_HANDLER" {
-        tx.contract_address.ok_or(custom_hint_error("tx.contract_address is None"))?
-    } else {
-        tx.sender_address.ok_or(custom_hint_error("tx.sender_address is None"))?
-    };
-    insert_value_from_var_name(vars::ids::CONTRACT_ADDRESS, contract_address, vm, ids_data, ap_tracking)
-}
-
-pub const TX_CALLDATA_LEN: &str = "memory[ap] = to_felt_or_relocatable(len(tx.calldata))";
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let n_events = get_integer_from_var_name(vars::ids::N_EVENTS, vm, ids_data, ap_tracking)?;
 
-pub fn tx_calldata_len(
+    let execution_helper = exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER)?;
+    let n_recorded_events = execute_coroutine(async {
+        let eh_ref = execution_helper.execution_helper.read().await;
+        eh_ref.event_log.values().map(Vec::len).sum::<usize>()
+    })?;
+
+    hint_assert!(
+        exec_scopes,
+        n_events == Felt252::from(n_recorded_events),
+        "Not all emitted events were output: {} out of {}",
+        n_events,
+        n_recorded_events
+    );
+
+    Ok(())
+}
+
+pub const READ_BLOCK_HASH: &str = indoc! {r#"
+    if ids.request_block_number > ids.current_block_number - ids.STORED_BLOCK_HASH_BUFFER:
+        block_hash = 0
+    else:
+        block_hash = next(execution_helper.execute_code_read_iter)
+    memory[ap] = to_felt_or_relocatable(block_hash)"#
+};
+
+pub async fn read_block_hash_async<PCS>(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let request_block_number = get_integer_from_var_name(vars::ids::REQUEST_BLOCK_NUMBER, vm, ids_data, ap_tracking)?;
+    let current_block_number = get_integer_from_var_name(vars::ids::CURRENT_BLOCK_NUMBER, vm, ids_data, ap_tracking)?;
+    let stored_block_hash_buffer = get_constant(vars::constants::STORED_BLOCK_HASH_BUFFER, constants)?;
+
+    // Only the hashes of blocks older than the buffer are available, other requests get 0.
+    // The first blocks of the chain have no such block.
+    let is_available = current_block_number >= *stored_block_hash_buffer
+        && request_block_number <= current_block_number - *stored_block_hash_buffer;
+    let block_hash = if !is_available {
+        Felt252::ZERO
+    } else {
+        let execution_helper = exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER)?;
+        let mut eh_ref = execution_helper.execution_helper.write().await;
+        // The read is consumed even if the provider knows the hash, to keep the replay in sync
+        let recorded_block_hash = eh_ref
+            .execute_code_read_iter
+            .next()
+            .ok_or(custom_hint_error("No more block hash reads available to replay"))?;
+
+        let provided_block_hash = request_block_number
+            .to_u64()
+            .and_then(|block_number| eh_ref.block_hash_provider.get_block_hash(block_number));
+        match provided_block_hash {
+            Some(block_hash) => {
+                hint_assert!(
+                    exec_scopes,
+                    block_hash == recorded_block_hash,
+                    "Inconsistent hash for block {request_block_number}: provided {}, read during execution {}",
+                    block_hash.to_hex_string(),
+                    recorded_block_hash.to_hex_string()
+                );
+                block_hash
+            }
+            None => recorded_block_hash,
+        }
+    };
+
+    insert_value_into_ap(vm, block_hash)
+}
+
+pub fn read_block_hash<PCS>(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    execute_coroutine(read_block_hash_async::<PCS>(vm, exec_scopes, ids_data, ap_tracking, constants))?
+}
+
+#[rustfmt::skip]
+pub const ENTER_CALL: &str = indoc! {r#"
+execution_helper.enter_call(
+    cairo_execution_info=ids.execution_context.execution_info)"#};
+
+pub async fn enter_call_async<PCS>(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let execution_info_ptr = vm.get_relocatable(
+        (get_ptr_from_var_name(vars::ids::EXECUTION_CONTEXT, vm, ids_data, ap_tracking)?
+            + ExecutionContext::execution_info_offset())
+        .unwrap(),
+    )?;
+
+    let execution_helper = exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER)?;
+    execution_helper.enter_call(Some(execution_info_ptr)).await
+}
+
+pub fn enter_call<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    execute_coroutine(enter_call_async::<PCS>(vm, exec_scopes, ids_data, ap_tracking))?
+}
+
+pub const EXIT_CALL: &str = "execution_helper.exit_call()";
+pub async fn exit_call_async<PCS>(exec_scopes: &mut ExecutionScopes) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let mut execution_helper = exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER)?;
+    execution_helper.exit_call().await;
+    Ok(())
+}
+
+pub fn exit_call<PCS>(
+    _vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
     _ids_data: &HashMap<String, HintReference>,
     _ap_tracking: &ApTracking,
     _constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    let tx = exec_scopes.get::<InternalTransaction>(vars::scopes::TX)?;
-    let len = tx.calldata.ok_or(custom_hint_error("tx.calldata is None"))?.len();
-    insert_value_into_ap(vm, Felt252::from(len))
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    execute_coroutine(exit_call_async::<PCS>(exec_scopes))?
 }
 
-pub const TX_CALLDATA: &str = "memory[ap] = to_felt_or_relocatable(segments.gen_arg(tx.calldata))";
+pub const ASSERT_NO_REENTRANCY: &str = "execution_helper.assert_no_reentrancy()";
+pub async fn assert_no_reentrancy_async<PCS>(exec_scopes: &mut ExecutionScopes) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let execution_helper = exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER)?;
+    let eh_ref = execution_helper.execution_helper.read().await;
+    if !eh_ref.forbid_reentrancy {
+        return Ok(());
+    }
 
-pub fn tx_calldata(
-    vm: &mut VirtualMachine,
+    let reentered_contract = eh_ref.find_reentered_contract();
+    hint_assert!(
+        exec_scopes,
+        reentered_contract.is_none(),
+        "Contract {} is re-entered, which is forbidden",
+        reentered_contract.unwrap_or_default().to_hex_string()
+    );
+
+    Ok(())
+}
+
+/// Checks that the current call does not re-enter a contract, if re-entries are forbidden by the
+/// configuration.
+pub fn assert_no_reentrancy<PCS>(
+    _vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     _ids_data: &HashMap<String, HintReference>,
     _ap_tracking: &ApTracking,
     _constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    let tx = exec_scopes.get::<InternalTransaction>(vars::scopes::TX)?;
-    let calldata =
-        tx.calldata.ok_or(custom_hint_error("tx.calldata is None"))?.iter().map(|felt| felt.into()).collect::<Vec<_>>();
-    let calldata_base = vm.add_memory_segment();
-    vm.load_data(calldata_base, &calldata)?;
-    insert_value_into_ap(vm, calldata_base)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    execute_coroutine(assert_no_reentrancy_async::<PCS>(exec_scopes))?
 }
 
-pub const TX_ENTRY_POINT_SELECTOR: &str = "memory[ap] = to_felt_or_relocatable(tx.entry_point_selector)";
-pub fn tx_entry_point_selector(
+pub const ASSERT_ENTRY_POINT_SELECTOR: &str = indoc! {r#"
+    assert ids.selector == execution_helper.call_info.call.entry_point_selector, (
+        "Entry point selector does not match the recorded call."
+    )"#
+};
+
+/// Checks that the selector dispatched by the OS is the one of the recorded call, so that a call
+/// is never replayed against the wrong entry point.
+pub fn assert_entry_point_selector<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let selector = get_integer_from_var_name(vars::ids::SELECTOR, vm, ids_data, ap_tracking)?;
+
+    let execution_helper = exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER)?;
+    let expected_selector = execute_coroutine(async {
+        let eh_ref = execution_helper.execution_helper.read().await;
+        eh_ref
+            .call_info
+            .as_ref()
+            .map(|call_info| call_info.call.entry_point_selector.0)
+            .ok_or(custom_hint_error("ExecutionHelper should have call_info"))
+    })??;
+
+    hint_assert!(
+        exec_scopes,
+        selector == expected_selector,
+        "Entry point selector mismatch: expected {}, got {}",
+        expected_selector.to_hex_string(),
+        selector.to_hex_string()
+    );
+
+    Ok(())
+}
+
+pub const ASSERT_VALIDATE_CALLER: &str = indoc! {r#"
+    assert execution_helper.call_info.call.caller_address == 0, (
+        "__validate__ must be called by the OS."
+    )"#
+};
+
+/// Checks that the current `__validate__` call comes from the OS, i.e. has a zero caller address.
+/// An account could otherwise be validated on behalf of another contract.
+pub fn assert_validate_caller<PCS>(
+    _vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
     _ids_data: &HashMap<String, HintReference>,
     _ap_tracking: &ApTracking,
     _constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    let tx = exec_scopes.get::<InternalTransaction>(vars::scopes::TX)?;
-    let entry_point_selector = tx.entry_point_selector.ok_or(custom_hint_error("tx.entry_point_selector is None"))?;
-    insert_value_into_ap(vm, entry_point_selector)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let execution_helper = exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER)?;
+    let caller_address = execute_coroutine(async {
+        let eh_ref = execution_helper.execution_helper.read().await;
+        eh_ref
+            .call_info
+            .as_ref()
+            .map(|call_info| Felt252::from(call_info.call.caller_address))
+            .ok_or(custom_hint_error("ExecutionHelper should have call_info"))
+    })??;
+
+    hint_assert!(
+        exec_scopes,
+        caller_address == Felt252::ZERO,
+        "__validate__ must be called by the OS, got caller {}",
+        caller_address.to_hex_string()
+    );
+
+    Ok(())
 }
 
-pub const RESOURCE_BOUNDS: &str = indoc! {r#"
-    from src.starkware.starknet.core.os.transaction_hash.transaction_hash import (
-        create_resource_bounds_list,
+pub const CONTRACT_ADDRESS: &str = indoc! {r#"
+    from starkware.starknet.business_logic.transaction.deprecated_objects import (
+        InternalL1Handler,
     )
-
-    ids.resource_bounds = (
-        0
-        if tx.version < 3
-        else segments.gen_arg(create_resource_bounds_list(tx.resource_bounds))
+    ids.contract_address = (
+        tx.contract_address if isinstance(tx, InternalL1Handler) else tx.sender_address
     )"#
 };
 
-pub fn resource_bounds(
+pub fn contract_address(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
@@ -633,66 +1283,291 @@ pub fn resource_bounds(
     _constants: &HashMap<String, Felt252>,
 ) -> Result<(), HintError> {
     let tx = exec_scopes.get::<InternalTransaction>(vars::scopes::TX)?;
-    let version = tx.version.unwrap_or_default();
-
-    let resource_bounds = if version < Felt252::THREE {
-        MaybeRelocatable::Int(Felt252::ZERO)
+    let contract_address = if tx.r#type == "L1_HANDLER" {
+        tx.contract_address.ok_or(custom_hint_error("tx.contract_address is None"))?
     } else {
-        let resource_bounds: Vec<_> =
-            create_resource_bounds_list(&tx.resource_bounds.ok_or(custom_hint_error("tx.resource_bounds is None"))?)
-                .iter()
-                .map(|f| MaybeRelocatable::Int(*f))
-                .collect();
-        vm.gen_arg(&resource_bounds)?
+        tx.sender_address.ok_or(custom_hint_error("tx.sender_address is None"))?
     };
+    insert_value_from_var_name(vars::ids::CONTRACT_ADDRESS, contract_address, vm, ids_data, ap_tracking)
+}
 
-    insert_value_from_var_name(vars::ids::RESOURCE_BOUNDS, resource_bounds, vm, ids_data, ap_tracking)
+/// Computes the hash of the L1-to-L2 message consumed by an L1 handler tx. The message is sent
+/// by `calldata[0]` with the rest of the calldata as payload.
+fn l1_handler_message_hash(tx: &InternalTransaction) -> Result<[u8; 32], HintError> {
+    if tx.r#type != "L1_HANDLER" {
+        return Err(custom_hint_error(format!("Expected an L1 handler tx, got {}", tx.r#type)));
+    }
+
+    let to_address = tx.contract_address.ok_or(custom_hint_error("tx.contract_address is None"))?;
+    let selector = tx.entry_point_selector.ok_or(custom_hint_error("tx.entry_point_selector is None"))?;
+    let nonce = tx.nonce.ok_or(custom_hint_error("tx.nonce is None"))?;
+    let calldata = tx.calldata.as_ref().ok_or(custom_hint_error("tx.calldata is None"))?;
+    let (from_address, payload) =
+        calldata.split_first().ok_or(custom_hint_error("The calldata of an L1 handler tx cannot be empty"))?;
+
+    Ok(compute_l1_to_l2_message_hash(*from_address, to_address, selector, payload, nonce))
 }
 
-pub const TX_MAX_FEE: &str = "memory[ap] = to_felt_or_relocatable(tx.max_fee if tx.version < 3 else 0)";
-pub fn tx_max_fee(
+pub const ASSERT_L1_TO_L2_MESSAGE_HASH: &str = indoc! {r#"
+    message_hash = ids.message_hash.low + ids.message_hash.high * 2**128
+    assert message_hash == int.from_bytes(tx.message_to_l2.get_hash(), "big"), (
+        "L1-to-L2 message hash mismatch."
+    )"#
+};
+
+/// Checks the hash of the L1-to-L2 message consumed by the current L1 handler tx, as computed by
+/// the OS, against the one computed from the tx.
+pub fn assert_l1_to_l2_message_hash(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
-    _ids_data: &HashMap<String, HintReference>,
-    _ap_tracking: &ApTracking,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
     _constants: &HashMap<String, Felt252>,
 ) -> Result<(), HintError> {
     let tx = exec_scopes.get::<InternalTransaction>(vars::scopes::TX)?;
-    let version = tx.version.unwrap_or(Felt252::ZERO);
-    let max_fee = if version < Felt252::THREE {
-        tx.max_fee.ok_or(custom_hint_error("tx.max_fee is None"))?
-    } else {
-        Felt252::ZERO
-    };
+    let expected_hash = l1_handler_message_hash(&tx)?;
+    let (expected_high, expected_low) = expected_hash.split_at(16);
 
-    insert_value_into_ap(vm, max_fee)
+    let message_hash_ptr = get_relocatable_from_var_name(vars::ids::MESSAGE_HASH, vm, ids_data, ap_tracking)?;
+    let low = vm.get_integer(message_hash_ptr)?.into_owned();
+    let high = vm.get_integer((message_hash_ptr + 1)?)?.into_owned();
+
+    hint_assert!(
+        exec_scopes,
+        low == Felt252::from_bytes_be_slice(expected_low) && high == Felt252::from_bytes_be_slice(expected_high),
+        "L1-to-L2 message hash mismatch for tx {}: expected 0x{}",
+        tx.hash_value.to_hex_string(),
+        hex::encode(expected_hash)
+    );
+
+    Ok(())
 }
 
-pub const TX_NONCE: &str = "memory[ap] = to_felt_or_relocatable(0 if tx.nonce is None else tx.nonce)";
-pub fn tx_nonce(
-    vm: &mut VirtualMachine,
+pub const INITIALIZE_L1_TO_L2_MESSAGES: &str = indoc! {r#"
+    l1_to_l2_messages = L1ToL2Messages(
+        tx.message_to_l2.get_hash() for tx in os_input.transactions if tx.tx_type.name == "L1_HANDLER"
+    )"#
+};
+
+/// Collects the L1-to-L2 messages that the L1 handler txs of the block can consume.
+/// A malformed L1 handler tx does not fail the block here: it fails when it consumes its message.
+pub fn initialize_l1_to_l2_messages(
+    _vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     _ids_data: &HashMap<String, HintReference>,
     _ap_tracking: &ApTracking,
     _constants: &HashMap<String, Felt252>,
 ) -> Result<(), HintError> {
-    let tx = exec_scopes.get::<InternalTransaction>(vars::scopes::TX)?;
-    let nonce = if tx.nonce.is_none() { 0.into() } else { tx.nonce.ok_or(custom_hint_error("tx.nonce is None"))? };
-    insert_value_into_ap(vm, nonce)
+    let os_input = exec_scopes.get::<Rc<StarknetOsInput>>(vars::scopes::OS_INPUT)?;
+    let message_hashes = os_input
+        .transactions
+        .iter()
+        .filter(|tx| tx.r#type == "L1_HANDLER")
+        .filter_map(|tx| l1_handler_message_hash(tx).ok());
+
+    // Consumed from within the tx scopes
+    set_variable_in_root_exec_scope(exec_scopes, vars::scopes::L1_TO_L2_MESSAGES, L1ToL2Messages::new(message_hashes));
+
+    Ok(())
 }
 
-pub const TX_TIP: &str = "memory[ap] = to_felt_or_relocatable(0 if tx.version < 3 else tx.tip)";
-pub fn tx_tip(
-    vm: &mut VirtualMachine,
+pub const CONSUME_L1_TO_L2_MESSAGE: &str = "l1_to_l2_messages.consume(tx.message_to_l2.get_hash())";
+
+/// Marks the message of the current L1 handler tx as consumed.
+pub fn consume_l1_to_l2_message(
+    _vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     _ids_data: &HashMap<String, HintReference>,
     _ap_tracking: &ApTracking,
     _constants: &HashMap<String, Felt252>,
 ) -> Result<(), HintError> {
-    let tx = exec_scopes.get::<InternalTransaction>(vars::scopes::TX)?;
-    let version = tx.version.unwrap_or(Felt252::ZERO);
-    let tip =
-        if version < Felt252::THREE { Felt252::ZERO } else { tx.tip.ok_or(custom_hint_error("tx.tip is None"))? };
+    let tx = exec_scopes.get_ref::<InternalTransaction>(vars::scopes::TX)?;
+    let message_hash = l1_handler_message_hash(tx)?;
+
+    let mut l1_to_l2_messages: L1ToL2Messages =
+        get_variable_from_root_exec_scope(exec_scopes, vars::scopes::L1_TO_L2_MESSAGES)?;
+    l1_to_l2_messages.consume(exec_scopes, message_hash)?;
+    set_variable_in_root_exec_scope(exec_scopes, vars::scopes::L1_TO_L2_MESSAGES, l1_to_l2_messages);
+
+    Ok(())
+}
+
+pub const TX_CALLDATA_LEN: &str = "memory[ap] = to_felt_or_relocatable(len(tx.calldata))";
+
+pub fn tx_calldata_len(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    _ids_data: &HashMap<String, HintReference>,
+    _ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError> {
+    let tx = exec_scopes.get::<InternalTransaction>(vars::scopes::TX)?;
+    let len = tx.calldata.ok_or(custom_hint_error("tx.calldata is None"))?.len();
+    insert_value_into_ap(vm, Felt252::from(len))
+}
+
+pub const TX_CALLDATA: &str = "memory[ap] = to_felt_or_relocatable(segments.gen_arg(tx.calldata))";
+
+pub fn tx_calldata(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    _ids_data: &HashMap<String, HintReference>,
+    _ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError> {
+    let tx = exec_scopes.get::<InternalTransaction>(vars::scopes::TX)?;
+    let calldata =
+        tx.calldata.ok_or(custom_hint_error("tx.calldata is None"))?.iter().map(|felt| felt.into()).collect::<Vec<_>>();
+    let calldata_base = vm.add_memory_segment();
+    vm.load_data(calldata_base, &calldata)?;
+    insert_value_into_ap(vm, calldata_base)
+}
+
+pub const LOAD_TX_CALLDATA: &str = indoc! {r#"
+    ids.calldata_size = len(tx.calldata)
+    ids.calldata = segments.gen_arg(arg=tx.calldata)"#
+};
+
+/// Lays out the calldata of the tx in a new segment, along with its size. A tx without calldata
+/// gets an empty segment.
+pub fn load_tx_calldata(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError> {
+    let tx = exec_scopes.get_ref::<InternalTransaction>(vars::scopes::TX)?;
+    let calldata: Vec<MaybeRelocatable> =
+        tx.calldata.as_deref().unwrap_or_default().iter().map(MaybeRelocatable::from).collect();
+
+    insert_value_from_var_name(vars::ids::CALLDATA_SIZE, calldata.len(), vm, ids_data, ap_tracking)?;
+    let calldata_base = vm.add_memory_segment();
+    vm.load_data(calldata_base, &calldata)?;
+    insert_value_from_var_name(vars::ids::CALLDATA, calldata_base, vm, ids_data, ap_tracking)
+}
+
+pub const TX_ENTRY_POINT_SELECTOR: &str = "memory[ap] = to_felt_or_relocatable(tx.entry_point_selector)";
+pub fn tx_entry_point_selector(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    _ids_data: &HashMap<String, HintReference>,
+    _ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError> {
+    let tx = exec_scopes.get::<InternalTransaction>(vars::scopes::TX)?;
+    let entry_point_selector = tx.entry_point_selector.ok_or(custom_hint_error("tx.entry_point_selector is None"))?;
+    insert_value_into_ap(vm, entry_point_selector)
+}
+
+pub const RESOURCE_BOUNDS: &str = indoc! {r#"
+    from src.starkware.starknet.core.os.transaction_hash.transaction_hash import (
+        create_resource_bounds_list,
+    )
+
+    ids.resource_bounds = (
+        0
+        if tx.version < 3
+        else segments.gen_arg(create_resource_bounds_list(tx.resource_bounds))
+    )"#
+};
+
+pub fn resource_bounds(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError> {
+    let tx = exec_scopes.get::<InternalTransaction>(vars::scopes::TX)?;
+    let version = tx.version.unwrap_or_default();
+
+    let resource_bounds = if version < Felt252::THREE {
+        MaybeRelocatable::Int(Felt252::ZERO)
+    } else {
+        let resource_bounds: Vec<_> =
+            create_resource_bounds_list(&tx.resource_bounds.ok_or(custom_hint_error("tx.resource_bounds is None"))?)
+                .iter()
+                .map(|f| MaybeRelocatable::Int(*f))
+                .collect();
+        vm.gen_arg(&resource_bounds)?
+    };
+
+    insert_value_from_var_name(vars::ids::RESOURCE_BOUNDS, resource_bounds, vm, ids_data, ap_tracking)
+}
+
+pub const FILL_RESOURCE_BOUNDS: &str = indoc! {r#"
+    from src.starkware.starknet.core.os.transaction_hash.transaction_hash import (
+        create_resource_bounds_list,
+    )
+
+    segments.write_arg(ids.resource_bounds, create_resource_bounds_list(tx.resource_bounds))"#
+};
+
+/// Writes the resource bounds of a v3 tx into the already allocated `ids.resource_bounds` array,
+/// as `(resource, max_amount, max_price_per_unit)` entries in the canonical order (L1_GAS, L2_GAS).
+pub fn fill_resource_bounds(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError> {
+    let tx = exec_scopes.get_ref::<InternalTransaction>(vars::scopes::TX)?;
+    let resource_bounds = tx.resource_bounds.as_ref().ok_or(custom_hint_error("tx.resource_bounds is None"))?;
+    let resource_bounds: Vec<_> =
+        create_resource_bounds_list(resource_bounds).into_iter().map(MaybeRelocatable::Int).collect();
+
+    let resource_bounds_ptr = get_ptr_from_var_name(vars::ids::RESOURCE_BOUNDS, vm, ids_data, ap_tracking)?;
+    vm.load_data(resource_bounds_ptr, &resource_bounds)?;
+
+    Ok(())
+}
+
+pub const TX_MAX_FEE: &str = "memory[ap] = to_felt_or_relocatable(tx.max_fee if tx.version < 3 else 0)";
+pub fn tx_max_fee(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    _ids_data: &HashMap<String, HintReference>,
+    _ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError> {
+    let tx = exec_scopes.get::<InternalTransaction>(vars::scopes::TX)?;
+    let version = tx.version.unwrap_or(Felt252::ZERO);
+    let max_fee = if version < Felt252::THREE {
+        tx.max_fee.ok_or(custom_hint_error("tx.max_fee is None"))?
+    } else {
+        Felt252::ZERO
+    };
+
+    insert_value_into_ap(vm, max_fee)
+}
+
+pub const TX_NONCE: &str = "memory[ap] = to_felt_or_relocatable(0 if tx.nonce is None else tx.nonce)";
+pub fn tx_nonce(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    _ids_data: &HashMap<String, HintReference>,
+    _ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError> {
+    let tx = exec_scopes.get::<InternalTransaction>(vars::scopes::TX)?;
+    let nonce = if tx.nonce.is_none() { 0.into() } else { tx.nonce.ok_or(custom_hint_error("tx.nonce is None"))? };
+    insert_value_into_ap(vm, nonce)
+}
+
+pub const TX_TIP: &str = "memory[ap] = to_felt_or_relocatable(0 if tx.version < 3 else tx.tip)";
+pub fn tx_tip(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    _ids_data: &HashMap<String, HintReference>,
+    _ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError> {
+    let tx = exec_scopes.get::<InternalTransaction>(vars::scopes::TX)?;
+    let version = tx.version.unwrap_or(Felt252::ZERO);
+    let tip =
+        if version < Felt252::THREE { Felt252::ZERO } else { tx.tip.ok_or(custom_hint_error("tx.tip is None"))? };
 
     insert_value_into_ap(vm, tip)
 }
@@ -729,7 +1604,7 @@ pub fn tx_paymaster_data_len(
     let len = if tx.version.unwrap_or(Felt252::ZERO) < Felt252::THREE {
         Felt252::ZERO
     } else {
-        tx.paymaster_data.ok_or(custom_hint_error("tx.paymaster_data is None"))?.len().into()
+        tx.paymaster_data.unwrap_or_default().len().into()
     };
     insert_value_into_ap(vm, len)
 }
@@ -748,12 +1623,8 @@ pub fn tx_paymaster_data(
     let paymaster_data = if tx.version.unwrap_or_default() < Felt252::THREE {
         MaybeRelocatable::Int(Felt252::ZERO)
     } else {
-        let data: Vec<MaybeRelocatable> = tx
-            .paymaster_data
-            .ok_or(custom_hint_error("tx.paymaster_data is None"))?
-            .iter()
-            .map(|f| MaybeRelocatable::Int(*f))
-            .collect();
+        let data: Vec<MaybeRelocatable> =
+            tx.paymaster_data.unwrap_or_default().into_iter().map(MaybeRelocatable::Int).collect();
         vm.gen_arg(&data)?
     };
     insert_value_into_ap(vm, paymaster_data)
@@ -797,6 +1668,13 @@ pub fn tx_fee_data_availability_mode(
     insert_value_into_ap(vm, fee_data_availability_mode)
 }
 
+/// Returns the account deployment data of a v3 tx. The field is only part of some v3 tx types
+/// (e.g. it is absent from the RPC representation of some of them), so a missing field is
+/// served as empty data.
+fn v3_account_deployment_data(tx: &InternalTransaction) -> Vec<Felt252> {
+    tx.account_deployment_data.clone().unwrap_or_default()
+}
+
 pub const TX_ACCOUNT_DEPLOYMENT_DATA_LEN: &str =
     "memory[ap] = to_felt_or_relocatable(0 if tx.version < 3 else len(tx.account_deployment_data))";
 pub fn tx_account_deployment_data_len(
@@ -810,7 +1688,7 @@ pub fn tx_account_deployment_data_len(
     let len = if tx.version.unwrap_or(Felt252::ZERO) < Felt252::THREE {
         0usize
     } else {
-        tx.account_deployment_data.ok_or(custom_hint_error("tx.account_deployment_data is None"))?.len()
+        v3_account_deployment_data(&tx).len()
     };
 
     insert_value_into_ap(vm, Felt252::from(len))
@@ -831,18 +1709,151 @@ pub fn tx_account_deployment_data(
     let account_deployment_data = if version < Felt252::THREE {
         MaybeRelocatable::Int(Felt252::ZERO)
     } else {
-        let data: Vec<MaybeRelocatable> = tx
-            .account_deployment_data
-            .ok_or(custom_hint_error("tx.account_deployment_data is None"))?
-            .iter()
-            .map(|f| MaybeRelocatable::Int(*f))
-            .collect();
+        let data: Vec<MaybeRelocatable> =
+            v3_account_deployment_data(&tx).into_iter().map(MaybeRelocatable::Int).collect();
         vm.gen_arg(&data)?
     };
 
     insert_value_into_ap(vm, account_deployment_data)
 }
 
+/// Writes `values` to a new segment and returns its start and end.
+fn gen_felt_array(vm: &mut VirtualMachine, values: &[Felt252]) -> Result<(Relocatable, Relocatable), HintError> {
+    let start = vm.add_memory_segment();
+    let values: Vec<_> = values.iter().copied().map(MaybeRelocatable::Int).collect();
+    let end = vm.load_data(start, &values)?;
+    Ok((start, end))
+}
+
+/// Returns the address of the account a tx originates from.
+fn tx_account_contract_address(tx: &InternalTransaction) -> Felt252 {
+    // Deploy account txs have no sender, the account is the deployed contract
+    tx.sender_address.or(tx.contract_address).unwrap_or_default()
+}
+
+/// Writes the flat tx info layout of pre-v3 txs, where the signature is passed as a (length,
+/// pointer) pair and there are no v3 fields.
+fn write_legacy_tx_info(
+    vm: &mut VirtualMachine,
+    tx: &InternalTransaction,
+    chain_id: Felt252,
+) -> Result<Relocatable, HintError> {
+    let signature = tx.signature.clone().unwrap_or_default();
+    let (signature_start, _) = gen_felt_array(vm, &signature)?;
+
+    let tx_info = TxInfoLayout {
+        version: tx.version.unwrap_or_default(),
+        account_contract_address: tx_account_contract_address(tx),
+        max_fee: tx.max_fee.unwrap_or_default(),
+        signature_len: Felt252::from(signature.len()),
+        signature: signature_start,
+        transaction_hash: tx.hash_value,
+        chain_id,
+        nonce: tx.nonce.unwrap_or_default(),
+    };
+    let tx_info_ptr = vm.add_memory_segment();
+    tx_info.to_memory(vm, tx_info_ptr)?;
+
+    Ok(tx_info_ptr)
+}
+
+/// Writes the tx info layout of v3 txs.
+fn write_v3_tx_info(
+    vm: &mut VirtualMachine,
+    tx: &InternalTransaction,
+    chain_id: Felt252,
+) -> Result<Relocatable, HintError> {
+    let resource_bounds = create_resource_bounds_list(
+        tx.resource_bounds.as_ref().ok_or(custom_hint_error("tx.resource_bounds is None"))?,
+    );
+    let (signature_start, signature_end) = gen_felt_array(vm, &tx.signature.clone().unwrap_or_default())?;
+    let (resource_bounds_start, resource_bounds_end) = gen_felt_array(vm, &resource_bounds)?;
+    let (paymaster_data_start, paymaster_data_end) =
+        gen_felt_array(vm, &tx.paymaster_data.clone().unwrap_or_default())?;
+    let (account_deployment_data_start, account_deployment_data_end) =
+        gen_felt_array(vm, &v3_account_deployment_data(tx))?;
+
+    let tx_info = TxInfoV3Layout {
+        version: tx.version.unwrap_or_default(),
+        account_contract_address: tx_account_contract_address(tx),
+        // v3 txs pay fees according to their resource bounds
+        max_fee: Felt252::ZERO,
+        signature_start,
+        signature_end,
+        transaction_hash: tx.hash_value,
+        chain_id,
+        nonce: tx.nonce.unwrap_or_default(),
+        resource_bounds_start,
+        resource_bounds_end,
+        tip: tx.tip.unwrap_or_default(),
+        paymaster_data_start,
+        paymaster_data_end,
+        nonce_data_availability_mode: tx.nonce_data_availability_mode.unwrap_or_default(),
+        fee_data_availability_mode: tx.fee_data_availability_mode.unwrap_or_default(),
+        account_deployment_data_start,
+        account_deployment_data_end,
+    };
+    let tx_info_ptr = vm.add_memory_segment();
+    tx_info.to_memory(vm, tx_info_ptr)?;
+
+    Ok(tx_info_ptr)
+}
+
+pub const GET_EXECUTION_INFO_LEGACY: &str = indoc! {r#"
+    ids.tx_info = segments.gen_arg([
+        tx.version,
+        tx.sender_address,
+        tx.max_fee,
+        len(tx.signature),
+        segments.gen_arg(tx.signature),
+        tx.hash_value,
+        os_input.general_config.chain_id.value,
+        tx.nonce,
+    ])"#
+};
+
+pub fn get_execution_info_legacy(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError> {
+    let tx = exec_scopes.get::<InternalTransaction>(vars::scopes::TX)?;
+    let block_context = exec_scopes.get_ref::<BlockContext>(vars::scopes::BLOCK_CONTEXT)?;
+    let chain_id = chain_id_to_felt(&block_context.chain_info().chain_id);
+
+    let tx_info_ptr = write_legacy_tx_info(vm, &tx, chain_id)?;
+    insert_value_from_var_name(vars::ids::TX_INFO, tx_info_ptr, vm, ids_data, ap_tracking)
+}
+
+pub const GET_EXECUTION_INFO: &str = indoc! {r#"
+    if tx.version < 3:
+        ids.tx_info = create_legacy_tx_info(segments=segments, tx=tx)
+    else:
+        ids.tx_info = create_tx_info(segments=segments, tx=tx)"#
+};
+
+/// Writes the tx info of the current tx, using the legacy layout for pre-v3 txs.
+pub fn get_execution_info(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError> {
+    let tx = exec_scopes.get::<InternalTransaction>(vars::scopes::TX)?;
+    if tx.version.unwrap_or_default() < Felt252::THREE {
+        return get_execution_info_legacy(vm, exec_scopes, ids_data, ap_tracking, constants);
+    }
+
+    let block_context = exec_scopes.get_ref::<BlockContext>(vars::scopes::BLOCK_CONTEXT)?;
+    let chain_id = chain_id_to_felt(&block_context.chain_info().chain_id);
+
+    let tx_info_ptr = write_v3_tx_info(vm, &tx, chain_id)?;
+    insert_value_from_var_name(vars::ids::TX_INFO, tx_info_ptr, vm, ids_data, ap_tracking)
+}
+
 pub const GEN_SIGNATURE_ARG: &str = indoc! {r#"
 	ids.signature_start = segments.gen_arg(arg=tx.signature)
 	ids.signature_len = len(tx.signature)"#
@@ -901,7 +1912,28 @@ where
     execute_coroutine(start_tx_async::<PCS>(vm, exec_scopes, ids_data, ap_tracking))?
 }
 
+pub const GET_TX_INDEX: &str = "ids.tx_index = execution_helper.tx_index";
+/// Serves the index of the current tx within the block.
+pub fn get_tx_index<PCS>(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let execution_helper = exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER)?;
+    let tx_index = execute_coroutine(async { execution_helper.execution_helper.read().await.tx_index })?
+        .ok_or(custom_hint_error("No transaction was started"))?;
+
+    insert_value_from_var_name(vars::ids::TX_INDEX, Felt252::from(tx_index), vm, ids_data, ap_tracking)
+}
+
 pub const IS_REVERTED: &str = "memory[ap] = to_felt_or_relocatable(execution_helper.tx_execution_info.is_reverted)";
+/// In validation-only mode, txs are reported as reverted so that the OS skips their `__execute__`
+/// call, which is not replayed.
 pub fn is_reverted<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
@@ -919,7 +1951,7 @@ where
             .tx_execution_info
             .as_ref()
             .ok_or(HintError::CustomHint("ExecutionHelper should have tx_execution_info".to_owned().into_boxed_str()))
-            .map(|tx_execution_info| tx_execution_info.is_reverted())
+            .map(|tx_execution_info| tx_execution_info.is_reverted() || eh_ref.validate_only)
     })??;
 
     insert_value_into_ap(vm, Felt252::from(is_reverted))
@@ -1264,16 +2296,12 @@ where
     let contract_address = get_integer_from_var_name(vars::ids::CONTRACT_ADDRESS, vm, ids_data, ap_tracking)?;
 
     let value = execution_helper
-        .read_storage_for_address(contract_address, key)
+        .serve_storage_read(contract_address, key)
         .await
         .map_err(|e| custom_hint_error(format!("Failed to read storage for contract {}: {e}", contract_address)))?;
 
     let ids_value = get_integer_from_var_name(vars::ids::VALUE, vm, ids_data, ap_tracking)?;
-    if ids_value != value {
-        return Err(HintError::AssertionFailed(
-            format!("Inconsistent storage value (expected {}, got {})", ids_value, value).into_boxed_str(),
-        ));
-    }
+    hint_assert!(exec_scopes, ids_value == value, "Inconsistent storage value (expected {}, got {})", ids_value, value);
 
     exec_scopes.insert_value(vars::scopes::VALUE, value);
 
@@ -1358,6 +2386,51 @@ pub fn set_fp_plus_4_to_tx_nonce(
     Ok(())
 }
 
+pub const WRITE_TX_INFO_NONCE: &str = "ids.tx_info.nonce = 0 if tx.nonce is None else tx.nonce";
+
+/// Writes the nonce of the current tx to the tx info of the call. Txs of older versions have no
+/// nonce, they are exposed to contracts with a zero nonce.
+pub fn write_tx_info_nonce(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError> {
+    let tx: &InternalTransaction = exec_scopes.get_ref(vars::scopes::TX)?;
+    let nonce = tx.nonce.unwrap_or_default();
+
+    let tx_info_ptr = get_ptr_from_var_name(vars::ids::TX_INFO, vm, ids_data, ap_tracking)?;
+    vm.insert_value((tx_info_ptr + TxInfo::nonce_offset())?, nonce)?;
+
+    Ok(())
+}
+
+pub const WRITE_TX_INFO_MAX_FEE: &str = "ids.tx_info.max_fee = tx.max_fee if tx.version < 3 else 0";
+
+/// Writes the max fee of the current tx to the tx info of the call. v3 txs bound their fees with
+/// resource bounds instead, their max fee is zero.
+pub fn write_tx_info_max_fee(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError> {
+    let tx: &InternalTransaction = exec_scopes.get_ref(vars::scopes::TX)?;
+    let version = strip_query_bit(tx.version.ok_or(custom_hint_error("tx.version is not set"))?);
+    let max_fee = if version < Felt252::THREE {
+        tx.max_fee.ok_or(custom_hint_error("tx.max_fee is not set"))?
+    } else {
+        Felt252::ZERO
+    };
+
+    let tx_info_ptr = get_ptr_from_var_name(vars::ids::TX_INFO, vm, ids_data, ap_tracking)?;
+    vm.insert_value((tx_info_ptr + TxInfo::max_fee_offset())?, max_fee)?;
+
+    Ok(())
+}
+
 pub fn enter_node_scope(node: UpdateTree<StorageLeaf>, exec_scopes: &mut ExecutionScopes) -> Result<(), HintError> {
     // vm_enter_scope(dict(node=new_node, **common_args))"#
     // In this implementation we assume that `common_args` is unpacked, having a
@@ -1912,18 +2985,37 @@ pub fn fetch_result(
 #[cfg(test)]
 mod tests {
     use std::cell::RefCell;
+    use std::collections::BTreeMap;
     use std::rc::Rc;
 
-    use blockifier::context::BlockContext;
+    use blockifier::blockifier::block::BlockInfo;
+    use blockifier::bouncer::BouncerConfig;
+    use blockifier::context::{BlockContext, ChainInfo};
+    use blockifier::execution::call_info::CallInfo;
+    use blockifier::execution::entry_point::{CallEntryPoint, CallType};
+    use blockifier::fee::actual_cost::TransactionReceipt;
+    use blockifier::transaction::objects::TransactionExecutionInfo;
+    use blockifier::versioned_constants::VersionedConstants;
+    use cairo_vm::hint_processor::builtin_hint_processor::builtin_hint_processor_definition::HintProcessorData;
     use cairo_vm::hint_processor::builtin_hint_processor::dict_manager::DictManager;
+    use cairo_vm::hint_processor::hint_processor_definition::HintProcessorLogic;
     use cairo_vm::types::relocatable::Relocatable;
     use num_bigint::BigUint;
     use rstest::{fixture, rstest};
+    use starknet_api::block::BlockNumber;
+    use starknet_api::core::{ChainId, ContractAddress, EntryPointSelector};
+    use starknet_api::deprecated_contract_class::{ContractClass, EntryPointType, FunctionAbiEntry, TypedParameter};
+    use starknet_api::transaction::{Calldata, Resource, ResourceBounds, ResourceBoundsMapping};
+    use starknet_os_types::casm_contract_class::GenericCasmContractClass;
+    use starknet_os_types::deprecated_compiled_class::GenericDeprecatedCompiledClass;
 
     use super::*;
-    use crate::config::STORED_BLOCK_HASH_BUFFER;
+    use crate::config::{AssertionMode, StarknetGeneralConfig, STORED_BLOCK_HASH_BUFFER};
     use crate::crypto::pedersen::PedersenHash;
+    use crate::execution::block_hash_provider::InMemoryBlockHashProvider;
     use crate::execution::helper::ContractStorageMap;
+    use crate::hints::SnosHintProcessor;
+    use crate::starknet::core::os::block_hash::Event;
     use crate::starknet::starknet_storage::{OsSingleStarknetStorage, StorageLeaf};
     use crate::starkware_utils::commitment_tree::base_types::Height;
     use crate::starkware_utils::commitment_tree::binary_fact_tree::BinaryFactTree;
@@ -1931,6 +3023,7 @@ mod tests {
     use crate::starkware_utils::commitment_tree::update_tree::TreeUpdate;
     use crate::storage::dict_storage::DictStorage;
     use crate::storage::storage::FactFetchingContext;
+    use crate::utils::get_selector_from_name;
 
     #[allow(clippy::upper_case_acronyms)]
     type PCS = OsSingleStarknetStorage<DictStorage, PedersenHash>;
@@ -1969,147 +3062,1966 @@ mod tests {
         // We pass the same tree as previous and updated tree as this is enough for the tests.
         let os_single_starknet_storage = OsSingleStarknetStorage::new(tree.clone(), tree, &[], ffc).await.unwrap();
 
-        {
-            let storage_by_address = &mut execution_helper.execution_helper.write().await.storage_by_address;
-            storage_by_address.insert(contract_address, os_single_starknet_storage);
+        {
+            let storage_by_address = &mut execution_helper.execution_helper.write().await.storage_by_address;
+            storage_by_address.insert(contract_address, os_single_starknet_storage);
+        }
+
+        execution_helper
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_read_keys(#[future] execution_helper_with_storage: EHW, contract_address: Felt252) {
+        let mut execution_helper = execution_helper_with_storage.await;
+
+        let other_contract_address = Felt252::from(301);
+        let other_key = Felt252::from(43);
+        {
+            let mut ffc = FactFetchingContext::<_, PedersenHash>::new(DictStorage::default());
+            let tree = PatriciaTree::empty_tree(&mut ffc, Height(251), StorageLeaf::empty()).await.unwrap();
+            let modifications = vec![(other_key.to_biguint(), StorageLeaf::new(Felt252::from(9000)))];
+            let mut facts = None;
+            let tree = tree.update(&mut ffc, modifications, &mut facts).await.unwrap();
+            let storage = OsSingleStarknetStorage::new(tree.clone(), tree, &[], ffc).await.unwrap();
+
+            execution_helper.execution_helper.write().await.storage_by_address.insert(other_contract_address, storage);
+        }
+
+        let key = Felt252::from(42);
+
+        // Reads that are not served to `storage_read` syscalls are not tracked
+        execution_helper.read_storage_for_address(contract_address, key).await.unwrap();
+        assert!(execution_helper.read_keys().await.is_empty());
+
+        assert_eq!(execution_helper.serve_storage_read(contract_address, key).await.unwrap(), Felt252::from(8000));
+        assert_eq!(
+            execution_helper.serve_storage_read(other_contract_address, other_key).await.unwrap(),
+            Felt252::from(9000)
+        );
+        // Reading a key twice does not duplicate it
+        execution_helper.serve_storage_read(contract_address, key).await.unwrap();
+
+        assert_eq!(
+            execution_helper.read_keys().await,
+            HashSet::from([(contract_address, key), (other_contract_address, other_key)])
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_current_calldata(mut execution_helper: EHW) {
+        let calldata = vec![Felt252::from(1), Felt252::from(0xabcd), Felt252::MAX];
+        let call_info = CallInfo {
+            call: CallEntryPoint { calldata: Calldata(Arc::new(calldata.clone())), ..Default::default() },
+            ..Default::default()
+        };
+        execution_helper.execution_helper.write().await.call_iter = vec![call_info].into_iter();
+
+        assert_eq!(execution_helper.current_calldata().await, None);
+
+        execution_helper.enter_call(None).await.unwrap();
+        assert_eq!(execution_helper.current_calldata().await, Some(calldata));
+
+        execution_helper.exit_call().await;
+        assert_eq!(execution_helper.current_calldata().await, None);
+    }
+
+    #[rstest]
+    #[case::matching_selector(Felt252::from(0x1234), true)]
+    #[case::other_selector(Felt252::from(0x4321), false)]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_assert_entry_point_selector(
+        execution_helper: EHW,
+        #[case] selector: Felt252,
+        #[case] expected_success: bool,
+    ) {
+        let call_info = CallInfo {
+            call: CallEntryPoint {
+                entry_point_selector: EntryPointSelector(Felt252::from(0x1234)),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        execution_helper.execution_helper.write().await.call_info = Some(call_info);
+
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let ids_data = HashMap::from([(vars::ids::SELECTOR.to_string(), HintReference::new_simple(-1))]);
+        insert_value_from_var_name(vars::ids::SELECTOR, selector, &mut vm, &ids_data, &ApTracking::new()).unwrap();
+
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, execution_helper);
+
+        let result = assert_entry_point_selector::<PCS>(
+            &mut vm,
+            &mut exec_scopes,
+            &ids_data,
+            &ApTracking::new(),
+            &HashMap::new(),
+        );
+        if expected_success {
+            result.unwrap();
+        } else {
+            assert!(matches!(result, Err(HintError::AssertionFailed(_))));
+        }
+    }
+
+    #[rstest]
+    #[case::os_caller(Felt252::ZERO, true)]
+    #[case::contract_caller(Felt252::from(0x1234), false)]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_assert_validate_caller(
+        execution_helper: EHW,
+        #[case] caller_address: Felt252,
+        #[case] expected_success: bool,
+    ) {
+        let call_info = CallInfo {
+            call: CallEntryPoint {
+                caller_address: ContractAddress::try_from(caller_address).unwrap(),
+                entry_point_selector: EntryPointSelector(get_selector_from_name("__validate__")),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        execution_helper.execution_helper.write().await.call_info = Some(call_info);
+
+        let mut vm = VirtualMachine::new(false);
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, execution_helper);
+
+        let result = assert_validate_caller::<PCS>(
+            &mut vm,
+            &mut exec_scopes,
+            &HashMap::new(),
+            &ApTracking::new(),
+            &HashMap::new(),
+        );
+        if expected_success {
+            result.unwrap();
+        } else {
+            assert!(matches!(result, Err(HintError::AssertionFailed(_))));
+        }
+    }
+
+    fn l1_handler_tx(nonce: u64) -> InternalTransaction {
+        InternalTransaction {
+            hash_value: Felt252::from(nonce),
+            r#type: "L1_HANDLER".to_string(),
+            contract_address: Some(Felt252::from(0x1234)),
+            entry_point_selector: Some(Felt252::from(0x5678)),
+            nonce: Some(Felt252::from(nonce)),
+            calldata: Some(vec![Felt252::from(0xabcd), Felt252::ONE]),
+            ..Default::default()
+        }
+    }
+
+    #[rstest]
+    #[case::consumed_once(vec![l1_handler_tx(1)], None)]
+    #[case::consumed_twice(vec![l1_handler_tx(1), l1_handler_tx(1)], Some("already consumed"))]
+    #[case::unknown_message(vec![l1_handler_tx(2)], Some("Unknown L1-to-L2 message"))]
+    fn test_consume_l1_to_l2_message(
+        #[case] executed_txs: Vec<InternalTransaction>,
+        #[case] expected_error: Option<&str>,
+    ) {
+        let mut vm = VirtualMachine::new(false);
+        let ids_data = HashMap::new();
+        let ap_tracking = ApTracking::new();
+        let constants = HashMap::new();
+
+        // The block contains a single message
+        let os_input = StarknetOsInput {
+            transactions: vec![l1_handler_tx(1), InternalTransaction::default()],
+            ..Default::default()
+        };
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::OS_INPUT, Rc::new(os_input));
+        initialize_l1_to_l2_messages(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &constants).unwrap();
+
+        // Txs run in a nested scope
+        exec_scopes.enter_scope(HashMap::new());
+        let mut result = Ok(());
+        for tx in executed_txs {
+            exec_scopes.insert_value(vars::scopes::TX, tx);
+            result = consume_l1_to_l2_message(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &constants);
+            if result.is_err() {
+                break;
+            }
+        }
+
+        match expected_error {
+            None => result.unwrap(),
+            Some(expected_error) => {
+                let error = result.unwrap_err();
+                assert!(
+                    matches!(&error, HintError::AssertionFailed(message) if message.contains(expected_error)),
+                    "{error}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_consume_l1_to_l2_message_lenient() {
+        let mut vm = VirtualMachine::new(false);
+        let os_input = StarknetOsInput { transactions: vec![l1_handler_tx(1)], ..Default::default() };
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::OS_INPUT, Rc::new(os_input));
+        exec_scopes.insert_value(vars::scopes::ASSERTION_MODE, AssertionMode::Lenient);
+        initialize_l1_to_l2_messages(&mut vm, &mut exec_scopes, &HashMap::new(), &ApTracking::new(), &HashMap::new())
+            .unwrap();
+
+        // Consuming a message twice or an unknown message is only reported
+        exec_scopes.enter_scope(HashMap::new());
+        for tx in [l1_handler_tx(1), l1_handler_tx(1), l1_handler_tx(2)] {
+            exec_scopes.insert_value(vars::scopes::TX, tx);
+            consume_l1_to_l2_message(&mut vm, &mut exec_scopes, &HashMap::new(), &ApTracking::new(), &HashMap::new())
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_consume_l1_to_l2_message_of_malformed_tx() {
+        let mut vm = VirtualMachine::new(false);
+        let ids_data = HashMap::new();
+        let ap_tracking = ApTracking::new();
+        let constants = HashMap::new();
+
+        let malformed_tx = InternalTransaction { nonce: None, ..l1_handler_tx(2) };
+        let os_input =
+            StarknetOsInput { transactions: vec![l1_handler_tx(1), malformed_tx.clone()], ..Default::default() };
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::OS_INPUT, Rc::new(os_input));
+        initialize_l1_to_l2_messages(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &constants)
+            .expect("A malformed L1 handler tx should not fail the block");
+
+        exec_scopes.enter_scope(HashMap::new());
+        exec_scopes.insert_value(vars::scopes::TX, l1_handler_tx(1));
+        consume_l1_to_l2_message(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &constants).unwrap();
+
+        // Only the malformed tx fails
+        exec_scopes.insert_value(vars::scopes::TX, malformed_tx);
+        let result = consume_l1_to_l2_message(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &constants);
+        assert!(matches!(result, Err(HintError::CustomHint(message)) if message.as_ref() == "tx.nonce is None"));
+    }
+
+    #[rstest]
+    #[case::matching_hash(0xc51a543ef9563ad2545342b390b67edf_u128, true)]
+    #[case::other_hash(0xc51a543ef9563ad2545342b390b67ede_u128, false)]
+    fn test_assert_l1_to_l2_message_hash(#[case] message_hash_high: u128, #[case] expected_success: bool) {
+        let tx = InternalTransaction {
+            r#type: "L1_HANDLER".to_string(),
+            contract_address: Some(
+                Felt252::from_hex("0x73314940630fd6dcda0d772d4c972c4e0a9946bef9dabf4ef84eda8ef542b82").unwrap(),
+            ),
+            entry_point_selector: Some(
+                Felt252::from_hex("0x2d757788a8d8d6f21d1cd40bce38a8222d70654214e96ff95d8086e684fbee5").unwrap(),
+            ),
+            nonce: Some(Felt252::from(775628)),
+            calldata: Some(vec![
+                Felt252::from_hex("0xc3511006c04ef1d78af4c8e0e74ec18a6e64ff9e").unwrap(),
+                Felt252::from_hex("0x689ead7d814e51ed93644bc145f0754839b8dcb340027ce0c30953f38f55d7").unwrap(),
+                Felt252::from_hex("0x2c68af0bb140000").unwrap(),
+                Felt252::ZERO,
+            ]),
+            ..Default::default()
+        };
+
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(2);
+        // ids.message_hash is a Uint256 (low, high)
+        vm.insert_value(Relocatable::from((1, 0)), Felt252::from(0xcddf9886aa36846cf70382362fc5fab3_u128)).unwrap();
+        vm.insert_value(Relocatable::from((1, 1)), Felt252::from(message_hash_high)).unwrap();
+
+        let ids_data = HashMap::from([(vars::ids::MESSAGE_HASH.to_string(), HintReference::new_simple(-2))]);
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::TX, tx);
+
+        let result =
+            assert_l1_to_l2_message_hash(&mut vm, &mut exec_scopes, &ids_data, &ApTracking::new(), &HashMap::new());
+        if expected_success {
+            result.unwrap();
+        } else {
+            assert!(matches!(result, Err(HintError::AssertionFailed(_))));
+        }
+    }
+
+    #[rstest]
+    #[tokio::test]
+    #[ignore] // TODO: fix
+    async fn test_cache_contract_storage_request_key(
+        #[future] execution_helper_with_storage: EHW,
+        contract_address: Felt252,
+    ) {
+        let execution_helper_with_storage = execution_helper_with_storage.await;
+
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(5);
+
+        let ap_tracking = ApTracking::new();
+        let constants = HashMap::new();
+
+        let ids_data = HashMap::from([
+            (vars::ids::SYSCALL_PTR.to_string(), HintReference::new_simple(-3)),
+            (vars::ids::CONTRACT_ADDRESS.to_string(), HintReference::new_simple(-2)),
+            (vars::ids::VALUE.to_string(), HintReference::new_simple(-1)),
+        ]);
+
+        // Make ids.request point to (1, 0)
+        insert_value_from_var_name(vars::ids::SYSCALL_PTR, (1, 0), &mut vm, &ids_data, &ap_tracking).unwrap();
+        let key = Felt252::from(42);
+        // request.key is at offset 1 in the structure
+        vm.insert_value(Relocatable::from((1, 1)), key).unwrap();
+
+        insert_value_from_var_name(vars::ids::CONTRACT_ADDRESS, contract_address, &mut vm, &ids_data, &ap_tracking)
+            .unwrap();
+        insert_value_from_var_name(vars::ids::VALUE, Felt252::from(8000), &mut vm, &ids_data, &ap_tracking).unwrap();
+
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, execution_helper_with_storage);
+
+        // Just make sure that the hint goes through, all meaningful assertions are
+        // in the implementation of the hint
+        cache_contract_storage_request_key::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &constants)
+            .expect("Hint should not fail");
+    }
+
+    #[test]
+    fn test_enter_scope_new_node() {
+        let preimage: Preimage = HashMap::new();
+        let descent_map: DescentMap = HashMap::new();
+        let patricia_skip_validation_runner: Option<PatriciaSkipValidationRunner> = None;
+        let left_child = Some(TreeUpdate::Leaf(StorageLeaf::new(Felt252::ZERO)));
+        let right_child: Option<TreeUpdate<StorageLeaf>> = None;
+        let case = DecodeNodeCase::Left;
+
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let ids_data = HashMap::from([(vars::ids::CHILD_BIT.to_string(), HintReference::new_simple(-1))]);
+
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::PREIMAGE, preimage.clone());
+        exec_scopes.insert_value(vars::scopes::DESCENT_MAP, descent_map.clone());
+        exec_scopes
+            .insert_value(vars::scopes::PATRICIA_SKIP_VALIDATION_RUNNER, patricia_skip_validation_runner.clone());
+        exec_scopes.insert_value(vars::scopes::LEFT_CHILD, left_child.clone());
+        exec_scopes.insert_value(vars::scopes::RIGHT_CHILD, right_child);
+        exec_scopes.insert_value(vars::scopes::CASE, case);
+
+        let ap_tracking = ApTracking::new();
+        let constants = HashMap::new();
+
+        enter_scope_new_node(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &constants)
+            .expect("Hint should succeed");
+
+        assert_eq!(exec_scopes.data.len(), 2, "A new scope should have been created");
+        assert_eq!(exec_scopes.data[1].len(), 4, "The new scope should contain 4 items");
+        assert_eq!(exec_scopes.get::<Preimage>(vars::scopes::PREIMAGE).unwrap(), preimage);
+        assert_eq!(exec_scopes.get::<DescentMap>(vars::scopes::DESCENT_MAP).unwrap(), descent_map);
+        assert_eq!(
+            exec_scopes
+                .get::<Option<PatriciaSkipValidationRunner>>(vars::scopes::PATRICIA_SKIP_VALIDATION_RUNNER)
+                .unwrap(),
+            patricia_skip_validation_runner
+        );
+        assert_eq!(exec_scopes.get::<UpdateTree<StorageLeaf>>(vars::scopes::NODE).unwrap(), left_child);
+
+        let child_bit = get_integer_from_var_name(vars::ids::CHILD_BIT, &vm, &ids_data, &ap_tracking).unwrap();
+        assert_eq!(child_bit, Felt252::ZERO);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    #[ignore] // TODO: fix
+    async fn test_write_syscall_result(#[future] execution_helper_with_storage: EHW, contract_address: Felt252) {
+        let mut execution_helper_with_storage = execution_helper_with_storage.await;
+
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(9);
+
+        let ap_tracking = ApTracking::new();
+        let constants = HashMap::new();
+
+        let ids_data = HashMap::from([
+            (vars::ids::SYSCALL_PTR.to_string(), HintReference::new_simple(-6)),
+            (vars::ids::PREV_VALUE.to_string(), HintReference::new_simple(-5)),
+            (vars::ids::CONTRACT_ADDRESS.to_string(), HintReference::new_simple(-4)),
+            (vars::ids::CONTRACT_STATE_CHANGES.to_string(), HintReference::new_simple(-3)),
+            (vars::ids::STATE_ENTRY.to_string(), HintReference::new_simple(-2)),
+            (vars::ids::NEW_STATE_ENTRY.to_string(), HintReference::new_simple(-1)),
+        ]);
+
+        let key = Felt252::from(42);
+        let value = Felt252::from(777);
+        insert_value_from_var_name(vars::ids::SYSCALL_PTR, Relocatable::from((1, 0)), &mut vm, &ids_data, &ap_tracking)
+            .unwrap();
+        // syscall_ptr.address is at offset 1 in the structure
+        vm.insert_value(Relocatable::from((1, 1)), key).unwrap();
+        // syscall_ptr.value is at offset 1 in the structure
+        vm.insert_value(Relocatable::from((1, 2)), value).unwrap();
+
+        insert_value_from_var_name(vars::ids::CONTRACT_ADDRESS, contract_address, &mut vm, &ids_data, &ap_tracking)
+            .unwrap();
+
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, execution_helper_with_storage.clone());
+
+        // Prepare the dict manager for `get_state_entry()`
+        let mut dict_manager = DictManager::new();
+        let contract_state_changes = dict_manager
+            .new_dict(&mut vm, HashMap::from([(contract_address.into(), MaybeRelocatable::from(123))]))
+            .unwrap();
+        exec_scopes.insert_value(vars::scopes::DICT_MANAGER, Rc::new(RefCell::new(dict_manager)));
+
+        insert_value_from_var_name(
+            vars::ids::CONTRACT_STATE_CHANGES,
+            contract_state_changes,
+            &mut vm,
+            &ids_data,
+            &ap_tracking,
+        )
+        .unwrap();
+
+        // Just make sure that the hint goes through, all meaningful assertions are
+        // in the implementation of the hint
+        write_syscall_result_deprecated::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &constants)
+            .expect("Hint should not fail");
+
+        // Check that the storage was updated
+        let prev_value = get_integer_from_var_name(vars::ids::PREV_VALUE, &vm, &ids_data, &ap_tracking).unwrap();
+        assert_eq!(prev_value, Felt252::from(8000));
+        let stored_value = execution_helper_with_storage.read_storage_for_address(contract_address, key).await.unwrap();
+        assert_eq!(stored_value, Felt252::from(777));
+
+        // Check the state entry
+        let state_entry = get_integer_from_var_name(vars::ids::STATE_ENTRY, &vm, &ids_data, &ap_tracking).unwrap();
+        assert_eq!(state_entry, Felt252::from(123));
+    }
+
+    #[test]
+    fn test_set_fp_plus_4_to_tx_nonce() {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+
+        let ap_tracking = ApTracking::new();
+        let constants = HashMap::new();
+
+        let ids_data = HashMap::new();
+
+        // insert tx with a nonce
+        let tx = InternalTransaction { nonce: Some(Felt252::THREE), ..Default::default() };
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::TX, tx);
+
+        set_fp_plus_4_to_tx_nonce(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &constants).unwrap();
+
+        let address: Relocatable = (vm.get_fp() + 4usize).unwrap();
+        let value = vm.get_integer(address).unwrap().into_owned();
+        assert_eq!(value, Felt252::THREE);
+    }
+
+    #[rstest]
+    #[case::with_nonce(Some(Felt252::THREE), Felt252::THREE)]
+    #[case::without_nonce(None, Felt252::ZERO)]
+    fn test_write_tx_info_nonce(#[case] nonce: Option<Felt252>, #[case] expected_nonce: Felt252) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        let tx_info_ptr = vm.add_memory_segment();
+        vm.set_fp(1);
+        vm.insert_value((vm.get_fp() - 1).unwrap(), tx_info_ptr).unwrap();
+
+        let ids_data = HashMap::from([(vars::ids::TX_INFO.to_string(), HintReference::new_simple(-1))]);
+        let tx = InternalTransaction { version: Some(Felt252::ONE), nonce, ..Default::default() };
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::TX, tx);
+
+        write_tx_info_nonce(&mut vm, &mut exec_scopes, &ids_data, &ApTracking::new(), &HashMap::new()).unwrap();
+
+        let value = vm.get_integer((tx_info_ptr + TxInfo::nonce_offset()).unwrap()).unwrap().into_owned();
+        assert_eq!(value, expected_nonce);
+    }
+
+    #[rstest]
+    #[case::v1(Felt252::ONE, Some(Felt252::from(1_000_000)), Felt252::from(1_000_000))]
+    #[case::v3(Felt252::THREE, None, Felt252::ZERO)]
+    fn test_write_tx_info_max_fee(
+        #[case] version: Felt252,
+        #[case] max_fee: Option<Felt252>,
+        #[case] expected_max_fee: Felt252,
+    ) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        let tx_info_ptr = vm.add_memory_segment();
+        vm.set_fp(1);
+        vm.insert_value((vm.get_fp() - 1).unwrap(), tx_info_ptr).unwrap();
+
+        let ids_data = HashMap::from([(vars::ids::TX_INFO.to_string(), HintReference::new_simple(-1))]);
+        let tx = InternalTransaction { version: Some(version), max_fee, ..Default::default() };
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::TX, tx);
+
+        write_tx_info_max_fee(&mut vm, &mut exec_scopes, &ids_data, &ApTracking::new(), &HashMap::new()).unwrap();
+
+        let value = vm.get_integer((tx_info_ptr + TxInfo::max_fee_offset()).unwrap()).unwrap().into_owned();
+        assert_eq!(value, expected_max_fee);
+    }
+
+    #[rstest]
+    #[case::invoke(Some(vec![Felt252::from(0x100), Felt252::from(0x200), Felt252::MAX]))]
+    #[case::empty(Some(vec![]))]
+    #[case::missing(None)]
+    fn test_load_tx_calldata(#[case] calldata: Option<Vec<Felt252>>) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(2);
+
+        let ids_data = HashMap::from([
+            (vars::ids::CALLDATA_SIZE.to_string(), HintReference::new_simple(-2)),
+            (vars::ids::CALLDATA.to_string(), HintReference::new_simple(-1)),
+        ]);
+        let ap_tracking = ApTracking::new();
+        let tx = InternalTransaction {
+            r#type: "INVOKE_FUNCTION".to_string(),
+            calldata: calldata.clone(),
+            ..Default::default()
+        };
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::TX, tx);
+
+        load_tx_calldata(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &HashMap::new()).unwrap();
+
+        let expected_calldata = calldata.unwrap_or_default();
+        let calldata_size = get_integer_from_var_name(vars::ids::CALLDATA_SIZE, &vm, &ids_data, &ap_tracking).unwrap();
+        assert_eq!(calldata_size, Felt252::from(expected_calldata.len()));
+
+        let calldata_ptr = get_ptr_from_var_name(vars::ids::CALLDATA, &vm, &ids_data, &ap_tracking).unwrap();
+        let loaded_calldata: Vec<_> = vm
+            .get_integer_range(calldata_ptr, expected_calldata.len())
+            .unwrap()
+            .into_iter()
+            .map(|felt| felt.into_owned())
+            .collect();
+        assert_eq!(loaded_calldata, expected_calldata);
+        assert!(vm.get_maybe(&(calldata_ptr + expected_calldata.len()).unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_effective_transaction_version_strips_query_bit() {
+        let query_version = Felt252::TWO.pow(QUERY_VERSION_BASE_BIT) + Felt252::THREE;
+        let tx = InternalTransaction { version: Some(query_version), ..Default::default() };
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::TX, tx);
+
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        effective_transaction_version(&mut vm, &mut exec_scopes, &HashMap::new(), &ApTracking::new(), &HashMap::new())
+            .unwrap();
+        let effective_version = vm.get_integer(vm.get_ap()).unwrap().into_owned();
+        assert_eq!(effective_version, Felt252::THREE);
+
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        transaction_version(&mut vm, &mut exec_scopes, &HashMap::new(), &ApTracking::new(), &HashMap::new()).unwrap();
+        let raw_version = vm.get_integer(vm.get_ap()).unwrap().into_owned();
+        assert_eq!(raw_version, query_version);
+    }
+
+    #[rstest]
+    #[case::in_window(42, Felt252::from(0x90))]
+    #[case::last_in_window(100 - STORED_BLOCK_HASH_BUFFER, Felt252::from(0x90))]
+    #[case::out_of_window(95, Felt252::ZERO)]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_read_block_hash(
+        execution_helper: EHW,
+        #[case] request_block_number: u64,
+        #[case] expected_block_hash: Felt252,
+    ) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(2);
+        vm.insert_value(Relocatable::from((1, 0)), Felt252::from(request_block_number)).unwrap();
+        vm.insert_value(Relocatable::from((1, 1)), Felt252::from(100)).unwrap();
+        vm.set_ap(2);
+
+        let ids_data = HashMap::from([
+            (vars::ids::REQUEST_BLOCK_NUMBER.to_string(), HintReference::new_simple(-2)),
+            (vars::ids::CURRENT_BLOCK_NUMBER.to_string(), HintReference::new_simple(-1)),
+        ]);
+        let constants = HashMap::from([(
+            vars::constants::STORED_BLOCK_HASH_BUFFER.to_string(),
+            Felt252::from(STORED_BLOCK_HASH_BUFFER),
+        )]);
+
+        execution_helper.execution_helper.write().await.execute_code_read_iter =
+            vec![Felt252::from(0x90), Felt252::from(0x91)].into_iter();
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, execution_helper.clone());
+
+        read_block_hash::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ApTracking::new(), &constants).unwrap();
+
+        let block_hash = vm.get_integer(vm.get_ap()).unwrap().into_owned();
+        assert_eq!(block_hash, expected_block_hash);
+
+        // Out-of-window requests do not consume a recorded read
+        let n_remaining_reads = execution_helper.execution_helper.read().await.execute_code_read_iter.len();
+        let expected_remaining_reads = if expected_block_hash == Felt252::ZERO { 2 } else { 1 };
+        assert_eq!(n_remaining_reads, expected_remaining_reads);
+    }
+
+    #[rstest]
+    #[case::genesis(0, Felt252::ZERO)]
+    #[case::last_block_without_old_hash(STORED_BLOCK_HASH_BUFFER - 1, Felt252::ZERO)]
+    #[case::first_block_with_old_hash(STORED_BLOCK_HASH_BUFFER, Felt252::from(0x90))]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_read_block_hash_early_blocks(
+        #[case] current_block_number: u64,
+        #[case] expected_block_hash: Felt252,
+    ) {
+        let block_info =
+            BlockInfo { block_number: BlockNumber(current_block_number), ..BlockInfo::create_for_testing() };
+        let block_context = BlockContext::new(
+            block_info,
+            ChainInfo::create_for_testing(),
+            VersionedConstants::create_for_testing(),
+            BouncerConfig::max(),
+        );
+        let execution_helper =
+            EHW::new(ContractStorageMap::default(), vec![], &block_context, None, (Felt252::ZERO, Felt252::ZERO));
+        // There is no previous block context before the buffer is filled
+        assert_eq!(
+            execution_helper.execution_helper.read().await._prev_block_context.is_some(),
+            current_block_number >= STORED_BLOCK_HASH_BUFFER
+        );
+        execution_helper.execution_helper.write().await.execute_code_read_iter = vec![Felt252::from(0x90)].into_iter();
+
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(2);
+        vm.insert_value(Relocatable::from((1, 0)), Felt252::ZERO).unwrap();
+        vm.insert_value(Relocatable::from((1, 1)), Felt252::from(current_block_number)).unwrap();
+        vm.set_ap(2);
+
+        let ids_data = HashMap::from([
+            (vars::ids::REQUEST_BLOCK_NUMBER.to_string(), HintReference::new_simple(-2)),
+            (vars::ids::CURRENT_BLOCK_NUMBER.to_string(), HintReference::new_simple(-1)),
+        ]);
+        let constants = HashMap::from([(
+            vars::constants::STORED_BLOCK_HASH_BUFFER.to_string(),
+            Felt252::from(STORED_BLOCK_HASH_BUFFER),
+        )]);
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, execution_helper);
+
+        read_block_hash::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ApTracking::new(), &constants).unwrap();
+
+        let block_hash = vm.get_integer(vm.get_ap()).unwrap().into_owned();
+        assert_eq!(block_hash, expected_block_hash);
+    }
+
+    #[rstest]
+    #[case::consistent(Felt252::from(0x90), Ok(Felt252::from(0x90)))]
+    #[case::inconsistent(Felt252::from(0x91), Err(()))]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_read_block_hash_from_provider(
+        execution_helper: EHW,
+        #[case] recorded_block_hash: Felt252,
+        #[case] expected: Result<Felt252, ()>,
+    ) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(2);
+        vm.insert_value(Relocatable::from((1, 0)), Felt252::from(42)).unwrap();
+        vm.insert_value(Relocatable::from((1, 1)), Felt252::from(100)).unwrap();
+        vm.set_ap(2);
+
+        let ids_data = HashMap::from([
+            (vars::ids::REQUEST_BLOCK_NUMBER.to_string(), HintReference::new_simple(-2)),
+            (vars::ids::CURRENT_BLOCK_NUMBER.to_string(), HintReference::new_simple(-1)),
+        ]);
+        let constants = HashMap::from([(
+            vars::constants::STORED_BLOCK_HASH_BUFFER.to_string(),
+            Felt252::from(STORED_BLOCK_HASH_BUFFER),
+        )]);
+
+        execution_helper
+            .set_block_hash_provider(Rc::new(InMemoryBlockHashProvider::new(HashMap::from([(
+                42,
+                Felt252::from(0x90),
+            )]))))
+            .await;
+        execution_helper.execution_helper.write().await.execute_code_read_iter = vec![recorded_block_hash].into_iter();
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, execution_helper.clone());
+
+        let result = read_block_hash::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ApTracking::new(), &constants);
+
+        match expected {
+            Ok(expected_block_hash) => {
+                result.unwrap();
+                assert_eq!(vm.get_integer(vm.get_ap()).unwrap().into_owned(), expected_block_hash);
+            }
+            Err(()) => assert!(matches!(result, Err(HintError::AssertionFailed(_)))),
+        }
+    }
+
+    #[test]
+    fn test_fill_resource_bounds() {
+        let resource_bounds = ResourceBoundsMapping(BTreeMap::from([
+            (Resource::L1Gas, ResourceBounds { max_amount: 0x100, max_price_per_unit: 0x200 }),
+            (Resource::L2Gas, ResourceBounds { max_amount: 0x300, max_price_per_unit: 0x400 }),
+        ]));
+        let tx = InternalTransaction {
+            version: Some(Felt252::THREE),
+            resource_bounds: Some(resource_bounds),
+            ..Default::default()
+        };
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::TX, tx);
+
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        let resource_bounds_ptr = vm.add_memory_segment();
+        vm.set_fp(1);
+        vm.insert_value(Relocatable::from((1, 0)), resource_bounds_ptr).unwrap();
+        let ids_data = HashMap::from([(vars::ids::RESOURCE_BOUNDS.to_string(), HintReference::new_simple(-1))]);
+
+        fill_resource_bounds(&mut vm, &mut exec_scopes, &ids_data, &ApTracking::new(), &HashMap::new()).unwrap();
+
+        let memory: Vec<_> =
+            vm.get_integer_range(resource_bounds_ptr, 6).unwrap().into_iter().map(|felt| felt.into_owned()).collect();
+        let expected = vec![
+            Felt252::from_bytes_be_slice(b"L1_GAS"),
+            Felt252::from(0x100),
+            Felt252::from(0x200),
+            Felt252::from_bytes_be_slice(b"L2_GAS"),
+            Felt252::from(0x300),
+            Felt252::from(0x400),
+        ];
+        assert_eq!(memory, expected);
+    }
+
+    #[rstest]
+    #[case::deploy_account_with_data("DEPLOY_ACCOUNT", Some(vec![10, 11]), vec![10, 11])]
+    #[case::invoke_without_data("INVOKE_FUNCTION", None, vec![])]
+    fn test_tx_account_deployment_data_v3(
+        #[case] tx_type: &str,
+        #[case] account_deployment_data: Option<Vec<u64>>,
+        #[case] expected_data: Vec<u64>,
+    ) {
+        let tx = InternalTransaction {
+            r#type: tx_type.to_string(),
+            version: Some(Felt252::THREE),
+            account_deployment_data: account_deployment_data.map(|data| data.into_iter().map(Felt252::from).collect()),
+            ..Default::default()
+        };
+        let expected_data: Vec<_> = expected_data.into_iter().map(Felt252::from).collect();
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::TX, tx);
+
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        tx_account_deployment_data_len(&mut vm, &mut exec_scopes, &HashMap::new(), &ApTracking::new(), &HashMap::new())
+            .unwrap();
+        let len = vm.get_integer(vm.get_ap()).unwrap().into_owned();
+        assert_eq!(len, Felt252::from(expected_data.len()));
+
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        tx_account_deployment_data(&mut vm, &mut exec_scopes, &HashMap::new(), &ApTracking::new(), &HashMap::new())
+            .unwrap();
+        let data_ptr = vm.get_relocatable(vm.get_ap()).unwrap();
+        let data: Vec<_> = vm
+            .get_integer_range(data_ptr, expected_data.len())
+            .unwrap()
+            .into_iter()
+            .map(|felt| felt.into_owned())
+            .collect();
+        assert_eq!(data, expected_data);
+        assert_eq!(vm.segments.compute_effective_sizes()[data_ptr.segment_index as usize], expected_data.len());
+    }
+
+    #[rstest]
+    fn test_get_execution_info_legacy_tx(block_context: BlockContext) {
+        let tx = InternalTransaction {
+            r#type: "INVOKE_FUNCTION".to_string(),
+            version: Some(Felt252::ONE),
+            sender_address: Some(Felt252::from(0x1234)),
+            max_fee: Some(Felt252::from(1_000_000)),
+            signature: Some(vec![Felt252::from(5), Felt252::from(6)]),
+            nonce: Some(Felt252::from(7)),
+            hash_value: Felt252::from(0xabcdef),
+            ..Default::default()
+        };
+        let chain_id = chain_id_to_felt(&block_context.chain_info().chain_id);
+
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::TX, tx);
+        exec_scopes.insert_value(vars::scopes::BLOCK_CONTEXT, block_context);
+
+        let ids_data = HashMap::from([(vars::ids::TX_INFO.to_string(), HintReference::new_simple(-1))]);
+        let ap_tracking = ApTracking::new();
+
+        get_execution_info(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &HashMap::new()).unwrap();
+
+        let tx_info_ptr = get_ptr_from_var_name(vars::ids::TX_INFO, &vm, &ids_data, &ap_tracking).unwrap();
+        let tx_info = TxInfoLayout::from_memory(&vm, tx_info_ptr).unwrap();
+        assert_eq!(
+            tx_info,
+            TxInfoLayout {
+                version: Felt252::ONE,
+                account_contract_address: Felt252::from(0x1234),
+                max_fee: Felt252::from(1_000_000),
+                signature_len: Felt252::TWO,
+                signature: tx_info.signature,
+                transaction_hash: Felt252::from(0xabcdef),
+                chain_id,
+                nonce: Felt252::from(7),
+            }
+        );
+        let signature: Vec<_> =
+            vm.get_integer_range(tx_info.signature, 2).unwrap().into_iter().map(|felt| felt.into_owned()).collect();
+        assert_eq!(signature, vec![Felt252::from(5), Felt252::from(6)]);
+        // The legacy layout is flat, nothing is written past the nonce
+        assert_eq!(vm.segments.compute_effective_sizes()[tx_info_ptr.segment_index as usize], TxInfoLayout::n_fields());
+    }
+
+    #[rstest]
+    fn test_get_execution_info_v3_tx(block_context: BlockContext) {
+        let resource_bounds = ResourceBoundsMapping(BTreeMap::from([
+            (Resource::L1Gas, ResourceBounds { max_amount: 100, max_price_per_unit: 10 }),
+            (Resource::L2Gas, ResourceBounds { max_amount: 0, max_price_per_unit: 0 }),
+        ]));
+        let tx = InternalTransaction {
+            r#type: "INVOKE_FUNCTION".to_string(),
+            version: Some(Felt252::THREE),
+            sender_address: Some(Felt252::from(0x1234)),
+            signature: Some(vec![Felt252::from(5)]),
+            nonce: Some(Felt252::from(7)),
+            tip: Some(Felt252::from(3)),
+            resource_bounds: Some(resource_bounds.clone()),
+            hash_value: Felt252::from(0xabcdef),
+            ..Default::default()
+        };
+
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::TX, tx);
+        exec_scopes.insert_value(vars::scopes::BLOCK_CONTEXT, block_context);
+
+        let ids_data = HashMap::from([(vars::ids::TX_INFO.to_string(), HintReference::new_simple(-1))]);
+        let ap_tracking = ApTracking::new();
+
+        get_execution_info(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &HashMap::new()).unwrap();
+
+        let tx_info_ptr = get_ptr_from_var_name(vars::ids::TX_INFO, &vm, &ids_data, &ap_tracking).unwrap();
+        let tx_info = TxInfoV3Layout::from_memory(&vm, tx_info_ptr).unwrap();
+        assert_eq!(tx_info.version, Felt252::THREE);
+        assert_eq!(tx_info.max_fee, Felt252::ZERO);
+        assert_eq!(tx_info.tip, Felt252::from(3));
+        assert_eq!((tx_info.signature_end - tx_info.signature_start).unwrap(), 1);
+        assert_eq!(
+            (tx_info.resource_bounds_end - tx_info.resource_bounds_start).unwrap(),
+            create_resource_bounds_list(&resource_bounds).len()
+        );
+        assert_eq!(tx_info.paymaster_data_start, tx_info.paymaster_data_end);
+        assert_eq!(tx_info.account_deployment_data_start, tx_info.account_deployment_data_end);
+    }
+
+    #[rstest]
+    fn test_get_execution_info_v3_signature(block_context: BlockContext) {
+        let signature = vec![Felt252::from(0x51), Felt252::from(0x52), Felt252::from(0x53)];
+        let tx = InternalTransaction {
+            r#type: "INVOKE_FUNCTION".to_string(),
+            version: Some(Felt252::THREE),
+            sender_address: Some(Felt252::from(0x1234)),
+            signature: Some(signature.clone()),
+            nonce: Some(Felt252::ZERO),
+            resource_bounds: Some(ResourceBoundsMapping(BTreeMap::from([(
+                Resource::L1Gas,
+                ResourceBounds { max_amount: 100, max_price_per_unit: 10 },
+            )]))),
+            hash_value: Felt252::from(0xabcdef),
+            ..Default::default()
+        };
+
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::TX, tx);
+        exec_scopes.insert_value(vars::scopes::BLOCK_CONTEXT, block_context);
+
+        let ids_data = HashMap::from([(vars::ids::TX_INFO.to_string(), HintReference::new_simple(-1))]);
+        let ap_tracking = ApTracking::new();
+
+        get_execution_info(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &HashMap::new()).unwrap();
+
+        // Contracts read the signature as a span, i.e. through its start and end pointers
+        let tx_info_ptr = get_ptr_from_var_name(vars::ids::TX_INFO, &vm, &ids_data, &ap_tracking).unwrap();
+        let tx_info = TxInfoV3Layout::from_memory(&vm, tx_info_ptr).unwrap();
+        let signature_len = (tx_info.signature_end - tx_info.signature_start).unwrap();
+        let served_signature: Vec<_> = vm
+            .get_integer_range(tx_info.signature_start, signature_len)
+            .unwrap()
+            .into_iter()
+            .map(|felt| felt.into_owned())
+            .collect();
+        assert_eq!(served_signature, signature);
+    }
+
+    #[rstest]
+    #[case::legacy(Felt252::ONE)]
+    #[case::v3(Felt252::THREE)]
+    fn test_get_execution_info_chain_id(block_context: BlockContext, #[case] tx_version: Felt252) {
+        let chain_id = ChainId::Other("SN_CUSTOM".to_string());
+        let block_context = BlockContext::new(
+            block_context.block_info().clone(),
+            ChainInfo { chain_id: chain_id.clone(), ..block_context.chain_info().clone() },
+            block_context.versioned_constants().clone(),
+            BouncerConfig::max(),
+        );
+        let tx = InternalTransaction {
+            r#type: "INVOKE_FUNCTION".to_string(),
+            version: Some(tx_version),
+            sender_address: Some(Felt252::from(0x1234)),
+            resource_bounds: Some(ResourceBoundsMapping(BTreeMap::from([
+                (Resource::L1Gas, ResourceBounds { max_amount: 100, max_price_per_unit: 10 }),
+                (Resource::L2Gas, ResourceBounds { max_amount: 0, max_price_per_unit: 0 }),
+            ]))),
+            hash_value: Felt252::from(0xabcdef),
+            ..Default::default()
+        };
+
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::TX, tx);
+        exec_scopes.insert_value(vars::scopes::BLOCK_CONTEXT, block_context);
+
+        let ids_data = HashMap::from([(vars::ids::TX_INFO.to_string(), HintReference::new_simple(-1))]);
+        let ap_tracking = ApTracking::new();
+
+        get_execution_info(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &HashMap::new()).unwrap();
+
+        // The chain id has the same offset in the legacy and v3 layouts
+        let tx_info_ptr = get_ptr_from_var_name(vars::ids::TX_INFO, &vm, &ids_data, &ap_tracking).unwrap();
+        let served_chain_id = vm.get_integer((tx_info_ptr + TxInfo::chain_id_offset()).unwrap()).unwrap().into_owned();
+        assert_eq!(served_chain_id, chain_id_to_felt(&chain_id));
+    }
+
+    #[rstest]
+    #[case::with_paymaster_data(Some(vec![Felt252::from(11), Felt252::from(12), Felt252::from(13)]))]
+    #[case::without_paymaster_data(None)]
+    fn test_v3_tx_paymaster_data(block_context: BlockContext, #[case] paymaster_data: Option<Vec<Felt252>>) {
+        let expected_paymaster_data = paymaster_data.clone().unwrap_or_default();
+        let tx = InternalTransaction {
+            r#type: "INVOKE_FUNCTION".to_string(),
+            version: Some(Felt252::THREE),
+            sender_address: Some(Felt252::from(0x1234)),
+            resource_bounds: Some(ResourceBoundsMapping(BTreeMap::new())),
+            paymaster_data,
+            ..Default::default()
+        };
+
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+        vm.set_ap(1);
+
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::TX, tx);
+        exec_scopes.insert_value(vars::scopes::BLOCK_CONTEXT, block_context);
+
+        let ids_data = HashMap::from([(vars::ids::TX_INFO.to_string(), HintReference::new_simple(-1))]);
+        let ap_tracking = ApTracking::new();
+
+        // Length and array served on their own
+        tx_paymaster_data_len(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &HashMap::new()).unwrap();
+        assert_eq!(vm.get_integer(vm.get_ap()).unwrap().into_owned(), Felt252::from(expected_paymaster_data.len()));
+        vm.set_ap(2);
+        tx_paymaster_data(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &HashMap::new()).unwrap();
+        let paymaster_data_ptr = vm.get_relocatable(vm.get_ap()).unwrap();
+        let served_data: Vec<_> = vm
+            .get_integer_range(paymaster_data_ptr, expected_paymaster_data.len())
+            .unwrap()
+            .into_iter()
+            .map(|felt| felt.into_owned())
+            .collect();
+        assert_eq!(served_data, expected_paymaster_data);
+
+        // Array in the tx info
+        get_execution_info(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &HashMap::new()).unwrap();
+        let tx_info_ptr = get_ptr_from_var_name(vars::ids::TX_INFO, &vm, &ids_data, &ap_tracking).unwrap();
+        let tx_info = TxInfoV3Layout::from_memory(&vm, tx_info_ptr).unwrap();
+        let paymaster_data_len = (tx_info.paymaster_data_end - tx_info.paymaster_data_start).unwrap();
+        assert_eq!(paymaster_data_len, expected_paymaster_data.len());
+        let tx_info_data: Vec<_> = vm
+            .get_integer_range(tx_info.paymaster_data_start, paymaster_data_len)
+            .unwrap()
+            .into_iter()
+            .map(|felt| felt.into_owned())
+            .collect();
+        assert_eq!(tx_info_data, expected_paymaster_data);
+    }
+
+    /// Returns the execution info of a tx with a single (default) call to replay.
+    fn tx_execution_info_with_one_call() -> TransactionExecutionInfo {
+        TransactionExecutionInfo {
+            validate_call_info: None,
+            execute_call_info: Some(Default::default()),
+            fee_transfer_call_info: None,
+            revert_error: None,
+            transaction_receipt: TransactionReceipt {
+                fee: Default::default(),
+                gas: Default::default(),
+                da_gas: Default::default(),
+                resources: Default::default(),
+            },
+        }
+    }
+
+    /// Code of a hint that fails while a call is being replayed, see `run_tx`.
+    const FAILING_REPLAY_HINT: &str = "execution_helper.fail_replay()";
+
+    /// Runs a tx with a single call through the hint processor. If `fail_mid_replay` is set, a hint
+    /// of the call fails.
+    async fn run_tx(
+        hint_processor: &mut SnosHintProcessor<PCS>,
+        exec_helper: &EHW,
+        tx_hash: Felt252,
+        fail_mid_replay: bool,
+    ) -> Result<(), HintError> {
+        let mut vm = VirtualMachine::new(false);
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, exec_helper.clone());
+        exec_scopes.insert_value(vars::scopes::TX, InternalTransaction { hash_value: tx_hash, ..Default::default() });
+        let mut run_hint = |hint_code: &str| {
+            let hint_data: Box<dyn Any> =
+                Box::new(HintProcessorData::new_default(hint_code.to_string(), HashMap::new()));
+            hint_processor.execute_hint_extensive(&mut vm, &mut exec_scopes, &hint_data, &HashMap::new()).map(|_| ())
+        };
+
+        exec_helper.start_tx(None).await;
+        exec_helper.enter_call(None).await.unwrap();
+        if fail_mid_replay {
+            run_hint(FAILING_REPLAY_HINT)?;
+        }
+        run_hint(EXIT_CALL)?;
+        run_hint(END_TX)
+    }
+
+    fn hint_processor_failing_mid_replay() -> SnosHintProcessor<PCS> {
+        let mut hint_processor = SnosHintProcessor::<PCS>::default();
+        hint_processor.override_hint(
+            FAILING_REPLAY_HINT,
+            Box::new(|_, _, _, _, _| Err(HintError::AssertionFailed("Replayed call mismatch".into()))),
+        );
+        hint_processor
+    }
+
+    fn execution_helper_for_txs(
+        n_txs: usize,
+        continue_on_tx_failure: bool,
+        block_context: &BlockContext,
+        old_block_number_and_hash: (Felt252, Felt252),
+    ) -> EHW {
+        let general_config = StarknetGeneralConfig { continue_on_tx_failure, ..Default::default() };
+        EHW::new(
+            ContractStorageMap::default(),
+            vec![tx_execution_info_with_one_call(); n_txs],
+            block_context,
+            Some(Rc::new(StarknetOsInput { general_config, ..Default::default() })),
+            old_block_number_and_hash,
+        )
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_continue_on_tx_failure(block_context: BlockContext, old_block_number_and_hash: (Felt252, Felt252)) {
+        let exec_helper = execution_helper_for_txs(3, true, &block_context, old_block_number_and_hash);
+        let mut hint_processor = hint_processor_failing_mid_replay();
+
+        // The second tx fails while its call is replayed
+        run_tx(&mut hint_processor, &exec_helper, Felt252::from(1), false).await.unwrap();
+        run_tx(&mut hint_processor, &exec_helper, Felt252::from(2), true).await.unwrap();
+        run_tx(&mut hint_processor, &exec_helper, Felt252::from(3), false).await.unwrap();
+
+        assert_eq!(exec_helper.failed_tx_hashes().await, vec![Felt252::from(2)]);
+
+        // All three txs went through
+        let eh_ref = exec_helper.execution_helper.read().await;
+        assert!(eh_ref.tx_execution_info.is_none());
+        assert!(eh_ref.tx_execution_info_iter.clone().peekable().peek().is_none());
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_tx_failure_aborts_by_default(
+        block_context: BlockContext,
+        old_block_number_and_hash: (Felt252, Felt252),
+    ) {
+        let exec_helper = execution_helper_for_txs(1, false, &block_context, old_block_number_and_hash);
+        let mut hint_processor = hint_processor_failing_mid_replay();
+
+        let result = run_tx(&mut hint_processor, &exec_helper, Felt252::from(1), true).await;
+        assert!(
+            matches!(result, Err(HintError::AssertionFailed(message)) if message.as_ref() == "Replayed call mismatch")
+        );
+        assert!(exec_helper.failed_tx_hashes().await.is_empty());
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_continue_on_tx_failure_only_skips_assertions(
+        block_context: BlockContext,
+        old_block_number_and_hash: (Felt252, Felt252),
+    ) {
+        let exec_helper = execution_helper_for_txs(1, true, &block_context, old_block_number_and_hash);
+        // A hint that fails before writing its results
+        let mut hint_processor = SnosHintProcessor::<PCS>::default();
+        hint_processor.override_hint(
+            FAILING_REPLAY_HINT,
+            Box::new(|_, _, _, _, _| Err(custom_hint_error("Cannot compute the value of ids.x"))),
+        );
+
+        let result = run_tx(&mut hint_processor, &exec_helper, Felt252::from(1), true).await;
+        assert!(
+            matches!(result, Err(HintError::CustomHint(message)) if message.as_ref() == "Cannot compute the value of ids.x")
+        );
+        assert!(exec_helper.failed_tx_hashes().await.is_empty());
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_end_tx_records_calls_left_over(
+        block_context: BlockContext,
+        old_block_number_and_hash: (Felt252, Felt252),
+    ) {
+        let exec_helper = execution_helper_for_txs(2, true, &block_context, old_block_number_and_hash);
+        let mut vm = VirtualMachine::new(false);
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, exec_helper.clone());
+        exec_scopes
+            .insert_value(vars::scopes::TX, InternalTransaction { hash_value: Felt252::TWO, ..Default::default() });
+
+        // The first tx ends without its call being replayed
+        exec_helper.start_tx(None).await;
+        end_tx::<PCS>(&mut vm, &mut exec_scopes, &HashMap::new(), &ApTracking::new(), &HashMap::new()).unwrap();
+        assert_eq!(exec_helper.failed_tx_hashes().await, vec![Felt252::TWO]);
+
+        // The failure does not leak into the next tx
+        exec_helper.start_tx(None).await;
+        assert!(!exec_helper.execution_helper.read().await.current_tx_failed);
+    }
+
+    fn call_info_with_calldata(calldata: u64) -> CallInfo {
+        CallInfo {
+            call: CallEntryPoint { calldata: Calldata(Arc::new(vec![Felt252::from(calldata)])), ..Default::default() },
+            ..Default::default()
+        }
+    }
+
+    fn run_get_tx_index(exec_scopes: &mut ExecutionScopes) -> Result<Felt252, HintError> {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+        let ids_data = HashMap::from([(vars::ids::TX_INDEX.to_string(), HintReference::new_simple(-1))]);
+        let ap_tracking = ApTracking::new();
+
+        get_tx_index::<PCS>(&mut vm, exec_scopes, &ids_data, &ap_tracking, &HashMap::new())?;
+        get_integer_from_var_name(vars::ids::TX_INDEX, &vm, &ids_data, &ap_tracking)
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_get_tx_index(block_context: BlockContext, old_block_number_and_hash: (Felt252, Felt252)) {
+        let execution_helper = EHW::new(
+            ContractStorageMap::default(),
+            (0..3).map(|_| TransactionExecutionInfo::default()).collect(),
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        );
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, execution_helper.clone());
+
+        // No tx was started yet
+        assert!(matches!(run_get_tx_index(&mut exec_scopes), Err(HintError::CustomHint(_))));
+
+        for expected_tx_index in 0..3u64 {
+            execution_helper.start_tx(None).await;
+            assert_eq!(run_get_tx_index(&mut exec_scopes).unwrap(), Felt252::from(expected_tx_index));
+            execution_helper.end_tx().await;
+        }
+    }
+
+    /// Execution info of a tx of the given type: its validate call has calldata `[0x1]` and its
+    /// execute call `[0x2]`.
+    fn tx_execution_info_of_type(tx_type: &str) -> TransactionExecutionInfo {
+        let mut tx_execution_info = tx_execution_info_with_one_call();
+        let mut execute_call_info = call_info_with_calldata(0x2);
+        match tx_type {
+            "INVOKE_FUNCTION" => {
+                tx_execution_info.validate_call_info = Some(call_info_with_calldata(0x1));
+            }
+            "DEPLOY_ACCOUNT" => {
+                tx_execution_info.validate_call_info = Some(call_info_with_calldata(0x1));
+                execute_call_info.call.entry_point_type = EntryPointType::Constructor;
+            }
+            "L1_HANDLER" => {
+                execute_call_info.call.entry_point_type = EntryPointType::L1Handler;
+            }
+            _ => unreachable!("Unexpected tx type {tx_type}"),
+        }
+        tx_execution_info.execute_call_info = Some(execute_call_info);
+        tx_execution_info
+    }
+
+    #[rstest]
+    #[case::full_replay("INVOKE_FUNCTION", false, vec![0x1, 0x2])]
+    #[case::validate_only("INVOKE_FUNCTION", true, vec![0x1])]
+    // The constructor runs before `__validate_deploy__`, in both modes
+    #[case::deploy_account_full_replay("DEPLOY_ACCOUNT", false, vec![0x2, 0x1])]
+    #[case::deploy_account_validate_only("DEPLOY_ACCOUNT", true, vec![0x2, 0x1])]
+    // L1 handlers have no validation phase, their handler is always executed
+    #[case::l1_handler_validate_only("L1_HANDLER", true, vec![0x2])]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_start_tx_validate_only(
+        block_context: BlockContext,
+        old_block_number_and_hash: (Felt252, Felt252),
+        #[case] tx_type: &str,
+        #[case] validate_only: bool,
+        #[case] expected_calls: Vec<u64>,
+    ) {
+        let tx_execution_info = tx_execution_info_of_type(tx_type);
+
+        let mut general_config = StarknetGeneralConfig::default();
+        general_config.validate_only = validate_only;
+        let os_input = StarknetOsInput { general_config, ..Default::default() };
+        let mut exec_helper = EHW::new(
+            ContractStorageMap::default(),
+            vec![tx_execution_info],
+            &block_context,
+            Some(Rc::new(os_input)),
+            old_block_number_and_hash,
+        );
+
+        exec_helper.start_tx(None).await;
+        let mut replayed_calls = vec![];
+        while exec_helper.execution_helper.read().await.call_iter.len() > 0 {
+            exec_helper.enter_call(None).await.unwrap();
+            replayed_calls.push(exec_helper.current_calldata().await.unwrap()[0]);
+            exec_helper.exit_call().await;
+        }
+        // All the calls of the tx must have been consumed for the tx to end
+        exec_helper.end_tx().await;
+
+        assert_eq!(replayed_calls, expected_calls.into_iter().map(Felt252::from).collect::<Vec<_>>());
+    }
+
+    /// Runs the `enter_call` hint, as the OS does before executing an entry point.
+    fn run_enter_call_hint(exec_scopes: &mut ExecutionScopes) -> Result<(), HintError> {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+        let execution_context = vm.add_memory_segment();
+        let execution_info = vm.add_memory_segment();
+        vm.insert_value((1, 0).into(), execution_context).unwrap();
+        vm.insert_value((execution_context + ExecutionContext::execution_info_offset()).unwrap(), execution_info)
+            .unwrap();
+        let ids_data = HashMap::from([(vars::ids::EXECUTION_CONTEXT.to_string(), HintReference::new_simple(-1))]);
+
+        enter_call::<PCS>(&mut vm, exec_scopes, &ids_data, &ApTracking::new(), &HashMap::new())
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_validate_only_skips_execute_call(
+        block_context: BlockContext,
+        old_block_number_and_hash: (Felt252, Felt252),
+    ) {
+        let mut tx_execution_info = tx_execution_info_with_one_call();
+        tx_execution_info.validate_call_info = Some(call_info_with_calldata(0x1));
+        tx_execution_info.execute_call_info = Some(call_info_with_calldata(0x2));
+        tx_execution_info.fee_transfer_call_info = Some(call_info_with_calldata(0x3));
+
+        let mut general_config = StarknetGeneralConfig::default();
+        general_config.validate_only = true;
+        let os_input = StarknetOsInput { general_config, ..Default::default() };
+        let exec_helper = EHW::new(
+            ContractStorageMap::default(),
+            vec![tx_execution_info],
+            &block_context,
+            Some(Rc::new(os_input)),
+            old_block_number_and_hash,
+        );
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, exec_helper.clone());
+        exec_helper.start_tx(None).await;
+
+        // __validate__
+        run_enter_call_hint(&mut exec_scopes).unwrap();
+        assert_eq!(exec_helper.current_calldata().await.unwrap(), vec![Felt252::from(0x1)]);
+        exit_call::<PCS>(
+            &mut VirtualMachine::new(false),
+            &mut exec_scopes,
+            &HashMap::new(),
+            &ApTracking::new(),
+            &HashMap::new(),
+        )
+        .unwrap();
+
+        // __execute__ is skipped as if the tx was reverted
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        is_reverted::<PCS>(&mut vm, &mut exec_scopes, &HashMap::new(), &ApTracking::new(), &HashMap::new()).unwrap();
+        assert_eq!(vm.get_integer(vm.get_ap()).unwrap().into_owned(), Felt252::ONE);
+        crate::hints::skip_call::<PCS>(&mut vm, &mut exec_scopes, &HashMap::new(), &ApTracking::new(), &HashMap::new())
+            .unwrap();
+
+        // The fee transfer is still replayed
+        run_enter_call_hint(&mut exec_scopes).unwrap();
+        assert_eq!(exec_helper.current_calldata().await.unwrap(), vec![Felt252::from(0x3)]);
+        exit_call::<PCS>(
+            &mut VirtualMachine::new(false),
+            &mut exec_scopes,
+            &HashMap::new(),
+            &ApTracking::new(),
+            &HashMap::new(),
+        )
+        .unwrap();
+
+        // Entering a call that was not recorded fails instead of panicking
+        let error = run_enter_call_hint(&mut exec_scopes).unwrap_err();
+        assert!(matches!(error, HintError::AssertionFailed(message) if message.contains("No recorded call left")));
+    }
+
+    fn call_to(contract_address: u128, call_type: CallType, inner_calls: Vec<CallInfo>) -> CallInfo {
+        CallInfo {
+            call: CallEntryPoint {
+                storage_address: ContractAddress::from(contract_address),
+                call_type,
+                ..Default::default()
+            },
+            inner_calls,
+            ..Default::default()
+        }
+    }
+
+    #[rstest]
+    #[case::reentrant_forbidden(CallType::Call, true, true)]
+    #[case::reentrant_allowed(CallType::Call, false, false)]
+    #[case::library_call_forbidden(CallType::Delegate, true, false)]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_assert_no_reentrancy(
+        block_context: BlockContext,
+        old_block_number_and_hash: (Felt252, Felt252),
+        #[case] inner_call_type: CallType,
+        #[case] forbid_reentrancy: bool,
+        #[case] expect_error: bool,
+    ) {
+        // 0x100 calls 0x200, which calls back 0x100
+        let call_back = call_to(0x100, inner_call_type, vec![]);
+        let mut tx_execution_info = tx_execution_info_with_one_call();
+        tx_execution_info.execute_call_info =
+            Some(call_to(0x100, CallType::Call, vec![call_to(0x200, CallType::Call, vec![call_back])]));
+
+        let exec_helper = EHW::new(
+            ContractStorageMap::default(),
+            vec![tx_execution_info],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        );
+        exec_helper.execution_helper.write().await.forbid_reentrancy = forbid_reentrancy;
+        exec_helper.start_tx(None).await;
+        exec_helper.enter_call(None).await.unwrap();
+
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, exec_helper.clone());
+        let result = assert_no_reentrancy::<PCS>(
+            &mut VirtualMachine::new(false),
+            &mut exec_scopes,
+            &HashMap::new(),
+            &ApTracking::new(),
+            &HashMap::new(),
+        );
+
+        if expect_error {
+            assert!(matches!(result, Err(HintError::AssertionFailed(_))));
+        } else {
+            result.unwrap();
+        }
+    }
+
+    #[rstest]
+    fn test_nested_call_scopes_share_dict_manager(execution_helper: EHW) {
+        let mut vm = VirtualMachine::new(false);
+        let ids_data = HashMap::new();
+        let ap_tracking = ApTracking::new();
+        let constants = HashMap::new();
+
+        let dict_manager = Rc::new(RefCell::new(DictManager::new()));
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::DICT_MANAGER, dict_manager.clone());
+        exec_scopes.insert_value(vars::scopes::SYSCALL_HANDLER, OsSyscallHandlerWrapper::new(execution_helper));
+
+        // Each nested call allocates a dict in its own scope
+        let n_calls = 3;
+        for _ in 0..n_calls {
+            enter_scope_syscall_handler::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &constants).unwrap();
+            let scope_dict_manager = exec_scopes.get_dict_manager().unwrap();
+            assert!(Rc::ptr_eq(&scope_dict_manager, &dict_manager));
+            scope_dict_manager.borrow_mut().new_dict(&mut vm, HashMap::new()).unwrap();
+        }
+        enter_scope_with_dict_manager(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &constants).unwrap();
+        exec_scopes.get_dict_manager().unwrap().borrow_mut().new_dict(&mut vm, HashMap::new()).unwrap();
+
+        for _ in 0..=n_calls {
+            exec_scopes.exit_scope().unwrap();
+        }
+
+        // All the dicts are tracked by the manager of the outer scope
+        assert_eq!(dict_manager.borrow().trackers.len(), n_calls + 1);
+    }
+
+    #[rstest]
+    fn test_enter_scope_with_dict_manager_requires_manager() {
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        let result = enter_scope_with_dict_manager(
+            &mut VirtualMachine::new(false),
+            &mut exec_scopes,
+            &HashMap::new(),
+            &ApTracking::new(),
+            &HashMap::new(),
+        );
+
+        assert!(matches!(result, Err(HintError::VariableNotInScopeError(_))));
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_compiled_class_parsed_once_per_run(
+        block_context: BlockContext,
+        old_block_number_and_hash: (Felt252, Felt252),
+    ) {
+        let casm_bytes = include_bytes!(
+            "../../../../tests/integration/contracts/blockifier_contracts/feature_contracts/cairo1/compiled/\
+             test_contract.casm.json"
+        );
+        let compiled_class_hash = Felt252::from(0x1234);
+        let os_input = StarknetOsInput {
+            compiled_classes: HashMap::from([(
+                compiled_class_hash,
+                GenericCasmContractClass::from_bytes(casm_bytes.to_vec()),
+            )]),
+            ..Default::default()
+        };
+        let exec_helper = EHW::new(
+            ContractStorageMap::default(),
+            vec![tx_execution_info_with_one_call(), tx_execution_info_with_one_call()],
+            &block_context,
+            Some(Rc::new(os_input)),
+            old_block_number_and_hash,
+        );
+
+        // Two txs using the same class
+        let mut classes = vec![];
+        for _ in 0..2 {
+            exec_helper.start_tx(None).await;
+            exec_helper.enter_call(None).await.unwrap();
+            classes.push(exec_helper.execution_helper.write().await.get_compiled_class(compiled_class_hash).unwrap());
+            exec_helper.exit_call().await;
+            exec_helper.end_tx().await;
+        }
+
+        assert!(Rc::ptr_eq(&classes[0], &classes[1]));
+        assert_eq!(exec_helper.execution_helper.read().await.class_cache.n_parsed(), 1);
+
+        let unknown_class = exec_helper.execution_helper.write().await.get_compiled_class(Felt252::from(0x5678));
+        assert!(unknown_class.is_err());
+    }
+
+    #[rstest]
+    #[case::matching_hash(true)]
+    #[case::mismatched_hash(false)]
+    fn test_assert_compiled_class_hash(#[case] matching_hash: bool) {
+        let casm_bytes = include_bytes!(
+            "../../../../tests/integration/contracts/blockifier_contracts/feature_contracts/cairo1/compiled/\
+             test_contract.casm.json"
+        );
+        let compiled_class = GenericCasmContractClass::from_bytes(casm_bytes.to_vec());
+        let compiled_class_hash =
+            if matching_hash { Felt252::from(compiled_class.class_hash().unwrap()) } else { Felt252::from(0x1234) };
+
+        let os_input = StarknetOsInput {
+            compiled_classes: HashMap::from([(compiled_class_hash, compiled_class)]),
+            ..Default::default()
+        };
+        let tx = InternalTransaction {
+            r#type: "DECLARE".to_string(),
+            version: Some(Felt252::TWO),
+            compiled_class_hash: Some(compiled_class_hash),
+            ..Default::default()
+        };
+
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::OS_INPUT, Rc::new(os_input));
+        exec_scopes.insert_value(vars::scopes::TX, tx);
+        let result = assert_compiled_class_hash(
+            &mut VirtualMachine::new(false),
+            &mut exec_scopes,
+            &HashMap::new(),
+            &ApTracking::new(),
+            &HashMap::new(),
+        );
+
+        if matching_hash {
+            result.unwrap();
+        } else {
+            assert!(matches!(result, Err(HintError::AssertionFailed(_))));
+        }
+    }
+
+    #[test]
+    fn test_clear_tx_scope() {
+        let mut vm = VirtualMachine::new(false);
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::TX, InternalTransaction::default());
+        exec_scopes.insert_value(vars::scopes::OS_INPUT, 42u64);
+
+        clear_tx_scope(&mut vm, &mut exec_scopes, &HashMap::new(), &ApTracking::new(), &HashMap::new())
+            .expect("Hint should not fail");
+
+        assert!(exec_scopes.get::<InternalTransaction>(vars::scopes::TX).is_err());
+        // Variables that outlive the tx are kept
+        assert_eq!(exec_scopes.get::<u64>(vars::scopes::OS_INPUT).unwrap(), 42);
+
+        // Clearing an already cleared scope is a no-op
+        clear_tx_scope(&mut vm, &mut exec_scopes, &HashMap::new(), &ApTracking::new(), &HashMap::new())
+            .expect("Hint should not fail");
+    }
+
+    #[test]
+    fn test_load_next_tx_cancelled() {
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::CANCELLATION_FLAG, Some(Arc::new(AtomicBool::new(true))));
+        exec_scopes.insert_value(vars::scopes::TRANSACTIONS, vec![InternalTransaction::default()].into_iter());
+
+        let result = load_next_tx(
+            &mut VirtualMachine::new(false),
+            &mut exec_scopes,
+            &HashMap::new(),
+            &ApTracking::new(),
+            &HashMap::new(),
+        );
+
+        assert!(matches!(result, Err(HintError::CustomHint(_))));
+        // The tx is not consumed
+        let transactions = exec_scopes.get::<IntoIter<InternalTransaction>>(vars::scopes::TRANSACTIONS).unwrap();
+        assert_eq!(transactions.len(), 1);
+    }
+
+    #[rstest]
+    #[case::invoke_v1("INVOKE_FUNCTION", Felt252::ONE, None)]
+    #[case::invoke_v3_query("INVOKE_FUNCTION", Felt252::THREE + Felt252::TWO.pow(QUERY_VERSION_BASE_BIT), None)]
+    #[case::invoke_v4(
+        "INVOKE_FUNCTION",
+        Felt252::from(4),
+        Some("Unsupported version 4 for INVOKE_FUNCTION transaction 0x1234")
+    )]
+    #[case::deploy_account_v0(
+        "DEPLOY_ACCOUNT",
+        Felt252::ZERO,
+        Some("Unsupported version 0 for DEPLOY_ACCOUNT transaction 0x1234")
+    )]
+    #[case::l1_handler_v1("L1_HANDLER", Felt252::ONE, Some("Unsupported version 1 for L1_HANDLER transaction 0x1234"))]
+    fn test_load_next_tx_version(
+        #[case] tx_type: &str,
+        #[case] version: Felt252,
+        #[case] expected_error: Option<&str>,
+    ) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+        let ids_data = HashMap::from([(vars::ids::TX_TYPE.to_string(), HintReference::new_simple(-1))]);
+
+        let tx = InternalTransaction {
+            hash_value: Felt252::from(0x1234),
+            version: Some(version),
+            r#type: tx_type.to_string(),
+            ..Default::default()
+        };
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::TRANSACTIONS, vec![tx].into_iter());
+
+        let result = load_next_tx(&mut vm, &mut exec_scopes, &ids_data, &ApTracking::new(), &HashMap::new());
+
+        match expected_error {
+            None => {
+                result.unwrap();
+                assert_eq!(exec_scopes.get::<InternalTransaction>(vars::scopes::TX).unwrap().version, Some(version));
+            }
+            Some(expected_error) => {
+                let error = result.unwrap_err();
+                assert!(matches!(error, HintError::AssertionFailed(_)));
+                assert!(error.to_string().contains(expected_error), "Unexpected error: {error}");
+                assert!(exec_scopes.get::<InternalTransaction>(vars::scopes::TX).is_err());
+            }
+        }
+    }
+
+    #[rstest]
+    #[case::unknown_type("DEPLOY_CONTRACT", Some(Felt252::ZERO), "Unexpected transaction type: DEPLOY_CONTRACT.")]
+    #[case::missing_version("INVOKE_FUNCTION", None, "INVOKE_FUNCTION transaction 0x1234 has no version")]
+    fn test_load_next_tx_rejects_unversioned_tx(
+        #[case] tx_type: &str,
+        #[case] version: Option<Felt252>,
+        #[case] expected_error: &str,
+    ) {
+        let tx = InternalTransaction {
+            hash_value: Felt252::from(0x1234),
+            version,
+            r#type: tx_type.to_string(),
+            ..Default::default()
+        };
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::TRANSACTIONS, vec![tx].into_iter());
+
+        let result = load_next_tx(
+            &mut VirtualMachine::new(false),
+            &mut exec_scopes,
+            &HashMap::new(),
+            &ApTracking::new(),
+            &HashMap::new(),
+        );
+
+        assert!(matches!(result, Err(HintError::CustomHint(message)) if message.as_ref() == expected_error));
+        assert!(exec_scopes.get::<InternalTransaction>(vars::scopes::TX).is_err());
+    }
+
+    #[rstest]
+    #[case::all_consumed(0, 2, None)]
+    #[case::tx_left(1, 2, Some("Not all transactions were executed: 1 left"))]
+    #[case::execution_info_left(0, 1, Some("Not all transaction execution infos were consumed: 1 left"))]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_assert_transactions_consumed(
+        block_context: BlockContext,
+        old_block_number_and_hash: (Felt252, Felt252),
+        #[case] n_remaining_txs: usize,
+        #[case] n_started_txs: usize,
+        #[case] expected_error: Option<&str>,
+    ) {
+        let mut vm = VirtualMachine::new(false);
+        let ids_data = HashMap::new();
+        let ap_tracking = ApTracking::new();
+        let constants = HashMap::new();
+
+        let exec_helper = EHW::new(
+            ContractStorageMap::default(),
+            vec![tx_execution_info_with_one_call(); 2],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        );
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, exec_helper.clone());
+        exec_scopes.insert_value(
+            vars::scopes::TRANSACTIONS,
+            vec![InternalTransaction::default(); n_remaining_txs].into_iter(),
+        );
+
+        // The execution infos are consumed as txs are started
+        for _ in 0..n_started_txs {
+            exec_helper.start_tx(None).await;
         }
 
-        execution_helper
+        let result =
+            assert_transactions_consumed::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &constants);
+        match expected_error {
+            None => result.expect("Hint should not fail"),
+            Some(expected_error) => {
+                let error = result.expect_err("Hint should fail");
+                assert!(matches!(error, HintError::AssertionFailed(message) if message.as_ref() == expected_error));
+            }
+        }
     }
 
     #[rstest]
-    #[tokio::test]
-    #[ignore] // TODO: fix
-    async fn test_cache_contract_storage_request_key(
-        #[future] execution_helper_with_storage: EHW,
-        contract_address: Felt252,
+    #[case::all_output(3, None)]
+    #[case::event_dropped(2, Some("Not all emitted events were output: 2 out of 3"))]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_assert_events_consumed(
+        block_context: BlockContext,
+        old_block_number_and_hash: (Felt252, Felt252),
+        #[case] n_events: usize,
+        #[case] expected_error: Option<&str>,
     ) {
-        let execution_helper_with_storage = execution_helper_with_storage.await;
-
         let mut vm = VirtualMachine::new(false);
+        vm.set_fp(1);
         vm.add_memory_segment();
         vm.add_memory_segment();
-        vm.set_fp(5);
-
+        let ids_data = HashMap::from([(vars::ids::N_EVENTS.to_string(), HintReference::new_simple(-1))]);
         let ap_tracking = ApTracking::new();
         let constants = HashMap::new();
+        insert_value_from_var_name(vars::ids::N_EVENTS, Felt252::from(n_events), &mut vm, &ids_data, &ap_tracking)
+            .unwrap();
+
+        let exec_helper =
+            EHW::new(ContractStorageMap::default(), vec![], &block_context, None, old_block_number_and_hash);
+        // Two txs, emitting two events and one event
+        exec_helper.execution_helper.write().await.event_log =
+            BTreeMap::from([(0, vec![Event::default(), Event::default()]), (1, vec![Event::default()])]);
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, exec_helper);
+
+        let result = assert_events_consumed::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &constants);
+        match expected_error {
+            None => result.expect("Hint should not fail"),
+            Some(expected_error) => {
+                let error = result.expect_err("Hint should fail");
+                assert!(matches!(error, HintError::AssertionFailed(message) if message.as_ref() == expected_error));
+            }
+        }
+    }
+
+    #[rstest]
+    #[case::uninitialized_state(false)]
+    #[case::unknown_contract_error(true)]
+    fn test_get_contract_address_state_entry_unknown_contract(#[case] require_known_contracts: bool) {
+        let known_contract_address = Felt252::from(0x100);
+        let unknown_contract_address = Felt252::from(0x200);
+
+        let mut general_config = StarknetGeneralConfig::default();
+        general_config.require_known_contracts = require_known_contracts;
+        let os_input = StarknetOsInput { general_config, ..Default::default() };
+
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(3);
 
         let ids_data = HashMap::from([
-            (vars::ids::SYSCALL_PTR.to_string(), HintReference::new_simple(-3)),
+            (vars::ids::CONTRACT_STATE_CHANGES.to_string(), HintReference::new_simple(-3)),
             (vars::ids::CONTRACT_ADDRESS.to_string(), HintReference::new_simple(-2)),
-            (vars::ids::VALUE.to_string(), HintReference::new_simple(-1)),
+            (vars::ids::STATE_ENTRY.to_string(), HintReference::new_simple(-1)),
         ]);
+        let ap_tracking = ApTracking::new();
 
-        // Make ids.request point to (1, 0)
-        insert_value_from_var_name(vars::ids::SYSCALL_PTR, (1, 0), &mut vm, &ids_data, &ap_tracking).unwrap();
-        let key = Felt252::from(42);
-        // request.key is at offset 1 in the structure
-        vm.insert_value(Relocatable::from((1, 1)), key).unwrap();
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::OS_INPUT, Rc::new(os_input));
 
-        insert_value_from_var_name(vars::ids::CONTRACT_ADDRESS, contract_address, &mut vm, &ids_data, &ap_tracking)
+        // Only the known contract is part of the initial state
+        let mut dict_manager = DictManager::new();
+        let contract_state_changes = dict_manager
+            .new_dict(&mut vm, HashMap::from([(known_contract_address.into(), MaybeRelocatable::from(123))]))
             .unwrap();
-        insert_value_from_var_name(vars::ids::VALUE, Felt252::from(8000), &mut vm, &ids_data, &ap_tracking).unwrap();
+        exec_scopes.insert_value(vars::scopes::DICT_MANAGER, Rc::new(RefCell::new(dict_manager)));
 
-        let mut exec_scopes: ExecutionScopes = Default::default();
-        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, execution_helper_with_storage);
+        insert_value_from_var_name(
+            vars::ids::CONTRACT_STATE_CHANGES,
+            contract_state_changes,
+            &mut vm,
+            &ids_data,
+            &ap_tracking,
+        )
+        .unwrap();
+        insert_value_from_var_name(
+            vars::ids::CONTRACT_ADDRESS,
+            unknown_contract_address,
+            &mut vm,
+            &ids_data,
+            &ap_tracking,
+        )
+        .unwrap();
 
-        // Just make sure that the hint goes through, all meaningful assertions are
-        // in the implementation of the hint
-        cache_contract_storage_request_key::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &constants)
-            .expect("Hint should not fail");
-    }
+        let result =
+            get_contract_address_state_entry(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &HashMap::new());
 
-    #[test]
-    fn test_enter_scope_new_node() {
-        let preimage: Preimage = HashMap::new();
-        let descent_map: DescentMap = HashMap::new();
-        let patricia_skip_validation_runner: Option<PatriciaSkipValidationRunner> = None;
-        let left_child = Some(TreeUpdate::Leaf(StorageLeaf::new(Felt252::ZERO)));
-        let right_child: Option<TreeUpdate<StorageLeaf>> = None;
-        let case = DecodeNodeCase::Left;
+        if require_known_contracts {
+            assert!(matches!(result, Err(HintError::CustomHint(msg)) if msg.contains("0x200")));
+            return;
+        }
+        result.expect("Hint should not fail");
+
+        let state_entry_ptr = get_ptr_from_var_name(vars::ids::STATE_ENTRY, &vm, &ids_data, &ap_tracking).unwrap();
+        let state_entry = StateEntryLayout::from_memory(&vm, state_entry_ptr).unwrap();
+        assert_eq!(state_entry.class_hash, Felt252::ZERO);
+        assert_eq!(state_entry.nonce, Felt252::ZERO);
+        assert!(vm.get_maybe(&state_entry.storage_ptr).is_none());
+
+        // The entry is recorded in the dictionary, so that later accesses see the same state
+        let dict_manager = exec_scopes.get_dict_manager().unwrap();
+        let mut dict_manager = dict_manager.borrow_mut();
+        let tracker = dict_manager.get_tracker_mut(contract_state_changes).unwrap();
+        assert_eq!(
+            tracker.get_value(&unknown_contract_address.into()).unwrap(),
+            &MaybeRelocatable::from(state_entry_ptr)
+        );
+    }
 
+    #[rstest]
+    #[case::occupied(0x100, Some("Cannot deploy to 0x100: the address is occupied by class 0x1234."))]
+    #[case::uninitialized_entry(0x200, None)]
+    #[case::unknown_address(0x300, None)]
+    fn test_assert_address_unoccupied(#[case] contract_address: u64, #[case] expected_error: Option<&str>) {
         let mut vm = VirtualMachine::new(false);
         vm.add_memory_segment();
         vm.add_memory_segment();
-        vm.set_fp(1);
+        vm.set_fp(2);
 
-        let ids_data = HashMap::from([(vars::ids::CHILD_BIT.to_string(), HintReference::new_simple(-1))]);
+        let ids_data = HashMap::from([
+            (vars::ids::CONTRACT_STATE_CHANGES.to_string(), HintReference::new_simple(-2)),
+            (vars::ids::CONTRACT_ADDRESS.to_string(), HintReference::new_simple(-1)),
+        ]);
+        let ap_tracking = ApTracking::new();
 
-        let mut exec_scopes: ExecutionScopes = Default::default();
-        exec_scopes.insert_value(vars::scopes::PREIMAGE, preimage.clone());
-        exec_scopes.insert_value(vars::scopes::DESCENT_MAP, descent_map.clone());
-        exec_scopes
-            .insert_value(vars::scopes::PATRICIA_SKIP_VALIDATION_RUNNER, patricia_skip_validation_runner.clone());
-        exec_scopes.insert_value(vars::scopes::LEFT_CHILD, left_child.clone());
-        exec_scopes.insert_value(vars::scopes::RIGHT_CHILD, right_child);
-        exec_scopes.insert_value(vars::scopes::CASE, case);
+        // 0x100 holds a deployed contract, 0x200 was accessed but never deployed
+        let mut state_entries = HashMap::new();
+        for (address, class_hash) in [(0x100, 0x1234), (0x200, 0)] {
+            let state_entry_ptr = vm.add_memory_segment();
+            let state_entry = StateEntryLayout {
+                class_hash: Felt252::from(class_hash),
+                storage_ptr: vm.add_memory_segment(),
+                nonce: Felt252::ZERO,
+            };
+            state_entry.to_memory(&mut vm, state_entry_ptr).unwrap();
+            state_entries.insert(Felt252::from(address).into(), state_entry_ptr.into());
+        }
 
-        let ap_tracking = ApTracking::new();
-        let constants = HashMap::new();
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        let mut dict_manager = DictManager::new();
+        let contract_state_changes = dict_manager.new_dict(&mut vm, state_entries).unwrap();
+        exec_scopes.insert_value(vars::scopes::DICT_MANAGER, Rc::new(RefCell::new(dict_manager)));
 
-        enter_scope_new_node(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &constants)
-            .expect("Hint should succeed");
+        insert_value_from_var_name(
+            vars::ids::CONTRACT_STATE_CHANGES,
+            contract_state_changes,
+            &mut vm,
+            &ids_data,
+            &ap_tracking,
+        )
+        .unwrap();
+        insert_value_from_var_name(
+            vars::ids::CONTRACT_ADDRESS,
+            Felt252::from(contract_address),
+            &mut vm,
+            &ids_data,
+            &ap_tracking,
+        )
+        .unwrap();
 
-        assert_eq!(exec_scopes.data.len(), 2, "A new scope should have been created");
-        assert_eq!(exec_scopes.data[1].len(), 4, "The new scope should contain 4 items");
-        assert_eq!(exec_scopes.get::<Preimage>(vars::scopes::PREIMAGE).unwrap(), preimage);
-        assert_eq!(exec_scopes.get::<DescentMap>(vars::scopes::DESCENT_MAP).unwrap(), descent_map);
-        assert_eq!(
-            exec_scopes
-                .get::<Option<PatriciaSkipValidationRunner>>(vars::scopes::PATRICIA_SKIP_VALIDATION_RUNNER)
-                .unwrap(),
-            patricia_skip_validation_runner
-        );
-        assert_eq!(exec_scopes.get::<UpdateTree<StorageLeaf>>(vars::scopes::NODE).unwrap(), left_child);
+        let result = assert_address_unoccupied(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &HashMap::new());
 
-        let child_bit = get_integer_from_var_name(vars::ids::CHILD_BIT, &vm, &ids_data, &ap_tracking).unwrap();
-        assert_eq!(child_bit, Felt252::ZERO);
+        match expected_error {
+            None => result.expect("Hint should not fail"),
+            Some(expected_error) => {
+                let error = result.expect_err("Hint should fail");
+                assert!(matches!(error, HintError::AssertionFailed(message) if message.as_ref() == expected_error));
+            }
+        }
     }
 
     #[rstest]
-    #[tokio::test]
-    #[ignore] // TODO: fix
-    async fn test_write_syscall_result(#[future] execution_helper_with_storage: EHW, contract_address: Felt252) {
-        let mut execution_helper_with_storage = execution_helper_with_storage.await;
-
+    #[case::sender_validation("__validate__", 0x100, 5)]
+    #[case::sender_execution("__execute__", 0x100, 6)]
+    #[case::other_contract_validation("__validate__", 0x200, 3)]
+    #[case::unknown_contract("__validate__", 0x300, 0)]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_get_account_nonce(
+        execution_helper: EHW,
+        #[case] entry_point: &str,
+        #[case] contract_address: u64,
+        #[case] expected_nonce: u64,
+    ) {
         let mut vm = VirtualMachine::new(false);
         vm.add_memory_segment();
         vm.add_memory_segment();
-        vm.set_fp(9);
-
-        let ap_tracking = ApTracking::new();
-        let constants = HashMap::new();
+        vm.set_fp(3);
 
         let ids_data = HashMap::from([
-            (vars::ids::SYSCALL_PTR.to_string(), HintReference::new_simple(-6)),
-            (vars::ids::PREV_VALUE.to_string(), HintReference::new_simple(-5)),
-            (vars::ids::CONTRACT_ADDRESS.to_string(), HintReference::new_simple(-4)),
             (vars::ids::CONTRACT_STATE_CHANGES.to_string(), HintReference::new_simple(-3)),
-            (vars::ids::STATE_ENTRY.to_string(), HintReference::new_simple(-2)),
-            (vars::ids::NEW_STATE_ENTRY.to_string(), HintReference::new_simple(-1)),
+            (vars::ids::CONTRACT_ADDRESS.to_string(), HintReference::new_simple(-2)),
+            (vars::ids::NONCE.to_string(), HintReference::new_simple(-1)),
         ]);
+        let ap_tracking = ApTracking::new();
 
-        let key = Felt252::from(42);
-        let value = Felt252::from(777);
-        insert_value_from_var_name(vars::ids::SYSCALL_PTR, Relocatable::from((1, 0)), &mut vm, &ids_data, &ap_tracking)
-            .unwrap();
-        // syscall_ptr.address is at offset 1 in the structure
-        vm.insert_value(Relocatable::from((1, 1)), key).unwrap();
-        // syscall_ptr.value is at offset 1 in the structure
-        vm.insert_value(Relocatable::from((1, 2)), value).unwrap();
+        // The sender 0x100 sends a tx with nonce 5, which was already incremented in the state
+        let mut state_entries = HashMap::new();
+        for (address, nonce) in [(0x100, 6), (0x200, 3)] {
+            let state_entry_ptr = vm.add_memory_segment();
+            let state_entry = StateEntryLayout {
+                class_hash: Felt252::from(0x1234),
+                storage_ptr: vm.add_memory_segment(),
+                nonce: Felt252::from(nonce),
+            };
+            state_entry.to_memory(&mut vm, state_entry_ptr).unwrap();
+            state_entries.insert(Felt252::from(address).into(), state_entry_ptr.into());
+        }
 
-        insert_value_from_var_name(vars::ids::CONTRACT_ADDRESS, contract_address, &mut vm, &ids_data, &ap_tracking)
-            .unwrap();
+        let call_info = CallInfo {
+            call: CallEntryPoint {
+                entry_point_selector: EntryPointSelector(get_selector_from_name(entry_point)),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        execution_helper.execution_helper.write().await.call_info = Some(call_info);
+        let tx = InternalTransaction {
+            sender_address: Some(Felt252::from(0x100)),
+            nonce: Some(Felt252::from(5)),
+            ..Default::default()
+        };
 
         let mut exec_scopes: ExecutionScopes = Default::default();
-        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, execution_helper_with_storage.clone());
-
-        // Prepare the dict manager for `get_state_entry()`
         let mut dict_manager = DictManager::new();
-        let contract_state_changes = dict_manager
-            .new_dict(&mut vm, HashMap::from([(contract_address.into(), MaybeRelocatable::from(123))]))
-            .unwrap();
+        let contract_state_changes = dict_manager.new_dict(&mut vm, state_entries).unwrap();
         exec_scopes.insert_value(vars::scopes::DICT_MANAGER, Rc::new(RefCell::new(dict_manager)));
+        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, execution_helper);
+        exec_scopes.insert_value(vars::scopes::TX, tx);
 
         insert_value_from_var_name(
             vars::ids::CONTRACT_STATE_CHANGES,
@@ -2119,43 +5031,233 @@ mod tests {
             &ap_tracking,
         )
         .unwrap();
+        insert_value_from_var_name(
+            vars::ids::CONTRACT_ADDRESS,
+            Felt252::from(contract_address),
+            &mut vm,
+            &ids_data,
+            &ap_tracking,
+        )
+        .unwrap();
 
-        // Just make sure that the hint goes through, all meaningful assertions are
-        // in the implementation of the hint
-        write_syscall_result_deprecated::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &constants)
-            .expect("Hint should not fail");
-
-        // Check that the storage was updated
-        let prev_value = get_integer_from_var_name(vars::ids::PREV_VALUE, &vm, &ids_data, &ap_tracking).unwrap();
-        assert_eq!(prev_value, Felt252::from(8000));
-        let stored_value = execution_helper_with_storage.read_storage_for_address(contract_address, key).await.unwrap();
-        assert_eq!(stored_value, Felt252::from(777));
+        get_account_nonce::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &HashMap::new()).unwrap();
 
-        // Check the state entry
-        let state_entry = get_integer_from_var_name(vars::ids::STATE_ENTRY, &vm, &ids_data, &ap_tracking).unwrap();
-        assert_eq!(state_entry, Felt252::from(123));
+        assert_eq!(
+            get_integer_from_var_name(vars::ids::NONCE, &vm, &ids_data, &ap_tracking).unwrap(),
+            Felt252::from(expected_nonce)
+        );
     }
 
-    #[test]
-    fn test_set_fp_plus_4_to_tx_nonce() {
+    #[rstest]
+    #[case::matching_calldata(vec![Felt252::from(2), Felt252::ONE, Felt252::TWO], None)]
+    #[case::calldata_too_long(
+        vec![Felt252::from(2), Felt252::ONE, Felt252::TWO, Felt252::THREE],
+        Some("Constructor calldata length mismatch for class 0x1234: expected 3, got 4")
+    )]
+    #[case::overflowing_array_len(
+        vec![Felt252::from(u64::MAX), Felt252::ONE],
+        Some("Constructor calldata length mismatch for class 0x1234: expected 18446744073709551615, got 2")
+    )]
+    #[case::array_len_above_usize(
+        vec![Felt252::MAX, Felt252::ONE],
+        Some("Constructor calldata length mismatch for class 0x1234: expected 18446744073709551615, got 2")
+    )]
+    fn test_validate_constructor_calldata_len(#[case] calldata: Vec<Felt252>, #[case] expected_error: Option<&str>) {
+        let class_hash = Felt252::from(0x1234);
+
+        // constructor(keys_len: felt, keys: felt*)
+        let constructor = FunctionAbiEntry {
+            name: "constructor".to_string(),
+            inputs: vec![
+                TypedParameter { name: "keys_len".to_string(), r#type: "felt".to_string() },
+                TypedParameter { name: "keys".to_string(), r#type: "felt*".to_string() },
+            ],
+            ..Default::default()
+        };
+        let contract_class =
+            ContractClass { abi: Some(vec![ContractClassAbiEntry::Constructor(constructor)]), ..Default::default() };
+
+        let os_input = StarknetOsInput {
+            deprecated_compiled_classes: HashMap::from([(
+                class_hash,
+                GenericDeprecatedCompiledClass::from(contract_class),
+            )]),
+            ..Default::default()
+        };
+
         let mut vm = VirtualMachine::new(false);
         vm.add_memory_segment();
         vm.add_memory_segment();
+        vm.set_fp(4);
 
-        let ap_tracking = ApTracking::new();
-        let constants = HashMap::new();
-
-        let ids_data = HashMap::new();
+        let ids_data = HashMap::from([
+            (vars::ids::CONTRACT_ADDRESS_SALT.to_string(), HintReference::new_simple(-4)),
+            (vars::ids::CLASS_HASH.to_string(), HintReference::new_simple(-3)),
+            (vars::ids::CONSTRUCTOR_CALLDATA_SIZE.to_string(), HintReference::new_simple(-2)),
+            (vars::ids::CONSTRUCTOR_CALLDATA.to_string(), HintReference::new_simple(-1)),
+        ]);
 
-        // insert tx with a nonce
-        let tx = InternalTransaction { nonce: Some(Felt252::THREE), ..Default::default() };
+        let tx = InternalTransaction {
+            contract_address_salt: Some(Felt252::ZERO),
+            class_hash: Some(class_hash),
+            constructor_calldata: Some(calldata),
+            ..Default::default()
+        };
         let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::OS_INPUT, Rc::new(os_input));
         exec_scopes.insert_value(vars::scopes::TX, tx);
 
-        set_fp_plus_4_to_tx_nonce(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &constants).unwrap();
+        let result = prepare_constructor_execution::<PCS>(
+            &mut vm,
+            &mut exec_scopes,
+            &ids_data,
+            &ApTracking::new(),
+            &HashMap::new(),
+        );
+        match expected_error {
+            None => result.expect("Hint should not fail"),
+            Some(expected_error) => {
+                assert!(matches!(result, Err(HintError::AssertionFailed(msg)) if msg.as_ref() == expected_error))
+            }
+        }
+    }
 
-        let address: Relocatable = (vm.get_fp() + 4usize).unwrap();
-        let value = vm.get_integer(address).unwrap().into_owned();
-        assert_eq!(value, Felt252::THREE);
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_validate_constructor_calldata_len_cairo1(
+        block_context: BlockContext,
+        old_block_number_and_hash: (Felt252, Felt252),
+    ) {
+        let casm_bytes = include_bytes!(
+            "../../../../tests/integration/contracts/blockifier_contracts/feature_contracts/cairo1/compiled/\
+             test_contract.casm.json"
+        );
+        let class_hash = Felt252::from(0x1234);
+        let compiled_class_hash = Felt252::from(0x5678);
+        let os_input = Rc::new(StarknetOsInput {
+            class_hash_to_compiled_class_hash: HashMap::from([(class_hash, compiled_class_hash)]),
+            compiled_classes: HashMap::from([(
+                compiled_class_hash,
+                GenericCasmContractClass::from_bytes(casm_bytes.to_vec()),
+            )]),
+            ..Default::default()
+        });
+        let exec_helper = EHW::new(
+            ContractStorageMap::default(),
+            vec![],
+            &block_context,
+            Some(os_input.clone()),
+            old_block_number_and_hash,
+        );
+
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::OS_INPUT, os_input);
+        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, exec_helper.clone());
+
+        // The class has a constructor, whose arguments are not described by the CASM class
+        validate_constructor_calldata_len::<PCS>(&exec_scopes, class_hash, &[Felt252::ONE, Felt252::TWO])
+            .expect("The calldata of a Cairo 1 constructor should not be checked");
+        // The class is parsed through the cache of the run
+        assert_eq!(exec_helper.execution_helper.read().await.class_cache.n_parsed(), 1);
+    }
+
+    #[test]
+    fn test_prepare_constructor_execution_without_class_hash() {
+        let mut vm = VirtualMachine::new(false);
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::OS_INPUT, Rc::new(StarknetOsInput::default()));
+        exec_scopes.insert_value(vars::scopes::TX, InternalTransaction::default());
+
+        let result = prepare_constructor_execution::<PCS>(
+            &mut vm,
+            &mut exec_scopes,
+            &HashMap::new(),
+            &ApTracking::new(),
+            &HashMap::new(),
+        );
+        assert!(
+            matches!(result, Err(HintError::CustomHint(message)) if message.as_ref() == "tx.class_hash is not set")
+        );
+    }
+
+    #[rstest]
+    #[case::matching_calldata(vec![1, 2], None)]
+    #[case::mismatched_calldata(
+        vec![1, 3],
+        Some("Constructor calldata mismatch: the address is computed with [0x1, 0x3], but the constructor was called with [0x1, 0x2].")
+    )]
+    #[case::missing_argument(
+        vec![1],
+        Some("Constructor calldata mismatch: the address is computed with [0x1], but the constructor was called with [0x1, 0x2].")
+    )]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_assert_constructor_calldata(
+        block_context: BlockContext,
+        old_block_number_and_hash: (Felt252, Felt252),
+        #[case] constructor_calldata: Vec<u64>,
+        #[case] expected_error: Option<&str>,
+    ) {
+        // The constructor of the deployed account was executed with [1, 2]
+        let constructor_call = CallInfo {
+            call: CallEntryPoint {
+                calldata: Calldata(Arc::new(vec![Felt252::ONE, Felt252::TWO])),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let tx_execution_info =
+            TransactionExecutionInfo { execute_call_info: Some(constructor_call), ..Default::default() };
+        let execution_helper = EHW::new(
+            ContractStorageMap::default(),
+            vec![tx_execution_info],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        );
+        execution_helper.start_tx(None).await;
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, execution_helper);
+
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(2);
+        let ids_data = HashMap::from([
+            (vars::ids::CONSTRUCTOR_CALLDATA_SIZE.to_string(), HintReference::new_simple(-2)),
+            (vars::ids::CONSTRUCTOR_CALLDATA.to_string(), HintReference::new_simple(-1)),
+        ]);
+        let ap_tracking = ApTracking::new();
+
+        let constructor_calldata: Vec<MaybeRelocatable> =
+            constructor_calldata.into_iter().map(|felt| Felt252::from(felt).into()).collect();
+        let constructor_calldata_ptr = vm.add_memory_segment();
+        vm.load_data(constructor_calldata_ptr, &constructor_calldata).unwrap();
+        insert_value_from_var_name(
+            vars::ids::CONSTRUCTOR_CALLDATA_SIZE,
+            constructor_calldata.len(),
+            &mut vm,
+            &ids_data,
+            &ap_tracking,
+        )
+        .unwrap();
+        insert_value_from_var_name(
+            vars::ids::CONSTRUCTOR_CALLDATA,
+            constructor_calldata_ptr,
+            &mut vm,
+            &ids_data,
+            &ap_tracking,
+        )
+        .unwrap();
+
+        let result =
+            assert_constructor_calldata::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &HashMap::new());
+
+        match expected_error {
+            None => result.expect("Hint should not fail"),
+            Some(expected_error) => {
+                let error = result.expect_err("Hint should fail");
+                assert!(matches!(error, HintError::AssertionFailed(message) if message.as_ref() == expected_error));
+            }
+        }
     }
 }
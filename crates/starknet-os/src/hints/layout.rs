@@ -0,0 +1,221 @@
+//! Declarative description of Cairo struct layouts.
+//!
+//! `cairo_struct_layout!` declares a Rust struct mirroring a Cairo struct and implements
+//! `CairoType` for it. Unlike `#[derive(CairoType)]`, fields are not limited to felts: any type
+//! implementing `LayoutField` (currently felts and pointers) can be used.
+
+use cairo_vm::types::relocatable::Relocatable;
+use cairo_vm::vm::errors::memory_errors::MemoryError;
+use cairo_vm::vm::vm_core::VirtualMachine;
+use cairo_vm::Felt252;
+
+/// A value stored in a single memory cell of a Cairo struct.
+pub trait LayoutField: Sized {
+    fn read_field(vm: &VirtualMachine, address: Relocatable) -> Result<Self, MemoryError>;
+    fn write_field(&self, vm: &mut VirtualMachine, address: Relocatable) -> Result<(), MemoryError>;
+}
+
+impl LayoutField for Felt252 {
+    fn read_field(vm: &VirtualMachine, address: Relocatable) -> Result<Self, MemoryError> {
+        Ok(vm.get_integer(address)?.into_owned())
+    }
+
+    fn write_field(&self, vm: &mut VirtualMachine, address: Relocatable) -> Result<(), MemoryError> {
+        vm.insert_value(address, *self)
+    }
+}
+
+impl LayoutField for Relocatable {
+    fn read_field(vm: &VirtualMachine, address: Relocatable) -> Result<Self, MemoryError> {
+        vm.get_relocatable(address)
+    }
+
+    fn write_field(&self, vm: &mut VirtualMachine, address: Relocatable) -> Result<(), MemoryError> {
+        vm.insert_value(address, *self)
+    }
+}
+
+/// Declares a struct and implements `CairoType` for it.
+/// Each field occupies one memory cell, in declaration order, so fields must be listed exactly
+/// as they appear in the Cairo struct.
+macro_rules! cairo_struct_layout {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $($(#[$field_meta:meta])* $field_vis:vis $field:ident: $ty:ty),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $($(#[$field_meta])* $field_vis $field: $ty),*
+        }
+
+        impl $crate::cairo_types::traits::CairoType for $name {
+            fn from_memory(
+                vm: &::cairo_vm::vm::vm_core::VirtualMachine,
+                address: ::cairo_vm::types::relocatable::Relocatable,
+            ) -> Result<Self, ::cairo_vm::vm::errors::memory_errors::MemoryError> {
+                let mut offset = 0usize;
+                $(
+                    let $field = <$ty as $crate::hints::layout::LayoutField>::read_field(vm, (address + offset)?)?;
+                    offset += 1;
+                )*
+                let _ = offset;
+                Ok(Self { $($field),* })
+            }
+
+            fn to_memory(
+                &self,
+                vm: &mut ::cairo_vm::vm::vm_core::VirtualMachine,
+                address: ::cairo_vm::types::relocatable::Relocatable,
+            ) -> Result<(), ::cairo_vm::vm::errors::memory_errors::MemoryError> {
+                let mut offset = 0usize;
+                $(
+                    $crate::hints::layout::LayoutField::write_field(&self.$field, vm, (address + offset)?)?;
+                    offset += 1;
+                )*
+                let _ = offset;
+                Ok(())
+            }
+
+            fn n_fields() -> usize {
+                [$(stringify!($field)),*].len()
+            }
+        }
+    };
+}
+
+#[allow(unused_imports)]
+pub(crate) use cairo_struct_layout;
+
+cairo_struct_layout! {
+    /// Deprecated `TxInfo` struct, as exposed to Cairo 0 contracts.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct TxInfoLayout {
+        pub version: Felt252,
+        pub account_contract_address: Felt252,
+        pub max_fee: Felt252,
+        pub signature_len: Felt252,
+        pub signature: Relocatable,
+        pub transaction_hash: Felt252,
+        pub chain_id: Felt252,
+        pub nonce: Felt252,
+    }
+}
+
+cairo_struct_layout! {
+    /// Block info as exposed to deprecated (Cairo 0) contracts. Unlike the `BlockInfo` struct of
+    /// the execution info, the timestamp comes before the block number.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct DeprecatedBlockInfoLayout {
+        pub block_timestamp: Felt252,
+        pub block_number: Felt252,
+        pub sequencer_address: Felt252,
+    }
+}
+
+cairo_struct_layout! {
+    /// `TxInfo` struct of v3 transactions, as exposed to Cairo 1 contracts. Arrays are passed as
+    /// (start, end) pointer pairs.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct TxInfoV3Layout {
+        pub version: Felt252,
+        pub account_contract_address: Felt252,
+        pub max_fee: Felt252,
+        pub signature_start: Relocatable,
+        pub signature_end: Relocatable,
+        pub transaction_hash: Felt252,
+        pub chain_id: Felt252,
+        pub nonce: Felt252,
+        pub resource_bounds_start: Relocatable,
+        pub resource_bounds_end: Relocatable,
+        pub tip: Felt252,
+        pub paymaster_data_start: Relocatable,
+        pub paymaster_data_end: Relocatable,
+        pub nonce_data_availability_mode: Felt252,
+        pub fee_data_availability_mode: Felt252,
+        pub account_deployment_data_start: Relocatable,
+        pub account_deployment_data_end: Relocatable,
+    }
+}
+
+cairo_struct_layout! {
+    /// State of a contract, as tracked in the `contract_state_changes` dictionary.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct StateEntryLayout {
+        pub class_hash: Felt252,
+        pub storage_ptr: Relocatable,
+        pub nonce: Felt252,
+    }
+}
+
+cairo_struct_layout! {
+    /// State of the segment arena builtin (`SegmentArenaBuiltin`).
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct SegmentArenaBuiltinLayout {
+        pub infos: Relocatable,
+        pub n_segments: Felt252,
+        pub n_finalized: Felt252,
+    }
+}
+
+cairo_struct_layout! {
+    /// A segment allocated in the segment arena (`SegmentInfo`). `end` is only set once the
+    /// segment is squashed.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct SegmentInfoLayout {
+        pub start: Relocatable,
+        pub end: Relocatable,
+        pub finalization_index: Felt252,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cairo_vm::types::relocatable::MaybeRelocatable;
+
+    use super::*;
+    use crate::cairo_types::syscalls::TxInfo;
+    use crate::cairo_types::traits::CairoType;
+
+    #[test]
+    fn tx_info_layout_round_trip() {
+        let mut vm = VirtualMachine::new(false);
+        let tx_info_ptr = vm.add_memory_segment();
+        let signature_ptr = vm.add_memory_segment();
+
+        let tx_info = TxInfoLayout {
+            version: Felt252::ONE,
+            account_contract_address: Felt252::from(0x1234),
+            max_fee: Felt252::from(1_000_000),
+            signature_len: Felt252::TWO,
+            signature: signature_ptr,
+            transaction_hash: Felt252::from(0xabcdef),
+            chain_id: Felt252::from(0x534e5f5345504f4c4941u128),
+            nonce: Felt252::from(7),
+        };
+        tx_info.to_memory(&mut vm, tx_info_ptr).unwrap();
+
+        assert_eq!(TxInfoLayout::from_memory(&vm, tx_info_ptr).unwrap(), tx_info);
+
+        // The generated layout must agree with the offsets of the Cairo type
+        assert_eq!(TxInfoLayout::n_fields(), TxInfo::cairo_size());
+        assert_eq!(
+            vm.get_integer((tx_info_ptr + TxInfo::account_contract_address_offset()).unwrap()).unwrap().into_owned(),
+            tx_info.account_contract_address
+        );
+        assert_eq!(vm.get_relocatable((tx_info_ptr + TxInfo::signature_offset()).unwrap()).unwrap(), signature_ptr);
+    }
+
+    #[test]
+    fn tx_info_layout_read_rejects_wrong_cell_type() {
+        let mut vm = VirtualMachine::new(false);
+        let tx_info_ptr = vm.add_memory_segment();
+
+        // A felt where the signature pointer is expected
+        let values: Vec<MaybeRelocatable> = (0..TxInfoLayout::n_fields()).map(|i| Felt252::from(i).into()).collect();
+        vm.load_data(tx_info_ptr, &values).unwrap();
+
+        assert!(TxInfoLayout::from_memory(&vm, tx_info_ptr).is_err());
+    }
+}
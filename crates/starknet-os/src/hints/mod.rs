@@ -1,7 +1,12 @@
-use std::collections::{HashMap, HashSet};
+use std::any::Any;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::marker::PhantomData;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::PathBuf;
 use std::rc::Rc;
 
+use blockifier::abi::abi_utils::get_fee_token_var_address;
+use blockifier::transaction::objects::FeeType;
 use cairo_lang_casm::hints::{Hint, StarknetHint};
 use cairo_lang_casm::operand::{BinOpOperand, DerefOrImmediate, Operation, Register, ResOperand};
 use cairo_vm::hint_processor::builtin_hint_processor::builtin_hint_processor_definition::{
@@ -12,6 +17,7 @@ use cairo_vm::hint_processor::cairo_1_hint_processor::hint_processor::Cairo1Hint
 use cairo_vm::hint_processor::hint_processor_definition::{
     HintExtension, HintProcessor, HintProcessorLogic, HintReference,
 };
+use cairo_vm::hint_processor::hint_processor_utils::felt_to_usize;
 use cairo_vm::serde::deserialize_program::ApTracking;
 use cairo_vm::types::exec_scope::ExecutionScopes;
 use cairo_vm::types::relocatable::{MaybeRelocatable, Relocatable};
@@ -20,16 +26,27 @@ use cairo_vm::vm::runners::cairo_runner::{ResourceTracker, RunResources};
 use cairo_vm::vm::vm_core::VirtualMachine;
 use cairo_vm::Felt252;
 use indoc::indoc;
-use num_bigint::BigInt;
+use num_bigint::{BigInt, BigUint};
+use starknet_api::core::ContractAddress;
 
+use crate::cairo_types::dict_access::DictAccess;
+use crate::cairo_types::traits::CairoType;
+use crate::config::INPUT_PATH_ENV_VAR;
 use crate::execution::execute_syscalls;
 use crate::execution::helper::ExecutionHelperWrapper;
 use crate::execution::syscall_handler::OsSyscallHandlerWrapper;
+use crate::hints::assertions::hint_assert;
 use crate::hints::block_context::is_leaf;
+use crate::hints::layout::StateEntryLayout;
 use crate::io::input::StarknetOsInput;
+use crate::io::InternalTransaction;
+use crate::starknet::core::os::block_hash::{
+    calculate_events_commitment, calculate_state_diff_commitment, calculate_transactions_commitment, StateDiff,
+};
 use crate::starknet::starknet_storage::PerContractStorage;
-use crate::utils::execute_coroutine;
+use crate::utils::{custom_hint_error, execute_coroutine};
 
+mod assertions;
 pub mod block_context;
 mod bls_field;
 mod bls_utils;
@@ -40,6 +57,7 @@ mod execute_transactions;
 pub mod execution;
 mod find_element;
 mod kzg;
+pub mod layout;
 mod os;
 mod output;
 mod patricia;
@@ -66,9 +84,16 @@ pub type HintImpl = fn(
 fn hints<PCS>() -> HashMap<String, HintImpl> where
     PCS: PerContractStorage + 'static {
     let mut hints = HashMap::<String, HintImpl>::new();
+    hints.insert(ASSERT_BLOCK_GAS_WITHIN_LIMIT.into(), assert_block_gas_within_limit::<PCS>);
+    hints.insert(ASSERT_FEE_TRANSFER_TOKEN.into(), assert_fee_transfer_token::<PCS>);
+    hints.insert(ASSERT_SENDER_CAN_PAY_FEE.into(), assert_sender_can_pay_fee::<PCS>);
     hints.insert(BREAKPOINT.into(), breakpoint);
+    hints.insert(COMPUTE_EVENTS_COMMITMENT.into(), compute_events_commitment::<PCS>);
+    hints.insert(COMPUTE_STATE_DIFF_COMMITMENT.into(), compute_state_diff_commitment);
+    hints.insert(COMPUTE_TRANSACTIONS_COMMITMENT.into(), compute_transactions_commitment);
+    hints.insert(GET_ACTUAL_FEE_IN_WEI_AND_FRI.into(), get_actual_fee_in_wei_and_fri::<PCS>);
     hints.insert(INITIALIZE_CLASS_HASHES.into(), initialize_class_hashes);
-    hints.insert(INITIALIZE_STATE_CHANGES.into(), initialize_state_changes);
+    hints.insert(INITIALIZE_STATE_CHANGES.into(), initialize_state_changes::<PCS>);
     hints.insert(IS_ON_CURVE.into(), is_on_curve);
     hints.insert(OS_INPUT_TRANSACTIONS.into(), os_input_transactions);
     hints.insert(SEGMENTS_ADD.into(), segments_add);
@@ -86,12 +111,20 @@ fn hints<PCS>() -> HashMap<String, HintImpl> where
     hints.insert(block_context::ELEMENTS_GE_10.into(), block_context::elements_ge_10);
     hints.insert(block_context::ELEMENTS_GE_2.into(), block_context::elements_ge_2);
     hints.insert(block_context::FEE_TOKEN_ADDRESS.into(), block_context::fee_token_address);
+    hints.insert(block_context::FIND_CLASS_ENTRY_POINT.into(), block_context::find_class_entry_point_hint);
+    hints.insert(block_context::GET_BLOCK_INFO_DEPRECATED.into(), block_context::get_block_info_deprecated);
     hints.insert(block_context::GET_BLOCK_MAPPING.into(), block_context::get_block_mapping);
+    hints.insert(block_context::GET_GAS_PRICES.into(), block_context::get_gas_prices);
+    hints.insert(block_context::GET_STARKNET_VERSION.into(), block_context::get_starknet_version);
     hints.insert(block_context::IS_LEAF.into(), is_leaf);
     hints.insert(block_context::LOAD_CLASS_FACTS.into(), block_context::load_class_facts);
     hints.insert(block_context::LOAD_CLASS_INNER.into(), block_context::load_class_inner);
     hints.insert(block_context::SEQUENCER_ADDRESS.into(), block_context::sequencer_address);
     hints.insert(bls_field::COMPUTE_IDS_LOW.into(), bls_field::compute_ids_low);
+    hints.insert(builtins::FILL_BITWISE_BUILTIN_OUTPUTS.into(), builtins::fill_bitwise_builtin_outputs);
+    hints.insert(builtins::FILL_EC_OP_BUILTIN_OUTPUTS.into(), builtins::fill_ec_op_builtin_outputs);
+    hints.insert(builtins::FILL_KECCAK_BUILTIN_OUTPUTS.into(), builtins::fill_keccak_builtin_outputs);
+    hints.insert(builtins::FINALIZE_SEGMENT_ARENA.into(), builtins::finalize_segment_arena::<PCS>);
     hints.insert(builtins::SELECTED_BUILTINS.into(), builtins::selected_builtins);
     hints.insert(builtins::SELECT_BUILTIN.into(), builtins::select_builtin);
     hints.insert(builtins::UPDATE_BUILTIN_PTRS.into(), builtins::update_builtin_ptrs);
@@ -107,7 +140,16 @@ fn hints<PCS>() -> HashMap<String, HintImpl> where
     hints.insert(execute_transactions::SET_SHA256_SEGMENT_IN_SYSCALL_HANDLER.into(), execute_transactions::set_sha256_segment_in_syscall_handler::<PCS>);
     hints.insert(execute_transactions::START_TX_VALIDATE_DECLARE_EXECUTION_CONTEXT.into(), execute_transactions::start_tx_validate_declare_execution_context::<PCS>);
     hints.insert(execution::ADD_RELOCATION_RULE.into(), execution::add_relocation_rule);
+    hints.insert(execution::ASSERT_ADDRESS_UNOCCUPIED.into(), execution::assert_address_unoccupied);
+    hints.insert(execution::ASSERT_COMPILED_CLASS_HASH.into(), execution::assert_compiled_class_hash);
+    hints.insert(execution::ASSERT_CONSTRUCTOR_CALLDATA.into(), execution::assert_constructor_calldata::<PCS>);
+    hints.insert(execution::ASSERT_ENTRY_POINT_SELECTOR.into(), execution::assert_entry_point_selector::<PCS>);
+    hints.insert(execution::ASSERT_EVENTS_CONSUMED.into(), execution::assert_events_consumed::<PCS>);
+    hints.insert(execution::ASSERT_L1_TO_L2_MESSAGE_HASH.into(), execution::assert_l1_to_l2_message_hash);
+    hints.insert(execution::ASSERT_NO_REENTRANCY.into(), execution::assert_no_reentrancy::<PCS>);
     hints.insert(execution::ASSERT_TRANSACTION_HASH.into(), execution::assert_transaction_hash);
+    hints.insert(execution::ASSERT_TRANSACTIONS_CONSUMED.into(), execution::assert_transactions_consumed::<PCS>);
+    hints.insert(execution::ASSERT_VALIDATE_CALLER.into(), execution::assert_validate_caller::<PCS>);
     hints.insert(execution::CACHE_CONTRACT_STORAGE_REQUEST_KEY.into(), execution::cache_contract_storage_request_key::<PCS>);
     hints.insert(execution::CACHE_CONTRACT_STORAGE_SYSCALL_REQUEST_ADDRESS.into(), execution::cache_contract_storage_syscall_request_address::<PCS>);
     hints.insert(execution::CHECK_EXECUTION.into(), execution::check_execution::<PCS>);
@@ -115,7 +157,10 @@ fn hints<PCS>() -> HashMap<String, HintImpl> where
     hints.insert(execution::CHECK_NEW_DEPLOY_RESPONSE.into(), execution::check_new_deploy_response);
     hints.insert(execution::CHECK_NEW_SYSCALL_RESPONSE.into(), execution::check_new_syscall_response);
     hints.insert(execution::CHECK_SYSCALL_RESPONSE.into(), execution::check_syscall_response);
+    hints.insert(execution::CLEAR_TX_SCOPE.into(), execution::clear_tx_scope);
+    hints.insert(execution::CONSUME_L1_TO_L2_MESSAGE.into(), execution::consume_l1_to_l2_message);
     hints.insert(execution::CONTRACT_ADDRESS.into(), execution::contract_address);
+    hints.insert(execution::EFFECTIVE_TRANSACTION_VERSION.into(), execution::effective_transaction_version);
     hints.insert(execution::END_TX.into(), execution::end_tx::<PCS>);
     hints.insert(execution::ENTER_CALL.into(), execution::enter_call::<PCS>);
     hints.insert(execution::ENTER_SCOPE_DEPRECATED_SYSCALL_HANDLER.into(), execution::enter_scope_deprecated_syscall_handler::<PCS>);
@@ -127,24 +172,33 @@ fn hints<PCS>() -> HashMap<String, HintImpl> where
     hints.insert(execution::ENTER_SCOPE_NODE.into(), execution::enter_scope_node_hint);
     hints.insert(execution::ENTER_SCOPE_RIGHT_CHILD.into(), execution::enter_scope_right_child);
     hints.insert(execution::ENTER_SCOPE_SYSCALL_HANDLER.into(), execution::enter_scope_syscall_handler::<PCS>);
+    hints.insert(execution::ENTER_SCOPE_WITH_DICT_MANAGER.into(), execution::enter_scope_with_dict_manager);
     hints.insert(execution::ENTER_SYSCALL_SCOPES.into(), execution::enter_syscall_scopes::<PCS>);
     hints.insert(execution::EXIT_CALL.into(), execution::exit_call::<PCS>);
     hints.insert(execution::EXIT_TX.into(), execution::exit_tx);
     hints.insert(execution::FETCH_RESULT.into(), execution::fetch_result);
+    hints.insert(execution::FILL_RESOURCE_BOUNDS.into(), execution::fill_resource_bounds);
     hints.insert(execution::GEN_CLASS_HASH_ARG.into(), execution::gen_class_hash_arg);
     hints.insert(execution::GEN_SIGNATURE_ARG.into(), execution::gen_signature_arg);
+    hints.insert(execution::GET_ACCOUNT_NONCE.into(), execution::get_account_nonce::<PCS>);
     hints.insert(execution::GET_BLOCK_HASH_CONTRACT_ADDRESS_STATE_ENTRY_AND_SET_NEW_STATE_ENTRY.into(), execution::get_block_hash_contract_address_state_entry_and_set_new_state_entry);
     hints.insert(execution::GET_CONTRACT_ADDRESS_STATE_ENTRY.into(), execution::get_contract_address_state_entry);
     hints.insert(execution::GET_CONTRACT_ADDRESS_STATE_ENTRY_AND_SET_NEW_STATE_ENTRY.into(), execution::get_contract_address_state_entry_and_set_new_state_entry);
     hints.insert(execution::GET_CONTRACT_ADDRESS_STATE_ENTRY_AND_SET_NEW_STATE_ENTRY_2.into(), execution::get_contract_address_state_entry_and_set_new_state_entry);
+    hints.insert(execution::GET_EXECUTION_INFO.into(), execution::get_execution_info);
+    hints.insert(execution::GET_EXECUTION_INFO_LEGACY.into(), execution::get_execution_info_legacy);
     hints.insert(execution::GET_OLD_BLOCK_NUMBER_AND_HASH.into(), execution::get_old_block_number_and_hash::<PCS>);
+    hints.insert(execution::GET_TX_INDEX.into(), execution::get_tx_index::<PCS>);
     hints.insert(execution::INITIAL_GE_REQUIRED_GAS.into(), execution::initial_ge_required_gas);
+    hints.insert(execution::INITIALIZE_L1_TO_L2_MESSAGES.into(), execution::initialize_l1_to_l2_messages);
     hints.insert(execution::IS_DEPRECATED.into(), execution::is_deprecated);
     hints.insert(execution::IS_REVERTED.into(), execution::is_reverted::<PCS>);
     hints.insert(execution::LOAD_NEXT_TX.into(), execution::load_next_tx);
+    hints.insert(execution::LOAD_TX_CALLDATA.into(), execution::load_tx_calldata);
     hints.insert(execution::LOG_ENTER_SYSCALL.into(), execution::log_enter_syscall);
     hints.insert(execution::OS_CONTEXT_SEGMENTS.into(), execution::os_context_segments);
-    hints.insert(execution::PREPARE_CONSTRUCTOR_EXECUTION.into(), execution::prepare_constructor_execution);
+    hints.insert(execution::PREPARE_CONSTRUCTOR_EXECUTION.into(), execution::prepare_constructor_execution::<PCS>);
+    hints.insert(execution::READ_BLOCK_HASH.into(), execution::read_block_hash::<PCS>);
     hints.insert(execution::RESOURCE_BOUNDS.into(), execution::resource_bounds);
     hints.insert(execution::SET_AP_TO_TX_NONCE.into(), execution::set_ap_to_tx_nonce);
     hints.insert(execution::SET_FP_PLUS_4_TO_TX_NONCE.into(), execution::set_fp_plus_4_to_tx_nonce);
@@ -167,6 +221,8 @@ fn hints<PCS>() -> HashMap<String, HintImpl> where
     hints.insert(execution::WRITE_OLD_BLOCK_TO_STORAGE.into(), execution::write_old_block_to_storage::<PCS>);
     hints.insert(execution::WRITE_SYSCALL_RESULT.into(), execution::write_syscall_result::<PCS>);
     hints.insert(execution::WRITE_SYSCALL_RESULT_DEPRECATED.into(), execution::write_syscall_result_deprecated::<PCS>);
+    hints.insert(execution::WRITE_TX_INFO_MAX_FEE.into(), execution::write_tx_info_max_fee);
+    hints.insert(execution::WRITE_TX_INFO_NONCE.into(), execution::write_tx_info_nonce);
     hints.insert(find_element::SEARCH_SORTED_OPTIMISTIC.into(), find_element::search_sorted_optimistic);
     hints.insert(os::CONFIGURE_KZG_MANAGER.into(), os::configure_kzg_manager);
     hints.insert(os::WRITE_FULL_OUTPUT_TO_MEM.into(), os::write_full_output_to_mem);
@@ -185,6 +241,9 @@ fn hints<PCS>() -> HashMap<String, HintImpl> where
     hints.insert(patricia::SET_SIBLINGS.into(), patricia::set_siblings);
     hints.insert(patricia::SPLIT_DESCEND.into(), patricia::split_descend);
     hints.insert(patricia::WRITE_CASE_NOT_LEFT_TO_AP.into(), patricia::write_case_not_left_to_ap);
+    hints.insert(state::ASSERT_INITIAL_STATE_ROOT.into(), state::assert_initial_state_root);
+    hints.insert(state::COMMIT_CONTRACT_STORAGE.into(), state::commit_contract_storage::<PCS>);
+    hints.insert(state::COMPUTE_NEW_CLASSES_ROOT.into(), state::compute_new_classes_root);
     hints.insert(state::DECODE_NODE.into(), state::decode_node_hint);
     hints.insert(state::DECODE_NODE_2.into(), state::decode_node_hint);
     hints.insert(state::ENTER_SCOPE_COMMITMENT_INFO_BY_ADDRESS.into(), state::enter_scope_commitment_info_by_address::<PCS>);
@@ -271,6 +330,18 @@ static EXTENSIVE_HINTS: [(&str, ExtensiveHintImpl); 2] = [
     (deprecated_compiled_class::LOAD_DEPRECATED_CLASS, deprecated_compiled_class::load_deprecated_class),
 ];
 
+/// Replacement of a hint, e.g. a stub returning a canned response in tests. Unlike `HintImpl`,
+/// it can capture state, like a prebuilt value to inject.
+pub type HintOverride = Box<
+    dyn Fn(
+        &mut VirtualMachine,
+        &mut ExecutionScopes,
+        &HashMap<String, HintReference>,
+        &ApTracking,
+        &HashMap<String, Felt252>,
+    ) -> Result<(), HintError>,
+>;
+
 pub struct SnosHintProcessor<PCS>
 where
     PCS: PerContractStorage,
@@ -279,6 +350,8 @@ where
     cairo1_builtin_hint_proc: Cairo1HintProcessor,
     hints: HashMap<String, HintImpl>,
     extensive_hints: HashMap<String, ExtensiveHintImpl>,
+    hint_overrides: HashMap<String, HintOverride>,
+    catch_hint_panics: bool,
     run_resources: RunResources,
     _phantom: PhantomData<PCS>,
 }
@@ -316,6 +389,8 @@ where
             cairo1_builtin_hint_proc: Cairo1HintProcessor::new(Default::default(), Default::default(), true),
             hints,
             extensive_hints,
+            hint_overrides: Default::default(),
+            catch_hint_panics: false,
             run_resources: Default::default(),
             _phantom: Default::default(),
         }
@@ -358,6 +433,45 @@ where
             .cloned()
             .collect::<HashSet<_>>()
     }
+
+    /// Replaces the implementation of the hint with code `hint_code`. Overrides take precedence
+    /// over all the other hints, including extensive and builtin ones, so that tests can skip the
+    /// hints requiring a full input.
+    pub fn override_hint(&mut self, hint_code: &str, hint_override: HintOverride) {
+        self.hint_overrides.insert(hint_code.to_string(), hint_override);
+    }
+
+    /// Turns the hint with code `hint_code` into a no-op.
+    pub fn stub_hint(&mut self, hint_code: &str) {
+        self.override_hint(hint_code, Box::new(|_, _, _, _, _| Ok(())));
+    }
+
+    /// If enabled, a panic in a hint is reported as a `HintError::CustomHint` naming the hint
+    /// instead of unwinding through the VM and aborting the whole process.
+    pub fn set_catch_hint_panics(&mut self, catch_hint_panics: bool) {
+        self.catch_hint_panics = catch_hint_panics;
+    }
+}
+
+/// Short name of a hint, used in error messages: the first line of the code of Cairo 0 hints.
+fn hint_name(hint_data: &dyn Any) -> String {
+    if let Some(hpd) = hint_data.downcast_ref::<HintProcessorData>() {
+        return hpd.code.lines().next().unwrap_or_default().trim().to_string();
+    }
+    if let Some(hint) = hint_data.downcast_ref::<Hint>() {
+        return format!("{hint:?}");
+    }
+    "<unknown hint>".to_string()
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "<non-string panic payload>"
+    }
 }
 
 impl<PCS> HintProcessorLogic for SnosHintProcessor<PCS>
@@ -381,9 +495,73 @@ where
         exec_scopes: &mut ExecutionScopes,
         hint_data: &Box<dyn core::any::Any>,
         constants: &HashMap<String, Felt252>,
+    ) -> Result<HintExtension, HintError> {
+        let result = if !self.catch_hint_panics {
+            self.execute_hint_unguarded(vm, exec_scopes, hint_data, constants)
+        } else {
+            // The VM state may be left inconsistent by the panicking hint, but the run is aborted
+            // by the returned error anyway.
+            catch_unwind(AssertUnwindSafe(|| self.execute_hint_unguarded(vm, exec_scopes, hint_data, constants)))
+                .unwrap_or_else(|payload| {
+                    Err(custom_hint_error(format!(
+                        "Hint '{}' panicked: {}",
+                        hint_name(hint_data.as_ref()),
+                        panic_message(payload.as_ref())
+                    )))
+                })
+        };
+
+        result.or_else(|error| skip_failed_tx::<PCS>(exec_scopes, error))
+    }
+}
+
+/// Skips the rest of the tx being executed when one of its hints fails an assertion, if the run is
+/// configured to continue past failed txs, see `StarknetGeneralConfig::continue_on_tx_failure`.
+/// Otherwise, or if the hint failed outside of a tx, the error is returned as is.
+///
+/// Only assertion failures are recovered from: the hints reporting them only check the replay and
+/// write nothing, while any other failing hint may leave values that the OS reads uninitialized.
+fn skip_failed_tx<PCS>(exec_scopes: &ExecutionScopes, error: HintError) -> Result<HintExtension, HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    if !matches!(error, HintError::AssertionFailed(_)) {
+        return Err(error);
+    }
+    let (Ok(execution_helper), Ok(tx)) = (
+        exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER),
+        exec_scopes.get_ref::<InternalTransaction>(vars::scopes::TX),
+    ) else {
+        return Err(error);
+    };
+
+    if execute_coroutine(execution_helper.fail_tx(tx.hash_value, &error))? {
+        Ok(HintExtension::default())
+    } else {
+        Err(error)
+    }
+}
+
+impl<PCS> SnosHintProcessor<PCS>
+where
+    PCS: PerContractStorage + 'static,
+{
+    // The hint data is boxed by the VM and forwarded as is to the builtin hint processor
+    #[allow(clippy::borrowed_box)]
+    fn execute_hint_unguarded(
+        &mut self,
+        vm: &mut VirtualMachine,
+        exec_scopes: &mut ExecutionScopes,
+        hint_data: &Box<dyn core::any::Any>,
+        constants: &HashMap<String, Felt252>,
     ) -> Result<HintExtension, HintError> {
         if let Some(hpd) = hint_data.downcast_ref::<HintProcessorData>() {
             let hint_code = hpd.code.as_str();
+            if let Some(hint_override) = self.hint_overrides.get(hint_code) {
+                return hint_override(vm, exec_scopes, &hpd.ids_data, &hpd.ap_tracking, constants)
+                    .map(|_| HintExtension::default());
+            }
+
             if let Some(hint_impl) = self.hints.get(hint_code) {
                 return hint_impl(vm, exec_scopes, &hpd.ids_data, &hpd.ap_tracking, constants)
                     .map(|_| HintExtension::default());
@@ -417,6 +595,15 @@ where
     }
 }
 
+/// Returns the scope variable `name`. Unlike `ExecutionScopes::get`, which reports a variable
+/// of the wrong type as missing, the error names the expected type.
+pub fn get_typed<T: Any + Clone>(exec_scopes: &ExecutionScopes, name: &str) -> Result<T, HintError> {
+    let variable = exec_scopes.get_any_boxed_ref(name)?;
+    variable.downcast_ref::<T>().cloned().ok_or_else(|| {
+        custom_hint_error(format!("Scope variable {name} is not of the expected type {}", std::any::type_name::<T>()))
+    })
+}
+
 pub fn hint_stub(
     _vm: &mut VirtualMachine,
     _exec_scopes: &mut ExecutionScopes,
@@ -438,11 +625,26 @@ pub const STARKNET_OS_INPUT: &str = indoc! {r#"
 
 pub fn starknet_os_input(
     vm: &mut VirtualMachine,
-    _exec_scopes: &mut ExecutionScopes,
+    exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     _constants: &HashMap<String, Felt252>,
 ) -> Result<(), HintError> {
+    // The input is usually deserialized by the caller of the OS (see `run_os_with_input`) and set
+    // in the root scope before the run starts. Otherwise, it is loaded from the input file.
+    if exec_scopes.get_ref::<Rc<StarknetOsInput>>(vars::scopes::OS_INPUT).is_err() {
+        let input_path = os_input_path(exec_scopes).ok_or_else(|| {
+            custom_hint_error(format!(
+                "The OS input must be set in the execution scopes, or its path provided through {}",
+                INPUT_PATH_ENV_VAR
+            ))
+        })?;
+        let os_input = StarknetOsInput::load_streaming(&input_path).map_err(|e| {
+            custom_hint_error(format!("Failed to load the OS input from {}: {e}", input_path.display()))
+        })?;
+        exec_scopes.insert_value(vars::scopes::OS_INPUT, Rc::new(os_input));
+    }
+
     let initial_carried_outputs_ptr =
         get_ptr_from_var_name(vars::ids::INITIAL_CARRIED_OUTPUTS, vm, ids_data, ap_tracking)?;
 
@@ -455,6 +657,15 @@ pub fn starknet_os_input(
     vm.insert_value(messages_to_l2, temp_segment).map_err(|e| e.into())
 }
 
+/// Path of the OS input file: the one set in the scope if any, then the one from the
+/// `SNOS_INPUT_PATH` environment variable.
+fn os_input_path(exec_scopes: &ExecutionScopes) -> Option<PathBuf> {
+    exec_scopes
+        .get::<PathBuf>(vars::scopes::INPUT_PATH)
+        .ok()
+        .or_else(|| std::env::var_os(INPUT_PATH_ENV_VAR).map(PathBuf::from))
+}
+
 pub const INITIALIZE_STATE_CHANGES: &str = indoc! {r#"
     from starkware.python.utils import from_bytes
 
@@ -465,14 +676,23 @@ pub const INITIALIZE_STATE_CHANGES: &str = indoc! {r#"
     }"#
 };
 
-pub fn initialize_state_changes(
+/// Also seeds the storage of the contracts with the initial storage values of the input, if any.
+pub fn initialize_state_changes<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     _ids_data: &HashMap<String, HintReference>,
     _ap_tracking: &ApTracking,
     _constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
     let os_input = exec_scopes.get::<Rc<StarknetOsInput>>(vars::scopes::OS_INPUT)?;
+    if os_input.contracts.values().any(|contract_state| !contract_state.storage.is_empty()) {
+        let mut execution_helper = exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER)?;
+        execute_coroutine(seed_initial_storage(&mut execution_helper, &os_input))??;
+    }
+
     let mut state_dict: HashMap<MaybeRelocatable, MaybeRelocatable> = HashMap::new();
     for (addr, contract_state) in &os_input.contracts {
         let change_base = vm.add_memory_segment();
@@ -488,6 +708,24 @@ pub fn initialize_state_changes(
     Ok(())
 }
 
+async fn seed_initial_storage<PCS>(
+    execution_helper: &mut ExecutionHelperWrapper<PCS>,
+    os_input: &StarknetOsInput,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    for (address, contract_state) in &os_input.contracts {
+        for (key, value) in &contract_state.storage {
+            execution_helper.write_storage_for_address(*address, *key, *value).await.map_err(|_| {
+                custom_hint_error(format!("Cannot seed the storage of unknown contract {}", address.to_hex_string()))
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
 pub const INITIALIZE_CLASS_HASHES: &str = "initial_dict = os_input.class_hash_to_compiled_class_hash";
 
 pub fn initialize_class_hashes(
@@ -589,6 +827,179 @@ where
     insert_value_into_ap(vm, Felt252::from(actual_fee.0))
 }
 
+pub const GET_ACTUAL_FEE_IN_WEI_AND_FRI: &str = indoc! {r#"
+    ids.actual_fee_wei, ids.actual_fee_fri = os_input.general_config.fee_in_wei_and_fri(
+        fee=execution_helper.tx_execution_info.actual_fee,
+        fee_type=FeeType.STRK if tx.version == 3 else FeeType.ETH,
+    )"#
+};
+
+/// Serves the actual fee of the current tx in both ETH and STRK, whichever currency it was paid
+/// in: v3 txs pay in STRK, older ones in ETH.
+pub fn get_actual_fee_in_wei_and_fri<PCS>(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let os_input = exec_scopes.get::<Rc<StarknetOsInput>>(vars::scopes::OS_INPUT)?;
+    let tx = exec_scopes.get_ref::<InternalTransaction>(vars::scopes::TX)?;
+    let fee_type = match tx.version {
+        Some(version) if version == Felt252::THREE => FeeType::Strk,
+        Some(_) => FeeType::Eth,
+        None => return Err(custom_hint_error("tx.version is None")),
+    };
+
+    let execution_helper = exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER)?;
+    let actual_fee = execute_coroutine(async {
+        let eh_ref = execution_helper.execution_helper.read().await;
+        eh_ref
+            .tx_execution_info
+            .as_ref()
+            .ok_or(custom_hint_error("ExecutionHelper should have tx_execution_info"))
+            .map(|tx_execution_info| tx_execution_info.transaction_receipt.fee)
+    })??;
+
+    let (actual_fee_wei, actual_fee_fri) = os_input
+        .general_config
+        .fee_in_wei_and_fri(actual_fee.0, fee_type)
+        .ok_or_else(|| custom_hint_error(format!("Failed to convert the actual fee {}", actual_fee.0)))?;
+    insert_value_from_var_name(vars::ids::ACTUAL_FEE_WEI, Felt252::from(actual_fee_wei), vm, ids_data, ap_tracking)?;
+    insert_value_from_var_name(vars::ids::ACTUAL_FEE_FRI, Felt252::from(actual_fee_fri), vm, ids_data, ap_tracking)
+}
+
+pub const ASSERT_SENDER_CAN_PAY_FEE: &str = indoc! {r#"
+    fee_token_address = os_input.general_config.fee_token_address_for_version(tx.version)
+    balance = execution_helper.read_fee_token_balance(
+        fee_token_address=fee_token_address, account=tx.sender_address
+    )
+    actual_fee = execution_helper.tx_execution_info.actual_fee
+    assert balance >= actual_fee, (
+        f"Sender {hex(tx.sender_address)} cannot pay the actual fee {actual_fee} "
+        f"of tx {hex(tx.hash_value)}: balance is {balance}."
+    )"#
+};
+
+/// Asserts that the sender of the current tx holds enough fee tokens to pay its actual fee.
+/// The balance, a u256 stored as (low, high) in the `ERC20_balances` variable of the fee token,
+/// is read from the storage served to the run.
+pub fn assert_sender_can_pay_fee<PCS>(
+    _vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    _ids_data: &HashMap<String, HintReference>,
+    _ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let os_input = exec_scopes.get::<Rc<StarknetOsInput>>(vars::scopes::OS_INPUT)?;
+    let tx = exec_scopes.get::<InternalTransaction>(vars::scopes::TX)?;
+    let tx_version = tx.version.ok_or(custom_hint_error("tx.version is None"))?;
+    let sender_address = tx.sender_address.ok_or(custom_hint_error("tx.sender_address is None"))?;
+    let fee_token_address = *os_input.general_config.fee_token_address_for_version(tx_version).0.key();
+
+    let sender_contract_address = ContractAddress::try_from(sender_address)
+        .map_err(|e| custom_hint_error(format!("Invalid sender address {sender_address:#x}: {e}")))?;
+    let balance_low_key = *get_fee_token_var_address(sender_contract_address).0.key();
+
+    let mut execution_helper = exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER)?;
+    let (actual_fee, balance_low, balance_high) = execute_coroutine(async {
+        let actual_fee = execution_helper
+            .execution_helper
+            .read()
+            .await
+            .tx_execution_info
+            .as_ref()
+            .ok_or(custom_hint_error("ExecutionHelper should have tx_execution_info"))?
+            .transaction_receipt
+            .fee;
+        let balance_high_key = balance_low_key + Felt252::ONE;
+        let balance_low = execution_helper.read_storage_for_address(fee_token_address, balance_low_key).await;
+        let balance_high = execution_helper.read_storage_for_address(fee_token_address, balance_high_key).await;
+        let (Ok(balance_low), Ok(balance_high)) = (balance_low, balance_high) else {
+            return Err(custom_hint_error(format!(
+                "The fee token balance of {sender_address:#x} was not served to the run (fee token \
+                 {fee_token_address:#x}, key {balance_low_key:#x})"
+            )));
+        };
+        Ok::<_, HintError>((actual_fee, balance_low, balance_high))
+    })??;
+
+    let balance = (balance_high.to_biguint() << 128) + balance_low.to_biguint();
+    hint_assert!(
+        exec_scopes,
+        balance >= BigUint::from(actual_fee.0),
+        "Sender {sender_address:#x} cannot pay the actual fee {} of tx {:#x}: balance is {balance}",
+        actual_fee.0,
+        tx.hash_value
+    );
+
+    Ok(())
+}
+
+pub const ASSERT_FEE_TRANSFER_TOKEN: &str = indoc! {r#"
+    fee_transfer_call_info = execution_helper.tx_execution_info.fee_transfer_call_info
+    if fee_transfer_call_info is not None:
+        fee_token_address = os_input.general_config.fee_token_address_for_version(tx.version)
+        assert fee_transfer_call_info.call.storage_address == fee_token_address, (
+            f"Fee transfer of tx {hex(tx.hash_value)} targets "
+            f"{hex(fee_transfer_call_info.call.storage_address)} instead of the fee token "
+            f"{hex(fee_token_address)}."
+        )"#
+};
+
+/// Checks that the fee transfer of the current tx, if any, calls the fee token configured for the
+/// tx version (STRK for v3 txs, ETH otherwise). The OS charges the fee on the configured token, so
+/// a transfer recorded on another contract would be replayed against the wrong storage.
+pub fn assert_fee_transfer_token<PCS>(
+    _vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    _ids_data: &HashMap<String, HintReference>,
+    _ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let os_input = exec_scopes.get::<Rc<StarknetOsInput>>(vars::scopes::OS_INPUT)?;
+    let tx = exec_scopes.get::<InternalTransaction>(vars::scopes::TX)?;
+
+    let execution_helper = exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER)?;
+    let fee_transfer_address = execute_coroutine(async {
+        let eh_ref = execution_helper.execution_helper.read().await;
+        eh_ref.tx_execution_info.as_ref().ok_or(custom_hint_error("ExecutionHelper should have tx_execution_info")).map(
+            |tx_execution_info| {
+                tx_execution_info
+                    .fee_transfer_call_info
+                    .as_ref()
+                    .map(|call_info| *call_info.call.storage_address.0.key())
+            },
+        )
+    })??;
+    // Txs that do not pay a fee have no fee transfer
+    let Some(fee_transfer_address) = fee_transfer_address else {
+        return Ok(());
+    };
+
+    let tx_version = tx.version.ok_or(custom_hint_error("tx.version is None"))?;
+    let fee_token_address = *os_input.general_config.fee_token_address_for_version(tx_version).0.key();
+    hint_assert!(
+        exec_scopes,
+        fee_transfer_address == fee_token_address,
+        "Fee transfer of tx {:#x} targets {:#x} instead of the fee token {:#x}",
+        tx.hash_value,
+        fee_transfer_address,
+        fee_token_address
+    );
+
+    Ok(())
+}
+
 pub const IS_ON_CURVE: &str = "ids.is_on_curve = (y * y) % SECP_P == y_square_int";
 
 pub fn is_on_curve(
@@ -673,9 +1084,7 @@ where
     PCS: PerContractStorage + 'static,
 {
     let mut execution_helper = exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER)?;
-    execution_helper.skip_call().await;
-
-    Ok(())
+    execution_helper.skip_call().await
 }
 
 pub fn skip_call<PCS>(
@@ -704,3 +1113,240 @@ pub fn os_input_transactions(
     let num_txns = os_input.transactions.len();
     vm.insert_value((vm.get_fp() + 12)?, num_txns).map_err(HintError::Memory)
 }
+
+pub const COMPUTE_EVENTS_COMMITMENT: &str = indoc! {r#"
+    from starkware.starknet.core.os.block_hash import calculate_event_commitment
+
+    ids.events_commitment = calculate_event_commitment(
+        tx_hashes=[tx.hash_value for tx in os_input.transactions],
+        event_log=execution_helper.event_log,
+    )"#
+};
+
+pub async fn compute_events_commitment_async<PCS>(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let os_input = exec_scopes.get::<Rc<StarknetOsInput>>(vars::scopes::OS_INPUT)?;
+    let execution_helper = exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER)?;
+    let eh_ref = execution_helper.execution_helper.read().await;
+
+    // Every tx of the block is started, even the ones skipped by the OS, so each one has an entry
+    if eh_ref.event_log.len() != os_input.transactions.len() {
+        return Err(custom_hint_error(format!(
+            "Events were recorded for {} txs, the block has {}",
+            eh_ref.event_log.len(),
+            os_input.transactions.len()
+        )));
+    }
+    let mut events = vec![];
+    for (tx_index, tx) in os_input.transactions.iter().enumerate() {
+        let tx_events = eh_ref.event_log.get(&tx_index).ok_or_else(|| {
+            custom_hint_error(format!(
+                "No events recorded for tx {} at index {tx_index}",
+                tx.hash_value.to_hex_string()
+            ))
+        })?;
+        events.extend(tx_events.iter().map(|event| (tx.hash_value, event.clone())));
+    }
+
+    let events_commitment = calculate_events_commitment(&events).await?;
+    insert_value_from_var_name(vars::ids::EVENTS_COMMITMENT, events_commitment, vm, ids_data, ap_tracking)
+}
+
+pub fn compute_events_commitment<PCS>(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    execute_coroutine(compute_events_commitment_async::<PCS>(vm, exec_scopes, ids_data, ap_tracking))?
+}
+
+pub const COMPUTE_TRANSACTIONS_COMMITMENT: &str = indoc! {r#"
+    from starkware.starknet.core.os.block_hash import calculate_tx_commitment
+
+    ids.transactions_commitment = calculate_tx_commitment(
+        tx_hashes_and_signatures=[(tx.hash_value, tx.signature) for tx in os_input.transactions]
+    )"#
+};
+
+pub fn compute_transactions_commitment(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError> {
+    let os_input = exec_scopes.get::<Rc<StarknetOsInput>>(vars::scopes::OS_INPUT)?;
+
+    // Transactions without a signature (deploy, L1 handler) commit to an empty signature
+    let txs: Vec<_> =
+        os_input.transactions.iter().map(|tx| (tx.hash_value, tx.signature.clone().unwrap_or_default())).collect();
+
+    let transactions_commitment = execute_coroutine(calculate_transactions_commitment(&txs))??;
+    insert_value_from_var_name(vars::ids::TRANSACTIONS_COMMITMENT, transactions_commitment, vm, ids_data, ap_tracking)
+}
+
+pub const COMPUTE_STATE_DIFF_COMMITMENT: &str = indoc! {r#"
+    from starkware.starknet.core.os.block_hash import calculate_state_diff_commitment
+    from starkware.starknet.core.os.state.state_diff import StateDiff
+
+    state_diff = StateDiff.from_squashed_dicts(
+        memory=memory,
+        contract_state_changes=ids.squashed_contract_state_changes_start,
+        n_contract_state_changes=ids.n_contract_state_changes,
+        contract_class_changes=ids.squashed_contract_class_changes_start,
+        n_contract_class_changes=ids.n_contract_class_changes,
+        deprecated_declared_classes=[
+            tx.class_hash for tx in os_input.transactions if tx.type == "DECLARE" and tx.version < 2
+        ],
+    )
+    ids.state_diff_commitment = calculate_state_diff_commitment(state_diff)"#
+};
+
+/// Reads the storage updates recorded in the (unsquashed) storage dict of a contract, between
+/// its previous and new state. Keys written back to their initial value are left out.
+fn read_storage_diff(
+    vm: &VirtualMachine,
+    prev_storage_ptr: Relocatable,
+    new_storage_ptr: Relocatable,
+) -> Result<BTreeMap<Felt252, Felt252>, HintError> {
+    let n_accesses = (new_storage_ptr - prev_storage_ptr)? / DictAccess::cairo_size();
+
+    // Key -> (initial value, last written value)
+    let mut accesses = BTreeMap::<Felt252, (Felt252, Felt252)>::new();
+    for i in 0..n_accesses {
+        let access_ptr = (prev_storage_ptr + i * DictAccess::cairo_size())?;
+        let key = vm.get_integer((access_ptr + DictAccess::key_offset())?)?.into_owned();
+        let prev_value = vm.get_integer((access_ptr + DictAccess::prev_value_offset())?)?.into_owned();
+        let new_value = vm.get_integer((access_ptr + DictAccess::new_value_offset())?)?.into_owned();
+        accesses.entry(key).or_insert((prev_value, new_value)).1 = new_value;
+    }
+
+    Ok(accesses
+        .into_iter()
+        .filter(|(_, (initial_value, final_value))| initial_value != final_value)
+        .map(|(key, (_, final_value))| (key, final_value))
+        .collect())
+}
+
+/// Builds the state diff of the block from the squashed contract state and contract class dicts.
+fn read_state_diff(
+    vm: &VirtualMachine,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    os_input: &StarknetOsInput,
+) -> Result<StateDiff, HintError> {
+    let mut state_diff = StateDiff::default();
+
+    let contract_state_changes =
+        get_ptr_from_var_name(vars::ids::SQUASHED_CONTRACT_STATE_CHANGES_START, vm, ids_data, ap_tracking)?;
+    let n_contract_state_changes =
+        felt_to_usize(&get_integer_from_var_name(vars::ids::N_CONTRACT_STATE_CHANGES, vm, ids_data, ap_tracking)?)?;
+    for i in 0..n_contract_state_changes {
+        let access_ptr = (contract_state_changes + i * DictAccess::cairo_size())?;
+        let address = vm.get_integer((access_ptr + DictAccess::key_offset())?)?.into_owned();
+        let prev_state =
+            StateEntryLayout::from_memory(vm, vm.get_relocatable((access_ptr + DictAccess::prev_value_offset())?)?)?;
+        let new_state =
+            StateEntryLayout::from_memory(vm, vm.get_relocatable((access_ptr + DictAccess::new_value_offset())?)?)?;
+
+        if new_state.class_hash != prev_state.class_hash {
+            state_diff.updated_contracts.insert(address, new_state.class_hash);
+        }
+        if new_state.nonce != prev_state.nonce {
+            state_diff.nonces.insert(address, new_state.nonce);
+        }
+        let storage_diff = read_storage_diff(vm, prev_state.storage_ptr, new_state.storage_ptr)?;
+        if !storage_diff.is_empty() {
+            state_diff.storage_diffs.insert(address, storage_diff);
+        }
+    }
+
+    let contract_class_changes =
+        get_ptr_from_var_name(vars::ids::SQUASHED_CONTRACT_CLASS_CHANGES_START, vm, ids_data, ap_tracking)?;
+    let n_contract_class_changes =
+        felt_to_usize(&get_integer_from_var_name(vars::ids::N_CONTRACT_CLASS_CHANGES, vm, ids_data, ap_tracking)?)?;
+    for i in 0..n_contract_class_changes {
+        let access_ptr = (contract_class_changes + i * DictAccess::cairo_size())?;
+        let class_hash = vm.get_integer((access_ptr + DictAccess::key_offset())?)?.into_owned();
+        let prev_compiled_class_hash = vm.get_integer((access_ptr + DictAccess::prev_value_offset())?)?.into_owned();
+        let compiled_class_hash = vm.get_integer((access_ptr + DictAccess::new_value_offset())?)?.into_owned();
+        if compiled_class_hash != prev_compiled_class_hash {
+            state_diff.declared_classes.insert(class_hash, compiled_class_hash);
+        }
+    }
+
+    // Cairo 0 classes are not part of the class dict, they are only known from the declare txs
+    state_diff.deprecated_declared_classes = os_input
+        .transactions
+        .iter()
+        .filter(|tx| tx.r#type == "DECLARE" && tx.version.is_some_and(|version| version < Felt252::TWO))
+        .filter_map(|tx| tx.class_hash)
+        .collect();
+
+    Ok(state_diff)
+}
+
+pub fn compute_state_diff_commitment(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError> {
+    let os_input = exec_scopes.get::<Rc<StarknetOsInput>>(vars::scopes::OS_INPUT)?;
+    let state_diff = read_state_diff(vm, ids_data, ap_tracking, &os_input)?;
+
+    let state_diff_commitment = calculate_state_diff_commitment(&state_diff);
+    insert_value_from_var_name(vars::ids::STATE_DIFF_COMMITMENT, state_diff_commitment, vm, ids_data, ap_tracking)
+}
+
+pub const ASSERT_BLOCK_GAS_WITHIN_LIMIT: &str = indoc! {r#"
+    block_gas_consumed = execution_helper.block_gas_consumed
+    block_max_gas = os_input.general_config.block_max_gas
+    assert block_gas_consumed <= block_max_gas, (
+        f"The block consumed {block_gas_consumed} gas, above the limit of {block_max_gas}."
+    )"#
+};
+
+pub async fn assert_block_gas_within_limit_async<PCS>(exec_scopes: &mut ExecutionScopes) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let os_input = exec_scopes.get::<Rc<StarknetOsInput>>(vars::scopes::OS_INPUT)?;
+    let execution_helper = exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER)?;
+    let block_gas_consumed = execution_helper.execution_helper.read().await.block_gas_consumed;
+    let block_max_gas = os_input.general_config.block_max_gas;
+
+    hint_assert!(
+        exec_scopes,
+        block_gas_consumed <= block_max_gas,
+        "The block consumed {block_gas_consumed} gas, above the limit of {block_max_gas}."
+    );
+
+    Ok(())
+}
+
+pub fn assert_block_gas_within_limit<PCS>(
+    _vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    _ids_data: &HashMap<String, HintReference>,
+    _ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    execute_coroutine(assert_block_gas_within_limit_async::<PCS>(exec_scopes))?
+}
@@ -13,17 +13,31 @@ use cairo_vm::vm::errors::hint_errors::HintError;
 use cairo_vm::vm::vm_core::VirtualMachine;
 use cairo_vm::{any_box, Felt252};
 use indoc::indoc;
+use num_bigint::BigUint;
+use num_traits::{One, ToPrimitive};
 
 use super::bls_utils::split;
 use crate::cairo_types::traits::CairoType;
 use crate::cairo_types::trie::NodeEdge;
+use crate::crypto::pedersen::PedersenHash;
+use crate::crypto::poseidon::PoseidonHash;
 use crate::execution::helper::ExecutionHelperWrapper;
+use crate::hints::assertions::hint_assert;
 use crate::hints::types::{get_hash_builtin_fields, skip_verification_if_configured, PatriciaTreeMode, Preimage};
 use crate::hints::vars;
 use crate::io::input::StarknetOsInput;
+use crate::starknet::business_logic::fact_state::contract_class_objects::ContractClassLeaf;
+use crate::starknet::business_logic::fact_state::contract_state_objects::ContractState;
 use crate::starknet::starknet_storage::{CommitmentInfo, PerContractStorage, StorageLeaf};
+use crate::starkware_utils::commitment_tree::base_types::{Height, Length, NodePath};
+use crate::starkware_utils::commitment_tree::binary_fact_tree::BinaryFactTree;
+use crate::starkware_utils::commitment_tree::errors::TreeError;
+use crate::starkware_utils::commitment_tree::patricia_tree::nodes::{BinaryNodeFact, EdgeNodeFact, PatriciaNodeFact};
+use crate::starkware_utils::commitment_tree::patricia_tree::patricia_tree::PatriciaTree;
 use crate::starkware_utils::commitment_tree::update_tree::{decode_node, DecodeNodeCase, DecodedNode, UpdateTree};
-use crate::utils::{execute_coroutine, get_constant};
+use crate::storage::dict_storage::DictStorage;
+use crate::storage::storage::{DbObject, Fact, FactFetchingContext, HashFunctionType};
+use crate::utils::{custom_hint_error, execute_coroutine, get_constant};
 
 fn assert_tree_height_eq_merkle_height(tree_height: Felt252, merkle_height: Felt252) -> Result<(), HintError> {
     if tree_height != merkle_height {
@@ -79,6 +93,104 @@ pub fn set_preimage_for_state_commitments(
     Ok(())
 }
 
+pub const ASSERT_INITIAL_STATE_ROOT: &str = indoc! {r#"
+	commitment_info = os_input.contract_state_commitment_info
+	for address, contract_state in os_input.contracts.items():
+	    leaf = get_leaf_from_preimage(
+	        root=commitment_info.previous_root,
+	        height=commitment_info.tree_height,
+	        index=address,
+	        preimage=commitment_info.commitment_facts,
+	    )
+	    assert leaf == contract_state.hash(), (
+	        f"The state of contract {address} is inconsistent with the initial state root.")"#
+};
+
+/// Checks that the contract states of the input are the leaves of the initial contract state
+/// tree, by descending from its root through the commitment facts.
+pub fn assert_initial_state_root(
+    _vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    _ids_data: &HashMap<String, HintReference>,
+    _ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError> {
+    let os_input = exec_scopes.get::<Rc<StarknetOsInput>>(vars::scopes::OS_INPUT)?;
+    let commitment_info = &os_input.contract_state_commitment_info;
+
+    for (address, contract_state) in &os_input.contracts {
+        let leaf = get_leaf_from_preimage(
+            commitment_info.previous_root,
+            commitment_info.tree_height,
+            &address.to_biguint(),
+            &commitment_info.commitment_facts,
+        )?;
+        let contract_state_hash =
+            Felt252::from_bytes_be_slice(&<ContractState as Fact<DictStorage, PedersenHash>>::hash(contract_state));
+        hint_assert!(
+            exec_scopes,
+            leaf == contract_state_hash,
+            "The state of contract {} is inconsistent with the initial state root {}",
+            address.to_hex_string(),
+            commitment_info.previous_root.to_hex_string()
+        );
+    }
+
+    Ok(())
+}
+
+fn pedersen(x: &Felt252, y: &Felt252) -> Felt252 {
+    Felt252::from_bytes_be_slice(&PedersenHash::hash(&x.to_bytes_be(), &y.to_bytes_be()))
+}
+
+/// Returns the leaf at `index` of a Patricia tree, descending from `root` with the preimages of
+/// the nodes on the path. The preimage of each node is checked against its hash. An empty
+/// subtree on the path means the leaf is empty, i.e. 0.
+fn get_leaf_from_preimage(
+    root: Felt252,
+    height: usize,
+    index: &BigUint,
+    preimage: &HashMap<Felt252, Vec<Felt252>>,
+) -> Result<Felt252, HintError> {
+    let mut node = root;
+    let mut remaining_height = height;
+
+    while remaining_height > 0 && node != Felt252::ZERO {
+        let children = preimage
+            .get(&node)
+            .ok_or_else(|| custom_hint_error(format!("No preimage for node {}", node.to_hex_string())))?;
+
+        let (computed_hash, next_node, descent_length) = match children.as_slice() {
+            [left, right] => {
+                let is_right = index.bit(remaining_height as u64 - 1);
+                (pedersen(left, right), if is_right { *right } else { *left }, 1)
+            }
+            [length, path, bottom] => {
+                let length = length
+                    .to_usize()
+                    .filter(|length| (1..=remaining_height).contains(length))
+                    .ok_or_else(|| custom_hint_error(format!("Invalid edge length {length}")))?;
+                let index_path = (index >> (remaining_height - length)) & ((BigUint::one() << length) - 1u32);
+                // An edge leading away from the index means the leaf is empty
+                let next_node = if path.to_biguint() == index_path { *bottom } else { Felt252::ZERO };
+                (pedersen(bottom, path) + Felt252::from(length), next_node, length)
+            }
+            _ => return Err(custom_hint_error(format!("Invalid preimage for node {}", node.to_hex_string()))),
+        };
+        if computed_hash != node {
+            return Err(custom_hint_error(format!(
+                "Preimage of node {} does not match its hash",
+                node.to_hex_string()
+            )));
+        }
+
+        node = next_node;
+        remaining_height -= descent_length;
+    }
+
+    Ok(node)
+}
+
 pub const SET_PREIMAGE_FOR_CLASS_COMMITMENTS: &str = indoc! {r#"
 	ids.initial_root = os_input.contract_class_commitment_info.previous_root
 	ids.final_root = os_input.contract_class_commitment_info.updated_root
@@ -126,6 +238,92 @@ pub fn set_preimage_for_class_commitments(
     Ok(())
 }
 
+pub const COMPUTE_NEW_CLASSES_ROOT: &str = indoc! {r#"
+	commitment_info = os_input.contract_class_commitment_info
+	ids.new_classes_root = compute_new_classes_root(
+	    previous_root=commitment_info.previous_root,
+	    height=commitment_info.tree_height,
+	    preimage=commitment_info.commitment_facts,
+	    declared_classes={
+	        class_hash: os_input.class_hash_to_compiled_class_hash[class_hash]
+	        for class_hash in os_input.declared_class_hash_to_component_hashes
+	    },
+	)"#
+};
+
+/// Updates the classes trie with the classes declared in the block, each class hash pointing to
+/// its compiled class hash, and writes the new root. The nodes of the previous trie are read
+/// from the commitment facts of the input.
+pub fn compute_new_classes_root(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError> {
+    let os_input = exec_scopes.get::<Rc<StarknetOsInput>>(vars::scopes::OS_INPUT)?;
+
+    let modifications = os_input
+        .declared_class_hash_to_component_hashes
+        .keys()
+        .map(|class_hash| {
+            let compiled_class_hash = os_input.class_hash_to_compiled_class_hash.get(class_hash).ok_or_else(|| {
+                custom_hint_error(format!("No compiled class hash for class {}", class_hash.to_hex_string()))
+            })?;
+            Ok((class_hash.to_biguint(), ContractClassLeaf::create(*compiled_class_hash)))
+        })
+        .collect::<Result<Vec<_>, HintError>>()?;
+
+    let new_classes_root =
+        execute_coroutine(update_classes_trie(&os_input.contract_class_commitment_info, modifications))??;
+    insert_value_from_var_name(vars::ids::NEW_CLASSES_ROOT, new_classes_root, vm, ids_data, ap_tracking)
+}
+
+/// Stores the commitment facts of the classes trie and applies `modifications` to it. Facts that
+/// are not a valid binary or edge node fail the update, naming the node they are the preimage of.
+async fn update_classes_trie(
+    commitment_info: &CommitmentInfo,
+    modifications: Vec<(BigUint, ContractClassLeaf)>,
+) -> Result<Felt252, HintError> {
+    let mut ffc = FactFetchingContext::<_, PoseidonHash>::new(DictStorage::default());
+    {
+        let mut storage = ffc.acquire_storage().await;
+        for (node_hash, preimage) in &commitment_info.commitment_facts {
+            let node = match preimage.as_slice() {
+                [left, right] => {
+                    PatriciaNodeFact::Binary(BinaryNodeFact { left_node: (*left).into(), right_node: (*right).into() })
+                }
+                [length, path, bottom] => {
+                    let length = length
+                        .to_u64()
+                        .filter(|length| (1..=commitment_info.tree_height as u64).contains(length))
+                        .ok_or_else(|| {
+                            custom_hint_error(format!(
+                                "Invalid edge length {length} for node {}",
+                                node_hash.to_hex_string()
+                            ))
+                        })?;
+                    let edge = EdgeNodeFact::new((*bottom).into(), NodePath(path.to_biguint()), Length(length))
+                        .map_err(|e| {
+                            custom_hint_error(format!("Invalid edge node {}: {e}", node_hash.to_hex_string()))
+                        })?;
+                    PatriciaNodeFact::Edge(edge)
+                }
+                _ => {
+                    return Err(custom_hint_error(format!("Invalid preimage for node {}", node_hash.to_hex_string())));
+                }
+            };
+            node.set(&mut *storage, &node_hash.to_bytes_be()).await.map_err(TreeError::from)?;
+        }
+    }
+
+    let previous_tree =
+        PatriciaTree { root: commitment_info.previous_root.into(), height: Height(commitment_info.tree_height as u64) };
+    let updated_tree = previous_tree.update(&mut ffc, modifications, &mut None).await?;
+
+    Ok(Felt252::from_bytes_be_slice(&updated_tree.root))
+}
+
 pub const SET_PREIMAGE_FOR_CURRENT_COMMITMENT_INFO: &str = indoc! {r#"
 	commitment_info = commitment_info_by_address[ids.contract_address]
 	ids.initial_contract_state_root = commitment_info.previous_root
@@ -338,6 +536,28 @@ where
     Ok(())
 }
 
+pub const COMMIT_CONTRACT_STORAGE: &str = "commitment_info_by_address = execution_helper.compute_storage_commitments()";
+
+/// Applies the storage changes recorded during the execution of the block to the storage trie of
+/// each contract, and stores the resulting commitment info (including the updated root) of each
+/// contract in the current scope.
+pub fn commit_contract_storage<PCS>(
+    _vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    _ids_data: &HashMap<String, HintReference>,
+    _ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let execution_helper: ExecutionHelperWrapper<PCS> = exec_scopes.get(vars::scopes::EXECUTION_HELPER)?;
+    let commitment_info_by_address = execute_coroutine(execution_helper.compute_storage_commitments())??;
+    exec_scopes.insert_value(vars::scopes::COMMITMENT_INFO_BY_ADDRESS, commitment_info_by_address);
+
+    Ok(())
+}
+
 pub const WRITE_SPLIT_RESULT: &str = indoc! {r#"
     from starkware.starknet.core.os.data_availability.bls_utils import split
 
@@ -365,10 +585,13 @@ mod tests {
     use std::borrow::Cow;
     use std::rc::Rc;
 
+    use blockifier::context::BlockContext;
     use rstest::{fixture, rstest};
 
     use super::*;
+    use crate::execution::helper::ContractStorageMap;
     use crate::hints::types::PatriciaSkipValidationRunner;
+    use crate::starknet::starknet_storage::OsSingleStarknetStorage;
 
     #[fixture]
     fn os_input() -> StarknetOsInput {
@@ -385,19 +608,124 @@ mod tests {
                 tree_height: 251_usize,
                 commitment_facts: Default::default(),
             },
-            deprecated_compiled_classes: Default::default(),
-            compiled_classes: Default::default(),
-            contracts: Default::default(),
-            contract_address_to_class_hash: Default::default(),
-            class_hash_to_compiled_class_hash: Default::default(),
-            general_config: Default::default(),
-            transactions: Default::default(),
-            declared_class_hash_to_component_hashes: Default::default(),
-            new_block_hash: Default::default(),
-            prev_block_hash: Default::default(),
-            compiled_class_visited_pcs: Default::default(),
-            full_output: false,
+            ..Default::default()
+        }
+    }
+
+    #[rstest]
+    #[case::consistent(None, None, true)]
+    #[case::tampered_root(Some(Felt252::from(0x1234)), None, false)]
+    #[case::tampered_nonce(None, Some(Felt252::from(8)), false)]
+    #[tokio::test]
+    async fn test_assert_initial_state_root(
+        mut os_input: StarknetOsInput,
+        #[case] tampered_root: Option<Felt252>,
+        #[case] tampered_nonce: Option<Felt252>,
+        #[case] expect_success: bool,
+    ) {
+        let mut ffc = FactFetchingContext::<_, PedersenHash>::new(DictStorage::default());
+        let storage_tree = PatriciaTree::empty_tree(&mut ffc, Height(251), StorageLeaf::empty()).await.unwrap();
+        let contracts = HashMap::from([
+            (Felt252::from(0x100), ContractState::create(vec![1; 32], storage_tree.clone(), Felt252::ONE)),
+            (Felt252::from(0x200), ContractState::create(vec![2; 32], storage_tree, Felt252::TWO)),
+        ]);
+
+        // Build the state tree holding the contracts
+        let empty_contract_state = ContractState::empty(Height(251), &mut ffc).await.unwrap();
+        let empty_tree = PatriciaTree::empty_tree(&mut ffc, Height(251), empty_contract_state).await.unwrap();
+        let modifications = contracts.iter().map(|(address, state)| (address.to_biguint(), state.clone())).collect();
+        let commitment_info =
+            CommitmentInfo::create_from_modifications(empty_tree, None, modifications, &mut ffc).await.unwrap();
+
+        os_input.contract_state_commitment_info =
+            CommitmentInfo { previous_root: tampered_root.unwrap_or(commitment_info.updated_root), ..commitment_info };
+        os_input.contracts = contracts;
+        if let Some(nonce) = tampered_nonce {
+            os_input.contracts.get_mut(&Felt252::from(0x100)).unwrap().nonce = nonce;
         }
+
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::OS_INPUT, Rc::new(os_input));
+        let result = assert_initial_state_root(
+            &mut VirtualMachine::new(false),
+            &mut exec_scopes,
+            &HashMap::new(),
+            &ApTracking::new(),
+            &HashMap::new(),
+        );
+
+        assert_eq!(result.is_ok(), expect_success, "{result:?}");
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_compute_new_classes_root(mut os_input: StarknetOsInput) {
+        let mut ffc = FactFetchingContext::<_, PoseidonHash>::new(DictStorage::default());
+        let (existing_class_hash, existing_compiled_class_hash) = (Felt252::from(0x10), Felt252::from(0x11));
+        let (declared_class_hash, declared_compiled_class_hash) = (Felt252::from(0x20), Felt252::from(0x21));
+
+        // The classes trie before the block holds a single class
+        let empty_tree = PatriciaTree::empty_tree(&mut ffc, Height(251), ContractClassLeaf::empty()).await.unwrap();
+        let previous_tree = empty_tree
+            .update(
+                &mut ffc,
+                vec![(existing_class_hash.to_biguint(), ContractClassLeaf::create(existing_compiled_class_hash))],
+                &mut None,
+            )
+            .await
+            .unwrap();
+        let commitment_info = CommitmentInfo::create_from_modifications(
+            previous_tree,
+            None,
+            vec![(declared_class_hash.to_biguint(), ContractClassLeaf::create(declared_compiled_class_hash))],
+            &mut ffc,
+        )
+        .await
+        .unwrap();
+        let expected_root = commitment_info.updated_root;
+
+        os_input.contract_class_commitment_info = commitment_info;
+        os_input.declared_class_hash_to_component_hashes = HashMap::from([(declared_class_hash, vec![])]);
+        os_input.class_hash_to_compiled_class_hash =
+            HashMap::from([(declared_class_hash, declared_compiled_class_hash)]);
+
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+        let ids_data = HashMap::from([(vars::ids::NEW_CLASSES_ROOT.to_string(), HintReference::new_simple(-1))]);
+        let ap_tracking = ApTracking::new();
+
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::OS_INPUT, Rc::new(os_input));
+
+        compute_new_classes_root(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &HashMap::new()).unwrap();
+
+        let new_classes_root =
+            get_integer_from_var_name(vars::ids::NEW_CLASSES_ROOT, &vm, &ids_data, &ap_tracking).unwrap();
+        assert_eq!(new_classes_root, expected_root);
+        assert_ne!(new_classes_root, Felt252::ZERO);
+    }
+
+    #[rstest]
+    #[case::zero_edge_length(vec![Felt252::ZERO, Felt252::ZERO, Felt252::ONE], "Invalid edge length 0")]
+    #[case::edge_longer_than_the_trie(vec![Felt252::from(252), Felt252::ZERO, Felt252::ONE], "Invalid edge length 252")]
+    #[case::edge_path_too_long(vec![Felt252::ONE, Felt252::from(2), Felt252::ONE], "Invalid edge node")]
+    #[case::wrong_arity(vec![Felt252::ONE], "Invalid preimage for node")]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_update_classes_trie_rejects_malformed_facts(
+        #[case] preimage: Vec<Felt252>,
+        #[case] expected_error: &str,
+    ) {
+        let commitment_info = CommitmentInfo {
+            previous_root: Felt252::from(0x123),
+            tree_height: 251,
+            commitment_facts: HashMap::from([(Felt252::from(0x123), preimage)]),
+            ..Default::default()
+        };
+
+        let error = update_classes_trie(&commitment_info, vec![]).await.unwrap_err().to_string();
+        assert!(error.contains(expected_error) && error.contains("0x123"), "{error}");
     }
 
     #[rstest]
@@ -542,6 +870,48 @@ mod tests {
         // * hash_ptr.result
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_commit_contract_storage() {
+        type PCS = OsSingleStarknetStorage<DictStorage, PedersenHash>;
+
+        let contract_address = Felt252::from(300);
+        let writes = [(Felt252::from(42), Felt252::from(8000)), (Felt252::from(43), Felt252::from(9000))];
+
+        let mut ffc = FactFetchingContext::<_, PedersenHash>::new(DictStorage::default());
+        let previous_tree = PatriciaTree::empty_tree(&mut ffc, Height(251), StorageLeaf::empty()).await.unwrap();
+        // Recompute the expected root independently of the execution helper
+        let modifications: Vec<_> =
+            writes.iter().map(|(key, value)| (key.to_biguint(), StorageLeaf::new(*value))).collect();
+        let updated_tree = previous_tree.clone().update(&mut ffc, modifications, &mut None).await.unwrap();
+        let expected_root = Felt252::from_bytes_be_slice(&updated_tree.root);
+
+        let storage = PCS::new(previous_tree, updated_tree, &[], ffc).await.unwrap();
+        let mut execution_helper = ExecutionHelperWrapper::<PCS>::new(
+            ContractStorageMap::from([(contract_address, storage)]),
+            vec![],
+            &BlockContext::create_for_account_testing(),
+            None,
+            (Felt252::ZERO, Felt252::ZERO),
+        );
+        for (key, value) in writes {
+            execution_helper.write_storage_for_address(contract_address, key, value).await.unwrap();
+        }
+
+        let mut vm = VirtualMachine::new(false);
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, execution_helper);
+
+        commit_contract_storage::<PCS>(&mut vm, &mut exec_scopes, &HashMap::new(), &ApTracking::new(), &HashMap::new())
+            .unwrap();
+
+        let commitment_info_by_address: HashMap<Felt252, CommitmentInfo> =
+            exec_scopes.get(vars::scopes::COMMITMENT_INFO_BY_ADDRESS).unwrap();
+        assert_eq!(commitment_info_by_address.len(), 1);
+        let commitment_info = &commitment_info_by_address[&contract_address];
+        assert_eq!(commitment_info.updated_root, expected_root);
+        assert_ne!(commitment_info.updated_root, commitment_info.previous_root);
+    }
+
     #[test]
     pub fn test_write_split_result() {
         let mut vm = VirtualMachine::new(false);
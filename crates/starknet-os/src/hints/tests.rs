@@ -1,23 +1,36 @@
 #[cfg(test)]
 pub mod tests {
+    use std::sync::Mutex;
+
+    use blockifier::abi::abi_utils::get_fee_token_var_address;
     use blockifier::context::BlockContext;
+    use blockifier::execution::call_info::{CallExecution, CallInfo, OrderedEvent};
+    use blockifier::execution::entry_point::CallEntryPoint;
     use blockifier::fee::actual_cost::TransactionReceipt;
     use blockifier::transaction::objects::TransactionExecutionInfo;
     use cairo_vm::serde::deserialize_program::ApTracking;
     use cairo_vm::types::exec_scope::ExecutionScopes;
     use num_bigint::BigInt;
     use rstest::{fixture, rstest};
-    use starknet_api::transaction::Fee;
+    use starknet_api::core::{ContractAddress, PatriciaKey};
+    use starknet_api::transaction::{EventContent, EventData, EventKey, Fee};
+    use starknet_api::{contract_address, felt, patricia_key};
     use vars::ids::{ARRAY_PTR, ELM_SIZE, EXISTS, INDEX, KEY, N_ELMS};
 
-    use crate::config::STORED_BLOCK_HASH_BUFFER;
+    use crate::config::{INPUT_PATH_ENV_VAR, STORED_BLOCK_HASH_BUFFER};
     use crate::crypto::pedersen::PedersenHash;
     use crate::execution::helper::ContractStorageMap;
     use crate::hints::execute_transactions::fill_holes_in_rc96_segment;
     use crate::hints::find_element::search_sorted_optimistic;
     use crate::hints::*;
-    use crate::starknet::starknet_storage::OsSingleStarknetStorage;
+    use crate::io::InternalTransaction;
+    use crate::starknet::business_logic::fact_state::contract_state_objects::ContractState;
+    use crate::starknet::starknet_storage::{OsSingleStarknetStorage, StorageLeaf};
+    use crate::starkware_utils::commitment_tree::base_types::Height;
+    use crate::starkware_utils::commitment_tree::binary_fact_tree::BinaryFactTree;
+    use crate::starkware_utils::commitment_tree::patricia_tree::patricia_tree::PatriciaTree;
     use crate::storage::dict_storage::DictStorage;
+    use crate::storage::storage::FactFetchingContext;
     use crate::utils::set_variable_in_root_exec_scope;
 
     #[allow(clippy::upper_case_acronyms)]
@@ -59,6 +72,9 @@ pub mod tests {
         (Felt252::from(block_context.block_info().block_number.0 - STORED_BLOCK_HASH_BUFFER), Felt252::from(66_u64))
     }
 
+    const STRK_FEE_TOKEN: &str = "0x57";
+    const ETH_FEE_TOKEN: &str = "0xe7";
+
     #[fixture]
     fn transaction_execution_info() -> TransactionExecutionInfo {
         TransactionExecutionInfo {
@@ -113,6 +129,192 @@ pub mod tests {
         assert_eq!(fee, 1234.into());
     }
 
+    #[rstest]
+    #[case::eth(Felt252::ONE, 1_000)]
+    #[case::strk(Felt252::THREE, 2_500_000)]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_get_actual_fee_in_wei_and_fri(
+        block_context: BlockContext,
+        transaction_execution_info: TransactionExecutionInfo,
+        old_block_number_and_hash: (Felt252, Felt252),
+        #[case] tx_version: Felt252,
+        #[case] actual_fee: u128,
+    ) {
+        let mut vm = VirtualMachine::new(false);
+        vm.set_fp(2);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        let ids_data = ids_data![vars::ids::ACTUAL_FEE_WEI, vars::ids::ACTUAL_FEE_FRI];
+        let ap_tracking = ApTracking::default();
+
+        let mut transaction_execution_info = transaction_execution_info;
+        transaction_execution_info.transaction_receipt.fee = Fee(actual_fee);
+        let exec_helper = ExecutionHelperWrapper::<PCS>::new(
+            ContractStorageMap::default(),
+            vec![transaction_execution_info],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        );
+        exec_helper.start_tx(None).await;
+
+        // 1 ETH = 2500 STRK
+        let mut os_input = os_input_with_transactions(vec![]);
+        os_input.general_config.eth_price_in_fri = Some(2_500_000_000_000_000_000_000);
+
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::scopes::OS_INPUT, Rc::new(os_input));
+        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, exec_helper);
+        exec_scopes
+            .insert_value(vars::scopes::TX, InternalTransaction { version: Some(tx_version), ..Default::default() });
+
+        get_actual_fee_in_wei_and_fri::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &Default::default())
+            .expect("get_actual_fee_in_wei_and_fri() failed");
+
+        let fee_wei = get_integer_from_var_name(vars::ids::ACTUAL_FEE_WEI, &vm, &ids_data, &ap_tracking).unwrap();
+        let fee_fri = get_integer_from_var_name(vars::ids::ACTUAL_FEE_FRI, &vm, &ids_data, &ap_tracking).unwrap();
+        assert_eq!(fee_wei, Felt252::from(1_000));
+        assert_eq!(fee_fri, Felt252::from(2_500_000));
+    }
+
+    #[rstest]
+    #[case::sufficient_balance(Some((2_000, 0)), None)]
+    #[case::exact_balance(Some((1_234, 0)), None)]
+    #[case::balance_in_high_word(Some((0, 1)), None)]
+    #[case::insufficient_balance(Some((1_000, 0)), Some("cannot pay the actual fee 1234 of tx 0x5: balance is 1000"))]
+    #[case::balance_not_served(None, Some("The fee token balance of 0x200 was not served to the run"))]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_assert_sender_can_pay_fee(
+        block_context: BlockContext,
+        transaction_execution_info: TransactionExecutionInfo,
+        old_block_number_and_hash: (Felt252, Felt252),
+        #[case] balance: Option<(u128, u128)>,
+        #[case] expected_error: Option<&str>,
+    ) {
+        let sender_address = Felt252::from(0x200);
+        let os_input = os_input_with_transactions(vec![]);
+        let fee_token_address = *os_input.general_config.fee_token_address_for_version(Felt252::ONE).0.key();
+
+        let mut ffc = FactFetchingContext::<_, PedersenHash>::new(DictStorage::default());
+        let tree = PatriciaTree::empty_tree(&mut ffc, Height(251), StorageLeaf::empty()).await.unwrap();
+        let storage = OsSingleStarknetStorage::new(tree.clone(), tree, &[], ffc).await.unwrap();
+        // The storage of the fee token is only part of the run if the balance is served
+        let storage_by_address = match balance {
+            Some(_) => ContractStorageMap::from([(fee_token_address, storage)]),
+            None => ContractStorageMap::default(),
+        };
+        let mut exec_helper = EHW::new(
+            storage_by_address,
+            vec![transaction_execution_info],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        );
+        exec_helper.start_tx(None).await;
+        if let Some((low, high)) = balance {
+            let balance_key = *get_fee_token_var_address(ContractAddress::try_from(sender_address).unwrap()).0.key();
+            exec_helper.write_storage_for_address(fee_token_address, balance_key, low.into()).await.unwrap();
+            exec_helper
+                .write_storage_for_address(fee_token_address, balance_key + Felt252::ONE, high.into())
+                .await
+                .unwrap();
+        }
+
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::scopes::OS_INPUT, Rc::new(os_input));
+        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, exec_helper);
+        exec_scopes.insert_value(
+            vars::scopes::TX,
+            InternalTransaction {
+                hash_value: Felt252::from(5),
+                version: Some(Felt252::ONE),
+                sender_address: Some(sender_address),
+                ..Default::default()
+            },
+        );
+
+        let result = assert_sender_can_pay_fee::<PCS>(
+            &mut VirtualMachine::new(false),
+            &mut exec_scopes,
+            &HashMap::new(),
+            &ApTracking::new(),
+            &HashMap::new(),
+        );
+
+        match expected_error {
+            None => result.unwrap(),
+            Some(expected_error) => {
+                let error = result.unwrap_err().to_string();
+                assert!(error.contains(expected_error), "Unexpected error: {error}");
+            }
+        }
+    }
+
+    #[rstest]
+    #[case::no_fee_transfer(Felt252::ONE, None, None)]
+    #[case::eth_for_v1(Felt252::ONE, Some(ETH_FEE_TOKEN), None)]
+    #[case::strk_for_v3(Felt252::THREE, Some(STRK_FEE_TOKEN), None)]
+    #[case::eth_for_v3(
+        Felt252::THREE,
+        Some(ETH_FEE_TOKEN),
+        Some("Fee transfer of tx 0x5 targets 0xe7 instead of the fee token 0x57")
+    )]
+    #[case::other_contract(
+        Felt252::ONE,
+        Some("0x123"),
+        Some("Fee transfer of tx 0x5 targets 0x123 instead of the fee token 0xe7")
+    )]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_assert_fee_transfer_token(
+        block_context: BlockContext,
+        mut transaction_execution_info: TransactionExecutionInfo,
+        old_block_number_and_hash: (Felt252, Felt252),
+        #[case] version: Felt252,
+        #[case] fee_transfer_address: Option<&str>,
+        #[case] expected_error: Option<&str>,
+    ) {
+        let mut os_input = os_input_with_transactions(vec![]);
+        os_input.general_config.starknet_os_config.fee_token_address = contract_address!(STRK_FEE_TOKEN);
+        os_input.general_config.starknet_os_config.deprecated_fee_token_address = contract_address!(ETH_FEE_TOKEN);
+
+        transaction_execution_info.fee_transfer_call_info = fee_transfer_address.map(|address| CallInfo {
+            call: CallEntryPoint { storage_address: contract_address!(address), ..Default::default() },
+            ..Default::default()
+        });
+        let exec_helper = EHW::new(
+            ContractStorageMap::default(),
+            vec![transaction_execution_info],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        );
+        exec_helper.start_tx(None).await;
+
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::scopes::OS_INPUT, Rc::new(os_input));
+        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, exec_helper);
+        exec_scopes.insert_value(
+            vars::scopes::TX,
+            InternalTransaction { hash_value: Felt252::from(5), version: Some(version), ..Default::default() },
+        );
+
+        let result = assert_fee_transfer_token::<PCS>(
+            &mut VirtualMachine::new(false),
+            &mut exec_scopes,
+            &HashMap::new(),
+            &ApTracking::new(),
+            &HashMap::new(),
+        );
+
+        match expected_error {
+            None => result.unwrap(),
+            Some(expected_error) => {
+                let error = result.unwrap_err().to_string();
+                assert!(error.contains(expected_error), "Unexpected error: {error}");
+            }
+        }
+    }
+
     #[test]
     fn test_is_on_curve() {
         let mut vm = VirtualMachine::new(false);
@@ -257,6 +459,603 @@ pub mod tests {
         assert!(exec_helper_box.execution_helper.read().await.call_iter.clone().peekable().peek().is_none());
     }
 
+    fn os_input_with_transactions(transactions: Vec<InternalTransaction>) -> StarknetOsInput {
+        StarknetOsInput { transactions, ..Default::default() }
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_initialize_state_changes_seeds_initial_storage(
+        block_context: BlockContext,
+        old_block_number_and_hash: (Felt252, Felt252),
+    ) {
+        let contract_address = Felt252::from(0x300);
+        let initial_storage =
+            HashMap::from([(Felt252::from(1), Felt252::from(100)), (Felt252::from(2), Felt252::from(200))]);
+
+        let mut ffc = FactFetchingContext::<_, PedersenHash>::new(DictStorage::default());
+        let tree = PatriciaTree::empty_tree(&mut ffc, Height(251), StorageLeaf::empty()).await.unwrap();
+        let storage = OsSingleStarknetStorage::new(tree.clone(), tree.clone(), &[], ffc).await.unwrap();
+        let exec_helper = EHW::new(
+            ContractStorageMap::from([(contract_address, storage)]),
+            vec![],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        );
+
+        let mut contract_state = ContractState::create(vec![0; 32], tree, Felt252::ZERO);
+        contract_state.storage = initial_storage.clone();
+        let mut os_input = os_input_with_transactions(vec![]);
+        os_input.contracts = HashMap::from([(contract_address, contract_state)]);
+
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::scopes::OS_INPUT, Rc::new(os_input));
+        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, exec_helper.clone());
+        initialize_state_changes::<PCS>(
+            &mut VirtualMachine::new(false),
+            &mut exec_scopes,
+            &HashMap::new(),
+            &ApTracking::new(),
+            &HashMap::new(),
+        )
+        .unwrap();
+
+        let mut exec_helper = exec_helper;
+        for (key, value) in initial_storage {
+            assert_eq!(exec_helper.read_storage_for_address(contract_address, key).await.unwrap(), value);
+        }
+    }
+
+    fn call_info_with_events(
+        contract_address: ContractAddress,
+        events: Vec<(usize, Vec<u64>, Vec<u64>)>,
+        inner_calls: Vec<CallInfo>,
+    ) -> CallInfo {
+        let events = events
+            .into_iter()
+            .map(|(order, keys, data)| OrderedEvent {
+                order,
+                event: EventContent {
+                    keys: keys.into_iter().map(|key| EventKey(Felt252::from(key))).collect(),
+                    data: EventData(data.into_iter().map(Felt252::from).collect()),
+                },
+            })
+            .collect();
+
+        CallInfo {
+            call: CallEntryPoint { storage_address: contract_address, ..Default::default() },
+            execution: CallExecution { events, ..Default::default() },
+            inner_calls,
+            ..Default::default()
+        }
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_compute_events_commitment(
+        block_context: BlockContext,
+        transaction_execution_info: TransactionExecutionInfo,
+        old_block_number_and_hash: (Felt252, Felt252),
+    ) {
+        let mut vm = VirtualMachine::new(false);
+        vm.set_fp(1);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+
+        let ids_data = ids_data![vars::ids::EVENTS_COMMITMENT];
+        let ap_tracking = ApTracking::default();
+
+        // The first tx emits one event and calls another contract that emits a second one,
+        // the second tx emits a single event.
+        let mut first_tx_execution_info = transaction_execution_info.clone();
+        first_tx_execution_info.execute_call_info = Some(call_info_with_events(
+            contract_address!("0x100"),
+            vec![(0, vec![0xaa], vec![1, 2, 3])],
+            vec![call_info_with_events(contract_address!("0x200"), vec![(1, vec![0xbb, 0xcc], vec![])], vec![])],
+        ));
+        let mut second_tx_execution_info = transaction_execution_info;
+        second_tx_execution_info.execute_call_info =
+            Some(call_info_with_events(contract_address!("0x100"), vec![(0, vec![], vec![42])], vec![]));
+
+        let transactions = vec![
+            InternalTransaction { hash_value: Felt252::from(0x1111), ..Default::default() },
+            InternalTransaction { hash_value: Felt252::from(0x2222), ..Default::default() },
+        ];
+
+        let exec_helper = EHW::new(
+            ContractStorageMap::default(),
+            vec![first_tx_execution_info, second_tx_execution_info],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        );
+        // Only the events matter here, so the calls of each tx are dropped instead of being replayed
+        for _ in 0..2 {
+            exec_helper.start_tx(None).await;
+            exec_helper.execution_helper.write().await.call_iter = vec![].into_iter();
+            exec_helper.end_tx().await;
+        }
+
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::scopes::OS_INPUT, Rc::new(os_input_with_transactions(transactions)));
+        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, exec_helper);
+
+        compute_events_commitment::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &Default::default())
+            .expect("compute_events_commitment failed");
+
+        let events_commitment = get_integer_from_var_name(vars::ids::EVENTS_COMMITMENT, &vm, &ids_data, &ap_tracking)
+            .expect("events_commitment should be set");
+        assert_eq!(
+            events_commitment,
+            Felt252::from_hex("0x69a5e7cabb7f71f6c829e2507525fb88d046182a0e4c2ebf0c4a040e76433f0").unwrap()
+        );
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_compute_events_commitment_requires_events_of_every_tx(
+        block_context: BlockContext,
+        transaction_execution_info: TransactionExecutionInfo,
+        old_block_number_and_hash: (Felt252, Felt252),
+    ) {
+        let mut vm = VirtualMachine::new(false);
+        vm.set_fp(1);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+
+        let ids_data = ids_data![vars::ids::EVENTS_COMMITMENT];
+        let ap_tracking = ApTracking::default();
+
+        // The block has two txs but only the first one was started
+        let transactions = vec![
+            InternalTransaction { hash_value: Felt252::from(0x1111), ..Default::default() },
+            InternalTransaction { hash_value: Felt252::from(0x2222), ..Default::default() },
+        ];
+        let exec_helper = EHW::new(
+            ContractStorageMap::default(),
+            vec![transaction_execution_info],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        );
+        exec_helper.start_tx(None).await;
+        exec_helper.execution_helper.write().await.call_iter = vec![].into_iter();
+        exec_helper.end_tx().await;
+
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::scopes::OS_INPUT, Rc::new(os_input_with_transactions(transactions)));
+        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, exec_helper);
+
+        let error =
+            compute_events_commitment::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &Default::default())
+                .unwrap_err();
+        assert!(
+            error.to_string().contains("Events were recorded for 1 txs, the block has 2"),
+            "Unexpected error: {error}"
+        );
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_compute_transactions_commitment() {
+        let mut vm = VirtualMachine::new(false);
+        vm.set_fp(1);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+
+        let ids_data = ids_data![vars::ids::TRANSACTIONS_COMMITMENT];
+        let ap_tracking = ApTracking::default();
+
+        let transactions = vec![
+            InternalTransaction {
+                hash_value: Felt252::from(0x1111),
+                signature: Some(vec![Felt252::from(5), Felt252::from(6)]),
+                ..Default::default()
+            },
+            // No signature, e.g. an L1 handler
+            InternalTransaction { hash_value: Felt252::from(0x2222), ..Default::default() },
+        ];
+
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::scopes::OS_INPUT, Rc::new(os_input_with_transactions(transactions)));
+
+        compute_transactions_commitment(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &Default::default())
+            .expect("compute_transactions_commitment failed");
+
+        let transactions_commitment =
+            get_integer_from_var_name(vars::ids::TRANSACTIONS_COMMITMENT, &vm, &ids_data, &ap_tracking)
+                .expect("transactions_commitment should be set");
+        assert_eq!(
+            transactions_commitment,
+            Felt252::from_hex("0x4ac7a9ac51ec8764174f2fd5d0337233722e22fa31c34c13b3ee8ef36d1a6ae").unwrap()
+        );
+    }
+
+    /// Writes a squashed `DictAccess` list to a new segment and returns its start.
+    fn write_dict_accesses(vm: &mut VirtualMachine, accesses: &[[MaybeRelocatable; 3]]) -> Relocatable {
+        let start = vm.add_memory_segment();
+        let values: Vec<_> = accesses.iter().flatten().cloned().collect();
+        vm.load_data(start, &values).unwrap();
+        start
+    }
+
+    /// Writes the previous and new state entries of a contract, along with the storage accesses
+    /// made in between.
+    fn write_contract_state_change(
+        vm: &mut VirtualMachine,
+        address: u64,
+        class_hashes: (u64, u64),
+        nonces: (u64, u64),
+        storage_accesses: &[(u64, u64, u64)],
+    ) -> [MaybeRelocatable; 3] {
+        let storage_accesses: Vec<_> = storage_accesses
+            .iter()
+            .map(|(key, prev_value, new_value)| {
+                [Felt252::from(*key).into(), Felt252::from(*prev_value).into(), Felt252::from(*new_value).into()]
+            })
+            .collect();
+        let storage_start = write_dict_accesses(vm, &storage_accesses);
+        let storage_end = (storage_start + storage_accesses.len() * DictAccess::cairo_size()).unwrap();
+
+        let prev_state = vm.add_memory_segment();
+        StateEntryLayout {
+            class_hash: Felt252::from(class_hashes.0),
+            storage_ptr: storage_start,
+            nonce: Felt252::from(nonces.0),
+        }
+        .to_memory(vm, prev_state)
+        .unwrap();
+        let new_state = vm.add_memory_segment();
+        StateEntryLayout {
+            class_hash: Felt252::from(class_hashes.1),
+            storage_ptr: storage_end,
+            nonce: Felt252::from(nonces.1),
+        }
+        .to_memory(vm, new_state)
+        .unwrap();
+
+        [Felt252::from(address).into(), prev_state.into(), new_state.into()]
+    }
+
+    #[test]
+    fn test_compute_state_diff_commitment() {
+        let mut vm = VirtualMachine::new(false);
+        vm.set_fp(5);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+
+        let ids_data = ids_data![
+            vars::ids::SQUASHED_CONTRACT_STATE_CHANGES_START,
+            vars::ids::N_CONTRACT_STATE_CHANGES,
+            vars::ids::SQUASHED_CONTRACT_CLASS_CHANGES_START,
+            vars::ids::N_CONTRACT_CLASS_CHANGES,
+            vars::ids::STATE_DIFF_COMMITMENT
+        ];
+        let ap_tracking = ApTracking::default();
+
+        let contract_state_changes = vec![
+            // Deployed contracts
+            write_contract_state_change(&mut vm, 0, (0, 1), (0, 0), &[]),
+            write_contract_state_change(&mut vm, 2, (0, 3), (0, 0), &[]),
+            // Storage updates, the second write to key 5 overrides the first one
+            write_contract_state_change(&mut vm, 4, (0x44, 0x44), (0, 0), &[(5, 0, 3), (7, 0, 8), (5, 3, 6)]),
+            // Key 12 is written without being modified
+            write_contract_state_change(&mut vm, 9, (0x99, 0x99), (0, 0), &[(10, 0, 11), (12, 1, 1)]),
+            write_contract_state_change(&mut vm, 17, (0x1717, 0x1717), (0, 18), &[]),
+            // Replaced class
+            write_contract_state_change(&mut vm, 19, (0x1919, 20), (0, 0), &[]),
+            // Key 1 is set back to its initial value, so the contract has no storage diff
+            write_contract_state_change(&mut vm, 21, (0x2121, 0x2121), (0, 0), &[(1, 4, 5), (1, 5, 4)]),
+        ];
+        let n_contract_state_changes = contract_state_changes.len();
+        let contract_state_changes_start = write_dict_accesses(&mut vm, &contract_state_changes);
+
+        let contract_class_changes: Vec<_> = [(12u64, 0u64, 13u64), (14, 0, 15), (30, 31, 31)]
+            .into_iter()
+            .map(|(class_hash, prev_compiled_class_hash, compiled_class_hash)| {
+                [
+                    Felt252::from(class_hash).into(),
+                    Felt252::from(prev_compiled_class_hash).into(),
+                    Felt252::from(compiled_class_hash).into(),
+                ]
+            })
+            .collect();
+        let contract_class_changes_start = write_dict_accesses(&mut vm, &contract_class_changes);
+
+        insert_value_from_var_name(
+            vars::ids::SQUASHED_CONTRACT_STATE_CHANGES_START,
+            contract_state_changes_start,
+            &mut vm,
+            &ids_data,
+            &ap_tracking,
+        )
+        .unwrap();
+        insert_value_from_var_name(
+            vars::ids::N_CONTRACT_STATE_CHANGES,
+            Felt252::from(n_contract_state_changes),
+            &mut vm,
+            &ids_data,
+            &ap_tracking,
+        )
+        .unwrap();
+        insert_value_from_var_name(
+            vars::ids::SQUASHED_CONTRACT_CLASS_CHANGES_START,
+            contract_class_changes_start,
+            &mut vm,
+            &ids_data,
+            &ap_tracking,
+        )
+        .unwrap();
+        insert_value_from_var_name(
+            vars::ids::N_CONTRACT_CLASS_CHANGES,
+            Felt252::from(contract_class_changes.len()),
+            &mut vm,
+            &ids_data,
+            &ap_tracking,
+        )
+        .unwrap();
+
+        let transactions = vec![
+            InternalTransaction {
+                r#type: "DECLARE".to_string(),
+                version: Some(Felt252::ONE),
+                class_hash: Some(Felt252::from(16)),
+                ..Default::default()
+            },
+            // Cairo 1 declare, already part of the class changes
+            InternalTransaction {
+                r#type: "DECLARE".to_string(),
+                version: Some(Felt252::TWO),
+                class_hash: Some(Felt252::from(12)),
+                ..Default::default()
+            },
+            InternalTransaction { r#type: "INVOKE_FUNCTION".to_string(), ..Default::default() },
+        ];
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::scopes::OS_INPUT, Rc::new(os_input_with_transactions(transactions)));
+
+        compute_state_diff_commitment(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &Default::default())
+            .expect("compute_state_diff_commitment failed");
+
+        // Same diff as the state diff hash regression test of starknet_api
+        let state_diff_commitment =
+            get_integer_from_var_name(vars::ids::STATE_DIFF_COMMITMENT, &vm, &ids_data, &ap_tracking)
+                .expect("state_diff_commitment should be set");
+        assert_eq!(
+            state_diff_commitment,
+            Felt252::from_hex("0x281f5966e49ad7dad9323826d53d1d27c0c4e6ebe5525e2e2fbca549bfa0a67").unwrap()
+        );
+    }
+
+    fn run_starknet_os_input_hint(exec_scopes: &mut ExecutionScopes) -> (VirtualMachine, Result<(), HintError>) {
+        let mut vm = VirtualMachine::new(false);
+        vm.set_fp(1);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        let initial_carried_outputs = vm.add_memory_segment();
+        vm.insert_value(Relocatable::from((1, 0)), initial_carried_outputs).unwrap();
+
+        let ids_data = ids_data![vars::ids::INITIAL_CARRIED_OUTPUTS];
+        let ap_tracking = ApTracking::default();
+
+        let result = starknet_os_input(&mut vm, exec_scopes, &ids_data, &ap_tracking, &Default::default());
+        (vm, result)
+    }
+
+    #[test]
+    fn test_starknet_os_input_uses_pre_populated_input() {
+        let mut exec_scopes = ExecutionScopes::new();
+        let transactions = vec![InternalTransaction { hash_value: felt!(1u64), ..Default::default() }];
+        exec_scopes.insert_value(vars::scopes::OS_INPUT, Rc::new(os_input_with_transactions(transactions)));
+
+        let (vm, result) = run_starknet_os_input_hint(&mut exec_scopes);
+        result.expect("starknet_os_input() failed");
+
+        // Both carried outputs point to fresh temporary segments
+        for offset in 0..2 {
+            let ptr = vm.get_relocatable(Relocatable::from((2, offset))).unwrap();
+            assert!(ptr.segment_index < 0);
+        }
+
+        // The input set by the caller is left untouched
+        let os_input = exec_scopes.get::<Rc<StarknetOsInput>>(vars::scopes::OS_INPUT).unwrap();
+        assert_eq!(os_input.transactions.len(), 1);
+        assert_eq!(os_input.transactions[0].hash_value, felt!(1u64));
+    }
+
+    #[test]
+    fn test_override_starknet_os_input() {
+        let transactions = vec![InternalTransaction { hash_value: felt!(3u64), ..Default::default() }];
+        let prebuilt_input = Rc::new(os_input_with_transactions(transactions));
+
+        let mut hint_processor = SnosHintProcessor::<PCS>::default();
+        let injected_input = prebuilt_input.clone();
+        hint_processor.override_hint(
+            STARKNET_OS_INPUT,
+            Box::new(move |_, exec_scopes, _, _, _| {
+                exec_scopes.insert_value(vars::scopes::OS_INPUT, injected_input.clone());
+                Ok(())
+            }),
+        );
+
+        // Neither an input nor the initial carried outputs are required by the stub
+        let mut vm = VirtualMachine::new(false);
+        let mut exec_scopes = ExecutionScopes::new();
+        let hint_data: Box<dyn Any> =
+            Box::new(HintProcessorData::new_default(STARKNET_OS_INPUT.to_string(), HashMap::new()));
+        hint_processor
+            .execute_hint_extensive(&mut vm, &mut exec_scopes, &hint_data, &HashMap::new())
+            .expect("the overridden hint failed");
+
+        let os_input = exec_scopes.get::<Rc<StarknetOsInput>>(vars::scopes::OS_INPUT).unwrap();
+        assert!(Rc::ptr_eq(&os_input, &prebuilt_input));
+        assert_eq!(vm.segments.num_segments(), 0);
+    }
+
+    #[test]
+    fn test_stub_hint() {
+        let mut hint_processor = SnosHintProcessor::<PCS>::default();
+        hint_processor.stub_hint(STARKNET_OS_INPUT);
+
+        let mut vm = VirtualMachine::new(false);
+        let mut exec_scopes = ExecutionScopes::new();
+        let hint_data: Box<dyn Any> =
+            Box::new(HintProcessorData::new_default(STARKNET_OS_INPUT.to_string(), HashMap::new()));
+        hint_processor
+            .execute_hint_extensive(&mut vm, &mut exec_scopes, &hint_data, &HashMap::new())
+            .expect("the stubbed hint failed");
+
+        assert!(exec_scopes.get_ref::<Rc<StarknetOsInput>>(vars::scopes::OS_INPUT).is_err());
+    }
+
+    fn run_panicking_hint(catch_hint_panics: bool) -> Result<HintExtension, HintError> {
+        let mut hint_processor = SnosHintProcessor::<PCS>::default();
+        hint_processor.override_hint(STARKNET_OS_INPUT, Box::new(|_, _, _, _, _| panic!("deliberate panic")));
+        hint_processor.set_catch_hint_panics(catch_hint_panics);
+
+        let mut vm = VirtualMachine::new(false);
+        let mut exec_scopes = ExecutionScopes::new();
+        let hint_data: Box<dyn Any> =
+            Box::new(HintProcessorData::new_default(STARKNET_OS_INPUT.to_string(), HashMap::new()));
+        hint_processor.execute_hint_extensive(&mut vm, &mut exec_scopes, &hint_data, &HashMap::new())
+    }
+
+    #[test]
+    fn test_catch_hint_panics() {
+        let error = run_panicking_hint(true).unwrap_err();
+
+        assert!(matches!(error, HintError::CustomHint(_)));
+        let message = error.to_string();
+        assert!(message.contains("deliberate panic"), "Unexpected error: {message}");
+        assert!(message.contains(STARKNET_OS_INPUT.lines().next().unwrap()), "Unexpected error: {message}");
+    }
+
+    #[test]
+    #[should_panic(expected = "deliberate panic")]
+    fn test_hint_panics_propagate_by_default() {
+        let _ = run_panicking_hint(false);
+    }
+
+    /// Serializes the tests that depend on the input path environment variable.
+    static INPUT_PATH_ENV_VAR_LOCK: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn test_starknet_os_input_requires_input() {
+        let _guard = INPUT_PATH_ENV_VAR_LOCK.lock().unwrap();
+        std::env::remove_var(INPUT_PATH_ENV_VAR);
+        let mut exec_scopes = ExecutionScopes::new();
+
+        let (_vm, result) = run_starknet_os_input_hint(&mut exec_scopes);
+        assert!(matches!(result, Err(HintError::CustomHint(_))));
+    }
+
+    #[test]
+    fn test_get_typed() {
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::scopes::COMPILED_CLASS_HASH, Felt252::from(0x1234));
+
+        assert_eq!(get_typed::<Felt252>(&exec_scopes, vars::scopes::COMPILED_CLASS_HASH).unwrap(), felt!(0x1234u64));
+        assert!(matches!(
+            get_typed::<Felt252>(&exec_scopes, vars::scopes::OS_INPUT),
+            Err(HintError::VariableNotInScopeError(_))
+        ));
+    }
+
+    #[test]
+    fn test_get_typed_wrong_type() {
+        let mut exec_scopes = ExecutionScopes::new();
+        // Stored as a u64 instead of a felt
+        exec_scopes.insert_value(vars::scopes::COMPILED_CLASS_HASH, 0x1234u64);
+
+        let error = get_typed::<Felt252>(&exec_scopes, vars::scopes::COMPILED_CLASS_HASH).unwrap_err();
+        let HintError::CustomHint(message) = error else {
+            panic!("Unexpected error: {error:?}");
+        };
+        assert!(message.contains(vars::scopes::COMPILED_CLASS_HASH), "{message}");
+        assert!(message.contains(std::any::type_name::<Felt252>()), "{message}");
+    }
+
+    #[test]
+    fn test_starknet_os_input_loads_input_path_from_env_var() {
+        let _guard = INPUT_PATH_ENV_VAR_LOCK.lock().unwrap();
+
+        let input_path = std::env::temp_dir().join(format!("os_input_{}.json", uuid::Uuid::new_v4()));
+        let transactions = vec![InternalTransaction { hash_value: felt!(2u64), ..Default::default() }];
+        std::fs::write(&input_path, serde_json::to_vec(&os_input_with_transactions(transactions)).unwrap()).unwrap();
+
+        std::env::set_var(INPUT_PATH_ENV_VAR, &input_path);
+        let mut exec_scopes = ExecutionScopes::new();
+        let (_vm, result) = run_starknet_os_input_hint(&mut exec_scopes);
+        std::env::remove_var(INPUT_PATH_ENV_VAR);
+        std::fs::remove_file(&input_path).unwrap();
+
+        result.expect("starknet_os_input() failed");
+        let os_input = exec_scopes.get::<Rc<StarknetOsInput>>(vars::scopes::OS_INPUT).unwrap();
+        assert_eq!(os_input.transactions.len(), 1);
+        assert_eq!(os_input.transactions[0].hash_value, felt!(2u64));
+    }
+
+    #[rstest]
+    #[case::under_the_limit(1501, true)]
+    #[case::at_the_limit(1500, true)]
+    #[case::over_the_limit(1499, false)]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_assert_block_gas_within_limit(
+        block_context: BlockContext,
+        transaction_execution_info: TransactionExecutionInfo,
+        old_block_number_and_hash: (Felt252, Felt252),
+        #[case] block_max_gas: u64,
+        #[case] expect_success: bool,
+    ) {
+        let mut vm = VirtualMachine::new(false);
+        let ids_data = Default::default();
+        let ap_tracking = ApTracking::default();
+
+        let tx_execution_infos: Vec<_> = [1000, 500]
+            .into_iter()
+            .map(|gas_consumed| TransactionExecutionInfo {
+                execute_call_info: Some(CallInfo {
+                    execution: CallExecution { gas_consumed, ..Default::default() },
+                    ..Default::default()
+                }),
+                ..transaction_execution_info.clone()
+            })
+            .collect();
+        let exec_helper = EHW::new(
+            ContractStorageMap::default(),
+            tx_execution_infos,
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        );
+        // Only the gas matters here, so the calls of each tx are dropped instead of being replayed
+        for _ in 0..2 {
+            exec_helper.start_tx(None).await;
+            exec_helper.execution_helper.write().await.call_iter = vec![].into_iter();
+            exec_helper.end_tx().await;
+        }
+
+        let mut os_input = os_input_with_transactions(vec![]);
+        os_input.general_config.block_max_gas = block_max_gas;
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::scopes::OS_INPUT, Rc::new(os_input));
+        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, exec_helper);
+
+        let result = assert_block_gas_within_limit::<PCS>(
+            &mut vm,
+            &mut exec_scopes,
+            &ids_data,
+            &ap_tracking,
+            &Default::default(),
+        );
+        if expect_success {
+            result.expect("assert_block_gas_within_limit failed");
+        } else {
+            assert!(matches!(result, Err(HintError::AssertionFailed(_))));
+        }
+    }
+
     #[test]
     fn test_built_in_extensive_hints_have_no_duplicates() {
         // find all occurrences of a hint in EXTENSIVE_HINTS
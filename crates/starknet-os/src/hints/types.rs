@@ -6,6 +6,7 @@ use cairo_vm::vm::errors::hint_errors::HintError;
 use cairo_vm::Felt252;
 
 use crate::cairo_types::builtins::{HashBuiltin, SpongeHashBuiltin};
+use crate::hints::assertions::hint_assert;
 use crate::hints::vars;
 use crate::utils::get_variable_from_root_exec_scope;
 
@@ -16,6 +17,41 @@ pub struct PatriciaSkipValidationRunner {
     pub verified_addresses: HashSet<Relocatable>,
 }
 
+/// L1-to-L2 messages of the block, identified by their hash. Each message can be consumed by a
+/// single L1 handler tx.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct L1ToL2Messages {
+    pending: HashSet<[u8; 32]>,
+    consumed: HashSet<[u8; 32]>,
+}
+
+impl L1ToL2Messages {
+    pub fn new(message_hashes: impl IntoIterator<Item = [u8; 32]>) -> Self {
+        Self { pending: message_hashes.into_iter().collect(), consumed: HashSet::new() }
+    }
+
+    /// Marks a message as consumed. The message must be known and not consumed yet (see
+    /// `hint_assert!`).
+    pub fn consume(&mut self, exec_scopes: &ExecutionScopes, message_hash: [u8; 32]) -> Result<(), HintError> {
+        let was_pending = self.pending.remove(&message_hash);
+        let was_consumed = !self.consumed.insert(message_hash);
+        hint_assert!(
+            exec_scopes,
+            !was_consumed,
+            "L1-to-L2 message 0x{} was already consumed",
+            hex::encode(message_hash)
+        );
+        hint_assert!(
+            exec_scopes,
+            was_pending || was_consumed,
+            "Unknown L1-to-L2 message 0x{}",
+            hex::encode(message_hash)
+        );
+
+        Ok(())
+    }
+}
+
 /// Specifies if we are in the state or class update part of the OS.
 ///
 /// The Patricia-related hints have the same Python code but differ in the structures
@@ -1,7 +1,9 @@
 pub mod scopes {
+    pub const ASSERTION_MODE: &str = "__assertion_mode";
     pub const BLOCK_CONTEXT: &str = "block_context";
     pub const BYTECODE_SEGMENT_STRUCTURE: &str = "bytecode_segment_structure";
     pub const BYTECODE_SEGMENTS: &str = "bytecode_segments";
+    pub const CANCELLATION_FLAG: &str = "__cancellation_flag";
     pub const CASE: &str = "case";
     pub const COMMITMENT_INFO: &str = "commitment_info";
     pub const COMMITMENT_INFO_BY_ADDRESS: &str = "commitment_info_by_address";
@@ -18,7 +20,9 @@ pub mod scopes {
     pub const EXECUTION_HELPER: &str = "execution_helper";
     pub const FIND_ELEMENT_MAX_SIZE: &str = "__find_element_max_size";
     pub const INITIAL_DICT: &str = "initial_dict";
+    pub const INPUT_PATH: &str = "input_path";
     pub const IS_DEPRECATED: &str = "is_deprecated";
+    pub const L1_TO_L2_MESSAGES: &str = "l1_to_l2_messages";
     pub const N_SELECTED_BUILTINS: &str = "n_selected_builtins";
     pub const NODE: &str = "node";
     pub const LEFT_CHILD: &str = "left_child";
@@ -35,15 +39,20 @@ pub mod scopes {
 }
 
 pub mod ids {
+    pub const ACTUAL_FEE_FRI: &str = "actual_fee_fri";
+    pub const ACTUAL_FEE_WEI: &str = "actual_fee_wei";
     pub const ADDITIONAL_DATA: &str = "additional_data";
     pub const ALL_ENCODINGS: &str = "all_encodings";
     pub const ARRAY_PTR: &str = "array_ptr";
     pub const BIT: &str = "bit";
+    pub const BITWISE_PTR: &str = "bitwise_ptr";
     pub const BLOB_LENGTH: &str = "starkware.starknet.core.os.data_availability.commitment.BLOB_LENGTH";
+    pub const BUILTIN_LIST: &str = "builtin_list";
     pub const BUILTIN_PARAMS: &str = "builtin_params";
     pub const BUILTIN_PTRS: &str = "builtin_ptrs";
     pub const CALL_RESPONSE: &str = "call_response";
     pub const CALLDATA: &str = "calldata";
+    pub const CALLDATA_SIZE: &str = "calldata_size";
     pub const CHILD_BIT: &str = "child_bit";
     pub const CLASS_HASH: &str = "class_hash";
     pub const CLASS_HASH_PTR: &str = "class_hash_ptr";
@@ -63,17 +72,22 @@ pub mod ids {
     pub const DA_SIZE: &str = "da_size";
     pub const DA_START: &str = "da_start";
     pub const DATA_TO_HASH: &str = "data_to_hash";
+    pub const DEPRECATED_BLOCK_INFO: &str = "deprecated_block_info";
     pub const DEPRECATED_TX_INFO: &str = "deprecated_tx_info";
     pub const DESCEND: &str = "descend";
     pub const DEST_PTR: &str = "dest_ptr";
+    pub const EC_OP_PTR: &str = "ec_op_ptr";
     pub const EDGE: &str = "edge";
     pub const ELEMENTS: &str = "elements";
     pub const ELEMENTS_END: &str = "elements_end";
     pub const ELM_SIZE: &str = "elm_size";
+    pub const ENTRY_POINT_OFFSET: &str = "entry_point_offset";
     pub const ENTRY_POINT_RETURN_VALUES: &str = "entry_point_return_values";
+    pub const ENTRY_POINT_TYPE: &str = "entry_point_type";
     pub const EXECUTION_CONTEXT: &str = "execution_context";
     pub const EXISTS: &str = "exists";
     pub const EVALS: &str = "evals";
+    pub const EVENTS_COMMITMENT: &str = "events_commitment";
     pub const FINAL_CONTRACT_STATE_ROOT: &str = "final_contract_state_root";
     pub const FINAL_ROOT: &str = "final_root";
     pub const HASH_PTR: &str = "hash_ptr";
@@ -89,24 +103,37 @@ pub mod ids {
     pub const INITIAL_ROOT: &str = "initial_root";
     pub const IS_ON_CURVE: &str = "is_on_curve";
     pub const USE_KZG_DA: &str = "use_kzg_da";
+    pub const KECCAK_PTR: &str = "keccak_ptr";
     pub const KZG_COMMITMENT: &str = "kzg_commitment";
     pub const KEY: &str = "key";
     pub const KZG_COMMITMENTS: &str = "kzg_commitments";
+    pub const L1_DATA_GAS_PRICE_FRI: &str = "l1_data_gas_price_fri";
+    pub const L1_DATA_GAS_PRICE_WEI: &str = "l1_data_gas_price_wei";
+    pub const L1_GAS_PRICE_FRI: &str = "l1_gas_price_fri";
+    pub const L1_GAS_PRICE_WEI: &str = "l1_gas_price_wei";
     pub const LENGTH: &str = "length";
     pub const LOW: &str = "low";
     pub const MAX_FEE: &str = "max_fee";
+    pub const MESSAGE_HASH: &str = "message_hash";
     pub const N: &str = "n";
     pub const N_BLOBS: &str = "n_blobs";
+    pub const N_BLOCKS: &str = "n_blocks";
     pub const N_BUILTINS: &str = "n_builtins";
+    pub const N_CONTRACT_CLASS_CHANGES: &str = "n_contract_class_changes";
+    pub const N_CONTRACT_STATE_CHANGES: &str = "n_contract_state_changes";
     pub const N_ELMS: &str = "n_elms";
+    pub const N_EVENTS: &str = "n_events";
     pub const N_COMPILED_CLASS_FACTS: &str = "n_compiled_class_facts";
+    pub const N_INSTANCES: &str = "n_instances";
     pub const N_SELECTED_BUILTINS: &str = "n_selected_builtins";
     pub const N_TXS: &str = "n_txs";
     pub const N_UPDATES: &str = "n_updates";
+    pub const NEW_CLASSES_ROOT: &str = "new_classes_root";
     pub const NEW_LENGTH: &str = "new_length";
     pub const NEW_ROOT: &str = "new_root";
     pub const NEW_STATE_ENTRY: &str = "new_state_entry";
     pub const NODE: &str = "node";
+    pub const NONCE: &str = "nonce";
     pub const NOT_ON_CURVE: &str = "not_on_curve";
     pub const OLD_BLOCK_HASH: &str = "old_block_hash";
     pub const OLD_BLOCK_NUMBER: &str = "old_block_number";
@@ -126,6 +153,7 @@ pub mod ids {
     pub const RETDATA_SIZE: &str = "retdata_size";
     pub const RETURN_BUILTIN_PTRS: &str = "return_builtin_ptrs";
     pub const SECP_P: &str = "SECP_P";
+    pub const SEGMENT_ARENA: &str = "segment_arena";
     pub const SEGMENT_LENGTH: &str = "segment_length";
     pub const SELECT_BUILTIN: &str = "select_builtin";
     pub const SELECTED_ENCODINGS: &str = "selected_encodings";
@@ -137,13 +165,18 @@ pub mod ids {
     pub const SIBLINGS: &str = "siblings";
     pub const SIGNATURE_LEN: &str = "signature_len";
     pub const SIGNATURE_START: &str = "signature_start";
+    pub const SQUASHED_CONTRACT_CLASS_CHANGES_START: &str = "squashed_contract_class_changes_start";
+    pub const SQUASHED_CONTRACT_STATE_CHANGES_START: &str = "squashed_contract_state_changes_start";
     pub const SRC_PTR: &str = "src_ptr";
+    pub const STATE_DIFF_COMMITMENT: &str = "state_diff_commitment";
     pub const STATE_ENTRY: &str = "state_entry";
     pub const STATE_UPDATES_START: &str = "state_updates_start";
     pub const STATE_UPDATES_END: &str = "state_updates_end";
     pub const SYSCALL_PTR: &str = "syscall_ptr";
     pub const TRANSACTION_HASH: &str = "transaction_hash";
+    pub const TRANSACTIONS_COMMITMENT: &str = "transactions_commitment";
     pub const TX_EXECUTION_CONTEXT: &str = "tx_execution_context";
+    pub const TX_INDEX: &str = "tx_index";
     pub const TX_INFO: &str = "tx_info";
     pub const TX_TYPE: &str = "tx_type";
     pub const TX_VERSION: &str = "tx_version";
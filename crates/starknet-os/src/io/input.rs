@@ -1,20 +1,33 @@
 use std::collections::HashMap;
-use std::io::Write;
-use std::{fs, path};
+use std::io::{BufReader, Read, Write};
+use std::{fmt, fs, path};
 
 use cairo_vm::Felt252;
-use serde::{Deserialize, Serialize};
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_with::serde_as;
 use starknet_os_types::casm_contract_class::GenericCasmContractClass;
+use starknet_os_types::class_hash_utils::ContractClassComponentHashes;
 use starknet_os_types::deprecated_compiled_class::GenericDeprecatedCompiledClass;
+use starknet_os_types::sierra_contract_class::StarknetCoreSierraContractClass;
 
 use super::InternalTransaction;
 use crate::config::StarknetGeneralConfig;
+use crate::crypto::poseidon::poseidon_hash_many_bytes;
 use crate::error::SnOsError;
 use crate::starknet::business_logic::fact_state::contract_state_objects::ContractState;
 use crate::starknet::starknet_storage::CommitmentInfo;
 use crate::utils::Felt252HexNoPrefix;
 
+/// Version of the shape of the serialized OS input. Bump it and add a migration step to
+/// `StarknetOsInput::migrate` whenever a change breaks previously serialized inputs.
+pub const OS_INPUT_SCHEMA_VERSION: u32 = 2;
+
+/// Inputs serialized before `schema_version` was introduced follow the v1 schema.
+const fn default_schema_version() -> u32 {
+    1
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct StarknetOsInput {
     pub contract_state_commitment_info: CommitmentInfo,
@@ -22,6 +35,7 @@ pub struct StarknetOsInput {
     pub deprecated_compiled_classes: HashMap<Felt252, GenericDeprecatedCompiledClass>,
     pub compiled_classes: HashMap<Felt252, GenericCasmContractClass>,
     pub compiled_class_visited_pcs: HashMap<Felt252, Vec<Felt252>>,
+    #[serde(deserialize_with = "deserialize_contracts")]
     pub contracts: HashMap<Felt252, ContractState>,
     pub contract_address_to_class_hash: HashMap<Felt252, Felt252>,
     pub class_hash_to_compiled_class_hash: HashMap<Felt252, Felt252>,
@@ -31,22 +45,353 @@ pub struct StarknetOsInput {
     pub declared_class_hash_to_component_hashes: HashMap<Felt252, Vec<Felt252>>,
     pub new_block_hash: Felt252,
     pub prev_block_hash: Felt252,
+    /// Hashes of past blocks, by block number, served to `get_block_hash` requests.
+    #[serde(default)]
+    pub block_hashes: HashMap<u64, Felt252>,
     pub full_output: bool,
+    #[serde(default = "default_schema_version")]
+    pub schema_version: u32,
+}
+
+impl Default for StarknetOsInput {
+    /// An input without any tx, class or contract, at the current schema version.
+    fn default() -> Self {
+        Self {
+            contract_state_commitment_info: Default::default(),
+            contract_class_commitment_info: Default::default(),
+            deprecated_compiled_classes: Default::default(),
+            compiled_classes: Default::default(),
+            compiled_class_visited_pcs: Default::default(),
+            contracts: Default::default(),
+            contract_address_to_class_hash: Default::default(),
+            class_hash_to_compiled_class_hash: Default::default(),
+            general_config: Default::default(),
+            transactions: Default::default(),
+            declared_class_hash_to_component_hashes: Default::default(),
+            new_block_hash: Default::default(),
+            prev_block_hash: Default::default(),
+            block_hashes: Default::default(),
+            full_output: false,
+            schema_version: OS_INPUT_SCHEMA_VERSION,
+        }
+    }
+}
+
+/// Deserializes the contracts of the input, given either as a map or as a list of
+/// (address, contract state) pairs. Duplicate addresses are rejected instead of silently keeping
+/// one of the states.
+fn deserialize_contracts<'de, D>(deserializer: D) -> Result<HashMap<Felt252, ContractState>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct ContractsVisitor;
+
+    impl ContractsVisitor {
+        fn insert<E: de::Error>(
+            contracts: &mut HashMap<Felt252, ContractState>,
+            address: Felt252,
+            contract_state: ContractState,
+        ) -> Result<(), E> {
+            if contracts.insert(address, contract_state).is_some() {
+                return Err(E::custom(format!("Duplicate contract address {address:#x}")));
+            }
+            Ok(())
+        }
+    }
+
+    impl<'de> Visitor<'de> for ContractsVisitor {
+        type Value = HashMap<Felt252, ContractState>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a map or a list of (address, contract state) pairs")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut contracts = HashMap::with_capacity(map.size_hint().unwrap_or_default());
+            while let Some((address, contract_state)) = map.next_entry()? {
+                Self::insert(&mut contracts, address, contract_state)?;
+            }
+            Ok(contracts)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut contracts = HashMap::with_capacity(seq.size_hint().unwrap_or_default());
+            while let Some((address, contract_state)) = seq.next_element()? {
+                Self::insert(&mut contracts, address, contract_state)?;
+            }
+            Ok(contracts)
+        }
+    }
+
+    deserializer.deserialize_any(ContractsVisitor)
 }
 
 impl StarknetOsInput {
+    /// Loads the OS input from a file. Files with the `.bin` extension are read as MessagePack,
+    /// anything else as JSON. JSON inputs of an older schema version are migrated.
     pub fn load(path: &path::Path) -> Result<Self, SnOsError> {
+        if is_binary_input(path) {
+            let raw_input = fs::read(path)?;
+            return check_schema_version(rmp_serde::from_slice(&raw_input)?);
+        }
+
         let raw_input = fs::read_to_string(path)?;
-        let input = serde_json::from_str(&raw_input)?;
+        let value: serde_json::Value = deserialize_json(serde_json::Deserializer::from_str(&raw_input))?;
+        let schema_version = match value.get("schema_version") {
+            Some(schema_version) => serde_json::from_value(schema_version.clone())?,
+            None => default_schema_version(),
+        };
+
+        if schema_version < OS_INPUT_SCHEMA_VERSION {
+            Self::migrate(value, schema_version)
+        } else {
+            check_schema_version(serde_json::from_value(value)?)
+        }
+    }
+
+    /// Loads the OS input from a file without reading the whole file in memory first.
+    /// Prefer this over `load` for very large inputs. Inputs of an older schema version are
+    /// loaded with `load` instead, as they must be migrated.
+    pub fn load_streaming(path: &path::Path) -> Result<Self, SnOsError> {
+        // Inputs of another schema version may not deserialize as the current one
+        let schema_version = read_schema_version(path)?;
+        if schema_version < OS_INPUT_SCHEMA_VERSION {
+            return Self::load(path);
+        }
+        if schema_version > OS_INPUT_SCHEMA_VERSION {
+            return Err(SnOsError::CatchAll(format!("Unsupported OS input schema version: {schema_version}")));
+        }
 
-        Ok(input)
+        let file = fs::File::open(path)?;
+        if is_binary_input(path) {
+            Ok(rmp_serde::from_read(BufReader::new(file))?)
+        } else {
+            deserialize_json(serde_json::Deserializer::from_reader(BufReader::new(file)))
+        }
     }
 
+    /// Deserializes the OS input directly from a reader. Inputs of an older schema version are
+    /// rejected, as migrating them requires the whole input in memory (see `load`).
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, SnOsError> {
+        let input: Self = deserialize_json(serde_json::Deserializer::from_reader(reader))?;
+        if input.schema_version < OS_INPUT_SCHEMA_VERSION {
+            return Err(SnOsError::CatchAll(format!(
+                "OS input schema version {} must be migrated, load the input with `StarknetOsInput::load`",
+                input.schema_version
+            )));
+        }
+        check_schema_version(input)
+    }
+
+    /// Upgrades a serialized input from an older schema version to the current one, then
+    /// deserializes it.
+    pub fn migrate(mut value: serde_json::Value, from_version: u32) -> Result<Self, SnOsError> {
+        if from_version > OS_INPUT_SCHEMA_VERSION {
+            return Err(SnOsError::CatchAll(format!("Unsupported OS input schema version: {from_version}")));
+        }
+        let input = value
+            .as_object_mut()
+            .ok_or_else(|| SnOsError::CatchAll("The OS input must be a JSON object".to_string()))?;
+
+        if from_version < 2 {
+            // v1 txs referred to their class as `contract_hash`, which is now only used by the
+            // deprecated `DEPLOY` txs
+            let transactions = input.get_mut("transactions").and_then(|transactions| transactions.as_array_mut());
+            for tx in transactions.into_iter().flatten().filter_map(|tx| tx.as_object_mut()) {
+                if tx.get("type").and_then(|tx_type| tx_type.as_str()) == Some("DEPLOY") {
+                    continue;
+                }
+                if let Some(class_hash) = tx.remove("contract_hash") {
+                    tx.insert("class_hash".to_string(), class_hash);
+                }
+            }
+        }
+        input.insert("schema_version".to_string(), OS_INPUT_SCHEMA_VERSION.into());
+
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Writes the OS input to a file, in the format selected by the file extension (see `load`).
     pub fn dump(&self, path: &path::Path) -> Result<(), SnOsError> {
-        fs::File::create(path)?.write_all(&serde_json::to_vec(&self)?)?;
+        let serialized_input =
+            if is_binary_input(path) { rmp_serde::to_vec_named(&self)? } else { serde_json::to_vec(&self)? };
+        fs::File::create(path)?.write_all(&serialized_input)?;
+
+        Ok(())
+    }
+
+    /// Converts an input file from one format to the other, e.g. from JSON to the binary format
+    /// which is much faster to load for big blocks.
+    pub fn convert(from: &path::Path, to: &path::Path) -> Result<(), SnOsError> {
+        Self::load_streaming(from)?.dump(to)
+    }
+
+    /// Checks that every class declared in the block has a compiled class hash, and that its
+    /// class hash matches the hash of its component hashes.
+    pub fn validate_class_hashes(&self) -> Result<(), SnOsError> {
+        for (class_hash, component_hashes) in &self.declared_class_hash_to_component_hashes {
+            if !self.class_hash_to_compiled_class_hash.contains_key(class_hash) {
+                return Err(SnOsError::CatchAll(format!(
+                    "Declared class {class_hash:#x} has no entry in class_hash_to_compiled_class_hash"
+                )));
+            }
+
+            let computed_class_hash = hash_class_components(component_hashes);
+            if computed_class_hash != *class_hash {
+                return Err(SnOsError::CatchAll(format!(
+                    "Class hash mismatch: input has {class_hash:#x}, component hashes give {computed_class_hash:#x}"
+                )));
+            }
+        }
 
         Ok(())
     }
+
+    /// Reports the transactions, contracts and class mappings that differ between two inputs.
+    /// Meant for debugging inputs that are expected to be identical but lead to different runs.
+    pub fn diff(&self, other: &Self) -> InputDiff {
+        let n_transactions = self.transactions.len().max(other.transactions.len());
+        let transactions = (0..n_transactions)
+            .filter_map(|index| {
+                let fields = diff_transaction_fields(self.transactions.get(index), other.transactions.get(index));
+                (!fields.is_empty()).then_some(TransactionDiff { index, fields })
+            })
+            .collect();
+
+        InputDiff {
+            transactions,
+            contracts: diff_keys(&self.contracts, &other.contracts),
+            contract_address_to_class_hash: diff_keys(
+                &self.contract_address_to_class_hash,
+                &other.contract_address_to_class_hash,
+            ),
+            class_hash_to_compiled_class_hash: diff_keys(
+                &self.class_hash_to_compiled_class_hash,
+                &other.class_hash_to_compiled_class_hash,
+            ),
+        }
+    }
+}
+
+/// Differences between two OS inputs, as returned by `StarknetOsInput::diff`. Keys present in
+/// only one of the inputs are reported as differing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputDiff {
+    pub transactions: Vec<TransactionDiff>,
+    /// Addresses of the contracts with a different state.
+    pub contracts: Vec<Felt252>,
+    pub contract_address_to_class_hash: Vec<Felt252>,
+    pub class_hash_to_compiled_class_hash: Vec<Felt252>,
+}
+
+impl InputDiff {
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+            && self.contracts.is_empty()
+            && self.contract_address_to_class_hash.is_empty()
+            && self.class_hash_to_compiled_class_hash.is_empty()
+    }
+}
+
+/// A transaction that differs between two inputs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionDiff {
+    /// Position of the transaction in the block.
+    pub index: usize,
+    /// Names of the differing fields, as serialized. Lists all the fields set in either
+    /// transaction if the transaction is missing from one of the inputs.
+    pub fields: Vec<String>,
+}
+
+fn diff_transaction_fields(tx: Option<&InternalTransaction>, other_tx: Option<&InternalTransaction>) -> Vec<String> {
+    let to_fields = |tx: Option<&InternalTransaction>| match tx {
+        Some(tx) => match serde_json::to_value(tx).expect("transactions are always serializable") {
+            serde_json::Value::Object(fields) => fields,
+            _ => unreachable!("transactions are serialized as JSON objects"),
+        },
+        None => serde_json::Map::new(),
+    };
+    let fields = to_fields(tx);
+    let other_fields = to_fields(other_tx);
+
+    let mut names: Vec<String> = fields
+        .keys()
+        .chain(other_fields.keys())
+        .filter(|name| fields.get(*name) != other_fields.get(*name))
+        .cloned()
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+fn diff_keys<V: PartialEq>(map: &HashMap<Felt252, V>, other: &HashMap<Felt252, V>) -> Vec<Felt252> {
+    let mut keys: Vec<Felt252> =
+        map.keys().chain(other.keys()).filter(|key| map.get(*key) != other.get(*key)).copied().collect();
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+/// Extension of OS input files serialized as MessagePack instead of JSON.
+pub const BINARY_INPUT_EXTENSION: &str = "bin";
+
+/// Deserializes a JSON input, which may be followed by whitespace (e.g. a final newline) but
+/// nothing else: anything after the input likely means that the file is corrupted.
+fn deserialize_json<'de, R: serde_json::de::Read<'de>, T: Deserialize<'de>>(
+    mut deserializer: serde_json::Deserializer<R>,
+) -> Result<T, SnOsError> {
+    let input = T::deserialize(&mut deserializer)?;
+    deserializer.end().map_err(|e| {
+        SnOsError::CatchAll(format!("Unexpected data after the OS input at line {}, column {}", e.line(), e.column()))
+    })?;
+
+    Ok(input)
+}
+
+/// Rejects inputs serialized with a schema version newer than this one.
+fn check_schema_version(input: StarknetOsInput) -> Result<StarknetOsInput, SnOsError> {
+    if input.schema_version > OS_INPUT_SCHEMA_VERSION {
+        return Err(SnOsError::CatchAll(format!("Unsupported OS input schema version: {}", input.schema_version)));
+    }
+    Ok(input)
+}
+
+/// Reads the schema version of an input file, skipping over the rest of the input without
+/// keeping it in memory.
+fn read_schema_version(path: &path::Path) -> Result<u32, SnOsError> {
+    #[derive(Deserialize)]
+    struct SchemaVersion {
+        #[serde(default = "default_schema_version")]
+        schema_version: u32,
+    }
+
+    let reader = BufReader::new(fs::File::open(path)?);
+    let SchemaVersion { schema_version } = if is_binary_input(path) {
+        rmp_serde::from_read(reader)?
+    } else {
+        deserialize_json(serde_json::Deserializer::from_reader(reader))?
+    };
+
+    Ok(schema_version)
+}
+
+fn is_binary_input(path: &path::Path) -> bool {
+    path.extension().is_some_and(|extension| extension == BINARY_INPUT_EXTENSION)
+}
+
+/// Computes the hash of a Sierra class from its version, entry points, ABI and Sierra program.
+pub fn compute_sierra_class_hash(contract_class: &StarknetCoreSierraContractClass) -> Felt252 {
+    let component_hashes = ContractClassComponentHashes::from(contract_class.clone());
+    hash_class_components(&component_hashes.to_vec())
+}
+
+fn hash_class_components(component_hashes: &[Felt252]) -> Felt252 {
+    let component_bytes: Vec<_> = component_hashes.iter().map(|felt| felt.to_bytes_be()).collect();
+    let component_slices: Vec<&[u8]> = component_bytes.iter().map(|bytes| bytes.as_slice()).collect();
+    let hash = poseidon_hash_many_bytes(&component_slices).expect("felts always fit in a field element");
+
+    Felt252::from(hash)
 }
 
 #[serde_as]
@@ -56,3 +401,326 @@ pub struct StorageCommitment {
     pub root: Felt252,
     pub height: usize,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use cairo_lang_starknet_classes::contract_class::ContractClass;
+    use rstest::rstest;
+    use starknet_os_types::hash::Hash;
+    use starknet_os_types::sierra_contract_class::GenericSierraContractClass;
+
+    use super::*;
+    use crate::starkware_utils::commitment_tree::base_types::Height;
+    use crate::starkware_utils::commitment_tree::patricia_tree::patricia_tree::PatriciaTree;
+
+    fn os_input() -> StarknetOsInput {
+        let transactions = vec![
+            InternalTransaction {
+                hash_value: Felt252::from(0x1111),
+                version: Some(Felt252::ONE),
+                sender_address: Some(Felt252::from(0x100)),
+                calldata: Some(vec![Felt252::ONE, Felt252::TWO]),
+                signature: Some(vec![Felt252::THREE]),
+                r#type: "INVOKE_FUNCTION".to_string(),
+                ..Default::default()
+            },
+            InternalTransaction {
+                hash_value: Felt252::from(0x2222),
+                contract_address: Some(Felt252::from(0x200)),
+                entry_point_selector: Some(Felt252::from(0x300)),
+                r#type: "L1_HANDLER".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        StarknetOsInput {
+            compiled_class_visited_pcs: HashMap::from([(Felt252::from(0x10), vec![Felt252::ONE, Felt252::TWO])]),
+            contract_address_to_class_hash: HashMap::from([(Felt252::from(0x100), Felt252::from(0x10))]),
+            class_hash_to_compiled_class_hash: HashMap::from([(Felt252::from(0x10), Felt252::from(0x20))]),
+            transactions,
+            new_block_hash: Felt252::from(0x5555),
+            prev_block_hash: Felt252::from(0x4444),
+            full_output: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_from_reader_matches_from_str() {
+        let serialized = serde_json::to_string(&os_input()).unwrap();
+
+        let from_str: StarknetOsInput = serde_json::from_str(&serialized).unwrap();
+        let from_reader = StarknetOsInput::from_reader(Cursor::new(serialized.as_bytes())).unwrap();
+
+        // `StarknetOsInput` does not implement `PartialEq`, compare the JSON representations instead
+        assert_eq!(serde_json::to_value(&from_reader).unwrap(), serde_json::to_value(&from_str).unwrap());
+        assert_eq!(from_reader.transactions, from_str.transactions);
+    }
+
+    #[rstest]
+    #[case::newline("\n")]
+    #[case::mixed_whitespace(" \r\n\t\n")]
+    fn test_trailing_whitespace_is_accepted(#[case] trailer: &str) {
+        let serialized = serde_json::to_string(&os_input()).unwrap() + trailer;
+        let path = std::env::temp_dir().join(format!("os_input_{}.json", uuid::Uuid::new_v4()));
+        fs::write(&path, &serialized).unwrap();
+
+        let loaded = StarknetOsInput::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(serde_json::to_value(loaded.unwrap()).unwrap(), serde_json::to_value(os_input()).unwrap());
+        StarknetOsInput::from_reader(Cursor::new(serialized.as_bytes())).unwrap();
+    }
+
+    #[rstest]
+    #[case::garbage("\ngarbage")]
+    #[case::second_object("{}")]
+    fn test_trailing_data_is_rejected(#[case] trailer: &str) {
+        let serialized = serde_json::to_string(&os_input()).unwrap() + trailer;
+        let path = std::env::temp_dir().join(format!("os_input_{}.json", uuid::Uuid::new_v4()));
+        fs::write(&path, &serialized).unwrap();
+
+        let loaded = StarknetOsInput::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        let from_reader = StarknetOsInput::from_reader(Cursor::new(serialized.as_bytes()));
+        for result in [loaded, from_reader] {
+            let error = result.unwrap_err().to_string();
+            assert!(error.contains("Unexpected data after the OS input"), "Unexpected error: {error}");
+        }
+    }
+
+    #[rstest]
+    #[case::json("json")]
+    #[case::binary(BINARY_INPUT_EXTENSION)]
+    fn test_dump_and_load(#[case] extension: &str) {
+        let os_input = os_input();
+        let path = std::env::temp_dir().join(format!("os_input_{}.{extension}", uuid::Uuid::new_v4()));
+
+        os_input.dump(&path).unwrap();
+        let loaded = StarknetOsInput::load(&path).unwrap();
+        let loaded_streaming = StarknetOsInput::load_streaming(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(serde_json::to_value(&loaded).unwrap(), serde_json::to_value(&os_input).unwrap());
+        assert_eq!(serde_json::to_value(&loaded_streaming).unwrap(), serde_json::to_value(&os_input).unwrap());
+    }
+
+    #[test]
+    fn test_convert_json_to_binary() {
+        let json_path = std::env::temp_dir().join(format!("os_input_{}.json", uuid::Uuid::new_v4()));
+        let binary_path = json_path.with_extension(BINARY_INPUT_EXTENSION);
+        os_input().dump(&json_path).unwrap();
+
+        StarknetOsInput::convert(&json_path, &binary_path).unwrap();
+        let from_json = StarknetOsInput::load(&json_path).unwrap();
+        let from_binary = StarknetOsInput::load(&binary_path).unwrap();
+        // The binary file must not be readable as JSON
+        let as_json = serde_json::from_slice::<serde_json::Value>(&fs::read(&binary_path).unwrap());
+        fs::remove_file(&json_path).unwrap();
+        fs::remove_file(&binary_path).unwrap();
+
+        assert!(as_json.is_err());
+        assert_eq!(serde_json::to_value(&from_binary).unwrap(), serde_json::to_value(&from_json).unwrap());
+        assert_eq!(from_binary.transactions, from_json.transactions);
+    }
+
+    #[test]
+    fn test_load_v1_input() {
+        let mut os_input = os_input();
+        os_input.transactions.extend([
+            InternalTransaction {
+                hash_value: Felt252::from(0x3333),
+                class_hash: Some(Felt252::from(0x10)),
+                r#type: "DECLARE".to_string(),
+                ..Default::default()
+            },
+            InternalTransaction {
+                hash_value: Felt252::from(0x4444),
+                contract_hash: Some(Felt252::from(0x10)),
+                r#type: "DEPLOY".to_string(),
+                ..Default::default()
+            },
+        ]);
+
+        // v1 inputs have no schema version and refer to the class of a declare as `contract_hash`
+        let mut v1_input = serde_json::to_value(&os_input).unwrap();
+        let v1_fields = v1_input.as_object_mut().unwrap();
+        v1_fields.remove("schema_version");
+        let declare_tx = v1_fields["transactions"][2].as_object_mut().unwrap();
+        let class_hash = declare_tx.remove("class_hash").unwrap();
+        declare_tx.insert("contract_hash".to_string(), class_hash);
+
+        let path = std::env::temp_dir().join(format!("os_input_{}.json", uuid::Uuid::new_v4()));
+        fs::write(&path, serde_json::to_string(&v1_input).unwrap()).unwrap();
+        let loaded = StarknetOsInput::load(&path);
+        let loaded_streaming = StarknetOsInput::load_streaming(&path);
+        fs::remove_file(&path).unwrap();
+
+        for migrated in [loaded.unwrap(), loaded_streaming.unwrap()] {
+            assert_eq!(migrated.schema_version, OS_INPUT_SCHEMA_VERSION);
+            assert_eq!(migrated.transactions, os_input.transactions);
+            assert_eq!(migrated.contract_address_to_class_hash, os_input.contract_address_to_class_hash);
+            assert_eq!(migrated.new_block_hash, os_input.new_block_hash);
+        }
+
+        let from_reader = StarknetOsInput::from_reader(Cursor::new(serde_json::to_vec(&v1_input).unwrap()));
+        assert!(matches!(from_reader, Err(SnOsError::CatchAll(_))));
+    }
+
+    #[rstest]
+    #[case::json("json")]
+    #[case::binary(BINARY_INPUT_EXTENSION)]
+    fn test_load_streaming_rejects_newer_schema_version(#[case] extension: &str) {
+        // Only the schema version is read from inputs of another version
+        let input = serde_json::json!({ "schema_version": OS_INPUT_SCHEMA_VERSION + 1 });
+        let path = std::env::temp_dir().join(format!("os_input_{}.{extension}", uuid::Uuid::new_v4()));
+        let serialized = if is_binary_input(&path) {
+            rmp_serde::to_vec_named(&input).unwrap()
+        } else {
+            serde_json::to_vec(&input).unwrap()
+        };
+        fs::write(&path, serialized).unwrap();
+
+        let loaded_streaming = StarknetOsInput::load_streaming(&path);
+        fs::remove_file(&path).unwrap();
+
+        let error = loaded_streaming.unwrap_err().to_string();
+        assert!(
+            error.contains(&format!("Unsupported OS input schema version: {}", OS_INPUT_SCHEMA_VERSION + 1)),
+            "Unexpected error: {error}"
+        );
+    }
+
+    fn contract_state(nonce: u64) -> ContractState {
+        let storage_commitment_tree = PatriciaTree { root: Hash::empty(), height: Height(251) };
+        ContractState::create(vec![1; 32], storage_commitment_tree, Felt252::from(nonce))
+    }
+
+    #[test]
+    fn test_contracts_as_list_of_pairs() {
+        let mut input = serde_json::to_value(os_input()).unwrap();
+        input["contracts"] = serde_json::json!([["0x100", contract_state(1)], ["0x200", contract_state(2)]]);
+
+        let input: StarknetOsInput = serde_json::from_value(input).unwrap();
+
+        assert_eq!(input.contracts.len(), 2);
+        assert_eq!(input.contracts[&Felt252::from(0x200)].nonce, Felt252::TWO);
+    }
+
+    #[test]
+    fn test_duplicate_contract_address() {
+        let mut input = serde_json::to_value(os_input()).unwrap();
+        input["contracts"] = serde_json::json!([["0x100", contract_state(1)], ["0x100", contract_state(2)]]);
+
+        let error = serde_json::from_value::<StarknetOsInput>(input).unwrap_err();
+
+        assert!(error.to_string().contains("Duplicate contract address 0x100"), "{error}");
+    }
+
+    const TEST_CONTRACT_CLASS_HASH: &str = "0x76ed28a848a27cdd621a1580c819bba209ce2d15097737b7dc44bf7daca4c15";
+
+    fn test_contract_sierra_class() -> StarknetCoreSierraContractClass {
+        let sierra_bytes = include_bytes!(
+            "../../../../tests/integration/contracts/blockifier_contracts/feature_contracts/cairo1/compiled/\
+             test_contract.sierra"
+        );
+        let contract_class: ContractClass = serde_json::from_slice(sierra_bytes).unwrap();
+
+        GenericSierraContractClass::from(contract_class).to_starknet_core_contract_class().unwrap()
+    }
+
+    #[test]
+    fn test_compute_sierra_class_hash() {
+        let class_hash = compute_sierra_class_hash(&test_contract_sierra_class());
+
+        assert_eq!(class_hash, Felt252::from_hex_unchecked(TEST_CONTRACT_CLASS_HASH));
+    }
+
+    #[test]
+    fn test_compute_sierra_class_hash_without_abi() {
+        let sierra_bytes = include_bytes!(
+            "../../../../tests/integration/contracts/blockifier_contracts/feature_contracts/cairo1/compiled/\
+             test_contract.sierra"
+        );
+        let mut contract_class: ContractClass = serde_json::from_slice(sierra_bytes).unwrap();
+        contract_class.abi = None;
+
+        let abi_less_class =
+            GenericSierraContractClass::from(contract_class).to_starknet_core_contract_class().unwrap();
+        assert_eq!(abi_less_class.abi, "");
+
+        // The ABI component of the hash is the hash of the empty string
+        let component_hashes = ContractClassComponentHashes::from(abi_less_class.clone()).to_vec();
+        assert_eq!(
+            component_hashes[4],
+            Felt252::from_hex_unchecked("0x1d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470")
+        );
+
+        // The rest of the class is unchanged
+        let mut expected_class = test_contract_sierra_class();
+        expected_class.abi = String::new();
+        assert_eq!(abi_less_class, expected_class);
+
+        let class_hash = compute_sierra_class_hash(&abi_less_class);
+        assert_ne!(class_hash, Felt252::from_hex_unchecked(TEST_CONTRACT_CLASS_HASH));
+    }
+
+    #[rstest]
+    #[case::valid(Felt252::from_hex_unchecked(TEST_CONTRACT_CLASS_HASH), true, true)]
+    #[case::wrong_class_hash(Felt252::from(0x1234), true, false)]
+    #[case::missing_compiled_class_hash(Felt252::from_hex_unchecked(TEST_CONTRACT_CLASS_HASH), false, false)]
+    fn test_validate_class_hashes(
+        #[case] class_hash: Felt252,
+        #[case] has_compiled_class_hash: bool,
+        #[case] expected_valid: bool,
+    ) {
+        let component_hashes = ContractClassComponentHashes::from(test_contract_sierra_class()).to_vec();
+
+        let mut os_input = os_input();
+        os_input.declared_class_hash_to_component_hashes = HashMap::from([(class_hash, component_hashes)]);
+        if has_compiled_class_hash {
+            os_input.class_hash_to_compiled_class_hash.insert(class_hash, Felt252::from(0x20));
+        }
+
+        assert_eq!(os_input.validate_class_hashes().is_ok(), expected_valid);
+    }
+
+    #[test]
+    fn test_diff() {
+        let input = os_input();
+        assert!(input.diff(&os_input()).is_empty());
+
+        let mut other = os_input();
+        other.transactions[1].nonce = Some(Felt252::from(7));
+        other.class_hash_to_compiled_class_hash.insert(Felt252::from(0x11), Felt252::from(0x21));
+
+        let diff = input.diff(&other);
+        assert_eq!(diff.transactions, vec![TransactionDiff { index: 1, fields: vec!["nonce".to_string()] }]);
+        assert!(diff.contracts.is_empty());
+        assert!(diff.contract_address_to_class_hash.is_empty());
+        assert_eq!(diff.class_hash_to_compiled_class_hash, vec![Felt252::from(0x11)]);
+    }
+
+    #[test]
+    fn test_diff_reports_missing_transaction() {
+        let input = os_input();
+        let mut other = os_input();
+        other.transactions.pop();
+
+        let diff = input.diff(&other);
+        assert_eq!(diff.transactions.len(), 1);
+        assert_eq!(diff.transactions[0].index, 1);
+        assert!(diff.transactions[0].fields.contains(&"hash_value".to_string()));
+    }
+
+    #[test]
+    fn test_migrate_rejects_future_version() {
+        let input = serde_json::to_value(os_input()).unwrap();
+
+        let result = StarknetOsInput::migrate(input, OS_INPUT_SCHEMA_VERSION + 1);
+        assert!(matches!(result, Err(SnOsError::CatchAll(_))));
+    }
+}
@@ -1,6 +1,8 @@
 pub mod classes;
 pub mod input;
 pub mod output;
+pub mod prover_trace;
+pub mod segment_dump;
 
 use cairo_vm::Felt252;
 use serde::{Deserialize, Serialize};
@@ -2,6 +2,7 @@ use std::collections::HashMap;
 
 use cairo_vm::types::relocatable::MaybeRelocatable;
 use cairo_vm::vm::runners::builtin_runner::BuiltinRunner;
+use cairo_vm::vm::runners::cairo_pie::CairoPie;
 use cairo_vm::vm::vm_core::VirtualMachine;
 use cairo_vm::Felt252;
 use num_traits::{ToPrimitive, Zero};
@@ -67,12 +68,82 @@ pub struct StarknetOsOutput {
     pub classes: HashMap<Felt252, Felt252>,
 }
 
+/// The result of a complete OS run: the Cairo PIE to prove and the parsed output of the run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OsOutput {
+    pub cairo_pie: CairoPie,
+    pub output: StarknetOsOutput,
+}
+
+/// A message sent from L2 to L1 (`MessageToL1Header` followed by the payload).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct MessageToL1 {
+    pub from_address: Felt252,
+    pub to_address: Felt252,
+    pub payload: Vec<Felt252>,
+}
+
+/// A message sent from L1 to L2 and consumed by an L1 handler tx (`MessageToL2Header` followed by
+/// the payload).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct MessageToL2 {
+    pub from_address: Felt252,
+    pub to_address: Felt252,
+    pub nonce: Felt252,
+    pub selector: Felt252,
+    pub payload: Vec<Felt252>,
+}
+
 impl StarknetOsOutput {
     pub fn from_run(vm: &VirtualMachine) -> Result<Self, SnOsError> {
         let (output_base, output_size) = get_output_info(vm)?;
         let raw_output = get_raw_output(vm, output_base, output_size)?;
         deserialize_os_output(&mut raw_output.into_iter())
     }
+
+    /// Parses the raw output of an OS run, i.e. the content of the output segment.
+    pub fn from_raw_output(raw_output: &[Felt252]) -> Result<Self, SnOsError> {
+        deserialize_os_output(&mut raw_output.iter().copied())
+    }
+
+    /// Number of the block proven by the run.
+    pub fn block_number(&self) -> Result<u64, SnOsError> {
+        self.new_block_number
+            .to_u64()
+            .ok_or(SnOsError::Output(format!("Block number {} does not fit in a u64", self.new_block_number)))
+    }
+
+    /// Splits the serialized L2-to-L1 messages into individual messages.
+    pub fn l1_messages(&self) -> Result<Vec<MessageToL1>, SnOsError> {
+        let mut output_iter = self.messages_to_l1.iter().copied().peekable();
+        let mut messages = vec![];
+        while output_iter.peek().is_some() {
+            let from_address = next_or_fail(&mut output_iter, "L1 message from_address")?;
+            let to_address = next_or_fail(&mut output_iter, "L1 message to_address")?;
+            let payload_size = next_as_usize(&mut output_iter, "L1 message payload")?;
+            let payload = read_segment(&mut output_iter, payload_size, "L1 message payload elements")?;
+            messages.push(MessageToL1 { from_address, to_address, payload });
+        }
+
+        Ok(messages)
+    }
+
+    /// Splits the serialized L1-to-L2 messages into individual messages.
+    pub fn l2_messages(&self) -> Result<Vec<MessageToL2>, SnOsError> {
+        let mut output_iter = self.messages_to_l2.iter().copied().peekable();
+        let mut messages = vec![];
+        while output_iter.peek().is_some() {
+            let from_address = next_or_fail(&mut output_iter, "L2 message from_address")?;
+            let to_address = next_or_fail(&mut output_iter, "L2 message to_address")?;
+            let nonce = next_or_fail(&mut output_iter, "L2 message nonce")?;
+            let selector = next_or_fail(&mut output_iter, "L2 message selector")?;
+            let payload_size = next_as_usize(&mut output_iter, "L2 message payload")?;
+            let payload = read_segment(&mut output_iter, payload_size, "L2 message payload elements")?;
+            messages.push(MessageToL2 { from_address, to_address, nonce, selector, payload });
+        }
+
+        Ok(messages)
+    }
 }
 
 /// Gets the output base segment and the output size from the VM return values and the VM
@@ -372,4 +443,91 @@ mod tests {
 
         assert_eq!(deserialized_os_output, os_output);
     }
+
+    #[test]
+    fn parse_raw_output() {
+        let nonce_and_n_updates =
+            (Felt252::ONE * Felt252::TWO.pow(64u32) + Felt252::from(3)) * Felt252::TWO.pow(64u32) + Felt252::ONE;
+        let raw_output: Vec<Felt252> = [
+            // Header
+            vec![0x1, 0x2, 9, 10, 0x11, 0x12, 0, 0x13, 0, 0].into_iter().map(Felt252::from).collect::<Vec<_>>(),
+            // Messages to L1, then to L2
+            vec![4, 0x100, 0x200, 1, 7].into_iter().map(Felt252::from).collect(),
+            vec![6, 0x300, 0x400, 5, 0x500, 1, 8].into_iter().map(Felt252::from).collect(),
+            // One contract with a new class hash, a nonce of 3 and one storage update
+            vec![Felt252::ONE, Felt252::from(0x600), nonce_and_n_updates, Felt252::from(0x700)],
+            vec![0x42, 0x43].into_iter().map(Felt252::from).collect(),
+            // One declared class
+            vec![1, 0x800, 0x900].into_iter().map(Felt252::from).collect(),
+        ]
+        .concat();
+
+        let os_output = StarknetOsOutput::from_raw_output(&raw_output).unwrap();
+
+        assert_eq!(os_output.initial_root, Felt252::from(0x1));
+        assert_eq!(os_output.final_root, Felt252::from(0x2));
+        assert_eq!(os_output.prev_block_number, Felt252::from(9));
+        assert_eq!(os_output.block_number().unwrap(), 10);
+        assert_eq!(os_output.prev_block_hash, Felt252::from(0x11));
+        assert_eq!(os_output.new_block_hash, Felt252::from(0x12));
+        assert_eq!(os_output.starknet_os_config_hash, Felt252::from(0x13));
+        assert_eq!(
+            os_output.l1_messages().unwrap(),
+            vec![MessageToL1 {
+                from_address: Felt252::from(0x100),
+                to_address: Felt252::from(0x200),
+                payload: vec![Felt252::from(7)]
+            }]
+        );
+        assert_eq!(
+            os_output.l2_messages().unwrap(),
+            vec![MessageToL2 {
+                from_address: Felt252::from(0x300),
+                to_address: Felt252::from(0x400),
+                nonce: Felt252::from(5),
+                selector: Felt252::from(0x500),
+                payload: vec![Felt252::from(8)]
+            }]
+        );
+        assert_eq!(
+            os_output.contracts,
+            vec![ContractChanges {
+                addr: Felt252::from(0x600),
+                nonce: Felt252::from(3),
+                class_hash: Some(Felt252::from(0x700)),
+                storage_changes: HashMap::from([(Felt252::from(0x42), Felt252::from(0x43))]),
+            }]
+        );
+        assert_eq!(os_output.classes, HashMap::from([(Felt252::from(0x800), Felt252::from(0x900))]));
+    }
+
+    #[test]
+    fn parse_raw_output_class_replaced_with_zero() {
+        let class_updated = Felt252::TWO.pow(128u32);
+        let raw_output: Vec<Felt252> = [
+            vec![0x1, 0x2, 9, 10, 0x11, 0x12, 0, 0x13, 0, 0].into_iter().map(Felt252::from).collect::<Vec<_>>(),
+            vec![Felt252::ZERO, Felt252::ZERO],
+            // The class of the first contract is replaced with zero, the second one is unchanged
+            vec![Felt252::TWO, Felt252::from(0x600), class_updated, Felt252::ZERO],
+            vec![Felt252::from(0x601), Felt252::ZERO],
+            vec![Felt252::ZERO],
+        ]
+        .concat();
+
+        let os_output = StarknetOsOutput::from_raw_output(&raw_output).unwrap();
+
+        // A class hash set to zero is a change, unlike a class that was not updated
+        let class_hashes: Vec<_> =
+            os_output.contracts.iter().map(|changes| (changes.addr, changes.class_hash)).collect();
+        assert_eq!(class_hashes, vec![(Felt252::from(0x600), Some(Felt252::ZERO)), (Felt252::from(0x601), None)]);
+    }
+
+    #[test]
+    fn l1_messages_rejects_truncated_payload() {
+        let raw_output: Vec<Felt252> =
+            vec![0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 3, 0x100, 0x200, 2, 0, 0, 0].into_iter().map(Felt252::from).collect();
+        let os_output = StarknetOsOutput::from_raw_output(&raw_output).unwrap();
+
+        assert!(os_output.l1_messages().is_err());
+    }
 }
@@ -0,0 +1,98 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use cairo_vm::vm::runners::cairo_runner::CairoRunner;
+use cairo_vm::vm::trace::trace_entry::RelocatedTraceEntry;
+use cairo_vm::Felt252;
+
+use crate::error::SnOsError;
+
+/// Files the execution trace and memory of a run are written to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProverTracePaths {
+    pub trace: PathBuf,
+    pub memory: PathBuf,
+}
+
+/// Relocated execution trace and memory of an OS run, as consumed by the Stone prover.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProverTrace {
+    pub trace: Vec<RelocatedTraceEntry>,
+    pub memory: Vec<Option<Felt252>>,
+}
+
+impl ProverTrace {
+    /// Collects the trace and memory of a run. The runner must have been run with the trace
+    /// enabled and its memory must have been relocated.
+    pub fn from_runner(cairo_runner: &CairoRunner) -> Result<Self, SnOsError> {
+        let trace = cairo_runner
+            .relocated_trace
+            .clone()
+            .ok_or_else(|| SnOsError::CatchAll("The trace is only available once relocated".to_string()))?;
+
+        Ok(Self { trace, memory: cairo_runner.relocated_memory.clone() })
+    }
+
+    /// Writes the trace in the binary format of the prover: the (ap, fp, pc) registers of each
+    /// step, as little-endian u64s.
+    pub fn write_trace<W: Write>(&self, dest: &mut W) -> std::io::Result<()> {
+        for entry in &self.trace {
+            dest.write_all(&(entry.ap as u64).to_le_bytes())?;
+            dest.write_all(&(entry.fp as u64).to_le_bytes())?;
+            dest.write_all(&(entry.pc as u64).to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the memory in the binary format of the prover: the address of each set cell as a
+    /// little-endian u64, followed by its value as 32 little-endian bytes.
+    pub fn write_memory<W: Write>(&self, dest: &mut W) -> std::io::Result<()> {
+        for (address, value) in self.memory.iter().enumerate() {
+            if let Some(value) = value {
+                dest.write_all(&(address as u64).to_le_bytes())?;
+                dest.write_all(&value.to_bytes_le())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn dump(&self, paths: &ProverTracePaths) -> Result<(), SnOsError> {
+        let mut trace_file = BufWriter::new(File::create(&paths.trace)?);
+        self.write_trace(&mut trace_file)?;
+        trace_file.flush()?;
+
+        let mut memory_file = BufWriter::new(File::create(&paths.memory)?);
+        self.write_memory(&mut memory_file)?;
+        memory_file.flush()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prover_trace_encoding() {
+        let prover_trace = ProverTrace {
+            trace: vec![RelocatedTraceEntry { pc: 1, ap: 2, fp: 3 }],
+            memory: vec![None, Some(Felt252::from(0x10)), None, Some(Felt252::from(0x20))],
+        };
+
+        let mut trace = vec![];
+        prover_trace.write_trace(&mut trace).unwrap();
+        assert_eq!(trace, [2u64, 3, 1].iter().flat_map(|register| register.to_le_bytes()).collect::<Vec<_>>());
+
+        let mut memory = vec![];
+        prover_trace.write_memory(&mut memory).unwrap();
+        // Unset cells are skipped
+        assert_eq!(memory.len(), 2 * (8 + 32));
+        assert_eq!(&memory[..8], &1u64.to_le_bytes());
+        assert_eq!(&memory[8..40], &Felt252::from(0x10).to_bytes_le());
+        assert_eq!(&memory[40..48], &3u64.to_le_bytes());
+    }
+}
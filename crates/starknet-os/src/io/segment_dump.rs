@@ -0,0 +1,76 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use cairo_vm::vm::runners::cairo_runner::CairoRunner;
+use serde::{Deserialize, Serialize};
+
+use crate::error::SnOsError;
+
+/// Size and position in the relocated memory of a memory segment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SegmentInfo {
+    pub index: usize,
+    pub size: usize,
+    pub relocated_base: usize,
+}
+
+/// Memory usage of a builtin, including the output builtin.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuiltinUsage {
+    pub name: String,
+    pub segment_index: usize,
+    pub used_cells: usize,
+}
+
+/// Layout of the memory of an OS run, used to diagnose layout issues.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SegmentDump {
+    pub segments: Vec<SegmentInfo>,
+    pub builtins: Vec<BuiltinUsage>,
+}
+
+impl SegmentDump {
+    /// Collects the segments of a run. The memory of the runner must have been relocated.
+    pub fn from_runner(cairo_runner: &CairoRunner) -> Result<Self, SnOsError> {
+        let vm_segments = &cairo_runner.vm.segments;
+        let relocation_table = vm_segments
+            .relocate_segments()
+            .map_err(|e| SnOsError::CatchAll(format!("Segments cannot be dumped before relocation: {e}")))?;
+
+        let segments = relocation_table
+            .iter()
+            .enumerate()
+            .map(|(index, relocated_base)| SegmentInfo {
+                index,
+                size: vm_segments.get_segment_size(index).unwrap_or_default(),
+                relocated_base: *relocated_base,
+            })
+            .collect();
+
+        let builtins = cairo_runner
+            .vm
+            .get_builtin_runners()
+            .iter()
+            .map(|builtin| {
+                let used_cells = builtin.get_used_cells(vm_segments).map_err(|e| {
+                    SnOsError::CatchAll(format!("Failed to get the cells used by {}: {e}", builtin.name()))
+                })?;
+                Ok(BuiltinUsage {
+                    name: builtin.name().to_str().to_string(),
+                    segment_index: builtin.base(),
+                    used_cells,
+                })
+            })
+            .collect::<Result<_, SnOsError>>()?;
+
+        Ok(Self { segments, builtins })
+    }
+
+    pub fn dump(&self, path: &Path) -> Result<(), SnOsError> {
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(BufWriter::new(file), self)?;
+
+        Ok(())
+    }
+}
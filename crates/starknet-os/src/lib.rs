@@ -1,21 +1,27 @@
+use std::path::Path;
 use std::rc::Rc;
+use std::sync::atomic::Ordering;
 
 use blockifier::context::BlockContext;
 use cairo_vm::cairo_run::CairoRunConfig;
 use cairo_vm::types::layout_name::LayoutName;
 use cairo_vm::types::program::Program;
+use cairo_vm::types::relocatable::Relocatable;
 use cairo_vm::vm::errors::vm_exception::VmException;
 use cairo_vm::vm::runners::cairo_pie::CairoPie;
 use cairo_vm::vm::runners::cairo_runner::CairoRunner;
-use error::SnOsError;
+use config::OsRunConfig;
+use error::{CompiledProgramError, OsRunError, SnOsError};
 use execution::deprecated_syscall_handler::DeprecatedOsSyscallHandlerWrapper;
 use execution::helper::ExecutionHelperWrapper;
-use io::output::StarknetOsOutput;
+use io::output::{OsOutput, StarknetOsOutput};
 
 use crate::execution::syscall_handler::OsSyscallHandlerWrapper;
 use crate::hints::types::{PatriciaSkipValidationRunner, PatriciaTreeMode};
-use crate::hints::vars;
+use crate::hints::{vars, SnosHintProcessor};
 use crate::io::input::StarknetOsInput;
+use crate::io::prover_trace::ProverTrace;
+use crate::io::segment_dump::SegmentDump;
 use crate::starknet::starknet_storage::PerContractStorage;
 
 mod cairo_types;
@@ -31,6 +37,9 @@ pub mod starkware_utils;
 pub mod storage;
 pub mod utils;
 
+/// Name of the function the OS program starts executing from.
+const OS_ENTRYPOINT: &str = "main";
+
 pub fn run_os<PCS>(
     compiled_os: &[u8],
     layout: LayoutName,
@@ -41,25 +50,51 @@ pub fn run_os<PCS>(
 where
     PCS: PerContractStorage + 'static,
 {
-    // Init CairoRunConfig
-    let cairo_run_config = CairoRunConfig { layout, relocate_mem: true, trace_enabled: true, ..Default::default() };
-    let allow_missing_builtins = cairo_run_config.allow_missing_builtins.unwrap_or(false);
+    let config = OsRunConfig { layout, ..Default::default() };
+    run_os_with_config(compiled_os, &config, os_input, block_context, execution_helper)
+}
 
+fn run_os_with_config<PCS>(
+    compiled_os: &[u8],
+    config: &OsRunConfig,
+    os_input: Rc<StarknetOsInput>,
+    block_context: BlockContext,
+    execution_helper: ExecutionHelperWrapper<PCS>,
+) -> Result<(CairoPie, StarknetOsOutput), SnOsError>
+where
+    PCS: PerContractStorage + 'static,
+{
     // Load the Starknet OS Program
-    let os_program =
-        Program::from_bytes(compiled_os, Some(cairo_run_config.entrypoint)).map_err(|e| SnOsError::Runner(e.into()))?;
+    let os_program = Program::from_bytes(compiled_os, Some(OS_ENTRYPOINT)).map_err(|e| SnOsError::Runner(e.into()))?;
 
-    // Init cairo runner
-    let mut cairo_runner = CairoRunner::new(
-        &os_program,
-        cairo_run_config.layout,
-        cairo_run_config.proof_mode,
-        cairo_run_config.trace_enabled,
-    )
-    .map_err(|e| SnOsError::Runner(e.into()))?;
+    run_os_program(&os_program, config, os_input, block_context, execution_helper, Default::default())
+}
 
-    // Init the Cairo VM
-    let end = cairo_runner.initialize(allow_missing_builtins).map_err(|e| SnOsError::Runner(e.into()))?;
+fn run_os_program<PCS>(
+    os_program: &Program,
+    config: &OsRunConfig,
+    os_input: Rc<StarknetOsInput>,
+    block_context: BlockContext,
+    execution_helper: ExecutionHelperWrapper<PCS>,
+    mut sn_hint_processor: SnosHintProcessor<PCS>,
+) -> Result<(CairoPie, StarknetOsOutput), SnOsError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    if let Some(max_transactions) = config.max_transactions {
+        let n_transactions = os_input.transactions.len();
+        if n_transactions > max_transactions {
+            return Err(SnOsError::TooManyTransactions { n_transactions, max_transactions });
+        }
+    }
+
+    // Init CairoRunConfig
+    let cairo_run_config =
+        CairoRunConfig { layout: config.layout, relocate_mem: true, trace_enabled: true, ..Default::default() };
+    let allow_missing_builtins = cairo_run_config.allow_missing_builtins.unwrap_or(false);
+
+    // Init cairo runner and the Cairo VM
+    let (mut cairo_runner, end) = init_os_runner(os_program, &cairo_run_config)?;
 
     // Setup Depsyscall Handler
     let deprecated_syscall_handler = DeprecatedOsSyscallHandlerWrapper::new(
@@ -81,11 +116,17 @@ where
         .insert_value(vars::scopes::PATRICIA_SKIP_VALIDATION_RUNNER, None::<PatriciaSkipValidationRunner>);
     cairo_runner.exec_scopes.insert_value(vars::scopes::PATRICIA_TREE_MODE, PatriciaTreeMode::State);
     cairo_runner.exec_scopes.insert_value::<Option<usize>>(vars::scopes::FIND_ELEMENT_MAX_SIZE, None);
+    cairo_runner.exec_scopes.insert_value(vars::scopes::CANCELLATION_FLAG, config.cancellation_flag.clone());
+    cairo_runner.exec_scopes.insert_value(vars::scopes::ASSERTION_MODE, config.assertion_mode);
 
     // Run the Cairo VM
-    let mut sn_hint_processor = hints::SnosHintProcessor::<PCS>::default();
-    cairo_runner
-        .run_until_pc(end, &mut sn_hint_processor)
+    sn_hint_processor.set_catch_hint_panics(config.catch_hint_panics);
+    let run_result = cairo_runner.run_until_pc(end, &mut sn_hint_processor);
+    // The run is aborted by a hint error when cancelled
+    if run_result.is_err() && is_cancelled(config) {
+        return Err(SnOsError::Cancelled);
+    }
+    run_result
         .map_err(|err| VmException::from_vm_error(&cairo_runner, err))
         .map_err(|e| SnOsError::Runner(e.into()))?;
 
@@ -107,8 +148,229 @@ where
     cairo_runner.read_return_values(allow_missing_builtins).map_err(|e| SnOsError::Runner(e.into()))?;
     cairo_runner.relocate(cairo_run_config.relocate_mem).map_err(|e| SnOsError::Runner(e.into()))?;
 
+    if let Some(segment_dump_path) = &config.segment_dump_path {
+        SegmentDump::from_runner(&cairo_runner)?.dump(segment_dump_path)?;
+    }
+    if let Some(prover_trace_paths) = &config.prover_trace_paths {
+        ProverTrace::from_runner(&cairo_runner)?.dump(prover_trace_paths)?;
+    }
+
     // Parse the Cairo VM output
     let pie = cairo_runner.get_cairo_pie().map_err(|e| SnOsError::PieParsing(format!("{e}")))?;
 
     Ok((pie, os_output))
 }
+
+/// Creates a runner for the OS program and initializes its segments, including one segment for
+/// each builtin declared by the program, e.g. `range_check96`. Returns the runner and the pc at
+/// which the run ends.
+fn init_os_runner(
+    os_program: &Program,
+    cairo_run_config: &CairoRunConfig,
+) -> Result<(CairoRunner, Relocatable), SnOsError> {
+    let allow_missing_builtins = cairo_run_config.allow_missing_builtins.unwrap_or(false);
+    let mut cairo_runner = CairoRunner::new(
+        os_program,
+        cairo_run_config.layout,
+        cairo_run_config.proof_mode,
+        cairo_run_config.trace_enabled,
+    )
+    .map_err(|e| SnOsError::Runner(e.into()))?;
+
+    let end = cairo_runner.initialize(allow_missing_builtins).map_err(|e| SnOsError::Runner(e.into()))?;
+
+    Ok((cairo_runner, end))
+}
+
+fn is_cancelled(config: &OsRunConfig) -> bool {
+    config.cancellation_flag.as_ref().is_some_and(|flag| flag.load(Ordering::Relaxed))
+}
+
+/// Runs the OS on an input that was already deserialized, e.g. built in memory or received over
+/// the network.
+pub fn run_os_with_input<PCS>(
+    compiled_os: &[u8],
+    os_input: StarknetOsInput,
+    config: OsRunConfig,
+    block_context: BlockContext,
+    execution_helper: ExecutionHelperWrapper<PCS>,
+) -> Result<(CairoPie, StarknetOsOutput), SnOsError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    run_os_with_config(compiled_os, &config, Rc::new(os_input), block_context, execution_helper)
+}
+
+/// Runs the OS with a compiled OS program that was already loaded, e.g. to run many blocks without
+/// parsing the program for each of them.
+pub fn run_os_with_program<PCS>(
+    compiled_os: &CompiledOs,
+    os_input: Rc<StarknetOsInput>,
+    config: OsRunConfig,
+    block_context: BlockContext,
+    execution_helper: ExecutionHelperWrapper<PCS>,
+) -> Result<(CairoPie, StarknetOsOutput), SnOsError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    run_os_program(&compiled_os.program, &config, os_input, block_context, execution_helper, Default::default())
+}
+
+/// Same as `run_os_with_program`, with a hint processor prepared by the caller, e.g. with some
+/// hints overridden (see `SnosHintProcessor::override_hint`).
+pub fn run_os_with_hint_processor<PCS>(
+    compiled_os: &CompiledOs,
+    os_input: Rc<StarknetOsInput>,
+    config: OsRunConfig,
+    block_context: BlockContext,
+    execution_helper: ExecutionHelperWrapper<PCS>,
+    hint_processor: SnosHintProcessor<PCS>,
+) -> Result<(CairoPie, StarknetOsOutput), SnOsError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    run_os_program(&compiled_os.program, &config, os_input, block_context, execution_helper, hint_processor)
+}
+
+/// Runs the OS on the input file at `input_path` using the compiled OS program at `compiled_os_path`
+/// and returns the Cairo PIE of the run along with its parsed output.
+///
+/// The block context and the execution helper are supplied by the caller, as with
+/// `run_os_with_program`, because the input file does not describe them: it holds neither the
+/// number, timestamp and gas prices of the block (`StarknetGeneralConfig::empty_block_context`
+/// only fills them with placeholders) nor the execution infos of its txs, which the OS replays and
+/// which only the execution of the block produces.
+pub fn run_os_from_path<PCS>(
+    input_path: &Path,
+    compiled_os_path: &Path,
+    config: OsRunConfig,
+    block_context: BlockContext,
+    execution_helper: ExecutionHelperWrapper<PCS>,
+) -> Result<OsOutput, OsRunError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let os_program = load_compiled_os(compiled_os_path)?;
+    let os_input = StarknetOsInput::load_streaming(input_path)
+        .and_then(|os_input| os_input.validate_class_hashes().map(|_| os_input))
+        .map_err(|error| OsRunError::Input { path: input_path.to_path_buf(), error })?;
+
+    let (cairo_pie, output) =
+        run_os_program(&os_program, &config, Rc::new(os_input), block_context, execution_helper, Default::default())?;
+
+    Ok(OsOutput { cairo_pie, output })
+}
+
+/// Reads and parses the compiled OS program at `path`.
+pub fn load_compiled_os(path: &Path) -> Result<Program, OsRunError> {
+    let to_error = |error: CompiledProgramError| OsRunError::CompiledProgram { path: path.to_path_buf(), error };
+
+    let compiled_os = std::fs::read(path).map_err(|e| to_error(e.into()))?;
+    Program::from_bytes(&compiled_os, Some(OS_ENTRYPOINT)).map_err(|e| to_error(e.into()))
+}
+
+/// The compiled OS program, parsed once so that it can be shared by several runs.
+#[derive(Clone, Debug)]
+pub struct CompiledOs {
+    program: Program,
+}
+
+impl CompiledOs {
+    /// Parses the compiled OS program from the content of its JSON file.
+    pub fn from_bytes(compiled_os: &[u8]) -> Result<Self, CompiledProgramError> {
+        let program = Program::from_bytes(compiled_os, Some(OS_ENTRYPOINT))?;
+        Ok(Self { program })
+    }
+
+    /// Reads and parses the compiled OS program at `path`.
+    pub fn load(path: &Path) -> Result<Self, OsRunError> {
+        load_compiled_os(path).map(|program| Self { program })
+    }
+
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use cairo_vm::serde::deserialize_program::ReferenceManager;
+    use cairo_vm::types::builtin_name::BuiltinName;
+    use cairo_vm::Felt252;
+
+    use super::*;
+
+    #[test]
+    fn load_compiled_os_missing_file() {
+        let path = std::env::temp_dir().join(format!("missing_os_{}.json", uuid::Uuid::new_v4()));
+
+        let err = load_compiled_os(&path).unwrap_err();
+
+        assert!(matches!(
+            &err,
+            OsRunError::CompiledProgram { error: CompiledProgramError::Io(e), .. }
+                if e.kind() == std::io::ErrorKind::NotFound
+        ));
+        assert!(err.to_string().contains(&path.display().to_string()));
+    }
+
+    #[test]
+    fn load_compiled_os_corrupt_program() {
+        let path = std::env::temp_dir().join(format!("corrupt_os_{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(&path, b"{\"data\": [\"0x1\", ").unwrap();
+
+        let result = load_compiled_os(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let err = result.unwrap_err();
+        assert!(matches!(&err, OsRunError::CompiledProgram { error: CompiledProgramError::Parse(_), .. }));
+        assert!(err.to_string().contains("not a valid compiled Cairo program"));
+    }
+
+    #[test]
+    fn init_os_runner_allocates_range_check96_segment() {
+        // A lone `ret` instruction: the program is initialized but never run
+        let data = vec![Felt252::from(0x208b7fff7fff7ffeu64).into()];
+        let program = Program::new(
+            vec![BuiltinName::range_check, BuiltinName::range_check96],
+            data,
+            Some(0),
+            HashMap::new(),
+            ReferenceManager::default(),
+            HashMap::new(),
+            vec![],
+            None,
+        )
+        .unwrap();
+        let cairo_run_config = CairoRunConfig { layout: config::default_layout(), ..Default::default() };
+
+        let (cairo_runner, _) = init_os_runner(&program, &cairo_run_config).unwrap();
+
+        let range_check96 = cairo_runner
+            .vm
+            .get_builtin_runners()
+            .iter()
+            .find(|builtin| builtin.name() == BuiltinName::range_check96)
+            .expect("range_check96 builtin runner");
+        assert!(range_check96.base() < cairo_runner.vm.segments.num_segments());
+        assert_ne!(
+            range_check96.base(),
+            cairo_runner
+                .vm
+                .get_builtin_runners()
+                .iter()
+                .find(|builtin| builtin.name() == BuiltinName::range_check)
+                .unwrap()
+                .base()
+        );
+    }
+
+    #[test]
+    fn compiled_os_from_corrupt_bytes() {
+        let err = CompiledOs::from_bytes(b"{\"data\": [\"0x1\", ").unwrap_err();
+
+        assert!(matches!(err, CompiledProgramError::Parse(_)));
+    }
+}
@@ -18,18 +18,22 @@ use crate::storage::storage::{DbObject, Fact, FactFetchingContext, HashFunctionT
 pub const UNINITIALIZED_CLASS_HASH: [u8; 32] = [0; 32];
 
 #[serde_as]
-#[derive(Deserialize, Clone, Debug, Serialize, PartialEq)]
+#[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq)]
 pub struct ContractState {
     pub contract_hash: Vec<u8>,
     pub storage_commitment_tree: PatriciaTree,
     pub nonce: Felt252,
+    /// Storage values to seed the contract with when initializing the state of the OS run. Only
+    /// used by test inputs, which cannot provide storage through the commitment trees.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub storage: HashMap<Felt252, Felt252>,
 }
 
 impl SerializationPrefix for ContractState {}
 
 impl ContractState {
     pub fn create(contract_hash: Vec<u8>, storage_commitment_tree: PatriciaTree, nonce: Felt252) -> Self {
-        Self { contract_hash, storage_commitment_tree, nonce }
+        Self { contract_hash, storage_commitment_tree, nonce, storage: HashMap::new() }
     }
 
     pub async fn empty<S, H>(
@@ -45,6 +49,7 @@ impl ContractState {
             contract_hash: UNINITIALIZED_CLASS_HASH.to_vec(),
             storage_commitment_tree: empty_tree,
             nonce: Felt252::ZERO,
+            storage: HashMap::new(),
         })
     }
 
@@ -75,7 +80,12 @@ impl ContractState {
         let updated_storage_commitment_tree =
             self.storage_commitment_tree.update(ffc, modifications, &mut facts).await?;
 
-        Ok(Self { contract_hash: class_hash_bytes, storage_commitment_tree: updated_storage_commitment_tree, nonce })
+        Ok(Self {
+            contract_hash: class_hash_bytes,
+            storage_commitment_tree: updated_storage_commitment_tree,
+            nonce,
+            storage: self.storage,
+        })
     }
 }
 
@@ -116,6 +126,19 @@ where
     }
 }
 
+/// Asserts that two contract states are equal, reporting which of the class hash, nonce or
+/// storage differs.
+#[cfg(test)]
+#[track_caller]
+pub(crate) fn assert_contract_state_eq(actual: &ContractState, expected: &ContractState) {
+    assert_eq!(actual.contract_hash, expected.contract_hash, "contract states have different class hashes");
+    assert_eq!(actual.nonce, expected.nonce, "contract states have different nonces");
+    assert_eq!(
+        actual.storage_commitment_tree, expected.storage_commitment_tree,
+        "contract states have different storage"
+    );
+}
+
 #[cfg(test)]
 mod tests {
     use rstest::rstest;
@@ -159,4 +182,34 @@ mod tests {
 
         assert_eq!(hash, expected_hash);
     }
+
+    fn contract_state(class_hash: u8, storage_root: u8, nonce: u64) -> ContractState {
+        let mut contract_hash = vec![0; 32];
+        contract_hash[31] = class_hash;
+        let mut root = [0; 32];
+        root[31] = storage_root;
+        let storage_commitment_tree = PatriciaTree { root: Hash::from_bytes_be(root), height: Height(251) };
+
+        ContractState::create(contract_hash, storage_commitment_tree, Felt252::from(nonce))
+    }
+
+    #[test]
+    fn test_contract_state_eq_identical() {
+        let state = contract_state(1, 2, 3);
+
+        assert_eq!(state, state.clone());
+        assert_contract_state_eq(&state, &contract_state(1, 2, 3));
+    }
+
+    #[rstest]
+    #[case::class_hash(contract_state(4, 2, 3))]
+    #[case::storage(contract_state(1, 4, 3))]
+    #[case::nonce(contract_state(1, 2, 4))]
+    fn test_contract_state_eq_differing(#[case] other: ContractState) {
+        let state = contract_state(1, 2, 3);
+
+        assert_ne!(state, other);
+        let result = std::panic::catch_unwind(|| assert_contract_state_eq(&state, &other));
+        assert!(result.is_err());
+    }
 }
@@ -0,0 +1,151 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use cairo_vm::Felt252;
+use num_bigint::BigUint;
+use starknet_crypto::{poseidon_hash_many, FieldElement};
+
+use crate::crypto::poseidon::PoseidonHash;
+use crate::starkware_utils::commitment_tree::base_types::Height;
+use crate::starkware_utils::commitment_tree::binary_fact_tree::BinaryFactTree;
+use crate::starkware_utils::commitment_tree::errors::TreeError;
+use crate::starkware_utils::commitment_tree::patricia_tree::patricia_tree::PatriciaTree;
+use crate::storage::dict_storage::DictStorage;
+use crate::storage::storage::FactFetchingContext;
+use crate::storage::storage_utils::SimpleLeafFact;
+
+/// Height of the Patricia trees used for the block header commitments (transactions, events...).
+pub const BLOCK_COMMITMENT_TREE_HEIGHT: u64 = 64;
+
+/// Prefix of the state diff commitment hash chain.
+const STARKNET_STATE_DIFF0: &[u8] = b"STARKNET_STATE_DIFF0";
+
+/// An event emitted by a contract, as committed to in the block header.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Event {
+    pub from_address: Felt252,
+    pub keys: Vec<Felt252>,
+    pub data: Vec<Felt252>,
+}
+
+fn felt_to_field_element(felt: &Felt252) -> FieldElement {
+    // A Felt252 is always a valid field element
+    FieldElement::from_bytes_be(&felt.to_bytes_be()).unwrap()
+}
+
+fn poseidon_hash_many_felts(felts: &[Felt252]) -> Felt252 {
+    let field_elements: Vec<_> = felts.iter().map(felt_to_field_element).collect();
+    Felt252::from_bytes_be(&poseidon_hash_many(&field_elements).to_bytes_be())
+}
+
+/// Computes the hash of an event emitted by the transaction with hash `tx_hash`:
+/// h(from_address, tx_hash, len(keys), *keys, len(data), *data).
+pub fn calculate_event_hash(event: &Event, tx_hash: Felt252) -> Felt252 {
+    let mut felts = Vec::with_capacity(4 + event.keys.len() + event.data.len());
+    felts.push(event.from_address);
+    felts.push(tx_hash);
+    felts.push(Felt252::from(event.keys.len()));
+    felts.extend_from_slice(&event.keys);
+    felts.push(Felt252::from(event.data.len()));
+    felts.extend_from_slice(&event.data);
+
+    poseidon_hash_many_felts(&felts)
+}
+
+/// Computes the root of a Patricia tree of height 64 whose leaves are the given values,
+/// indexed by their position in the list.
+async fn calculate_patricia_root(leaves: Vec<Felt252>) -> Result<Felt252, TreeError> {
+    let mut ffc = FactFetchingContext::<DictStorage, PoseidonHash>::new(DictStorage::default());
+    let tree =
+        PatriciaTree::empty_tree(&mut ffc, Height(BLOCK_COMMITMENT_TREE_HEIGHT), SimpleLeafFact::empty()).await?;
+
+    let modifications: Vec<_> =
+        leaves.into_iter().enumerate().map(|(index, leaf)| (BigUint::from(index), SimpleLeafFact::new(leaf))).collect();
+    let tree = tree.update(&mut ffc, modifications, &mut None).await?;
+
+    Ok(Felt252::from_bytes_be_slice(&tree.root))
+}
+
+/// Computes the events commitment of a block, given the events emitted in the block (in order)
+/// along with the hash of the transaction that emitted them.
+pub async fn calculate_events_commitment(events: &[(Felt252, Event)]) -> Result<Felt252, TreeError> {
+    let event_hashes = events.iter().map(|(tx_hash, event)| calculate_event_hash(event, *tx_hash)).collect();
+    calculate_patricia_root(event_hashes).await
+}
+
+/// Computes the hash committed to for a single transaction: h(tx_hash, *signature).
+/// An empty signature is replaced by `[0]`.
+pub fn calculate_tx_hash_with_signature(tx_hash: Felt252, signature: &[Felt252]) -> Felt252 {
+    let mut felts = Vec::with_capacity(1 + signature.len().max(1));
+    felts.push(tx_hash);
+    if signature.is_empty() {
+        felts.push(Felt252::ZERO);
+    } else {
+        felts.extend_from_slice(signature);
+    }
+
+    poseidon_hash_many_felts(&felts)
+}
+
+/// Computes the transactions commitment of a block, given the hash and signature of each
+/// transaction in the block (in order).
+pub async fn calculate_transactions_commitment(txs: &[(Felt252, Vec<Felt252>)]) -> Result<Felt252, TreeError> {
+    let tx_leaves =
+        txs.iter().map(|(tx_hash, signature)| calculate_tx_hash_with_signature(*tx_hash, signature)).collect();
+    calculate_patricia_root(tx_leaves).await
+}
+
+/// The changes applied to the state by a block, as committed to in the block header.
+/// Maps are ordered by key, which is the order in which the commitment hashes them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StateDiff {
+    /// Contracts that were deployed or whose class was replaced, mapped to their new class hash.
+    pub updated_contracts: BTreeMap<Felt252, Felt252>,
+    /// Cairo 1 classes declared in the block, mapped to their compiled class hash.
+    pub declared_classes: BTreeMap<Felt252, Felt252>,
+    /// Cairo 0 classes declared in the block.
+    pub deprecated_declared_classes: BTreeSet<Felt252>,
+    /// Storage updates, by contract address and then by storage key.
+    pub storage_diffs: BTreeMap<Felt252, BTreeMap<Felt252, Felt252>>,
+    /// New nonces of the contracts whose nonce changed.
+    pub nonces: BTreeMap<Felt252, Felt252>,
+}
+
+/// Computes the state diff commitment of a block:
+/// h("STARKNET_STATE_DIFF0", updated_contracts, declared_classes, deprecated_declared_classes,
+/// 1, 0, storage_diffs, nonces), where each section is prefixed by its number of entries.
+/// Contracts without storage updates are left out of the storage diffs section.
+pub fn calculate_state_diff_commitment(state_diff: &StateDiff) -> Felt252 {
+    let mut felts = vec![Felt252::from_bytes_be_slice(STARKNET_STATE_DIFF0)];
+
+    felts.push(Felt252::from(state_diff.updated_contracts.len()));
+    for (address, class_hash) in &state_diff.updated_contracts {
+        felts.extend([*address, *class_hash]);
+    }
+
+    felts.push(Felt252::from(state_diff.declared_classes.len()));
+    for (class_hash, compiled_class_hash) in &state_diff.declared_classes {
+        felts.extend([*class_hash, *compiled_class_hash]);
+    }
+
+    felts.push(Felt252::from(state_diff.deprecated_declared_classes.len()));
+    felts.extend(state_diff.deprecated_declared_classes.iter().copied());
+
+    // Placeholders
+    felts.extend([Felt252::ONE, Felt252::ZERO]);
+
+    let storage_diffs: Vec<_> = state_diff.storage_diffs.iter().filter(|(_, updates)| !updates.is_empty()).collect();
+    felts.push(Felt252::from(storage_diffs.len()));
+    for (address, updates) in storage_diffs {
+        felts.extend([*address, Felt252::from(updates.len())]);
+        for (key, value) in updates {
+            felts.extend([*key, *value]);
+        }
+    }
+
+    felts.push(Felt252::from(state_diff.nonces.len()));
+    for (address, nonce) in &state_diff.nonces {
+        felts.extend([*address, *nonce]);
+    }
+
+    poseidon_hash_many_felts(&felts)
+}
@@ -0,0 +1,83 @@
+use cairo_vm::Felt252;
+
+/// Size of a Keccak-256 block, in bytes.
+const KECCAK256_RATE: usize = 136;
+
+/// Computes the Keccak-256 hash of `data`, as used by Ethereum (i.e. with the original Keccak
+/// padding, not the SHA-3 one).
+fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut padded = data.to_vec();
+    padded.push(0x01);
+    padded.resize(padded.len().next_multiple_of(KECCAK256_RATE), 0);
+    *padded.last_mut().expect("the padded data cannot be empty") |= 0x80;
+
+    let mut state = [0u64; 25];
+    for block in padded.chunks(KECCAK256_RATE) {
+        for (lane, bytes) in state.iter_mut().zip(block.chunks(8)) {
+            *lane ^= u64::from_le_bytes(bytes.try_into().expect("the rate is a multiple of 8 bytes"));
+        }
+        keccak::f1600(&mut state);
+    }
+
+    let mut hash = [0u8; 32];
+    for (bytes, lane) in hash.chunks_mut(8).zip(state) {
+        bytes.copy_from_slice(&lane.to_le_bytes());
+    }
+    hash
+}
+
+/// Computes the hash of an L1-to-L2 message, as done by the Starknet core contract on L1 when
+/// the message is sent (`getL1ToL2MsgHash`):
+/// keccak256(from_address, to_address, nonce, selector, len(payload), *payload), where each
+/// value is encoded as a 32-byte big-endian word.
+pub fn compute_l1_to_l2_message_hash(
+    from_address: Felt252,
+    to_address: Felt252,
+    selector: Felt252,
+    payload: &[Felt252],
+    nonce: Felt252,
+) -> [u8; 32] {
+    let header = [from_address, to_address, nonce, selector, Felt252::from(payload.len())];
+
+    let mut data = Vec::with_capacity(32 * (header.len() + payload.len()));
+    for word in header.iter().chain(payload) {
+        data.extend_from_slice(&word.to_bytes_be());
+    }
+
+    keccak256(&data)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case::empty(b"", "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470")]
+    #[case::short(b"abc", "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45")]
+    #[case::single_padding_byte(&[0x61; 135], "34367dc248bbd832f4e3e69dfaac2f92638bd0bbd18f2912ba4ef454919cf446")]
+    #[case::full_block(&[0x61; 136], "a6c4d403279fe3e0af03729caada8374b5ca54d8065329a3ebcaeb4b60aa386e")]
+    fn test_keccak256(#[case] data: &[u8], #[case] expected: &str) {
+        assert_eq!(hex::encode(keccak256(data)), expected);
+    }
+
+    #[test]
+    fn test_compute_l1_to_l2_message_hash() {
+        // Message of an L1 handler tx on mainnet
+        let from_address = Felt252::from_hex("0xc3511006c04ef1d78af4c8e0e74ec18a6e64ff9e").unwrap();
+        let to_address =
+            Felt252::from_hex("0x73314940630fd6dcda0d772d4c972c4e0a9946bef9dabf4ef84eda8ef542b82").unwrap();
+        let selector = Felt252::from_hex("0x2d757788a8d8d6f21d1cd40bce38a8222d70654214e96ff95d8086e684fbee5").unwrap();
+        let payload = [
+            Felt252::from_hex("0x689ead7d814e51ed93644bc145f0754839b8dcb340027ce0c30953f38f55d7").unwrap(),
+            Felt252::from_hex("0x2c68af0bb140000").unwrap(),
+            Felt252::ZERO,
+        ];
+        let nonce = Felt252::from(775628);
+
+        let message_hash = compute_l1_to_l2_message_hash(from_address, to_address, selector, &payload, nonce);
+
+        assert_eq!(hex::encode(message_hash), "c51a543ef9563ad2545342b390b67edfcddf9886aa36846cf70382362fc5fab3");
+    }
+}
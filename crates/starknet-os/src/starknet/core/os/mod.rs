@@ -1,3 +1,5 @@
+pub mod block_hash;
 pub mod contract_class;
 pub mod kzg_manager;
+pub mod messages;
 pub mod transaction_hash;
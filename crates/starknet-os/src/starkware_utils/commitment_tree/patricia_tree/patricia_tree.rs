@@ -15,7 +15,7 @@ use crate::storage::storage::{FactFetchingContext, HashFunctionType, Storage};
 
 pub const EMPTY_NODE_HASH: [u8; 32] = [0; 32];
 
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PatriciaTree {
     pub root: Hash,
     pub height: Height,
@@ -78,6 +78,7 @@ mod tests {
 
     use super::*;
     use crate::crypto::pedersen::PedersenHash;
+    use crate::crypto::poseidon::PoseidonHash;
     use crate::starknet::starknet_storage::StorageLeaf;
     use crate::starkware_utils::commitment_tree::base_types::{Length, NodePath};
     use crate::starkware_utils::commitment_tree::patricia_tree::nodes::{BinaryNodeFact, EdgeNodeFact};
@@ -211,4 +212,38 @@ mod tests {
         let leaf_from_tree: Option<StorageLeaf> = tree.get_leaf(&mut ffc, index).await.unwrap();
         assert_matches!(leaf_from_tree, Some(l) if l == leaf);
     }
+
+    async fn root_with_hash_function<H>(leaves: &[(u64, u64)]) -> Hash
+    where
+        H: HashFunctionType + Sync + Send + 'static,
+    {
+        let mut ffc = FactFetchingContext::<_, H>::new(DictStorage::default());
+        let tree = PatriciaTree::empty_tree(&mut ffc, Height(251), StorageLeaf::empty()).await.unwrap();
+
+        let modifications = leaves
+            .iter()
+            .map(|(index, value)| (BigUint::from(*index), StorageLeaf::new(Felt252::from(*value))))
+            .collect();
+        let mut facts = None;
+        let tree = tree.update(&mut ffc, modifications, &mut facts).await.unwrap();
+
+        tree.root
+    }
+
+    /// The tree is generic over the hash function: the same leaves give a different root with
+    /// Pedersen (storage tries) and Poseidon (classes trie).
+    #[tokio::test]
+    async fn test_root_depends_on_hash_function() {
+        let leaves = [(1000, 2000), (1001, 3000), (5, 7)];
+
+        let pedersen_root = root_with_hash_function::<PedersenHash>(&leaves).await;
+        let poseidon_root = root_with_hash_function::<PoseidonHash>(&leaves).await;
+
+        assert_ne!(pedersen_root, EMPTY_NODE_HASH);
+        assert_ne!(poseidon_root, EMPTY_NODE_HASH);
+        assert_ne!(pedersen_root, poseidon_root);
+
+        // Sanity check: the root only depends on the leaves and the hash function
+        assert_eq!(root_with_hash_function::<PoseidonHash>(&leaves).await, poseidon_root);
+    }
 }
@@ -4,3 +4,4 @@ pub mod dict_storage;
 #[allow(clippy::module_inception)] // Use the same name as the parent module
 pub mod storage;
 pub mod storage_utils;
+pub mod witness_storage;
@@ -16,6 +16,9 @@ pub enum StorageError {
     #[error("Content not found in storage")]
     ContentNotFound,
 
+    #[error("Invalid proof for key {key:#x}: {reason}")]
+    InvalidProof { key: Felt252, reason: String },
+
     #[error(transparent)]
     Deserialize(#[from] DeserializeError),
 
@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use cairo_vm::Felt252;
+use futures_util::FutureExt;
+use num_bigint::BigUint;
+use num_traits::One;
+
+use crate::starknet::starknet_storage::StorageLeaf;
+use crate::starkware_utils::commitment_tree::base_types::Height;
+use crate::starkware_utils::commitment_tree::patricia_tree::nodes::PatriciaNodeFact;
+use crate::starkware_utils::serializable::Serializable;
+use crate::storage::storage::{DbObject, Fact, HashFunctionType, Storage, StorageError};
+
+/// A storage value along with the Merkle proof of its inclusion in a storage trie. The proof
+/// lists the nodes on the path from the root to the leaf, root first.
+pub struct WitnessEntry {
+    pub key: Felt252,
+    pub value: Felt252,
+    pub proof: Vec<PatriciaNodeFact>,
+}
+
+/// A storage backed by Merkle proofs only, for stateless runs.
+///
+/// Reads of trie nodes and leaves are served from the proofs of the witness entries, and each
+/// proof is verified against the root of the trie before any of its nodes is served. Reading
+/// anything that is not part of the witness fails with `StorageError::ContentNotFound` instead
+/// of returning `None`: a missing fact means that the witness is incomplete.
+///
+/// Facts written by the run (e.g. when computing the updated root) are kept in memory and
+/// served as is.
+pub struct WitnessStorage<H: HashFunctionType> {
+    root: Felt252,
+    height: Height,
+    entries: HashMap<Felt252, WitnessEntry>,
+    /// Serialized proof nodes by hash, along with the key of the entry whose proof holds them.
+    nodes: HashMap<Felt252, (Vec<u8>, Felt252)>,
+    /// Keys of the entries by value, to serve leaves.
+    keys_by_value: HashMap<Felt252, Felt252>,
+    written_facts: HashMap<Vec<u8>, Vec<u8>>,
+    _h: PhantomData<fn() -> H>,
+}
+
+impl<H> WitnessStorage<H>
+where
+    H: HashFunctionType,
+{
+    pub fn new(root: Felt252, height: Height, entries: Vec<WitnessEntry>) -> Result<Self, StorageError> {
+        let mut nodes = HashMap::new();
+        let mut keys_by_value = HashMap::new();
+        for entry in &entries {
+            for node in &entry.proof {
+                let node_hash = Felt252::from_bytes_be_slice(&<PatriciaNodeFact as Fact<Self, H>>::hash(node));
+                nodes.insert(node_hash, (node.serialize()?, entry.key));
+            }
+            keys_by_value.insert(entry.value, entry.key);
+        }
+        let entries = entries.into_iter().map(|entry| (entry.key, entry)).collect();
+
+        Ok(Self { root, height, entries, nodes, keys_by_value, written_facts: HashMap::new(), _h: Default::default() })
+    }
+
+    /// Returns the value at `key`, after verifying its proof.
+    pub fn read(&self, key: Felt252) -> Result<Felt252, StorageError> {
+        let entry = self.entries.get(&key).ok_or(StorageError::ContentNotFound)?;
+        self.verify(entry)?;
+        Ok(entry.value)
+    }
+
+    /// Checks that the proof of `entry` leads from the root of the trie to its value. A proof
+    /// ending with an edge that diverges from the key proves that the key is not in the trie,
+    /// which is only valid for a zero value.
+    fn verify(&self, entry: &WitnessEntry) -> Result<(), StorageError> {
+        let invalid_proof = |reason: String| StorageError::InvalidProof { key: entry.key, reason };
+
+        let key = entry.key.to_biguint();
+        let mut expected_hash = self.root;
+        let mut depth = 0u64;
+
+        for (i, node) in entry.proof.iter().enumerate() {
+            let node_hash = Felt252::from_bytes_be_slice(&<PatriciaNodeFact as Fact<Self, H>>::hash(node));
+            if node_hash != expected_hash {
+                return Err(invalid_proof(format!("node {i} has hash {node_hash:#x}, expected {expected_hash:#x}")));
+            }
+
+            match node {
+                PatriciaNodeFact::Binary(binary) => {
+                    if depth >= self.height.0 {
+                        return Err(invalid_proof(format!("binary node {i} is below the leaves")));
+                    }
+                    let is_right = key.bit(self.height.0 - depth - 1);
+                    let child = if is_right { &binary.right_node } else { &binary.left_node };
+                    expected_hash = Felt252::from_bytes_be_slice(child);
+                    depth += 1;
+                }
+                PatriciaNodeFact::Edge(edge) => {
+                    let length = edge.edge_length.0;
+                    if depth + length > self.height.0 {
+                        return Err(invalid_proof(format!("edge at node {i} goes below the leaves")));
+                    }
+                    let key_path = (&key >> (self.height.0 - depth - length)) & ((BigUint::one() << length) - 1u32);
+                    if key_path != edge.edge_path.0 {
+                        let is_last_node = i + 1 == entry.proof.len();
+                        if is_last_node && entry.value == Felt252::ZERO {
+                            return Ok(());
+                        }
+                        return Err(invalid_proof(format!("edge at node {i} does not lead to the key")));
+                    }
+                    expected_hash = Felt252::from_bytes_be_slice(&edge.bottom_node);
+                    depth += length;
+                }
+                PatriciaNodeFact::Empty(_) => {
+                    return Err(invalid_proof(format!("node {i} is an empty node")));
+                }
+            }
+        }
+
+        // An empty proof is only valid for an empty trie, whose root is the (zero) empty leaf
+        if depth != self.height.0 && !entry.proof.is_empty() {
+            return Err(invalid_proof(format!("the proof stops at depth {depth}")));
+        }
+        if expected_hash != entry.value {
+            return Err(invalid_proof(format!("the proof leads to {expected_hash:#x}, not to the value")));
+        }
+
+        Ok(())
+    }
+
+    fn get_witness_value(&self, key: &[u8]) -> Result<Vec<u8>, StorageError> {
+        if let Some(suffix) = key.strip_prefix(PatriciaNodeFact::db_key(&[]).as_slice()) {
+            let node_hash = Felt252::from_bytes_be_slice(suffix);
+            let (node, entry_key) = self.nodes.get(&node_hash).ok_or(StorageError::ContentNotFound)?;
+            self.read(*entry_key)?;
+            return Ok(node.clone());
+        }
+
+        if let Some(suffix) = key.strip_prefix(StorageLeaf::db_key(&[]).as_slice()) {
+            // The hash of a storage leaf is its value, and the empty leaf needs no proof
+            let value = Felt252::from_bytes_be_slice(suffix);
+            if value == Felt252::ZERO {
+                return Ok(StorageLeaf::empty().serialize()?);
+            }
+            let entry_key = self.keys_by_value.get(&value).ok_or(StorageError::ContentNotFound)?;
+            self.read(*entry_key)?;
+            return Ok(StorageLeaf::new(value).serialize()?);
+        }
+
+        Err(StorageError::ContentNotFound)
+    }
+}
+
+impl<H> Storage for WitnessStorage<H>
+where
+    H: HashFunctionType,
+{
+    async fn set_value(&mut self, key: Vec<u8>, value: Vec<u8>) -> Result<(), StorageError> {
+        self.written_facts.insert(key, value);
+        Ok(())
+    }
+
+    fn get_value(&self, key: &[u8]) -> impl futures::Future<Output = Result<Option<Vec<u8>>, StorageError>> + Send {
+        let result = match self.written_facts.get(key) {
+            Some(value) => Ok(Some(value.clone())),
+            None => self.get_witness_value(key).map(Some),
+        };
+        async move { result }.boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use starknet_os_types::hash::Hash;
+
+    use super::*;
+    use crate::crypto::pedersen::PedersenHash;
+    use crate::starknet::starknet_storage::{OsSingleStarknetStorage, PerContractStorage};
+    use crate::starkware_utils::commitment_tree::binary_fact_tree::BinaryFactTree;
+    use crate::starkware_utils::commitment_tree::patricia_tree::nodes::BinaryNodeFact;
+    use crate::starkware_utils::commitment_tree::patricia_tree::patricia_tree::PatriciaTree;
+    use crate::storage::dict_storage::DictStorage;
+    use crate::storage::storage::FactFetchingContext;
+
+    const HEIGHT: Height = Height(251);
+
+    /// Builds a storage trie holding `leaves` and returns its root along with the witness of
+    /// each leaf.
+    async fn build_witness(leaves: &[(u64, u64)]) -> (Felt252, Vec<WitnessEntry>) {
+        let mut ffc = FactFetchingContext::<_, PedersenHash>::new(DictStorage::default());
+        let tree = PatriciaTree::empty_tree(&mut ffc, HEIGHT, StorageLeaf::empty()).await.unwrap();
+        let modifications =
+            leaves.iter().map(|(key, value)| (BigUint::from(*key), StorageLeaf::new(Felt252::from(*value)))).collect();
+        let tree = tree.update(&mut ffc, modifications, &mut None).await.unwrap();
+        let root = Felt252::from_bytes_be_slice(&tree.root);
+
+        let storage = ffc.acquire_storage().await;
+        let mut entries = vec![];
+        for (key, value) in leaves {
+            let key = BigUint::from(*key);
+            let mut proof = vec![];
+            let mut node_hash = tree.root;
+            let mut depth = 0;
+            while depth < HEIGHT.0 {
+                let node = PatriciaNodeFact::get_or_fail(&*storage, &node_hash).await.unwrap();
+                match &node {
+                    PatriciaNodeFact::Binary(binary) => {
+                        let is_right = key.bit(HEIGHT.0 - depth - 1);
+                        node_hash = if is_right { binary.right_node } else { binary.left_node };
+                        depth += 1;
+                    }
+                    PatriciaNodeFact::Edge(edge) => {
+                        node_hash = edge.bottom_node;
+                        depth += edge.edge_length.0;
+                    }
+                    PatriciaNodeFact::Empty(_) => unreachable!("the leaves are not empty"),
+                }
+                proof.push(node);
+            }
+            entries.push(WitnessEntry { key: Felt252::from(&key), value: Felt252::from(*value), proof });
+        }
+
+        (root, entries)
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_read_from_witness() {
+        let (root, entries) = build_witness(&[(1, 10), (2, 20), (0x1234, 30)]).await;
+        let witness = WitnessStorage::<PedersenHash>::new(root, HEIGHT, entries.into_iter().skip(1).collect()).unwrap();
+
+        assert_eq!(witness.read(Felt252::from(2)).unwrap(), Felt252::from(20));
+
+        // The witness can back the storage of a contract in the OS
+        let tree = PatriciaTree { root: Hash::from(root), height: HEIGHT };
+        let ffc = FactFetchingContext::<_, PedersenHash>::new(witness);
+        let mut contract_storage = OsSingleStarknetStorage::new(tree.clone(), tree, &[], ffc).await.unwrap();
+        assert_eq!(contract_storage.read(BigUint::from(0x1234u32)).await, Some(Felt252::from(30)));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_read_missing_key() {
+        let (root, entries) = build_witness(&[(1, 10), (2, 20)]).await;
+        let witness = WitnessStorage::<PedersenHash>::new(root, HEIGHT, entries.into_iter().skip(1).collect()).unwrap();
+
+        assert!(matches!(witness.read(Felt252::from(1)), Err(StorageError::ContentNotFound)));
+
+        let tree = PatriciaTree { root: Hash::from(root), height: HEIGHT };
+        let mut ffc = FactFetchingContext::<_, PedersenHash>::new(witness);
+        let result: Result<Option<StorageLeaf>, _> = tree.get_leaf(&mut ffc, BigUint::from(1u32)).await;
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_read_invalid_proof() {
+        let (root, mut entries) = build_witness(&[(1, 10), (2, 20)]).await;
+        entries[1].value = Felt252::from(21);
+        let witness = WitnessStorage::<PedersenHash>::new(root, HEIGHT, entries).unwrap();
+
+        assert_eq!(witness.read(Felt252::from(1)).unwrap(), Felt252::from(10));
+        assert!(matches!(witness.read(Felt252::from(2)), Err(StorageError::InvalidProof { .. })));
+    }
+
+    #[rstest]
+    fn test_read_proof_longer_than_the_trie() {
+        let node_hash = |node: &PatriciaNodeFact| {
+            <PatriciaNodeFact as Fact<WitnessStorage<PedersenHash>, PedersenHash>>::hash(node)
+        };
+
+        // A trie of height 1 whose left leaf is also the hash of a binary node
+        let below_leaves = PatriciaNodeFact::Binary(BinaryNodeFact {
+            left_node: Hash::from(Felt252::from(1)),
+            right_node: Hash::from(Felt252::from(2)),
+        });
+        let left_leaf = node_hash(&below_leaves);
+        let root_node =
+            PatriciaNodeFact::Binary(BinaryNodeFact { left_node: left_leaf, right_node: Hash::from(Felt252::from(3)) });
+        let root = Felt252::from_bytes_be_slice(&node_hash(&root_node));
+
+        let entry = WitnessEntry { key: Felt252::ZERO, value: Felt252::from(1), proof: vec![root_node, below_leaves] };
+        let witness = WitnessStorage::<PedersenHash>::new(root, Height(1), vec![entry]).unwrap();
+
+        assert!(matches!(witness.read(Felt252::ZERO), Err(StorageError::InvalidProof { .. })));
+    }
+}
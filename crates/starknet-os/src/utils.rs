@@ -3,13 +3,30 @@ use std::collections::HashMap;
 use cairo_vm::types::exec_scope::ExecutionScopes;
 use cairo_vm::vm::errors::hint_errors::HintError;
 use cairo_vm::{any_box, Felt252};
+use num_bigint::BigUint;
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Number;
 use serde_with::{DeserializeAs, SerializeAs};
 use starknet_api::core::ChainId;
+use starknet_api::hash::starknet_keccak_hash;
 use starknet_os_types::chain_id::chain_id_to_felt;
 use tokio::task;
 
+/// Parses a felt from its digits in the given radix. Values outside of the field are rejected
+/// instead of being reduced modulo the prime.
+fn felt_from_str_radix(digits: &str, radix: u32) -> Result<Felt252, String> {
+    if digits.starts_with('-') {
+        return Err(format!("{digits} is negative"));
+    }
+    let value =
+        BigUint::parse_bytes(digits.as_bytes(), radix).ok_or_else(|| format!("{digits:?} is not a valid number"))?;
+    let felt = Felt252::from(&value);
+    if felt.to_biguint() != value {
+        return Err(format!("{digits} is out of range, felts must be lower than the field prime"));
+    }
+    Ok(felt)
+}
+
 pub(crate) struct Felt252Str;
 
 impl<'de> DeserializeAs<'de, Felt252> for Felt252Str {
@@ -20,7 +37,7 @@ impl<'de> DeserializeAs<'de, Felt252> for Felt252Str {
         let felt_str = String::deserialize(deserializer)?;
         let felt_str = felt_str.trim_start_matches("0x");
 
-        Felt252::from_hex(felt_str).map_err(|e| de::Error::custom(format!("felt from hex str parse error: {e}")))
+        felt_from_str_radix(felt_str, 16).map_err(|e| de::Error::custom(format!("felt from hex str parse error: {e}")))
     }
 }
 
@@ -42,10 +59,8 @@ impl<'de> DeserializeAs<'de, Felt252> for Felt252Num {
     {
         let felt_num = Number::deserialize(deserializer)?;
 
-        match Felt252::from_dec_str(&felt_num.to_string()) {
-            Ok(x) => Ok(x),
-            Err(e) => Err(de::Error::custom(format!("felt_from_number parse error: {e}"))),
-        }
+        felt_from_str_radix(&felt_num.to_string(), 10)
+            .map_err(|e| de::Error::custom(format!("felt_from_number parse error: {e}")))
     }
 }
 
@@ -67,7 +82,7 @@ impl<'de> DeserializeAs<'de, Felt252> for Felt252HexNoPrefix {
         D: Deserializer<'de>,
     {
         let felt_str = String::deserialize(deserializer)?;
-        Felt252::from_hex(&format!("0x{felt_str}")).map_err(de::Error::custom)
+        felt_from_str_radix(&felt_str, 16).map_err(de::Error::custom)
     }
 }
 
@@ -158,6 +173,19 @@ where
     exec_scopes.data[0].insert(name.to_string(), any_box!(value));
 }
 
+/// Name of the default entry point of Cairo 0 contracts, and of their default L1 handler.
+const DEFAULT_ENTRY_POINT_NAME: &str = "__default__";
+const DEFAULT_L1_ENTRY_POINT_NAME: &str = "__l1_default__";
+
+/// Computes the selector of an entry point from its name: the `starknet_keccak` of the name,
+/// i.e. its Keccak256 hash masked to 250 bits. Default entry points have selector 0.
+pub fn get_selector_from_name(name: &str) -> Felt252 {
+    if name == DEFAULT_ENTRY_POINT_NAME || name == DEFAULT_L1_ENTRY_POINT_NAME {
+        return Felt252::ZERO;
+    }
+    starknet_keccak_hash(name.as_bytes())
+}
+
 /// Builds a custom hint error
 pub(crate) fn custom_hint_error<S: Into<String>>(error: S) -> HintError {
     HintError::CustomHint(error.into().into_boxed_str())
@@ -165,6 +193,7 @@ pub(crate) fn custom_hint_error<S: Into<String>>(error: S) -> HintError {
 
 #[cfg(test)]
 mod tests {
+    use rstest::rstest;
     use serde_with::serde_as;
 
     use super::*;
@@ -181,4 +210,51 @@ mod tests {
         let c = ChainIdOnly { chain_id: ChainId::Sepolia };
         serde_json::to_string(&c).unwrap();
     }
+
+    #[rstest]
+    #[case::execute("__execute__", "0x15d40a3d6ca2ac30f4031e42be28da9b056fef9bb7357ac5e85627ee876e5ad")]
+    #[case::validate("__validate__", "0x162da33a4585851fe8d3af3c2a9c60b557814e221e0d4f30ff0b2189d9c7775")]
+    #[case::transfer("transfer", "0x83afd3f4caedc6eebf44246fe54e38c95e3179a5ec9ea81740eca5b482d12e")]
+    #[case::balance_of("balanceOf", "0x2e4263afad30923c891518314c3c95dbe830a16874e8abc5777a9a20b54c76e")]
+    #[case::default("__default__", "0x0")]
+    #[case::l1_default("__l1_default__", "0x0")]
+    fn test_get_selector_from_name(#[case] name: &str, #[case] expected_selector: &str) {
+        assert_eq!(get_selector_from_name(name), Felt252::from_hex_unchecked(expected_selector));
+    }
+
+    const FIELD_PRIME: &str = "3618502788666131213697322783095070105623107215331596699973092056135872020481";
+    const MAX_FELT: &str = "3618502788666131213697322783095070105623107215331596699973092056135872020480";
+
+    #[serde_as]
+    #[derive(Deserialize)]
+    struct FeltNum(#[serde_as(as = "Felt252Num")] Felt252);
+
+    #[serde_as]
+    #[derive(Deserialize)]
+    struct FeltStr(#[serde_as(as = "Felt252Str")] Felt252);
+
+    #[test]
+    fn felt252_num_accepts_max_felt() {
+        let FeltNum(felt) = serde_json::from_str(MAX_FELT).unwrap();
+        assert_eq!(felt, Felt252::MAX);
+    }
+
+    #[rstest]
+    #[case::field_prime(FIELD_PRIME, "out of range")]
+    #[case::negative("-1", "negative")]
+    fn felt252_num_rejects_out_of_range(#[case] value: &str, #[case] expected_error: &str) {
+        let err = serde_json::from_str::<FeltNum>(value).err().unwrap();
+        assert!(err.to_string().contains(expected_error), "Unexpected error: {err}");
+    }
+
+    #[test]
+    fn felt252_str_range() {
+        let max_felt = format!("\"{}\"", Felt252::MAX.to_hex_string());
+        let FeltStr(felt) = serde_json::from_str(&max_felt).unwrap();
+        assert_eq!(felt, Felt252::MAX);
+
+        let field_prime = "\"0x800000000000011000000000000000000000000000000000000000000000001\"";
+        let err = serde_json::from_str::<FeltStr>(field_prime).err().unwrap();
+        assert!(err.to_string().contains("out of range"), "Unexpected error: {err}");
+    }
 }
@@ -0,0 +1,199 @@
+//! Benchmark of the loading of a large OS input, in both of the supported formats: JSON and the
+//! binary (MessagePack) format selected by the `.bin` extension.
+//!
+//! Besides the load time, the peak memory allocated while loading is measured, to compare
+//! `StarknetOsInput::load`, which reads the whole file and parses it to a JSON value first, with
+//! `StarknetOsInput::load_streaming`, which deserializes the input as it reads the file.
+//!
+//! Run with `cargo bench -p tests --bench load_input`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use cairo_vm::Felt252;
+use criterion::measurement::{Measurement, ValueFormatter};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use starknet_os::io::input::{StarknetOsInput, BINARY_INPUT_EXTENSION};
+use starknet_os::io::InternalTransaction;
+
+/// Number of transactions of the benchmarked input.
+const N_TRANSACTIONS: u64 = 50_000;
+/// Number of contracts of the benchmarked input.
+const N_CONTRACTS: u64 = 200_000;
+
+/// Allocator keeping track of the memory allocated by the benchmark, and of its peak.
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK_ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK_ALLOCATED.fetch_max(allocated, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Measures the peak memory allocated during an iteration, in bytes, on top of what was already
+/// allocated when it started.
+struct PeakMemory;
+
+impl Measurement for PeakMemory {
+    type Intermediate = usize;
+    type Value = usize;
+
+    fn start(&self) -> Self::Intermediate {
+        let allocated = ALLOCATED.load(Ordering::Relaxed);
+        PEAK_ALLOCATED.store(allocated, Ordering::Relaxed);
+        allocated
+    }
+
+    fn end(&self, allocated_at_start: Self::Intermediate) -> Self::Value {
+        PEAK_ALLOCATED.load(Ordering::Relaxed) - allocated_at_start
+    }
+
+    fn add(&self, v1: &Self::Value, v2: &Self::Value) -> Self::Value {
+        v1 + v2
+    }
+
+    fn zero(&self) -> Self::Value {
+        0
+    }
+
+    fn to_f64(&self, value: &Self::Value) -> f64 {
+        *value as f64
+    }
+
+    fn formatter(&self) -> &dyn ValueFormatter {
+        &BytesFormatter
+    }
+}
+
+struct BytesFormatter;
+
+impl ValueFormatter for BytesFormatter {
+    fn scale_values(&self, typical_value: f64, values: &mut [f64]) -> &'static str {
+        let (factor, unit) = match typical_value {
+            v if v >= 1e9 => (1e-9, "GB"),
+            v if v >= 1e6 => (1e-6, "MB"),
+            v if v >= 1e3 => (1e-3, "KB"),
+            _ => (1.0, "B"),
+        };
+        values.iter_mut().for_each(|value| *value *= factor);
+        unit
+    }
+
+    fn scale_throughputs(&self, _typical_value: f64, _throughput: &Throughput, _values: &mut [f64]) -> &'static str {
+        // The benchmarks of this file do not set a throughput
+        "B"
+    }
+
+    fn scale_for_machines(&self, _values: &mut [f64]) -> &'static str {
+        "B"
+    }
+}
+
+/// An input with many transactions and contracts, the parts of the input that grow with the block.
+fn large_os_input() -> StarknetOsInput {
+    let transactions = (0..N_TRANSACTIONS)
+        .map(|i| InternalTransaction {
+            hash_value: Felt252::from(i),
+            version: Some(Felt252::ONE),
+            sender_address: Some(Felt252::from(0x100)),
+            calldata: Some(vec![Felt252::ONE, Felt252::TWO]),
+            signature: Some(vec![Felt252::THREE]),
+            r#type: "INVOKE_FUNCTION".to_string(),
+            ..Default::default()
+        })
+        .collect();
+    let contract_address_to_class_hash = (0..N_CONTRACTS).map(|i| (Felt252::from(i), Felt252::from(i + 1))).collect();
+
+    StarknetOsInput { transactions, contract_address_to_class_hash, ..Default::default() }
+}
+
+/// Dumps the input in both formats, returns the paths of the JSON and binary files.
+fn dump_os_input(os_input: &StarknetOsInput) -> [PathBuf; 2] {
+    let json_path = std::env::temp_dir().join(format!("os_input_{}.json", uuid::Uuid::new_v4()));
+    let binary_path = json_path.with_extension(BINARY_INPUT_EXTENSION);
+    os_input.dump(&json_path).expect("Failed to dump the input as JSON");
+    os_input.dump(&binary_path).expect("Failed to dump the input as binary");
+
+    [json_path, binary_path]
+}
+
+fn format_name(path: &Path) -> String {
+    path.extension().unwrap_or_default().to_string_lossy().into_owned()
+}
+
+fn bench_load_time(c: &mut Criterion) {
+    let paths = dump_os_input(&large_os_input());
+
+    let mut group = c.benchmark_group("load_input");
+    group.sample_size(10);
+    for path in &paths {
+        group.bench_with_input(BenchmarkId::from_parameter(format_name(path)), path, |b, path| {
+            b.iter(|| StarknetOsInput::load_streaming(path).expect("Failed to load the input"))
+        });
+    }
+    group.finish();
+
+    for path in &paths {
+        std::fs::remove_file(path).unwrap();
+    }
+}
+
+fn bench_load_peak_memory(c: &mut Criterion<PeakMemory>) {
+    let [json_path, binary_path] = dump_os_input(&large_os_input());
+
+    let mut group = c.benchmark_group("load_input_peak_memory");
+    group.sample_size(10);
+    // Criterion divides the measurement of a sample by its number of iterations: the peaks of the
+    // iterations are summed, so that their average is reported
+    let mut bench_loader = |name: &str, path: &Path, load: fn(&Path) -> StarknetOsInput| {
+        group.bench_function(name, |b| {
+            b.iter_custom(|iters| {
+                (0..iters)
+                    .map(|_| {
+                        let allocated_at_start = PeakMemory.start();
+                        let os_input = load(path);
+                        let peak = PeakMemory.end(allocated_at_start);
+                        drop(os_input);
+                        peak
+                    })
+                    .sum()
+            })
+        });
+    };
+    bench_loader("json/load", &json_path, |path| StarknetOsInput::load(path).expect("Failed to load the input"));
+    bench_loader("json/load_streaming", &json_path, |path| {
+        StarknetOsInput::load_streaming(path).expect("Failed to load the input")
+    });
+    bench_loader("bin/load_streaming", &binary_path, |path| {
+        StarknetOsInput::load_streaming(path).expect("Failed to load the input")
+    });
+    group.finish();
+
+    std::fs::remove_file(&json_path).unwrap();
+    std::fs::remove_file(&binary_path).unwrap();
+}
+
+criterion_group!(benches, bench_load_time);
+criterion_group! {
+    name = peak_memory_benches;
+    config = Criterion::default().with_measurement(PeakMemory);
+    targets = bench_load_peak_memory
+}
+criterion_main!(benches, peak_memory_benches);
@@ -0,0 +1,101 @@
+//! End-to-end benchmark of an OS run on synthetic blocks of increasing size.
+//!
+//! The benchmark runs the compiled OS program at `build/os_latest.json`, which is produced by
+//! `scripts/setup-tests.sh`, like for the integration tests. Only `run_os` is measured: the
+//! execution of the transactions with the blockifier, needed to build the OS input, is done
+//! outside of the measurement.
+//!
+//! Run with `cargo bench -p tests --bench run_os`.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use blockifier::abi::abi_utils::selector_from_name;
+use blockifier::context::BlockContext;
+use blockifier::transaction::test_utils::max_fee;
+use blockifier::transaction::transaction_execution::Transaction;
+use blockifier::transaction::transactions::L1HandlerTransaction;
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use starknet_api::core::{ContractAddress, EntryPointSelector, Nonce};
+use starknet_api::felt;
+use starknet_api::transaction::{Calldata, TransactionVersion};
+use starknet_os::config::default_layout;
+use starknet_os::run_os;
+use tokio::runtime::Runtime;
+
+#[allow(dead_code, unused_imports)]
+#[path = "../integration/common/mod.rs"]
+mod common;
+
+use common::state::initial_state_cairo0;
+use common::transaction_utils::execute_txs;
+
+/// Number of transactions of the benchmarked blocks.
+const BLOCK_SIZES: [usize; 3] = [1, 10, 50];
+
+/// A block of `n_txs` L1 handler txs calling `test_contract`, each with its own message nonce.
+fn synthetic_block_txs(contract_address: ContractAddress, n_txs: usize) -> Vec<Transaction> {
+    (0..n_txs)
+        .map(|i| {
+            L1HandlerTransaction {
+                paid_fee_on_l1: max_fee(),
+                tx: starknet_api::transaction::L1HandlerTransaction {
+                    contract_address,
+                    version: TransactionVersion::ZERO,
+                    nonce: Nonce(felt!(i as u64)),
+                    entry_point_selector: EntryPointSelector(selector_from_name("l1_handle").0),
+                    calldata: Calldata(Arc::new(vec![felt!(1234_u16), felt!(i as u64)])),
+                },
+                tx_hash: Default::default(),
+            }
+            .into()
+        })
+        .collect()
+}
+
+fn bench_run_os(c: &mut Criterion) {
+    let runtime = Runtime::new().expect("Failed to start the tokio runtime");
+
+    let mut group = c.benchmark_group("run_os");
+    // A single OS run takes seconds, keep the number of samples to the minimum
+    group.sample_size(10);
+
+    for n_txs in BLOCK_SIZES {
+        group.bench_with_input(BenchmarkId::from_parameter(n_txs), &n_txs, |b, &n_txs| {
+            b.iter_batched(
+                || {
+                    runtime.block_on(async {
+                        let block_context = BlockContext::create_for_account_testing();
+                        let initial_state = initial_state_cairo0(block_context.clone(), ()).await;
+                        let contract_address =
+                            initial_state.deployed_cairo0_contracts.get("test_contract").unwrap().address;
+
+                        let (os_input, execution_helper) = execute_txs(
+                            initial_state.cached_state,
+                            &block_context,
+                            synthetic_block_txs(contract_address, n_txs),
+                            initial_state.cairo0_compiled_classes,
+                            initial_state.cairo1_compiled_classes,
+                            HashMap::default(),
+                        )
+                        .await;
+                        (os_input, block_context, execution_helper)
+                    })
+                },
+                |(os_input, block_context, execution_helper)| {
+                    // The hints run coroutines, which requires being inside the runtime
+                    runtime.block_on(async {
+                        run_os(common::DEFAULT_COMPILED_OS, default_layout(), os_input, block_context, execution_helper)
+                            .expect("OS run failed")
+                    })
+                },
+                BatchSize::PerIteration,
+            );
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_run_os);
+criterion_main!(benches);
@@ -13,7 +13,7 @@ use starknet_os::config::{StarknetGeneralConfig, StarknetOsConfig, STORED_BLOCK_
 use starknet_os::crypto::pedersen::PedersenHash;
 use starknet_os::crypto::poseidon::PoseidonHash;
 use starknet_os::execution::helper::ExecutionHelperWrapper;
-use starknet_os::io::input::StarknetOsInput;
+use starknet_os::io::input::{StarknetOsInput, OS_INPUT_SCHEMA_VERSION};
 use starknet_os::io::InternalTransaction;
 use starknet_os::starknet::business_logic::fact_state::contract_class_objects::ContractClassLeaf;
 use starknet_os::starknet::business_logic::fact_state::contract_state_objects::ContractState;
@@ -213,7 +213,9 @@ where
         declared_class_hash_to_component_hashes,
         new_block_hash: Default::default(),
         prev_block_hash: Default::default(),
+        block_hashes: Default::default(),
         full_output: false,
+        schema_version: OS_INPUT_SCHEMA_VERSION,
     });
 
     let execution_helper = ExecutionHelperWrapper::new(
@@ -1,9 +1,10 @@
 use std::collections::HashMap;
+use std::path::Path;
 use std::rc::Rc;
 
 use blockifier::abi::abi_utils::selector_from_name;
 use blockifier::context::BlockContext;
-use blockifier::state::cached_state::CachedState;
+use blockifier::state::cached_state::{CachedState, CommitmentStateDiff};
 use blockifier::state::state_api::State;
 use blockifier::transaction::account_transaction::AccountTransaction;
 use blockifier::transaction::account_transaction::AccountTransaction::{Declare, DeployAccount, Invoke};
@@ -24,20 +25,21 @@ use starknet_api::transaction::{
 };
 use starknet_api::{contract_address, felt, patricia_key};
 use starknet_crypto::{pedersen_hash, FieldElement};
-use starknet_os::config::{BLOCK_HASH_CONTRACT_ADDRESS, STORED_BLOCK_HASH_BUFFER};
+use starknet_os::config::{OsRunConfig, BLOCK_HASH_CONTRACT_ADDRESS, STORED_BLOCK_HASH_BUFFER};
 use starknet_os::crypto::pedersen::PedersenHash;
 use starknet_os::crypto::poseidon::poseidon_hash_many_bytes;
-use starknet_os::error::SnOsError;
 use starknet_os::error::SnOsError::Runner;
+use starknet_os::error::{OsRunError, SnOsError};
 use starknet_os::execution::helper::ExecutionHelperWrapper;
+use starknet_os::hints::SnosHintProcessor;
 use starknet_os::io::input::StarknetOsInput;
-use starknet_os::io::output::StarknetOsOutput;
+use starknet_os::io::output::{OsOutput, StarknetOsOutput};
 use starknet_os::io::InternalTransaction;
 use starknet_os::starknet::business_logic::fact_state::state::SharedState;
 use starknet_os::starknet::core::os::transaction_hash::{L1_GAS, L2_GAS};
 use starknet_os::starknet::starknet_storage::OsSingleStarknetStorage;
 use starknet_os::storage::storage::Storage;
-use starknet_os::{config, run_os};
+use starknet_os::{config, run_os, run_os_from_path, run_os_with_hint_processor, run_os_with_program, CompiledOs};
 use starknet_os_types::casm_contract_class::GenericCasmContractClass;
 use starknet_os_types::chain_id::chain_id_to_felt;
 use starknet_os_types::class_hash_utils::ContractClassComponentHashes;
@@ -824,7 +826,8 @@ fn get_tx_hash(tx: &Transaction) -> TransactionHash {
     }
 }
 
-async fn execute_txs<S>(
+/// Executes the txs with the blockifier and builds the OS input and execution helper of the block.
+pub async fn execute_txs<S>(
     mut state: CachedState<SharedState<S, PedersenHash>>,
     block_context: &BlockContext,
     txs: Vec<Transaction>,
@@ -931,6 +934,145 @@ where
     result
 }
 
+/// Runs the OS over the tx at `tx_index` only, e.g. to bisect a failing block.
+///
+/// The OS program executes every tx of its input, so the other txs are left out of the input
+/// instead. The txs before `tx_index` are executed with the blockifier and their state diff is
+/// committed to the initial state of the block: the tx runs on the same state as in the full
+/// block, and the initial root of the run is the root after the preceding txs. The txs after
+/// `tx_index` are not executed.
+pub async fn execute_single_tx_and_run_os<S>(
+    compiled_os: &[u8],
+    mut state: CachedState<SharedState<S, PedersenHash>>,
+    block_context: BlockContext,
+    mut txs: Vec<Transaction>,
+    tx_index: usize,
+    deprecated_compiled_contract_classes: HashMap<ClassHash, GenericDeprecatedCompiledClass>,
+    compiled_contract_classes: HashMap<ClassHash, GenericCasmContractClass>,
+    declared_class_hash_to_component_hashes: HashMap<ClassHash, ContractClassComponentHashes>,
+) -> Result<(CairoPie, StarknetOsOutput), SnOsError>
+where
+    S: Storage,
+{
+    assert!(tx_index < txs.len(), "No tx at index {} in a block of {} txs", tx_index, txs.len());
+    let tx = txs.drain(tx_index..).next().unwrap();
+
+    for (index, preceding_tx) in txs.into_iter().enumerate() {
+        let tx_hash = get_tx_hash(&preceding_tx).to_hex_string();
+        let info = preceding_tx
+            .execute(&mut state, &block_context, true, true)
+            .unwrap_or_else(|e| panic!("Transaction {} ({}) failed in blockifier: {}", tx_hash, index, e));
+        assert!(!info.is_reverted(), "Transaction {} ({}) reverted: {:?}", tx_hash, index, info.revert_error);
+    }
+
+    let state_diff = CommitmentStateDiff::from(state.to_state_diff().expect("unable to generate state diff"));
+    let shared_state =
+        state.state.apply_commitment_state_diff(state_diff).await.expect("Failed to commit the preceding txs");
+
+    execute_txs_and_run_os(
+        compiled_os,
+        CachedState::new(shared_state),
+        block_context,
+        vec![tx],
+        deprecated_compiled_contract_classes,
+        compiled_contract_classes,
+        declared_class_hash_to_component_hashes,
+    )
+    .await
+}
+
+/// Same as `execute_txs_and_run_os`, but goes through the file-based entrypoint: the OS input
+/// is dumped to a temporary file and the compiled OS is read from the build directory.
+pub async fn execute_txs_and_run_os_from_path<S>(
+    config: OsRunConfig,
+    state: CachedState<SharedState<S, PedersenHash>>,
+    block_context: BlockContext,
+    txs: Vec<Transaction>,
+    deprecated_compiled_contract_classes: HashMap<ClassHash, GenericDeprecatedCompiledClass>,
+    compiled_contract_classes: HashMap<ClassHash, GenericCasmContractClass>,
+    declared_class_hash_to_component_hashes: HashMap<ClassHash, ContractClassComponentHashes>,
+) -> Result<OsOutput, OsRunError>
+where
+    S: Storage,
+{
+    let (os_input, execution_helper) = execute_txs(
+        state,
+        &block_context,
+        txs,
+        deprecated_compiled_contract_classes,
+        compiled_contract_classes,
+        declared_class_hash_to_component_hashes,
+    )
+    .await;
+
+    let input_path = std::env::temp_dir().join(format!("os_input_{}.json", uuid::Uuid::new_v4()));
+    os_input.dump(&input_path).expect("Failed to dump the OS input");
+    let compiled_os_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../build/os_latest.json");
+
+    let result = run_os_from_path(&input_path, &compiled_os_path, config, block_context, execution_helper);
+    let _ = std::fs::remove_file(&input_path);
+
+    result
+}
+
+/// Same as `execute_txs_and_run_os`, but with a compiled OS program that was already loaded, so that
+/// it can be shared by several runs.
+pub async fn execute_txs_and_run_os_with_program<S>(
+    compiled_os: &CompiledOs,
+    config: OsRunConfig,
+    state: CachedState<SharedState<S, PedersenHash>>,
+    block_context: BlockContext,
+    txs: Vec<Transaction>,
+    deprecated_compiled_contract_classes: HashMap<ClassHash, GenericDeprecatedCompiledClass>,
+    compiled_contract_classes: HashMap<ClassHash, GenericCasmContractClass>,
+    declared_class_hash_to_component_hashes: HashMap<ClassHash, ContractClassComponentHashes>,
+) -> Result<(CairoPie, StarknetOsOutput), SnOsError>
+where
+    S: Storage,
+{
+    let (os_input, execution_helper) = execute_txs(
+        state,
+        &block_context,
+        txs,
+        deprecated_compiled_contract_classes,
+        compiled_contract_classes,
+        declared_class_hash_to_component_hashes,
+    )
+    .await;
+
+    run_os_with_program(compiled_os, os_input, config, block_context, execution_helper)
+}
+
+/// Same as `execute_txs_and_run_os_with_program`, with a hint processor prepared by the test, e.g.
+/// to override some hints.
+#[allow(clippy::too_many_arguments)]
+pub async fn execute_txs_and_run_os_with_hint_processor<S>(
+    compiled_os: &CompiledOs,
+    config: OsRunConfig,
+    hint_processor: SnosHintProcessor<OsSingleStarknetStorage<S, PedersenHash>>,
+    state: CachedState<SharedState<S, PedersenHash>>,
+    block_context: BlockContext,
+    txs: Vec<Transaction>,
+    deprecated_compiled_contract_classes: HashMap<ClassHash, GenericDeprecatedCompiledClass>,
+    compiled_contract_classes: HashMap<ClassHash, GenericCasmContractClass>,
+    declared_class_hash_to_component_hashes: HashMap<ClassHash, ContractClassComponentHashes>,
+) -> Result<(CairoPie, StarknetOsOutput), SnOsError>
+where
+    S: Storage,
+{
+    let (os_input, execution_helper) = execute_txs(
+        state,
+        &block_context,
+        txs,
+        deprecated_compiled_contract_classes,
+        compiled_contract_classes,
+        declared_class_hash_to_component_hashes,
+    )
+    .await;
+
+    run_os_with_hint_processor(compiled_os, os_input, config, block_context, execution_helper, hint_processor)
+}
+
 #[rstest]
 #[case::no_calldata(vec![])]
 #[case::with_calldata(vec![Felt252::from(539), Felt252::from(337)])]
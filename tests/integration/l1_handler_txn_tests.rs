@@ -4,15 +4,16 @@ use std::sync::Arc;
 use blockifier::abi::abi_utils::selector_from_name;
 use blockifier::context::BlockContext;
 use blockifier::transaction::test_utils::{block_context, max_fee};
+use blockifier::transaction::transaction_execution::Transaction;
 use blockifier::transaction::transactions::L1HandlerTransaction;
 use futures::Future;
 use rstest::{fixture, rstest};
-use starknet_api::core::{ContractAddress, EntryPointSelector};
+use starknet_api::core::{ContractAddress, EntryPointSelector, Nonce};
 use starknet_api::felt;
 use starknet_api::transaction::{Calldata, Fee, TransactionVersion};
 
 use crate::common::state::{init_logging, initial_state_cairo0, initial_state_syscalls, StarknetTestState};
-use crate::common::transaction_utils::execute_txs_and_run_os;
+use crate::common::transaction_utils::{execute_single_tx_and_run_os, execute_txs_and_run_os};
 
 #[fixture]
 async fn l1_initial_state_cairo1(
@@ -74,3 +75,59 @@ async fn l1_handler<F>(
     .await
     .expect("OS run failed");
 }
+
+/// An L1 handler tx writing `value` at `key` in the storage of `contract_address`.
+fn l1_set_value_tx(contract_address: ContractAddress, nonce: u64, key: u64, value: u64, max_fee: Fee) -> Transaction {
+    L1HandlerTransaction {
+        paid_fee_on_l1: max_fee,
+        tx: starknet_api::transaction::L1HandlerTransaction {
+            contract_address,
+            version: TransactionVersion::ZERO,
+            nonce: Nonce(felt!(nonce)),
+            entry_point_selector: EntryPointSelector(selector_from_name("l1_handler_set_value").0),
+            calldata: Calldata(Arc::new(vec![felt!(1234_u16), felt!(key), felt!(value)])),
+        },
+        tx_hash: Default::default(),
+    }
+    .into()
+}
+
+#[rstest]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn run_single_tx(block_context: BlockContext, max_fee: Fee) {
+    let txs = |contract_address| {
+        vec![
+            l1_set_value_tx(contract_address, 0, 10, 1, max_fee),
+            l1_set_value_tx(contract_address, 1, 11, 2, max_fee),
+            l1_set_value_tx(contract_address, 2, 12, 3, max_fee),
+        ]
+    };
+
+    let mut outputs = vec![];
+    for tx_index in [0, 1] {
+        let (initial_state, contract_address) = l1_initial_state_cairo1(block_context.clone(), ()).await;
+        let (_pie, os_output) = execute_single_tx_and_run_os(
+            crate::common::DEFAULT_COMPILED_OS,
+            initial_state.cached_state,
+            block_context.clone(),
+            txs(contract_address),
+            tx_index,
+            initial_state.cairo0_compiled_classes,
+            initial_state.cairo1_compiled_classes,
+            HashMap::default(),
+        )
+        .await
+        .expect("OS run failed");
+        outputs.push((os_output, contract_address));
+    }
+    let [(first_output, _), (second_output, contract_address)] = outputs.try_into().unwrap();
+
+    // The second tx runs on the state left by the first one
+    assert_eq!(second_output.initial_root, first_output.final_root);
+
+    // Only the second tx is part of the run
+    let contract_changes: Vec<_> =
+        second_output.contracts.iter().filter(|changes| changes.addr == *contract_address.0.key()).collect();
+    assert_eq!(contract_changes.len(), 1);
+    assert_eq!(contract_changes[0].storage_changes, HashMap::from([(felt!(11_u64), felt!(2_u64))]));
+}
@@ -7,6 +7,7 @@ mod l1_handler_txn_tests;
 mod os;
 mod pie;
 mod run_os;
+mod run_os_from_path_tests;
 mod sharp;
 mod syscalls_tests;
 
@@ -0,0 +1,272 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use blockifier::abi::abi_utils::selector_from_name;
+use blockifier::context::BlockContext;
+use blockifier::transaction::test_utils::{block_context, max_fee};
+use blockifier::transaction::transaction_execution::Transaction;
+use blockifier::transaction::transactions::L1HandlerTransaction;
+use cairo_vm::Felt252;
+use rstest::rstest;
+use starknet_api::core::{ContractAddress, EntryPointSelector, Nonce};
+use starknet_api::felt;
+use starknet_api::transaction::{Calldata, Fee, TransactionVersion};
+use starknet_os::config::OsRunConfig;
+use starknet_os::error::{OsRunError, SnOsError};
+use starknet_os::hints::execution::{load_next_tx, LOAD_NEXT_TX};
+use starknet_os::hints::SnosHintProcessor;
+use starknet_os::io::output::MessageToL2;
+use starknet_os::io::prover_trace::ProverTracePaths;
+use starknet_os::io::segment_dump::SegmentDump;
+use starknet_os::CompiledOs;
+
+use crate::common::state::{init_logging, initial_state_cairo0};
+use crate::common::transaction_utils::{
+    execute_txs_and_run_os_from_path, execute_txs_and_run_os_with_hint_processor, execute_txs_and_run_os_with_program,
+};
+use crate::common::DEFAULT_COMPILED_OS;
+
+/// An L1 handler tx calling `test_contract`.
+fn l1_handler_tx(contract_address: ContractAddress, nonce: u64, max_fee: Fee) -> Transaction {
+    L1HandlerTransaction {
+        paid_fee_on_l1: max_fee,
+        tx: starknet_api::transaction::L1HandlerTransaction {
+            contract_address,
+            version: TransactionVersion::ZERO,
+            nonce: Nonce(felt!(nonce)),
+            entry_point_selector: EntryPointSelector(selector_from_name("l1_handle").0),
+            calldata: Calldata(Arc::new(vec![felt!(1234_u16), felt!(42_u16)])),
+        },
+        tx_hash: Default::default(),
+    }
+    .into()
+}
+
+/// A block with a single L1 handler tx calling `test_contract`.
+fn tiny_block_txs(contract_address: ContractAddress, max_fee: Fee) -> Vec<Transaction> {
+    vec![l1_handler_tx(contract_address, 0, max_fee)]
+}
+
+#[rstest]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn run_os_from_path_tiny_block(block_context: BlockContext, max_fee: Fee, #[from(init_logging)] _logging: ()) {
+    let initial_state = initial_state_cairo0(block_context.clone(), ()).await;
+    let contract_address = initial_state.deployed_cairo0_contracts.get("test_contract").unwrap().address;
+
+    let os_output = execute_txs_and_run_os_from_path(
+        OsRunConfig::default(),
+        initial_state.cached_state,
+        block_context.clone(),
+        tiny_block_txs(contract_address, max_fee),
+        initial_state.cairo0_compiled_classes,
+        initial_state.cairo1_compiled_classes,
+        HashMap::default(),
+    )
+    .await
+    .expect("OS run failed");
+
+    os_output.cairo_pie.run_validity_checks().expect("Validity check failed");
+
+    let output = os_output.output;
+    let block_number = block_context.block_info().block_number.0;
+    assert_eq!(output.block_number().unwrap(), block_number);
+    assert_eq!(output.prev_block_number, Felt252::from(block_number - 1));
+    assert_ne!(output.initial_root, Felt252::ZERO);
+    assert_eq!(output.os_program_hash, Felt252::ZERO);
+    assert_ne!(output.starknet_os_config_hash, Felt252::ZERO);
+    // The message consumed by the L1 handler tx
+    assert_eq!(
+        output.l2_messages().unwrap(),
+        vec![MessageToL2 {
+            from_address: felt!(1234_u16),
+            to_address: *contract_address.0.key(),
+            nonce: Felt252::ZERO,
+            selector: selector_from_name("l1_handle").0,
+            payload: vec![felt!(42_u16)],
+        }]
+    );
+    assert!(output.l1_messages().unwrap().is_empty());
+}
+
+#[rstest]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn run_os_from_path_dumps_segments(block_context: BlockContext, max_fee: Fee) {
+    let initial_state = initial_state_cairo0(block_context.clone(), ()).await;
+    let contract_address = initial_state.deployed_cairo0_contracts.get("test_contract").unwrap().address;
+
+    let segment_dump_path = std::env::temp_dir().join(format!("segments_{}.json", uuid::Uuid::new_v4()));
+    let config = OsRunConfig { segment_dump_path: Some(segment_dump_path.clone()), ..Default::default() };
+
+    execute_txs_and_run_os_from_path(
+        config,
+        initial_state.cached_state,
+        block_context,
+        tiny_block_txs(contract_address, max_fee),
+        initial_state.cairo0_compiled_classes,
+        initial_state.cairo1_compiled_classes,
+        HashMap::default(),
+    )
+    .await
+    .expect("OS run failed");
+
+    let dump = std::fs::read(&segment_dump_path).expect("The segments should have been dumped");
+    std::fs::remove_file(&segment_dump_path).unwrap();
+    let dump: SegmentDump = serde_json::from_slice(&dump).unwrap();
+
+    let output = dump.builtins.iter().find(|builtin| builtin.name == "output").expect("No output builtin in the dump");
+    assert!(output.used_cells > 0);
+    assert!(dump.builtins.iter().any(|builtin| builtin.name == "pedersen"));
+    // Every builtin segment is part of the segment map
+    for builtin in &dump.builtins {
+        assert!(builtin.segment_index < dump.segments.len(), "Missing segment for {}", builtin.name);
+    }
+}
+
+#[rstest]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn run_os_from_path_dumps_prover_trace(block_context: BlockContext, max_fee: Fee) {
+    let initial_state = initial_state_cairo0(block_context.clone(), ()).await;
+    let contract_address = initial_state.deployed_cairo0_contracts.get("test_contract").unwrap().address;
+
+    let run_id = uuid::Uuid::new_v4();
+    let prover_trace_paths = ProverTracePaths {
+        trace: std::env::temp_dir().join(format!("trace_{run_id}.bin")),
+        memory: std::env::temp_dir().join(format!("memory_{run_id}.bin")),
+    };
+    let config = OsRunConfig { prover_trace_paths: Some(prover_trace_paths.clone()), ..Default::default() };
+
+    execute_txs_and_run_os_from_path(
+        config,
+        initial_state.cached_state,
+        block_context,
+        tiny_block_txs(contract_address, max_fee),
+        initial_state.cairo0_compiled_classes,
+        initial_state.cairo1_compiled_classes,
+        HashMap::default(),
+    )
+    .await
+    .expect("OS run failed");
+
+    let trace = std::fs::read(&prover_trace_paths.trace).expect("The trace should have been written");
+    let memory = std::fs::read(&prover_trace_paths.memory).expect("The memory should have been written");
+    std::fs::remove_file(&prover_trace_paths.trace).unwrap();
+    std::fs::remove_file(&prover_trace_paths.memory).unwrap();
+
+    // 3 registers per step, an address and a felt per memory cell
+    assert!(!trace.is_empty());
+    assert_eq!(trace.len() % (3 * 8), 0);
+    assert!(!memory.is_empty());
+    assert_eq!(memory.len() % (8 + 32), 0);
+}
+
+#[rstest]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn run_os_cancelled_mid_run(block_context: BlockContext, max_fee: Fee) {
+    let compiled_os = CompiledOs::from_bytes(DEFAULT_COMPILED_OS).expect("Failed to parse the compiled OS");
+    let initial_state = initial_state_cairo0(block_context.clone(), ()).await;
+    let contract_address = initial_state.deployed_cairo0_contracts.get("test_contract").unwrap().address;
+    let txs = vec![l1_handler_tx(contract_address, 0, max_fee), l1_handler_tx(contract_address, 1, max_fee)];
+
+    // The flag is raised once the first tx is loaded: the run stops when loading the second one
+    let cancellation_flag = Arc::new(AtomicBool::new(false));
+    let mut hint_processor = SnosHintProcessor::default();
+    let flag = cancellation_flag.clone();
+    let n_loaded_txs = Rc::new(Cell::new(0));
+    let n_loaded = n_loaded_txs.clone();
+    hint_processor.override_hint(
+        LOAD_NEXT_TX,
+        Box::new(move |vm, exec_scopes, ids_data, ap_tracking, constants| {
+            load_next_tx(vm, exec_scopes, ids_data, ap_tracking, constants)?;
+            n_loaded.set(n_loaded.get() + 1);
+            flag.store(true, Ordering::Relaxed);
+            Ok(())
+        }),
+    );
+    let config = OsRunConfig { cancellation_flag: Some(cancellation_flag), ..Default::default() };
+
+    let result = execute_txs_and_run_os_with_hint_processor(
+        &compiled_os,
+        config,
+        hint_processor,
+        initial_state.cached_state,
+        block_context,
+        txs,
+        initial_state.cairo0_compiled_classes,
+        initial_state.cairo1_compiled_classes,
+        HashMap::default(),
+    )
+    .await;
+
+    assert!(matches!(result, Err(SnOsError::Cancelled)));
+    assert_eq!(n_loaded_txs.get(), 1);
+}
+
+#[rstest]
+#[case::over_the_limit(0, false)]
+#[case::at_the_limit(1, true)]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn run_os_from_path_max_transactions(
+    block_context: BlockContext,
+    max_fee: Fee,
+    #[case] max_transactions: usize,
+    #[case] expect_success: bool,
+) {
+    let initial_state = initial_state_cairo0(block_context.clone(), ()).await;
+    let contract_address = initial_state.deployed_cairo0_contracts.get("test_contract").unwrap().address;
+
+    let config = OsRunConfig { max_transactions: Some(max_transactions), ..Default::default() };
+
+    let result = execute_txs_and_run_os_from_path(
+        config,
+        initial_state.cached_state,
+        block_context,
+        tiny_block_txs(contract_address, max_fee),
+        initial_state.cairo0_compiled_classes,
+        initial_state.cairo1_compiled_classes,
+        HashMap::default(),
+    )
+    .await;
+
+    if expect_success {
+        result.expect("OS run failed");
+    } else {
+        assert!(matches!(
+            result,
+            Err(OsRunError::Run(SnOsError::TooManyTransactions { n_transactions: 1, max_transactions: 0 }))
+        ));
+    }
+}
+
+#[rstest]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn run_two_blocks_with_shared_compiled_os(block_context: BlockContext, max_fee: Fee) {
+    let compiled_os = CompiledOs::from_bytes(DEFAULT_COMPILED_OS).expect("Failed to parse the compiled OS");
+
+    let mut outputs = vec![];
+    for _ in 0..2 {
+        let initial_state = initial_state_cairo0(block_context.clone(), ()).await;
+        let contract_address = initial_state.deployed_cairo0_contracts.get("test_contract").unwrap().address;
+
+        let (pie, os_output) = execute_txs_and_run_os_with_program(
+            &compiled_os,
+            OsRunConfig::default(),
+            initial_state.cached_state,
+            block_context.clone(),
+            tiny_block_txs(contract_address, max_fee),
+            initial_state.cairo0_compiled_classes,
+            initial_state.cairo1_compiled_classes,
+            HashMap::default(),
+        )
+        .await
+        .expect("OS run failed");
+
+        pie.run_validity_checks().expect("Validity check failed");
+        outputs.push(os_output);
+    }
+
+    // Reusing the program does not leak anything from the first run into the second one
+    assert_eq!(outputs[0], outputs[1]);
+}